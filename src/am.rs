@@ -0,0 +1,631 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::DateTime;
+
+use crate::{
+    apply, branch, commit,
+    configuration::LOGGER_COMMANDS_FILE,
+    index::Index,
+    logger::Logger,
+    sequencer::SequencerStatus,
+    tree_handler::{self, Tree},
+    utils,
+};
+
+/// Name of the folder, inside the git directory, where the state of an `am` in progress is kept.
+pub const AM_DIR: &str = "rebase-apply";
+const NEXT_FILE: &str = "next";
+const LAST_FILE: &str = "last";
+const ORIG_HEAD_FILE: &str = "orig-head";
+
+/// Logs the 'git am' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_am(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git am': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// A patch received by email: who wrote it and when, the message of its commit and its diff.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MailPatch {
+    /// The author and date, as `<name> <<email>> <timestamp> <timezone>`.
+    pub author: String,
+    /// The subject, without its `[PATCH n/m]` prefix.
+    pub subject: String,
+    /// The message of the commit: the subject and the body of the email.
+    pub message: String,
+    /// The diff, with everything that follows the message.
+    pub patch: String,
+}
+
+impl MailPatch {
+    /// Parses an email with a patch, as `git format-patch` writes them.
+    ///
+    /// The author is taken from the `From` header and the date from the `Date` header. The message
+    /// is the subject followed by the body until the `---` line (or the diff, if there is none).
+    /// The RFC 2047 encoded-words of the author and the subject are decoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the email has no valid author or date, or no diff.
+    ///
+    pub fn parse(mail: &str) -> io::Result<MailPatch> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let (header, body) = mail.split_once("\n\n").unwrap_or((mail, ""));
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in header.lines() {
+            match (line.starts_with([' ', '\t']), headers.last_mut()) {
+                (true, Some((_, value))) => value.push_str(line),
+                _ => {
+                    if let Some((key, value)) = line.split_once(':') {
+                        headers.push((key.to_lowercase(), value.trim().to_string()));
+                    }
+                }
+            }
+        }
+        let header = |key: &str| {
+            headers
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let from =
+            header("from").ok_or_else(|| invalid("Patch does not have a valid e-mail address."))?;
+        let (name, email) = match from.split_once('<') {
+            Some((name, email)) => (name.trim().trim_matches('"'), email.trim_end_matches('>')),
+            None => (from, from),
+        };
+        let name = decode_header(name);
+        let date = header("date")
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .ok_or_else(|| invalid("Patch does not have a valid date."))?;
+        let decoded_subject = decode_header(header("subject").unwrap_or_default());
+        let mut subject = decoded_subject.trim();
+        while let Some(rest) = subject.strip_prefix('[') {
+            subject = rest
+                .split_once(']')
+                .map(|(_, rest)| rest)
+                .unwrap_or(rest)
+                .trim();
+        }
+
+        let patch_start = body
+            .find("\ndiff -")
+            .map(|position| position + 1)
+            .or_else(|| body.starts_with("diff -").then_some(0))
+            .ok_or_else(|| invalid("Patch is empty."))?;
+        let message_end = body
+            .find("\n---\n")
+            .filter(|position| *position < patch_start)
+            .or_else(|| body.starts_with("---\n").then_some(0))
+            .unwrap_or(patch_start);
+        let description = body[..message_end].trim();
+        let message = if description.is_empty() {
+            format!("{}\n", subject)
+        } else {
+            format!("{}\n\n{}\n", subject, description)
+        };
+        Ok(MailPatch {
+            author: format!(
+                "{} <{}> {} {}",
+                name,
+                email,
+                date.timestamp(),
+                date.format("%z")
+            ),
+            subject: subject.to_string(),
+            message,
+            patch: body[patch_start..].to_string(),
+        })
+    }
+}
+
+/// Decodes the RFC 2047 encoded-words (`=?<charset>?<q or b>?<text>?=`) of a header value, taking
+/// their text as UTF-8. The whitespace between two encoded-words is dropped, as they are one text.
+fn decode_header(value: &str) -> String {
+    let mut decoded = Vec::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let Some((bytes, length)) = decode_word(&rest[start..]) else {
+            decoded.extend_from_slice(&rest.as_bytes()[..start + 2]);
+            rest = &rest[start + 2..];
+            after_word = false;
+            continue;
+        };
+        if !after_word || !rest[..start].trim().is_empty() {
+            decoded.extend_from_slice(&rest.as_bytes()[..start]);
+        }
+        decoded.extend(bytes);
+        rest = &rest[start + length..];
+        after_word = true;
+    }
+    decoded.extend_from_slice(rest.as_bytes());
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Decodes the encoded-word at the start of `word`. Returns its bytes and its length, or None if
+/// it isn't a valid encoded-word.
+fn decode_word(word: &str) -> Option<(Vec<u8>, usize)> {
+    let (_charset, rest) = word.strip_prefix("=?")?.split_once('?')?;
+    let (encoding, rest) = rest.split_once('?')?;
+    let (text, _) = rest.split_once("?=")?;
+    let bytes = match encoding {
+        "q" | "Q" => {
+            let mut bytes = Vec::new();
+            let mut chars = text.bytes();
+            while let Some(byte) = chars.next() {
+                match byte {
+                    b'_' => bytes.push(b' '),
+                    b'=' => {
+                        let hex = [chars.next()?, chars.next()?];
+                        bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                    }
+                    byte => bytes.push(byte),
+                }
+            }
+            bytes
+        }
+        "b" | "B" => {
+            let mut bytes = Vec::new();
+            let (mut buffer, mut bits) = (0u32, 0);
+            for byte in text.bytes().filter(|byte| *byte != b'=') {
+                let value = match byte {
+                    b'A'..=b'Z' => byte - b'A',
+                    b'a'..=b'z' => byte - b'a' + 26,
+                    b'0'..=b'9' => byte - b'0' + 52,
+                    b'+' => 62,
+                    b'/' => 63,
+                    _ => return None,
+                };
+                buffer = (buffer << 6 | value as u32) & 0xFFFF;
+                bits += 6;
+                if bits >= 8 {
+                    bits -= 8;
+                    bytes.push((buffer >> bits) as u8);
+                }
+            }
+            bytes
+        }
+        _ => return None,
+    };
+    Some((bytes, word.len() - rest.len() + text.len() + 2))
+}
+
+/// Returns true if a line is the `From <hash> <date>` line that format-patch writes before every
+/// email.
+fn is_mbox_separator(line: &str) -> bool {
+    match line
+        .strip_prefix("From ")
+        .and_then(|rest| rest.split_once(' '))
+    {
+        Some((hash, date)) => {
+            hash.len() == 40
+                && hash.chars().all(|c| c.is_ascii_hexdigit())
+                && !date.trim().is_empty()
+        }
+        None => false,
+    }
+}
+
+/// Splits a mailbox into its emails. Every email starts with the `From <hash> <date>` line of
+/// format-patch, so the lines of the messages that start with `From ` are left alone. A file
+/// without that line is a single email.
+pub fn split_mbox(content: &str) -> Vec<String> {
+    let mut mails: Vec<String> = Vec::new();
+    for line in content.split_inclusive('\n') {
+        if is_mbox_separator(line) {
+            mails.push(String::new());
+        } else if let Some(mail) = mails.last_mut() {
+            mail.push_str(line);
+        } else {
+            mails.push(line.to_string());
+        }
+    }
+    mails
+        .into_iter()
+        .filter(|mail| !mail.trim().is_empty())
+        .collect()
+}
+
+/// Returns the path to a file of the state of the `am` in progress.
+fn state_path(git_dir: &str, file_name: &str) -> PathBuf {
+    Path::new(git_dir).join(AM_DIR).join(file_name)
+}
+
+/// Returns true if there is an `am` in progress in the repository.
+pub fn is_in_progress(git_dir: &str) -> bool {
+    state_path(git_dir, "").is_dir()
+}
+
+/// Reads a number of the state of the `am` in progress.
+fn read_number(git_dir: &str, file_name: &str) -> io::Result<usize> {
+    fs::read_to_string(state_path(git_dir, file_name))?
+        .trim()
+        .parse()
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("corrupt {}/{}", AM_DIR, file_name),
+            )
+        })
+}
+
+/// Returns the email of the patch the `am` in progress is at.
+fn current_patch(git_dir: &str) -> io::Result<MailPatch> {
+    let next = read_number(git_dir, NEXT_FILE)?;
+    MailPatch::parse(&fs::read_to_string(state_path(
+        git_dir,
+        &format!("{:04}", next),
+    ))?)
+}
+
+/// Moves the `am` in progress to the next patch.
+fn advance(git_dir: &str) -> io::Result<()> {
+    let next = read_number(git_dir, NEXT_FILE)?;
+    fs::write(state_path(git_dir, NEXT_FILE), format!("{}\n", next + 1))
+}
+
+/// Replaces the files of `old_tree` in the working tree with the ones of `new_tree` and rebuilds the index.
+fn reset_working_tree(
+    git_dir: &str,
+    root_dir: &str,
+    old_tree: &Tree,
+    new_tree: &Tree,
+) -> io::Result<()> {
+    old_tree.delete_directories(root_dir)?;
+    new_tree.create_directories(root_dir, git_dir)?;
+    let index_path = utils::get_index_file_path(git_dir);
+    new_tree
+        .build_index_file_from_tree(&index_path, git_dir, "")?
+        .write_file()
+}
+
+/// Resets the index and the working tree to a commit.
+fn reset_to(git_dir: &str, root_dir: &str, commit_hash: &str) -> io::Result<()> {
+    let index_path = utils::get_index_file_path(git_dir);
+    let index_tree = tree_handler::build_tree_from_index(&index_path, git_dir, "")?;
+    let tree = tree_handler::load_tree_from_commit(commit_hash, git_dir)?;
+    reset_working_tree(git_dir, root_dir, &index_tree, &tree)
+}
+
+/// Applies the remaining patches of the `am` in progress, committing each one with its author and message.
+/// If a patch doesn't apply, nothing of it is changed and the `am` stops, so the user can apply it by
+/// hand and run `--continue`, or use `--skip` or `--abort`.
+fn run(git_dir: &str, root_dir: &str, output: &mut impl Write) -> io::Result<SequencerStatus> {
+    let last = read_number(git_dir, LAST_FILE)?;
+    while read_number(git_dir, NEXT_FILE)? <= last {
+        let mail = current_patch(git_dir)?;
+        writeln!(output, "Applying: {}", mail.subject)?;
+        let index_path = utils::get_index_file_path(git_dir);
+        let mut index = Index::load(&index_path, git_dir, "")?;
+        let applied = apply::parse_patch(&mail.patch)
+            .and_then(|patches| apply::apply_to_index(&patches, &mut index, git_dir, root_dir));
+        if let Err(error) = applied {
+            writeln!(output, "{}", error)?;
+            writeln!(
+                output,
+                "Patch failed at {:04} {}",
+                read_number(git_dir, NEXT_FILE)?,
+                mail.subject
+            )?;
+            writeln!(
+                output,
+                "When you have resolved this problem, run \"git am --continue\"."
+            )?;
+            writeln!(
+                output,
+                "If you prefer to skip this patch, run \"git am --skip\" instead."
+            )?;
+            writeln!(
+                output,
+                "To restore the original branch and stop patching, run \"git am --abort\"."
+            )?;
+            return Ok(SequencerStatus::Stopped);
+        }
+        index.write_file()?;
+        commit::new_commit_with_author(git_dir, &mail.message, Some(&mail.author), "")?;
+        advance(git_dir)?;
+    }
+    fs::remove_dir_all(state_path(git_dir, ""))?;
+    Ok(SequencerStatus::Finished)
+}
+
+/// Starts applying the patches of some mailboxes, one commit per email.
+fn start(
+    git_dir: &str,
+    root_dir: &str,
+    mailboxes: &[String],
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    if is_in_progress(git_dir) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "previous rebase directory {} still exists but mbox given.",
+                state_path(git_dir, "").to_string_lossy()
+            ),
+        ));
+    }
+    let mut mails = Vec::new();
+    for mailbox in mailboxes {
+        mails.extend(split_mbox(&fs::read_to_string(mailbox)?));
+    }
+    if mails.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Patch format detection failed.",
+        ));
+    }
+    let orig_head = commit::read_head_commit_hash(git_dir)?;
+    fs::create_dir_all(state_path(git_dir, ""))?;
+    for (i, mail) in mails.iter().enumerate() {
+        fs::write(state_path(git_dir, &format!("{:04}", i + 1)), mail)?;
+    }
+    fs::write(state_path(git_dir, NEXT_FILE), "1\n")?;
+    fs::write(state_path(git_dir, LAST_FILE), format!("{}\n", mails.len()))?;
+    fs::write(state_path(git_dir, ORIG_HEAD_FILE), orig_head)?;
+    run(git_dir, root_dir, output)
+}
+
+/// Commits the changes the user staged for the patch the `am` stopped at, with the author and
+/// message of the patch, and applies the remaining ones.
+fn continue_am(
+    git_dir: &str,
+    root_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    let mail = current_patch(git_dir)?;
+    match commit::new_commit_with_author(git_dir, &mail.message, Some(&mail.author), "") {
+        Err(error) if error.to_string() == commit::NO_CHANGES => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Applying: {}\nNo changes - did you forget to use 'git add'?\nIf there is nothing left to stage, chances are that something else\nalready introduced the same changes; you might want to skip this patch.",
+                    mail.subject
+                ),
+            ));
+        }
+        result => result?,
+    };
+    advance(git_dir)?;
+    run(git_dir, root_dir, output)
+}
+
+/// Applies patches received by email (mailboxes written by `git format-patch`), creating a commit
+/// with the author, date and message of each one.
+///
+/// * `am <mbox>...` applies the patches of the mailboxes, in order.
+/// * `am --continue` commits the staged changes of the patch that failed and goes on.
+/// * `am --skip` drops the patch that failed and goes on.
+/// * `am --abort` puts the branch, the index and the working tree back to where they were.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `args` - The arguments of the command, the ones after 'am'.
+/// * `output` - Where the progress is written.
+///
+/// # Returns
+///
+/// Returns whether every patch was applied or the `am` stopped at one that doesn't apply.
+///
+/// # Errors
+///
+/// Returns an error if there is no `am` in progress for `--continue`, `--resolved`, `--skip` or `--abort`,
+/// a mailbox can't be read or an email is not a valid patch.
+///
+pub fn git_am(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    log_am(git_dir, args)?;
+    let action = args.first().map(|arg| arg.as_str());
+    if matches!(
+        action,
+        Some("--continue" | "--resolved" | "--skip" | "--abort")
+    ) && !is_in_progress(git_dir)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Resolve operation not in progress, we are not resuming.",
+        ));
+    }
+    match action {
+        Some("--continue") | Some("--resolved") => continue_am(git_dir, root_dir, output),
+        Some("--skip") => {
+            reset_to(git_dir, root_dir, &commit::read_head_commit_hash(git_dir)?)?;
+            advance(git_dir)?;
+            run(git_dir, root_dir, output)
+        }
+        Some("--abort") => {
+            let orig_head = fs::read_to_string(state_path(git_dir, ORIG_HEAD_FILE))?;
+            let orig_head = orig_head.trim();
+            reset_to(git_dir, root_dir, orig_head)?;
+            branch::update_branch_commit_hash(
+                &commit::get_branch_name(git_dir)?,
+                orig_head,
+                git_dir,
+            )?;
+            fs::remove_dir_all(state_path(git_dir, ""))?;
+            Ok(SequencerStatus::Finished)
+        }
+        Some(option) if option.starts_with('-') => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown option `{}'", option),
+        )),
+        _ => start(git_dir, root_dir, args, output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format_patch,
+        log::Log,
+        test_utils::{commit_file, create_repo},
+    };
+
+    fn run_command(
+        git_dir: &str,
+        args: &[&str],
+        command: fn(&str, &str, &[String], &mut Vec<u8>) -> io::Result<()>,
+    ) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        command(git_dir, "", &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    fn am(git_dir: &str, root_dir: &str, args: &[String], output: &mut Vec<u8>) -> io::Result<()> {
+        git_am(git_dir, root_dir, args, output).map(|_| ())
+    }
+
+    #[test]
+    fn test_mail_patch_parse() -> io::Result<()> {
+        let mail = "From: Lionel Messi <lio@afa.com>\nDate: Sun, 18 Dec 2022 15:00:00 -0300\nSubject: [PATCH 2/3] Ganar\n la copa\n\nCon penales.\n---\n a.txt | 1 +\n\ndiff --git a/a.txt b/a.txt\n";
+        let patch = MailPatch::parse(mail)?;
+        assert_eq!(patch.author, "Lionel Messi <lio@afa.com> 1671386400 -0300");
+        assert_eq!(patch.subject, "Ganar la copa");
+        assert_eq!(patch.message, "Ganar la copa\n\nCon penales.\n");
+        assert_eq!(patch.patch, "diff --git a/a.txt b/a.txt\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_mail_patch_parse_decodes_the_headers() -> io::Result<()> {
+        let mail = "From: =?UTF-8?q?Jos=C3=A9?= Pek_erman <jp@afa.com>\nDate: Sun, 18 Dec 2022 15:00:00 -0300\nSubject: =?UTF-8?q?[PATCH]=20Ganar=20el=20?=\n =?utf-8?b?Y2FtcGXDs24=?=\n\ndiff --git a/a.txt b/a.txt\n";
+        let patch = MailPatch::parse(mail)?;
+        assert_eq!(patch.author, "José Pek_erman <jp@afa.com> 1671386400 -0300");
+        assert_eq!(patch.subject, "Ganar el campeón");
+        assert_eq!(decode_header("=?UTF-8?x?a?= =?b"), "=?UTF-8?x?a?= =?b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_mbox_only_on_the_format_patch_separator() {
+        let from = format!("From {} Mon Sep 17 00:00:00 2001\n", "a".repeat(40));
+        let mail = "From: Lionel Messi <lio@afa.com>\nSubject: [PATCH] Ganar\n\nFrom the start.\n\nFrom abc Mon Sep 17 00:00:00 2001\n---\n";
+
+        let mails = split_mbox(&format!("{}{}{}{}", from, mail, from, mail));
+        let single = split_mbox(mail);
+
+        assert_eq!(mails, vec![mail.to_string(), mail.to_string()]);
+        assert_eq!(single, vec![mail.to_string()]);
+        assert!(!is_mbox_separator("From the start.\n"));
+        assert!(!is_mbox_separator(&format!("From {}\n", "a".repeat(40))));
+    }
+
+    /// Creates a repository with a base commit and two more, writes the patches of the last two
+    /// and moves master back to the base. Returns the git directory, the base and last commits and
+    /// the paths to the patches.
+    fn create_patches(root_dir: &str) -> io::Result<(String, [String; 2], [String; 2])> {
+        let git_dir = create_repo(root_dir)?;
+        let (a, b) = (format!("{}/a.txt", root_dir), format!("{}/b.txt", root_dir));
+        let base = commit_file(&git_dir, &a, "uno\ndos\ntres\n", "Base")?;
+        commit_file(
+            &git_dir,
+            &a,
+            "uno\nDOS\ntres\n",
+            "Cambiar a\n\nEn mayusculas.",
+        )?;
+        let last = commit_file(&git_dir, &b, "nuevo\n", "Agregar b")?;
+        let patches_dir = format!("{}/patches", root_dir);
+        run_command(
+            &git_dir,
+            &["-o", &patches_dir, &base],
+            format_patch::git_format_patch,
+        )?;
+        reset_to(&git_dir, "", &base)?;
+        branch::update_branch_commit_hash("master", &base, &git_dir)?;
+        let first = format!("{}/0001-Cambiar-a.patch", patches_dir);
+        let second = format!("{}/0002-Agregar-b.patch", patches_dir);
+        Ok((git_dir, [base, last], [first, second]))
+    }
+
+    #[test]
+    fn test_am_recreates_the_commits_of_format_patch() -> io::Result<()> {
+        let root_dir = "tests/am_round_trip";
+        let (git_dir, [_, last], [first, second]) = create_patches(root_dir)?;
+
+        let applied = run_command(&git_dir, &[&first, &second], am);
+        let head = commit::read_head_commit_hash(&git_dir)?;
+        let (new, original) = (
+            Log::load(Some(&head), &git_dir)?,
+            Log::load(Some(&last), &git_dir)?,
+        );
+        let message =
+            commit::get_commit_full_message(&commit::get_parent_hash(&head, &git_dir)?, &git_dir);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            applied?,
+            "Applying: Cambiar a\nApplying: Agregar b\n".to_string()
+        );
+        assert_eq!(new.tree_hash(), original.tree_hash());
+        assert_eq!(new.author().time, original.author().time);
+        assert_eq!(message?, "Cambiar a\n\nEn mayusculas.\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_am_stops_at_a_conflict_and_aborts() -> io::Result<()> {
+        let root_dir = "tests/am_conflict";
+        let (git_dir, _, [first, second]) = create_patches(root_dir)?;
+        let a = format!("{}/a.txt", root_dir);
+        commit_file(&git_dir, &a, "otro\n", "Conflicto")?;
+
+        let not_resolved = run_command(&git_dir, &["--resolved"], am);
+        let conflicted = run_command(&git_dir, &[&first, &second], am);
+        let in_progress = is_in_progress(&git_dir);
+        let nothing_added = run_command(&git_dir, &["--continue"], am);
+        let aborted = run_command(&git_dir, &["--abort"], am);
+        let after_abort = fs::read_to_string(&a);
+        let in_progress_after_abort = is_in_progress(&git_dir);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            not_resolved.unwrap_err().to_string(),
+            "Resolve operation not in progress, we are not resuming."
+        );
+        assert!(conflicted?.contains("Patch failed at 0001 Cambiar a\n"));
+        assert!(in_progress);
+        assert!(nothing_added
+            .unwrap_err()
+            .to_string()
+            .contains("No changes - did you forget to use 'git add'?"));
+        aborted?;
+        assert_eq!(after_abort?, "otro\n");
+        assert!(!in_progress_after_abort);
+        Ok(())
+    }
+}
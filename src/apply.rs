@@ -0,0 +1,937 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    cat_file,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff::{self, split_lines, DiffLine, Hunk},
+    hash_object,
+    index::Index,
+    logger::Logger,
+    utils,
+};
+
+/// Number of context lines at each end of a hunk that can be ignored when it doesn't apply as it is.
+pub const DEFAULT_FUZZ: usize = 2;
+/// Mode of the executable files in the patches.
+const EXECUTABLE_MODE: &str = "100755";
+
+/// Logs the 'git apply' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_apply(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git apply': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The changes of a patch to a single file.
+/// The old path is `None` for files the patch creates, and the new path for files it deletes.
+/// The modes and the hash of the old version are the ones of the extended headers of git, if the patch has them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub old_hash: Option<String>,
+    pub hunks: Vec<Hunk>,
+    pub binary: bool,
+}
+
+impl FilePatch {
+    /// Returns the path shown in the messages about the file: the new one, or the old one if it's deleted.
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Returns the patch that undoes this one: the paths, the modes and the sides of every hunk are swapped.
+    /// The hash of the old version is unknown, so the result can't fall back to a three-way merge.
+    pub fn reverse(&self) -> FilePatch {
+        let hunks = self
+            .hunks
+            .iter()
+            .map(|hunk| Hunk {
+                old_start: hunk.new_start,
+                old_lines: hunk.new_lines,
+                new_start: hunk.old_start,
+                new_lines: hunk.old_lines,
+                lines: hunk
+                    .lines
+                    .iter()
+                    .map(|line| match line {
+                        DiffLine::Context(content) => DiffLine::Context(content.clone()),
+                        DiffLine::Deletion(content) => DiffLine::Addition(content.clone()),
+                        DiffLine::Addition(content) => DiffLine::Deletion(content.clone()),
+                    })
+                    .collect(),
+            })
+            .collect();
+        FilePatch {
+            old_path: self.new_path.clone(),
+            new_path: self.old_path.clone(),
+            old_mode: self.new_mode.clone(),
+            new_mode: self.old_mode.clone(),
+            old_hash: None,
+            hunks,
+            binary: self.binary,
+        }
+    }
+}
+
+/// Returns the path of a `---` or `+++` line without its `a/` or `b/` prefix and a trailing
+/// timestamp, or `None` for `/dev/null`.
+fn patch_path(value: &str) -> Option<String> {
+    let value = value.trim_end_matches(['\n', '\r']);
+    let value = value.split('\t').next().unwrap_or(value);
+    if value == "/dev/null" {
+        return None;
+    }
+    Some(strip_prefix(value))
+}
+
+/// Removes the first component of a path of a patch (`a/` or `b/` in the ones of git).
+fn strip_prefix(path: &str) -> String {
+    match path.split_once('/') {
+        Some((_, rest)) => rest.to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Parses a hunk header, `@@ -<old_start>[,<old_lines>] +<new_start>[,<new_lines>] @@`.
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_lines) = range(old)?;
+    let (new_start, new_lines) = range(new)?;
+    Some(Hunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: Vec::new(),
+    })
+}
+
+/// Parses a patch with the unified diffs of one or more files, as `git diff` or `diff -u` write them.
+///
+/// The extended headers of git (`new file mode`, `deleted file mode`, `old mode`, `new mode`, `index`,
+/// `rename from`, `rename to`) are understood, and any text before the first diff, such as the message of an email, is skipped.
+///
+/// # Errors
+///
+/// Returns an error if a hunk is incomplete or the patch has no diff at all.
+///
+pub fn parse_patch(patch: &str) -> io::Result<Vec<FilePatch>> {
+    let corrupt = |line: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("error: corrupt patch at line {}", line + 1),
+        )
+    };
+    let lines = split_lines(patch);
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_end_matches(['\n', '\r']);
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let (old, new) = paths.split_once(" b/").unwrap_or((paths, paths));
+            patches.push(FilePatch {
+                old_path: Some(strip_prefix(old)),
+                new_path: Some(new.to_string()),
+                ..Default::default()
+            });
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            let is_new_file = lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "));
+            if is_new_file && patches.last().is_none_or(|last| !last.hunks.is_empty()) {
+                patches.push(FilePatch::default());
+            }
+            if let Some(last) = patches.last_mut() {
+                last.old_path = patch_path(old);
+            }
+        } else if let Some(new) = line.strip_prefix("+++ ") {
+            if let Some(last) = patches.last_mut() {
+                last.new_path = patch_path(new);
+            }
+        } else if let Some(last) = patches.last_mut() {
+            if let Some(mode) = line.strip_prefix("new file mode ") {
+                last.old_path = None;
+                last.new_mode = Some(mode.to_string());
+            } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+                last.new_path = None;
+                last.old_mode = Some(mode.to_string());
+            } else if let Some(mode) = line.strip_prefix("old mode ") {
+                last.old_mode = Some(mode.to_string());
+            } else if let Some(mode) = line.strip_prefix("new mode ") {
+                last.new_mode = Some(mode.to_string());
+            } else if let Some(hashes) = line.strip_prefix("index ") {
+                let (hashes, mode) = hashes.split_once(' ').unwrap_or((hashes, ""));
+                if let Some((old_hash, _)) = hashes.split_once("..") {
+                    last.old_hash =
+                        Some(old_hash.to_string()).filter(|hash| hash.chars().any(|c| c != '0'));
+                }
+                if !mode.is_empty() {
+                    last.old_mode.get_or_insert_with(|| mode.to_string());
+                    last.new_mode.get_or_insert_with(|| mode.to_string());
+                }
+            } else if let Some(path) = line.strip_prefix("rename from ") {
+                last.old_path = Some(path.to_string());
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                last.new_path = Some(path.to_string());
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                last.binary = true;
+            } else if let Some(mut hunk) = parse_hunk_header(line) {
+                let header = i;
+                let (mut old_left, mut new_left) = (hunk.old_lines, hunk.new_lines);
+                while old_left > 0 || new_left > 0 {
+                    i += 1;
+                    let line = lines.get(i).ok_or_else(|| corrupt(header))?;
+                    // A blank context line may have lost its space, so the line is all its content.
+                    let content = if line.starts_with('\n') {
+                        line.to_string()
+                    } else {
+                        line.get(1..).unwrap_or_default().to_string()
+                    };
+                    match line.chars().next() {
+                        Some(' ') | Some('\n') if old_left > 0 && new_left > 0 => {
+                            hunk.lines.push(DiffLine::Context(content));
+                            old_left -= 1;
+                            new_left -= 1;
+                        }
+                        Some('-') if old_left > 0 => {
+                            hunk.lines.push(DiffLine::Deletion(content));
+                            old_left -= 1;
+                        }
+                        Some('+') if new_left > 0 => {
+                            hunk.lines.push(DiffLine::Addition(content));
+                            new_left -= 1;
+                        }
+                        Some('\\') => remove_last_newline(&mut hunk),
+                        _ => return Err(corrupt(i)),
+                    }
+                }
+                if lines.get(i + 1).is_some_and(|next| next.starts_with('\\')) {
+                    i += 1;
+                    remove_last_newline(&mut hunk);
+                }
+                last.hunks.push(hunk);
+            }
+        }
+        i += 1;
+    }
+    if patches.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "error: No valid patches in input",
+        ));
+    }
+    Ok(patches)
+}
+
+/// Handles a `\ No newline at end of file` line: the last line of the hunk has no terminator.
+fn remove_last_newline(hunk: &mut Hunk) {
+    if let Some(
+        DiffLine::Context(content) | DiffLine::Deletion(content) | DiffLine::Addition(content),
+    ) = hunk.lines.last_mut()
+    {
+        if content.ends_with('\n') {
+            content.pop();
+        }
+    }
+}
+
+/// Returns true if the lines match the ones of the file starting at `position`.
+fn lines_match(file: &[String], position: usize, lines: &[&String]) -> bool {
+    position + lines.len() <= file.len()
+        && lines
+            .iter()
+            .zip(&file[position..])
+            .all(|(line, file_line)| *line == file_line)
+}
+
+/// Returns the lines of the file a hunk expects: its context and the lines it deletes.
+fn old_lines(hunk: &Hunk) -> Vec<&String> {
+    hunk.lines
+        .iter()
+        .filter_map(|line| match line {
+            DiffLine::Context(content) | DiffLine::Deletion(content) => Some(content),
+            DiffLine::Addition(_) => None,
+        })
+        .collect()
+}
+
+/// Returns the hunk without up to `fuzz` lines of context at each of its ends.
+fn trim_context(hunk: &Hunk, fuzz: usize) -> Hunk {
+    let is_context = |line: &&DiffLine| matches!(line, DiffLine::Context(_));
+    let leading = hunk.lines.iter().take_while(is_context).count().min(fuzz);
+    let trailing = hunk.lines[leading..]
+        .iter()
+        .rev()
+        .take_while(is_context)
+        .count()
+        .min(fuzz);
+    let old_lines = hunk.old_lines - leading - trailing;
+    let old_start = if old_lines == 0 && hunk.old_lines > 0 {
+        (hunk.old_start + leading).saturating_sub(1)
+    } else {
+        hunk.old_start + leading
+    };
+    Hunk {
+        old_start,
+        old_lines,
+        new_start: hunk.new_start + leading,
+        new_lines: hunk.new_lines - leading - trailing,
+        lines: hunk.lines[leading..hunk.lines.len() - trailing].to_vec(),
+    }
+}
+
+/// Applies the hunks of a patch to the content of a file, without fuzz.
+///
+/// # Returns
+///
+/// Returns the new content, or the line where the hunk that doesn't apply starts.
+///
+pub fn apply_hunks(content: &str, hunks: &[Hunk]) -> Result<String, usize> {
+    apply_hunks_with_fuzz(content, hunks, 0)
+}
+
+/// Applies the hunks of a patch to the content of a file.
+///
+/// Each hunk is looked for where its header says, and if the lines are not there (because the file
+/// changed before or after the patch was made), in the nearest position where they are. If they are
+/// nowhere, up to `fuzz` lines of context at each end of the hunk are ignored, one more each time.
+///
+/// # Returns
+///
+/// Returns the new content, or the line where the hunk that doesn't apply starts.
+///
+pub fn apply_hunks_with_fuzz(content: &str, hunks: &[Hunk], fuzz: usize) -> Result<String, usize> {
+    let file = split_lines(content);
+    let mut result: Vec<String> = Vec::new();
+    let mut position = 0;
+    let mut drift: isize = 0;
+    for hunk in hunks {
+        let (hunk, start, found) = (0..=fuzz)
+            .map(|fuzz| trim_context(hunk, fuzz))
+            .find_map(|hunk| {
+                let old = old_lines(&hunk);
+                let start = if hunk.old_lines == 0 {
+                    hunk.old_start
+                } else {
+                    hunk.old_start.saturating_sub(1)
+                };
+                let expected = (start as isize + drift).max(position as isize) as usize;
+                let last = file.len().saturating_sub(old.len());
+                let found = (0..=file.len())
+                    .flat_map(|distance| {
+                        [expected.checked_sub(distance), Some(expected + distance)]
+                    })
+                    .flatten()
+                    .filter(|candidate| *candidate >= position && *candidate <= last)
+                    .find(|candidate| lines_match(&file, *candidate, &old))?;
+                Some((hunk, start, found))
+            })
+            .ok_or(hunk.old_start)?;
+        result.extend(file[position..found].iter().cloned());
+        for line in &hunk.lines {
+            match line {
+                DiffLine::Context(content) | DiffLine::Addition(content) => {
+                    result.push(content.clone())
+                }
+                DiffLine::Deletion(_) => {}
+            }
+        }
+        position = found + old_lines(&hunk).len();
+        drift = found as isize - start as isize;
+    }
+    result.extend(file[position..].iter().cloned());
+    Ok(result.concat())
+}
+
+/// The options of the `git apply` command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyOptions {
+    /// If true (`--check`), the patches are only checked and nothing is changed.
+    pub check: bool,
+    /// If true (`--index`), the files are patched from the index, and both the index and the working tree are updated.
+    pub index: bool,
+    /// If true (`-R`), the patches are undone instead of applied.
+    pub reverse: bool,
+    /// If true (`-3`), a file the patch doesn't apply to is merged from the version the patch was made for.
+    /// It implies `--index`.
+    pub three_way: bool,
+    /// Lines of context at each end of a hunk that can be ignored (`--fuzz=<n>`).
+    pub fuzz: usize,
+    /// The files with the patches. If there are none, the patch is read from the standard input.
+    pub patches: Vec<String>,
+}
+
+impl Default for ApplyOptions {
+    fn default() -> Self {
+        ApplyOptions {
+            check: false,
+            index: false,
+            reverse: false,
+            three_way: false,
+            fuzz: DEFAULT_FUZZ,
+            patches: Vec::new(),
+        }
+    }
+}
+
+impl ApplyOptions {
+    /// Parses the arguments of `git apply` (the ones after 'apply').
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or has an invalid value.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<ApplyOptions> {
+        let invalid_option = |arg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: invalid option: {}", arg),
+            )
+        };
+        let mut options = ApplyOptions::default();
+        for arg in args {
+            match arg.as_str() {
+                "--check" => options.check = true,
+                "--index" => options.index = true,
+                "-R" | "--reverse" => options.reverse = true,
+                "-3" | "--3way" => options.three_way = true,
+                _ if arg.starts_with("--fuzz=") => {
+                    options.fuzz = arg
+                        .trim_start_matches("--fuzz=")
+                        .parse()
+                        .map_err(|_| invalid_option(arg))?
+                }
+                "-" => options.patches.push(arg.clone()),
+                _ if arg.starts_with('-') => return Err(invalid_option(arg)),
+                _ => options.patches.push(arg.clone()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Returns the current content of a file the patch changes: the one in the index, or the one in
+/// the working tree if there is no index.
+fn read_current(
+    path: &str,
+    index: Option<&Index>,
+    git_dir: &str,
+    root_dir: &str,
+) -> io::Result<String> {
+    let failed = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let file_path = Path::new(root_dir).join(path);
+    let Some(index) = index else {
+        let content = fs::read(&file_path)
+            .map_err(|_| failed(format!("error: {}: No such file or directory", path)))?;
+        return Ok(String::from_utf8_lossy(&content).to_string());
+    };
+    let hash = index
+        .get_hash(path)
+        .ok_or_else(|| failed(format!("error: {}: does not exist in index", path)))?;
+    if let Ok(content) = fs::read(&file_path) {
+        if &hash_object::hash_content(&content, "blob") != hash {
+            return Err(failed(format!("error: {}: does not match index", path)));
+        }
+    }
+    Ok(String::from_utf8_lossy(&cat_file::cat_file_return_bytes(hash, git_dir)?).to_string())
+}
+
+/// Merges the changes of a patch that doesn't apply into the current content of the file,
+/// from the version of the file the patch was made for.
+///
+/// # Returns
+///
+/// Returns the merged content and true if there are conflicts, or None if that version isn't in the repository.
+///
+fn apply_three_way(
+    patch: &FilePatch,
+    current: &str,
+    git_dir: &str,
+) -> io::Result<Option<(String, bool)>> {
+    let Some(hash) = patch
+        .old_hash
+        .as_ref()
+        .and_then(|hash| utils::expand_object_hash(hash, git_dir))
+    else {
+        return Ok(None);
+    };
+    let base =
+        String::from_utf8_lossy(&cat_file::cat_file_return_bytes(&hash, git_dir)?).to_string();
+    let Ok(theirs) = apply_hunks(&base, &patch.hunks) else {
+        return Ok(None);
+    };
+    Ok(Some(diff::merge_three_way(
+        &base,
+        current,
+        &theirs,
+        ("ours", "theirs"),
+    )))
+}
+
+/// Makes a file of the working tree executable or not, following a mode of a patch or a tree entry.
+#[cfg(unix)]
+pub fn set_file_mode(path: &Path, mode: &str) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let permissions = if mode == EXECUTABLE_MODE {
+        0o755
+    } else {
+        0o644
+    };
+    fs::set_permissions(path, fs::Permissions::from_mode(permissions))
+}
+
+/// Makes a file of the working tree executable or not, following a mode of a patch or a tree entry.
+#[cfg(not(unix))]
+pub fn set_file_mode(_path: &Path, _mode: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// Applies the patches of some files to the working tree, and to the index if there is one.
+///
+/// Every file is patched from its version in the index, or in the working tree if there is no index.
+/// The hunks can be found at an offset and with up to `options.fuzz` lines of context ignored. With
+/// `options.three_way`, a file the patch doesn't apply to is merged from the version the patch was made
+/// for, and the conflicts are left in the working tree. The patches are applied all or nothing: if one
+/// of them doesn't apply, nothing is changed.
+///
+/// # Arguments
+///
+/// * `patches` - The patches of the files.
+/// * `index` - The index, which is updated but not written.
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `options` - Whether nothing is changed (`check`), the fuzz and the fallback to a three-way merge.
+/// * `output` - Where the results of the three-way merges are written.
+///
+/// # Errors
+///
+/// Returns an error if a patch doesn't apply, or a file can't be read or written.
+///
+pub fn apply_patches(
+    patches: &[FilePatch],
+    mut index: Option<&mut Index>,
+    git_dir: &str,
+    root_dir: &str,
+    options: &ApplyOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let failed = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut results = Vec::new();
+    for patch in patches {
+        if patch.binary {
+            return Err(failed(format!(
+                "error: cannot apply binary patch to '{}' without full index line",
+                patch.path()
+            )));
+        }
+        let current = match &patch.old_path {
+            Some(path) => read_current(path, index.as_deref(), git_dir, root_dir)?,
+            None => String::new(),
+        };
+        if let (None, Some(path)) = (&patch.old_path, &patch.new_path) {
+            if index.as_ref().is_some_and(|index| index.contains(path)) {
+                return Err(failed(format!("error: {}: already exists in index", path)));
+            }
+            if Path::new(root_dir).join(path).exists() {
+                return Err(failed(format!(
+                    "error: {}: already exists in working directory",
+                    path
+                )));
+            }
+        }
+        let patch_failed = |line: usize| {
+            failed(format!(
+                "error: patch failed: {}:{}\nerror: {}: patch does not apply",
+                patch.path(),
+                line,
+                patch.path()
+            ))
+        };
+        match apply_hunks_with_fuzz(&current, &patch.hunks, options.fuzz) {
+            Ok(content) => results.push((patch, content, None)),
+            Err(line) if !options.three_way => return Err(patch_failed(line)),
+            Err(line) => {
+                writeln!(output, "Falling back to three-way merge...")?;
+                let (content, conflicts) = apply_three_way(patch, &current, git_dir)?
+                    .ok_or_else(|| {
+                        failed(format!(
+                            "{}\nerror: repository lacks the necessary blob to perform 3-way merge.",
+                            patch_failed(line)
+                        ))
+                    })?;
+                results.push((patch, content, Some(conflicts)));
+            }
+        }
+    }
+    if options.check {
+        return Ok(());
+    }
+
+    for (patch, new_content, merge) in results {
+        if let Some(old_path) = &patch.old_path {
+            if patch.new_path.as_ref() != Some(old_path) {
+                if let Some(index) = index.as_mut() {
+                    index.remove_file(old_path)?;
+                }
+                let file_path = Path::new(root_dir).join(old_path);
+                if file_path.is_file() {
+                    fs::remove_file(file_path)?;
+                }
+            }
+        }
+        if let Some(new_path) = &patch.new_path {
+            if let (Some(index), false) = (index.as_mut(), merge == Some(true)) {
+                let hash = hash_object::store_bytes_array_to_file(
+                    new_content.as_bytes().to_vec(),
+                    git_dir,
+                    "blob",
+                )?;
+                index.add_file(new_path, &hash)?;
+            }
+            let file_path = Path::new(root_dir).join(new_path);
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&file_path, new_content)?;
+            if let Some(mode) = &patch.new_mode {
+                if patch.old_mode.as_ref() != Some(mode) {
+                    set_file_mode(&file_path, mode)?;
+                }
+            }
+        }
+        match merge {
+            Some(true) => {
+                writeln!(
+                    output,
+                    "Applied patch to '{}' with conflicts.",
+                    patch.path()
+                )?;
+                writeln!(output, "U {}", patch.path())?;
+            }
+            Some(false) => writeln!(output, "Applied patch to '{}' cleanly.", patch.path())?,
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Applies the patches of some files to the index and the working tree, without fuzz.
+///
+/// Every file is patched from its version in the index. The patches are applied all or nothing:
+/// if one of them doesn't apply, nothing is changed.
+///
+/// # Arguments
+///
+/// * `patches` - The patches of the files.
+/// * `index` - The index, which is updated but not written.
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+///
+/// # Errors
+///
+/// Returns an error if a patch doesn't apply, or a file can't be read or written.
+///
+pub fn apply_to_index(
+    patches: &[FilePatch],
+    index: &mut Index,
+    git_dir: &str,
+    root_dir: &str,
+) -> io::Result<()> {
+    let options = ApplyOptions {
+        index: true,
+        fuzz: 0,
+        ..Default::default()
+    };
+    apply_patches(
+        patches,
+        Some(index),
+        git_dir,
+        root_dir,
+        &options,
+        &mut io::sink(),
+    )
+}
+
+/// Applies a patch to the files of the working tree, or of the index and the working tree with `--index`.
+///
+/// The patch can have the unified diffs of many files, as `git diff` or `diff -u` write them, and it is
+/// read from the files given or from the standard input. `--check` only tells whether it applies, `-R`
+/// undoes it and `-3` merges the files it doesn't apply to. If any file can't be patched, nothing is changed.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `args` - The arguments of the command, the ones after 'apply'.
+/// * `output` - Where the results of the three-way merges are written.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, the patch can't be read or parsed, or it doesn't apply.
+///
+pub fn git_apply(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    log_apply(git_dir, args)?;
+    let options = ApplyOptions::parse(args)?;
+    let mut text = String::new();
+    if options.patches.is_empty() {
+        io::stdin().read_to_string(&mut text)?;
+    }
+    for path in &options.patches {
+        if path == "-" {
+            io::stdin().read_to_string(&mut text)?;
+        } else {
+            text.push_str(&fs::read_to_string(path)?);
+        }
+    }
+    let mut patches = parse_patch(&text)?;
+    if options.reverse {
+        patches = patches.iter().map(FilePatch::reverse).collect();
+    }
+    if !options.index && !options.three_way {
+        return apply_patches(&patches, None, git_dir, root_dir, &options, output);
+    }
+    let index_path = utils::get_index_file_path(git_dir);
+    let mut index = Index::load(&index_path, git_dir, "")?;
+    apply_patches(
+        &patches,
+        Some(&mut index),
+        git_dir,
+        root_dir,
+        &options,
+        output,
+    )?;
+    if !options.check {
+        index.write_file()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add;
+
+    #[test]
+    fn test_parse_and_apply_hunks_with_offset() -> io::Result<()> {
+        let patch = "\
+diff --git a/src/a.txt b/src/a.txt
+index 1111111..2222222 100644
+--- a/src/a.txt
++++ b/src/a.txt
+@@ -2,3 +2,3 @@ uno
+ dos
+-tres
++TRES
+ cuatro
+diff --git a/nuevo.txt b/nuevo.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/nuevo.txt
+@@ -0,0 +1 @@
++hola
+\\ No newline at end of file
+";
+        let patches = parse_patch(patch)?;
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].path(), "src/a.txt");
+        assert_eq!(patches[1].old_path, None);
+
+        let moved = "cero\ncero\nuno\ndos\ntres\ncuatro\n";
+        assert_eq!(
+            apply_hunks(moved, &patches[0].hunks),
+            Ok("cero\ncero\nuno\ndos\nTRES\ncuatro\n".to_string())
+        );
+        assert_eq!(apply_hunks("", &patches[1].hunks), Ok("hola".to_string()));
+        assert_eq!(apply_hunks("uno\ndos\ncuatro\n", &patches[0].hunks), Err(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_blank_context_lines_without_their_space() -> io::Result<()> {
+        let patch = "\
+diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,3 @@
+ uno
+
+-tres
++TRES
+";
+        let patches = parse_patch(patch)?;
+        assert_eq!(
+            patches[0].hunks[0].lines[1],
+            DiffLine::Context("\n".to_string())
+        );
+        assert_eq!(
+            apply_hunks("uno\n\ntres\n", &patches[0].hunks),
+            Ok("uno\n\nTRES\n".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reverse_fuzz_and_three_way_merge() -> io::Result<()> {
+        let patch = "\
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+index 1111111..2222222
+--- a/run.sh
++++ b/run.sh
+@@ -1,5 +1,5 @@
+ uno
+ dos
+-tres
++TRES
+ cuatro
+ cinco
+";
+        let patches = parse_patch(patch)?;
+        assert_eq!(patches[0].old_mode.as_deref(), Some("100644"));
+        assert_eq!(patches[0].new_mode.as_deref(), Some("100755"));
+        assert_eq!(patches[0].old_hash.as_deref(), Some("1111111"));
+
+        let changed_context = "UNO\ndos\ntres\ncuatro\nCINCO\n";
+        assert_eq!(apply_hunks(changed_context, &patches[0].hunks), Err(1));
+        assert_eq!(
+            apply_hunks_with_fuzz(changed_context, &patches[0].hunks, 1),
+            Ok("UNO\ndos\nTRES\ncuatro\nCINCO\n".to_string())
+        );
+
+        let reversed = patches[0].reverse();
+        assert_eq!(reversed.new_mode.as_deref(), Some("100644"));
+        assert_eq!(
+            apply_hunks("uno\ndos\nTRES\ncuatro\ncinco\n", &reversed.hunks),
+            Ok("uno\ndos\ntres\ncuatro\ncinco\n".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_apply_check_index_reverse_and_three_way() -> io::Result<()> {
+        let root_dir = "tests/apply_cli";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        fs::write(format!("{}/index", git_dir), "")?;
+        let path = format!("{}/a.txt", root_dir);
+        let index_path = format!("{}/index", git_dir);
+        let content = "uno\ndos\ntres\ncuatro\ncinco\n";
+        fs::write(&path, content)?;
+        add::add(&path, &index_path, &git_dir, "", None)?;
+        let base_hash = hash_object::hash_content(content.as_bytes(), "blob");
+        let patch = format!(
+            "diff --git a/{0} b/{0}\nindex {1}..2222222 100644\n--- a/{0}\n+++ b/{0}\n@@ -3,3 +3,3 @@\n tres\n cuatro\n-cinco\n+CINCO\n",
+            path,
+            &base_hash[..7]
+        );
+        let patch_path = format!("{}/cambio.patch", root_dir);
+        fs::write(&patch_path, &patch)?;
+        let broken_path = format!("{}/roto.patch", root_dir);
+        fs::write(
+            &broken_path,
+            format!(
+                "{}diff --git a/{1} b/{1}\n--- a/{1}\n+++ b/{1}\n@@ -1 +1 @@\n-nada\n+algo\n",
+                patch, path
+            ),
+        )?;
+        let run = |args: &[&str]| -> io::Result<String> {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let mut output: Vec<u8> = Vec::new();
+            git_apply(&git_dir, "", &args, &mut output)?;
+            Ok(String::from_utf8_lossy(&output).to_string())
+        };
+
+        let checked = run(&["--check", &patch_path]);
+        let content_after_check = fs::read_to_string(&path);
+        let broken = run(&[&broken_path]);
+        let content_after_broken = fs::read_to_string(&path);
+        let applied = run(&["--index", &patch_path]);
+        let content_after_apply = fs::read_to_string(&path);
+        let index_hash = Index::load(&index_path, &git_dir, "")?
+            .get_hash(&path)
+            .cloned();
+        let reversed = run(&["-R", &patch_path]);
+        let content_after_reverse = fs::read_to_string(&path);
+
+        fs::write(&path, "uno\ndos\ntres\ncuatro\n5\n")?;
+        add::add(&path, &index_path, &git_dir, "", None)?;
+        let merged = run(&["-3", &patch_path]);
+        let content_after_merge = fs::read_to_string(&path);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(checked?, "");
+        assert_eq!(content_after_check?, content);
+        assert!(broken.is_err());
+        assert_eq!(content_after_broken?, content);
+        assert_eq!(applied?, "");
+        assert_eq!(content_after_apply?, "uno\ndos\ntres\ncuatro\nCINCO\n");
+        assert_eq!(
+            index_hash,
+            Some(hash_object::hash_content(
+                b"uno\ndos\ntres\ncuatro\nCINCO\n",
+                "blob"
+            ))
+        );
+        assert_eq!(reversed?, "");
+        assert_eq!(content_after_reverse?, content);
+        assert_eq!(
+            merged?,
+            format!(
+                "Falling back to three-way merge...\nApplied patch to '{0}' with conflicts.\nU {0}\n",
+                path
+            )
+        );
+        assert_eq!(
+            content_after_merge?,
+            "uno\ndos\ntres\ncuatro\n<<<<<<< ours\n5\n=======\nCINCO\n>>>>>>> theirs\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,599 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+use chrono::{Datelike, TimeZone, Timelike, Utc};
+use flate2::{
+    write::{DeflateEncoder, GzEncoder},
+    Compression, Crc,
+};
+
+use crate::{
+    attributes::Attributes, cat_file, configuration::LOGGER_COMMANDS_FILE, diff, log::Log,
+    logger::Logger, utils,
+};
+
+/// Mode of the tree entries that are trees, as `cat_tree` returns it.
+const TREE_MODE: &str = "40000";
+/// Mode of the tree entries that are symbolic links.
+const SYMLINK_MODE: &str = "120000";
+/// Mode of the tree entries that are executable files.
+const EXECUTABLE_MODE: &str = "100755";
+/// Size of the blocks of a tar archive.
+const TAR_BLOCK: usize = 512;
+
+/// Logs the 'git archive' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_archive(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git archive': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The formats an archive can be written in.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    #[default]
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Returns the format with the given name: `tar`, `tar.gz` (or `tgz`) or `zip`.
+    pub fn parse(name: &str) -> Option<ArchiveFormat> {
+        match name {
+            "tar" => Some(ArchiveFormat::Tar),
+            "tar.gz" | "tgz" => Some(ArchiveFormat::TarGz),
+            "zip" => Some(ArchiveFormat::Zip),
+            _ => None,
+        }
+    }
+
+    /// Returns the format that matches the extension of the file, if any.
+    fn from_file_name(name: &str) -> Option<ArchiveFormat> {
+        [".tar.gz", ".tgz", ".tar", ".zip"]
+            .iter()
+            .find(|extension| name.ends_with(*extension))
+            .and_then(|extension| ArchiveFormat::parse(&extension[1..]))
+    }
+}
+
+/// The options of the `git archive` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ArchiveOptions {
+    pub format: ArchiveFormat,
+    /// Prepended to the path of every file, for example `project-1.0/`.
+    pub prefix: String,
+    /// The file the archive is written to. If there is none, it's written to the output.
+    pub output: Option<String>,
+    /// The commit or tag whose tree is archived.
+    pub revision: String,
+    /// Only the files inside these paths are archived. If it's empty, every file is.
+    pub paths: Vec<String>,
+}
+
+impl ArchiveOptions {
+    /// Parses the arguments of `git archive` (the ones after 'archive').
+    /// If there is no `--format`, it's taken from the extension of the `--output` file, or tar by default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown, the format is not supported or there is no revision.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<ArchiveOptions> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let mut options = ArchiveOptions::default();
+        let mut format = None;
+        let mut revision = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--format=") {
+                format =
+                    Some(ArchiveFormat::parse(name).ok_or_else(|| {
+                        invalid(format!("fatal: Unknown archive format '{}'", name))
+                    })?);
+            } else if let Some(prefix) = arg.strip_prefix("--prefix=") {
+                options.prefix = prefix.to_string();
+            } else if let Some(output) = arg.strip_prefix("--output=") {
+                options.output = Some(output.to_string());
+            } else if arg == "-o" {
+                options.output = args.next().cloned();
+            } else if arg == "--" {
+                options.paths.extend(args.by_ref().cloned());
+            } else if arg.starts_with('-') {
+                return Err(invalid(format!("error: unknown option `{}'", arg)));
+            } else if revision.is_none() {
+                revision = Some(arg.clone());
+            } else {
+                options.paths.push(arg.clone());
+            }
+        }
+        options.format = format
+            .or_else(|| {
+                options
+                    .output
+                    .as_deref()
+                    .and_then(ArchiveFormat::from_file_name)
+            })
+            .unwrap_or_default();
+        options.revision = revision.ok_or_else(|| {
+            invalid(
+                "usage: git archive [--format=<fmt>] [--prefix=<prefix>/] <tree-ish> [<path>...]"
+                    .to_string(),
+            )
+        })?;
+        Ok(options)
+    }
+}
+
+/// A file of the archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// The path of the file inside the archive, with the prefix.
+    pub path: String,
+    /// The mode of the tree entry, such as `100644`, `100755` or `120000`.
+    pub mode: String,
+    /// The content of the file, or the target of the link.
+    pub content: Vec<u8>,
+}
+
+impl ArchiveEntry {
+    fn is_symlink(&self) -> bool {
+        self.mode == SYMLINK_MODE
+    }
+
+    /// Returns the permissions of the file in the archive.
+    fn permissions(&self) -> u32 {
+        match self.mode.as_str() {
+            EXECUTABLE_MODE => 0o755,
+            SYMLINK_MODE => 0o777,
+            _ => 0o644,
+        }
+    }
+}
+
+/// Adds to `entries` the files of a tree, walking its subtrees.
+/// The files and folders with the `export-ignore` attribute are left out.
+fn collect_entries(
+    tree_hash: &str,
+    path: &str,
+    git_dir: &str,
+    attributes: &Attributes,
+    entries: &mut Vec<(String, String, String)>,
+) -> io::Result<()> {
+    for (mode, name, hash) in cat_file::cat_tree(tree_hash, git_dir)? {
+        let entry_path = if path.is_empty() {
+            name
+        } else {
+            format!("{}/{}", path, name)
+        };
+        if attributes.get(&entry_path).export_ignore() {
+            continue;
+        }
+        if mode.trim() == TREE_MODE {
+            collect_entries(&hash, &entry_path, git_dir, attributes, entries)?;
+        } else {
+            entries.push((entry_path, mode.trim().to_string(), hash));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the files of the tree of the revision that go in the archive, sorted by path.
+fn archive_entries(
+    git_dir: &str,
+    tree_hash: &str,
+    options: &ArchiveOptions,
+) -> io::Result<Vec<ArchiveEntry>> {
+    let attributes = Attributes::load(git_dir);
+    let mut files = Vec::new();
+    collect_entries(tree_hash, "", git_dir, &attributes, &mut files)?;
+    files.sort();
+    files
+        .into_iter()
+        .filter(|(path, _, _)| diff::path_is_selected(path, &options.paths))
+        .map(|(path, mode, hash)| {
+            Ok(ArchiveEntry {
+                path: format!("{}{}", options.prefix, path),
+                mode,
+                content: cat_file::cat_file_return_bytes(&hash, git_dir)?,
+            })
+        })
+        .collect()
+}
+
+/// Writes a number in octal, padded with zeros and ended with a NUL, in a field of a tar header.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+/// Splits a path in the prefix and name fields of a ustar header, if it fits in them.
+fn split_tar_path(path: &str) -> Option<(&str, &str)> {
+    if path.len() <= 100 {
+        return Some(("", path));
+    }
+    path.match_indices('/')
+        .map(|(position, _)| position)
+        .find(|position| *position <= 155 && path.len() - position - 1 <= 100)
+        .map(|split| (&path[..split], &path[split + 1..]))
+}
+
+/// Returns the 512 bytes of the ustar header of an entry. Paths and links that don't fit in the
+/// header are cut, so they must also be given in a pax extended header before it.
+fn tar_header(path: &str, mode: u32, size: usize, mtime: i64, kind: u8, link: &[u8]) -> Vec<u8> {
+    let (prefix, name) = split_tar_path(path).unwrap_or(("", path));
+    let name = &name.as_bytes()[..name.len().min(100)];
+    let link = &link[..link.len().min(100)];
+    let mut header = vec![0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name);
+    write_octal(&mut header[100..108], mode as u64);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size as u64);
+    write_octal(&mut header[136..148], mtime.max(0) as u64);
+    header[156] = kind;
+    header[157..157 + link.len()].copy_from_slice(link);
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[265..269].copy_from_slice(b"root");
+    header[297..301].copy_from_slice(b"root");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+    let digits = format!("{:06o}\0 ", checksum);
+    header[148..156].copy_from_slice(digits.as_bytes());
+    header
+}
+
+/// Returns a record of a pax header: `<length> <key>=<value>\n`, where the length counts the whole record.
+fn pax_record(key: &str, value: &[u8]) -> Vec<u8> {
+    let size = key.len() + value.len() + 3;
+    let mut length = size;
+    while length != size + length.to_string().len() {
+        length = size + length.to_string().len();
+    }
+    let mut record = format!("{} {}=", length, key).into_bytes();
+    record.extend_from_slice(value);
+    record.push(b'\n');
+    record
+}
+
+/// Appends the data of an entry to a tar archive, padded to a whole block.
+fn push_tar_data(archive: &mut Vec<u8>, data: &[u8]) {
+    archive.extend_from_slice(data);
+    let padding = (TAR_BLOCK - data.len() % TAR_BLOCK) % TAR_BLOCK;
+    archive.extend(std::iter::repeat_n(0, padding));
+}
+
+/// Appends a file or a link to a tar archive, preceded by a pax extended header if its path or
+/// its link don't fit in the ustar header.
+fn push_tar_entry(archive: &mut Vec<u8>, entry: &ArchiveEntry, mtime: i64) {
+    let (kind, link, data): (u8, &[u8], &[u8]) = if entry.is_symlink() {
+        (b'2', &entry.content, &[])
+    } else {
+        (b'0', &[], &entry.content)
+    };
+    let mut extended = Vec::new();
+    if split_tar_path(&entry.path).is_none() {
+        extended.extend(pax_record("path", entry.path.as_bytes()));
+    }
+    if link.len() > 100 {
+        extended.extend(pax_record("linkpath", link));
+    }
+    if !extended.is_empty() {
+        archive.extend(tar_header(
+            &entry.path,
+            0o644,
+            extended.len(),
+            mtime,
+            b'x',
+            &[],
+        ));
+        push_tar_data(archive, &extended);
+    }
+    archive.extend(tar_header(
+        &entry.path,
+        entry.permissions(),
+        data.len(),
+        mtime,
+        kind,
+        link,
+    ));
+    push_tar_data(archive, data);
+}
+
+/// Returns the entries as a tar archive. As git does, it starts with a pax global header that
+/// keeps the hash of the commit.
+pub fn tar_archive(entries: &[ArchiveEntry], commit: &str, mtime: i64) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let comment = pax_record("comment", commit.as_bytes());
+    archive.extend(tar_header(
+        "pax_global_header",
+        0o666,
+        comment.len(),
+        mtime,
+        b'g',
+        &[],
+    ));
+    push_tar_data(&mut archive, &comment);
+    for entry in entries {
+        push_tar_entry(&mut archive, entry, mtime);
+    }
+    archive.extend(std::iter::repeat_n(0, 2 * TAR_BLOCK));
+    archive
+}
+
+/// Returns the time and date of the timestamp in the MS-DOS format of zip archives.
+fn dos_date_time(mtime: i64) -> (u16, u16) {
+    match Utc.timestamp_opt(mtime, 0).single() {
+        Some(date) if date.year() >= 1980 => (
+            ((date.hour() << 11) | (date.minute() << 5) | (date.second() / 2)) as u16,
+            (((date.year() - 1980) as u32) << 9 | (date.month() << 5) | date.day()) as u16,
+        ),
+        _ => (0, (1 << 5) | 1),
+    }
+}
+
+/// Returns the entries as a zip archive, with their content compressed with deflate.
+/// The hash of the commit is kept in the comment of the archive.
+pub fn zip_archive(entries: &[ArchiveEntry], commit: &str, mtime: i64) -> io::Result<Vec<u8>> {
+    let (time, date) = dos_date_time(mtime);
+    let mut archive: Vec<u8> = Vec::new();
+    let mut central_directory: Vec<u8> = Vec::new();
+    for entry in entries {
+        let mut crc = Crc::new();
+        crc.update(&entry.content);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&entry.content)?;
+        let compressed = encoder.finish()?;
+        let (method, data): (u16, &[u8]) = if compressed.len() < entry.content.len() {
+            (8, &compressed)
+        } else {
+            (0, &entry.content)
+        };
+        let offset = archive.len() as u32;
+        let mut fields: Vec<u8> = Vec::new();
+        fields.extend(20u16.to_le_bytes());
+        fields.extend(0x0800u16.to_le_bytes());
+        fields.extend(method.to_le_bytes());
+        fields.extend(time.to_le_bytes());
+        fields.extend(date.to_le_bytes());
+        fields.extend(crc.sum().to_le_bytes());
+        fields.extend((data.len() as u32).to_le_bytes());
+        fields.extend((entry.content.len() as u32).to_le_bytes());
+        fields.extend((entry.path.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+
+        archive.extend(0x04034b50u32.to_le_bytes());
+        archive.extend(&fields);
+        archive.extend(entry.path.as_bytes());
+        archive.extend(data);
+
+        let file_type = if entry.is_symlink() {
+            0o120000
+        } else {
+            0o100000
+        };
+        central_directory.extend(0x02014b50u32.to_le_bytes());
+        central_directory.extend((3u16 << 8 | 20).to_le_bytes());
+        central_directory.extend(&fields);
+        central_directory.extend(0u16.to_le_bytes());
+        central_directory.extend(0u16.to_le_bytes());
+        central_directory.extend(0u16.to_le_bytes());
+        central_directory.extend(((file_type | entry.permissions()) << 16).to_le_bytes());
+        central_directory.extend(offset.to_le_bytes());
+        central_directory.extend(entry.path.as_bytes());
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend(&central_directory);
+    archive.extend(0x06054b50u32.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend((entries.len() as u16).to_le_bytes());
+    archive.extend((entries.len() as u16).to_le_bytes());
+    archive.extend((central_directory.len() as u32).to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    archive.extend((commit.len() as u16).to_le_bytes());
+    archive.extend(commit.as_bytes());
+    Ok(archive)
+}
+
+/// Writes the archive of the tree of a revision, with the commit time as the time of every file.
+///
+/// # Errors
+///
+/// Returns an error if the revision can't be resolved, an object can't be read or a path doesn't fit in the format.
+///
+pub fn write_archive(
+    git_dir: &str,
+    options: &ArchiveOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let commit = utils::resolve_revision(&options.revision, git_dir)?;
+    let log = Log::load(Some(&commit), git_dir)?;
+    let mtime = log.committer().time;
+    let entries = archive_entries(git_dir, log.tree_hash(), options)?;
+    match options.format {
+        ArchiveFormat::Tar => output.write_all(&tar_archive(&entries, &commit, mtime)),
+        ArchiveFormat::TarGz => {
+            let mut encoder = GzEncoder::new(output, Compression::default());
+            encoder.write_all(&tar_archive(&entries, &commit, mtime))?;
+            encoder.finish()?;
+            Ok(())
+        }
+        ArchiveFormat::Zip => output.write_all(&zip_archive(&entries, &commit, mtime)?),
+    }
+}
+
+/// Creates an archive with the files of a revision, without the git directory.
+///
+/// `--format=<tar|tar.gz|zip>` chooses the format, `--prefix=<dir>/` puts every file inside a folder
+/// and `-o <file>` writes the archive to a file instead of the output. Paths after the revision limit
+/// the files archived, and the ones with the `export-ignore` attribute are always left out.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments of the command, the ones after 'archive'.
+/// * `output` - Where the archive is written if there is no `-o`.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, the revision can't be resolved or the archive can't be written.
+///
+pub fn git_archive(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    log_archive(git_dir, args)?;
+    let options = ArchiveOptions::parse(args)?;
+    match &options.output {
+        Some(path) => write_archive(git_dir, &options, &mut File::create(path)?),
+        None => write_archive(git_dir, &options, output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commit,
+        test_utils::{add_file, create_repo},
+    };
+    use flate2::read::{DeflateDecoder, GzDecoder};
+    use std::{fs, io::Read};
+
+    fn run_archive(git_dir: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_archive(git_dir, &args, &mut output)?;
+        Ok(output)
+    }
+
+    /// Returns the (name, mode, mtime, content) of the files of a tar archive.
+    fn read_tar(archive: &[u8]) -> Vec<(String, String, String, String)> {
+        let field = |block: &[u8]| {
+            String::from_utf8_lossy(block)
+                .trim_end_matches(['\0', ' '])
+                .to_string()
+        };
+        let mut files = Vec::new();
+        let mut position = 0;
+        while archive[position] != 0 {
+            let header = &archive[position..position + TAR_BLOCK];
+            let size = usize::from_str_radix(&field(&header[124..136]), 8).unwrap();
+            let content = &archive[position + TAR_BLOCK..position + TAR_BLOCK + size];
+            if header[156] == b'0' {
+                files.push((
+                    field(&header[..100]),
+                    field(&header[100..108]),
+                    field(&header[136..148]),
+                    String::from_utf8_lossy(content).to_string(),
+                ));
+            }
+            position += TAR_BLOCK + size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+        }
+        files
+    }
+
+    /// Creates a repository with `src/main.rs` and an export-ignored `secret.txt` committed, and
+    /// returns its git directory and the commit.
+    fn create_project(root_dir: &str) -> io::Result<(String, String)> {
+        let git_dir = create_repo(root_dir)?;
+        fs::create_dir_all(format!("{}/src", root_dir))?;
+        fs::write(
+            format!("{}/.gitattributes", root_dir),
+            "secret.txt export-ignore\n",
+        )?;
+        add_file(
+            &git_dir,
+            &format!("{}/src/main.rs", root_dir),
+            "fn main() {}\n",
+        )?;
+        add_file(&git_dir, &format!("{}/secret.txt", root_dir), "clave\n")?;
+        let commit = commit::new_commit(&git_dir, "Primero", "")?;
+        Ok((git_dir, commit))
+    }
+
+    #[test]
+    fn test_tar_archive_of_a_revision() -> io::Result<()> {
+        let root_dir = "tests/archive_tar";
+        let (git_dir, commit) = create_project(root_dir)?;
+        let mtime = Log::load(Some(&commit), &git_dir)?.committer().time;
+
+        let tar = run_archive(&git_dir, &["--prefix=messi/", "HEAD"]);
+        fs::remove_dir_all(root_dir)?;
+
+        let tar = tar?;
+        let main_path = format!("messi/{}/src/main.rs", root_dir);
+        assert_eq!(
+            read_tar(&tar),
+            vec![(
+                main_path,
+                "0000644".to_string(),
+                format!("{:011o}", mtime),
+                "fn main() {}\n".to_string()
+            )]
+        );
+        assert!(String::from_utf8_lossy(&tar).contains(&format!("comment={}", commit)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tar_gz_archive_is_the_compressed_tar() -> io::Result<()> {
+        let root_dir = "tests/archive_tar_gz";
+        let (git_dir, _) = create_project(root_dir)?;
+
+        let tar = run_archive(&git_dir, &["--prefix=messi/", "HEAD"]);
+        let tar_gz = run_archive(&git_dir, &["--format=tar.gz", "--prefix=messi/", "HEAD"]);
+        fs::remove_dir_all(root_dir)?;
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(tar_gz?.as_slice()).read_to_end(&mut decoded)?;
+        assert_eq!(decoded, tar?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_archive_of_a_revision() -> io::Result<()> {
+        let root_dir = "tests/archive_zip";
+        let (git_dir, commit) = create_project(root_dir)?;
+
+        let zip = run_archive(&git_dir, &["--format=zip", "master"]);
+        fs::remove_dir_all(root_dir)?;
+
+        let zip = zip?;
+        let name = format!("{}/src/main.rs", root_dir);
+        assert_eq!(&zip[..4], &0x04034b50u32.to_le_bytes());
+        assert_eq!(&zip[30..30 + name.len()], name.as_bytes());
+        let compressed_size = u32::from_le_bytes([zip[18], zip[19], zip[20], zip[21]]) as usize;
+        let data = &zip[30 + name.len()..30 + name.len() + compressed_size];
+        let mut content = String::new();
+        match u16::from_le_bytes([zip[8], zip[9]]) {
+            8 => DeflateDecoder::new(data).read_to_string(&mut content)?,
+            _ => data.as_ref().read_to_string(&mut content)?,
+        };
+        assert_eq!(content, "fn main() {}\n");
+        assert!(!String::from_utf8_lossy(&zip).contains("secret.txt"));
+        assert!(zip.ends_with(commit.as_bytes()));
+        Ok(())
+    }
+}
@@ -0,0 +1,404 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    binary::{self, BinaryPatterns},
+    cat_file, utils,
+};
+
+/// Name of the attributes file that can be placed in any directory of the working tree.
+pub const ATTRIBUTES_FILE: &str = ".gitattributes";
+/// Path, inside the git directory, of the attributes file that is not versioned.
+const INFO_ATTRIBUTES_FILE: &str = "info/attributes";
+
+/// The state an attribute can have for a path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeState {
+    /// The attribute was listed alone, for example `text`.
+    Set,
+    /// The attribute was listed with a leading '-', for example `-text`.
+    Unset,
+    /// The attribute was given a value, for example `eol=crlf`.
+    Value(String),
+}
+
+/// The line ending a text file has in the working tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Eol {
+    Lf,
+    Crlf,
+}
+
+/// How the changes of both sides are combined when a file was modified in both of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeDriver {
+    /// The files are merged line by line, leaving conflict markers where they differ.
+    Text,
+    /// Our version is kept and the file is reported as conflicted.
+    Binary,
+    /// Our version is kept and the file is not reported as conflicted.
+    Ours,
+    /// The lines of both versions are kept, without conflict markers.
+    Union,
+}
+
+/// A line of an attributes file: a pattern and the attributes given to the paths that match it.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    /// The directory of the attributes file, relative to the working tree. The pattern is relative to it.
+    base: String,
+    pattern: String,
+    states: Vec<(String, Option<AttributeState>)>,
+}
+
+/// The attributes that apply to a single path.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileAttributes {
+    states: HashMap<String, AttributeState>,
+}
+
+impl FileAttributes {
+    /// Returns the state of the attribute, or None if it is unspecified for the path.
+    pub fn state(&self, name: &str) -> Option<&AttributeState> {
+        self.states.get(name)
+    }
+
+    /// Returns true if the line endings of the file must be normalized.
+    /// With `text=auto`, only contents that don't look binary are normalized.
+    /// Giving an `eol` is the same as setting `text`, unless `text` is unset.
+    pub fn is_text(&self, content: &[u8]) -> bool {
+        match self.state("text") {
+            Some(AttributeState::Set) => true,
+            Some(AttributeState::Unset) => false,
+            Some(AttributeState::Value(value)) if value == "auto" => !binary::is_binary(content),
+            _ => self.eol().is_some(),
+        }
+    }
+
+    /// Returns the line ending the file must have in the working tree, if one was given.
+    pub fn eol(&self) -> Option<Eol> {
+        match self.state("eol") {
+            Some(AttributeState::Value(value)) if value == "lf" => Some(Eol::Lf),
+            Some(AttributeState::Value(value)) if value == "crlf" => Some(Eol::Crlf),
+            _ => None,
+        }
+    }
+
+    /// Returns Some(true) if the file must be diffed as binary (`-diff` or `binary`),
+    /// Some(false) if it must be diffed as text (`diff`) and None if its content decides.
+    pub fn is_binary(&self) -> Option<bool> {
+        match self.state("diff") {
+            Some(AttributeState::Unset) => Some(true),
+            Some(AttributeState::Set) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns the merge driver given with `merge`, `-merge` or `merge=<driver>`, if any.
+    pub fn merge_driver(&self) -> Option<MergeDriver> {
+        match self.state("merge")? {
+            AttributeState::Set => Some(MergeDriver::Text),
+            AttributeState::Unset => Some(MergeDriver::Binary),
+            AttributeState::Value(value) => match value.as_str() {
+                "text" => Some(MergeDriver::Text),
+                "binary" => Some(MergeDriver::Binary),
+                "ours" => Some(MergeDriver::Ours),
+                "union" => Some(MergeDriver::Union),
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns true if the file must be left out of archives.
+    pub fn export_ignore(&self) -> bool {
+        self.state("export-ignore") == Some(&AttributeState::Set)
+    }
+}
+
+/// The attributes of the paths of a repository, read from its attributes files.
+///
+/// As with ignore files, the `.gitattributes` of a directory applies to the paths under it, and the
+/// deepest file has precedence over the ones above it. Within a file, the last matching line wins.
+/// The patterns in the `info/attributes` file of the git directory have precedence over all of them.
+///
+/// ```text
+/// *.sh      text eol=lf
+/// *.bat     text eol=crlf
+/// *.png     binary
+/// CHANGELOG merge=union
+/// tests/**  export-ignore
+/// ```
+///
+/// The paths listed as binary in the configuration (see `BinaryPatterns`) are also taken into account
+/// when deciding if a file is binary.
+#[derive(Debug, Default, Clone)]
+pub struct Attributes {
+    working_dir: String,
+    root_rules: Vec<Rule>,
+    info_rules: Vec<Rule>,
+    binary: BinaryPatterns,
+}
+
+impl Attributes {
+    /// Loads the attributes of the repository whose git directory is `git_dir`.
+    /// The working tree is the parent of the git directory. Missing files are treated as empty.
+    pub fn load(git_dir: &str) -> Self {
+        let working_dir = Path::new(git_dir)
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let root_rules = read_rules(&Path::new(&working_dir).join(ATTRIBUTES_FILE), "");
+        let info_rules = read_rules(&Path::new(git_dir).join(INFO_ATTRIBUTES_FILE), "");
+        Self {
+            working_dir,
+            root_rules,
+            info_rules,
+            binary: BinaryPatterns::load(git_dir),
+        }
+    }
+
+    /// Creates the attributes from the content of a single attributes file at the root of the working tree.
+    pub fn from_content(content: &str) -> Self {
+        Self {
+            root_rules: parse_rules(content, ""),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the path relative to the working tree. Paths given relative to the current
+    /// directory, with the working tree as prefix, are accepted too.
+    fn relative_path<'a>(&self, path: &'a str) -> &'a str {
+        let path = path.trim_start_matches("./");
+        if self.working_dir.is_empty() {
+            return path;
+        }
+        path.strip_prefix(&self.working_dir)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .unwrap_or(path)
+    }
+
+    /// Returns the attributes that apply to the path.
+    pub fn get(&self, path: &str) -> FileAttributes {
+        let path = self.relative_path(path);
+        let mut nested_rules = Vec::new();
+        let mut base = String::new();
+        let mut components: Vec<&str> = path.split('/').collect();
+        components.pop();
+        for directory in components {
+            base = if base.is_empty() {
+                directory.to_string()
+            } else {
+                format!("{}/{}", base, directory)
+            };
+            let file = Path::new(&self.working_dir)
+                .join(&base)
+                .join(ATTRIBUTES_FILE);
+            nested_rules.extend(read_rules(&file, &base));
+        }
+
+        let mut attributes = FileAttributes::default();
+        let rules = self
+            .root_rules
+            .iter()
+            .chain(nested_rules.iter())
+            .chain(self.info_rules.iter());
+        for rule in rules {
+            let relative = match rule.base.as_str() {
+                "" => path,
+                base => match path
+                    .strip_prefix(base)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                {
+                    Some(relative) => relative,
+                    None => continue,
+                },
+            };
+            if !utils::matches_pattern(&rule.pattern, relative) {
+                continue;
+            }
+            for (name, state) in &rule.states {
+                match state {
+                    Some(state) => attributes.states.insert(name.clone(), state.clone()),
+                    None => attributes.states.remove(name),
+                };
+            }
+        }
+        attributes
+    }
+
+    /// Converts the content of a file of the working tree into the content to store in the repository.
+    /// The line endings of text files are normalized to LF.
+    pub fn clean(&self, path: &str, content: Vec<u8>) -> Vec<u8> {
+        if !self.get(path).is_text(&content) || !content.contains(&b'\r') {
+            return content;
+        }
+        let mut converted = Vec::with_capacity(content.len());
+        for (i, byte) in content.iter().enumerate() {
+            if *byte != b'\r' || content.get(i + 1) != Some(&b'\n') {
+                converted.push(*byte);
+            }
+        }
+        converted
+    }
+
+    /// Converts the content stored in the repository into the content to write in the working tree.
+    /// Text files with `eol=crlf` get CRLF line endings.
+    pub fn smudge(&self, path: &str, content: Vec<u8>) -> Vec<u8> {
+        let attributes = self.get(path);
+        if !attributes.is_text(&content) || attributes.eol() != Some(Eol::Crlf) {
+            return content;
+        }
+        let mut converted = Vec::with_capacity(content.len());
+        for (i, byte) in content.iter().enumerate() {
+            if *byte == b'\n' && (i == 0 || content[i - 1] != b'\r') {
+                converted.push(b'\r');
+            }
+            converted.push(*byte);
+        }
+        converted
+    }
+
+    /// Returns true if the file must be treated as binary. The `diff` attribute decides first, then
+    /// the binary patterns of the configuration and last the content.
+    pub fn is_binary_file(&self, path: &str, content: &[u8]) -> bool {
+        self.get(path)
+            .is_binary()
+            .unwrap_or_else(|| self.binary.is_binary_file(path, content))
+    }
+
+    /// Same as `is_binary_file`, reading the content from the blob `hash` only if the attributes don't decide.
+    pub fn is_binary_object(&self, path: &str, hash: &str, git_dir: &str) -> bool {
+        match self.get(path).is_binary() {
+            Some(binary) => binary,
+            None => {
+                self.binary.matches(path)
+                    || cat_file::cat_file_return_bytes(hash, git_dir)
+                        .map(|content| binary::is_binary(&content))
+                        .unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// Reads the rules of an attributes file. If it can't be read, there are no rules.
+fn read_rules(path: &Path, base: &str) -> Vec<Rule> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_rules(&content, base),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Parses the lines of an attributes file. Empty lines and comments are skipped, as well as
+/// patterns for directories, since attributes only apply to files.
+///
+/// `binary` is expanded to `-diff -merge -text`, and `!name` makes the attribute unspecified again.
+fn parse_rules(content: &str, base: &str) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let pattern = match fields.next() {
+            Some(pattern) if !pattern.starts_with('#') && !pattern.ends_with('/') => pattern,
+            _ => continue,
+        };
+        let mut states = Vec::new();
+        for field in fields {
+            if field == "binary" {
+                states.push(("binary".to_string(), Some(AttributeState::Set)));
+                for name in ["diff", "merge", "text"] {
+                    states.push((name.to_string(), Some(AttributeState::Unset)));
+                }
+            } else if let Some(name) = field.strip_prefix('-') {
+                states.push((name.to_string(), Some(AttributeState::Unset)));
+            } else if let Some(name) = field.strip_prefix('!') {
+                states.push((name.to_string(), None));
+            } else if let Some((name, value)) = field.split_once('=') {
+                let value = AttributeState::Value(value.to_string());
+                states.push((name.to_string(), Some(value)));
+            } else {
+                states.push((field.to_string(), Some(AttributeState::Set)));
+            }
+        }
+        rules.push(Rule {
+            base: base.to_string(),
+            pattern: pattern.to_string(),
+            states,
+        });
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_attributes_are_parsed_and_the_last_match_wins() {
+        let attributes = Attributes::from_content(
+            "# comment\n*.txt text\n*.bat text eol=crlf\n*.png binary\nlegacy.txt -text\ndocs/ export-ignore\ntests/** export-ignore\nCHANGELOG merge=union\n",
+        );
+
+        assert!(attributes.get("notes/a.txt").is_text(b"a\r\n"));
+        assert!(!attributes.get("legacy.txt").is_text(b"a\r\n"));
+        assert_eq!(attributes.get("run.bat").eol(), Some(Eol::Crlf));
+        assert_eq!(attributes.get("logo.png").is_binary(), Some(true));
+        assert_eq!(
+            attributes.get("logo.png").merge_driver(),
+            Some(MergeDriver::Binary)
+        );
+        assert_eq!(
+            attributes.get("CHANGELOG").merge_driver(),
+            Some(MergeDriver::Union)
+        );
+        assert_eq!(attributes.get("src/main.rs"), FileAttributes::default());
+        assert!(attributes.get("tests/data/a.txt").export_ignore());
+        assert!(!attributes.get("docs/index.md").export_ignore());
+    }
+
+    #[test]
+    fn test_line_endings_are_converted() {
+        let attributes = Attributes::from_content("*.txt text\n*.bat eol=crlf\n*.auto text=auto\n");
+
+        assert_eq!(attributes.clean("a.txt", b"a\r\nb\r\n".to_vec()), b"a\nb\n");
+        assert_eq!(
+            attributes.clean("a.rs", b"a\r\nb\r\n".to_vec()),
+            b"a\r\nb\r\n"
+        );
+        assert_eq!(attributes.clean("a.auto", b"a\r\n\0".to_vec()), b"a\r\n\0");
+        assert_eq!(
+            attributes.smudge("a.bat", b"a\nb\r\n".to_vec()),
+            b"a\r\nb\r\n"
+        );
+        assert_eq!(attributes.smudge("a.txt", b"a\nb\n".to_vec()), b"a\nb\n");
+    }
+
+    #[test]
+    fn test_nested_files_and_info_attributes_are_layered() -> io::Result<()> {
+        let root_dir = "tests/attributes_layers";
+        let git_dir = format!("{}/.mgit", root_dir);
+        fs::create_dir_all(format!("{}/info", git_dir))?;
+        fs::create_dir_all(format!("{}/vendor/lib", root_dir))?;
+        fs::write(
+            format!("{}/.gitattributes", root_dir),
+            "*.c text\n*.h text\n",
+        )?;
+        fs::write(
+            format!("{}/vendor/.gitattributes", root_dir),
+            "*.c -text\n/top.h -diff\n",
+        )?;
+        fs::write(format!("{}/info/attributes", git_dir), "*.h !text\n")?;
+
+        let attributes = Attributes::load(&git_dir);
+        let main = attributes.get(&format!("{}/src/main.c", root_dir));
+        let vendored = attributes.get("vendor/lib/util.c");
+        let header = attributes.get("vendor/top.h");
+        let nested_header = attributes.get("vendor/lib/top.h");
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(main.state("text"), Some(&AttributeState::Set));
+        assert_eq!(vendored.state("text"), Some(&AttributeState::Unset));
+        assert_eq!(header.state("text"), None);
+        assert_eq!(header.is_binary(), Some(true));
+        assert_eq!(nested_header.is_binary(), None);
+        Ok(())
+    }
+}
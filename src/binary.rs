@@ -0,0 +1,107 @@
+use crate::{cat_file, config::Config, utils};
+
+/// Number of bytes looked at to decide if a content is binary, the same amount git uses.
+const FIRST_FEW_BYTES: usize = 8000;
+/// Section of the configuration where the binary path patterns are listed.
+const CONFIG_SECTION: &str = "diff";
+/// Key of the configuration that lists the binary path patterns.
+const CONFIG_KEY: &str = "binary";
+
+/// Returns true if the content looks binary, that is, if there is a NUL byte in its first bytes.
+pub fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(FIRST_FEW_BYTES).any(|byte| *byte == 0)
+}
+
+/// The path patterns whose files are always treated as binary, whatever their content is.
+///
+/// They are listed in the configuration under `[diff]`, one or more per line:
+///
+/// ```text
+/// [diff]
+///     binary = *.png *.jpg
+///     binary = assets/**
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BinaryPatterns {
+    patterns: Vec<String>,
+}
+
+impl BinaryPatterns {
+    /// Creates the list from the given patterns.
+    pub fn new(patterns: &[&str]) -> Self {
+        Self {
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+        }
+    }
+
+    /// Loads the patterns listed in the configuration of the repository.
+    /// If the configuration can't be read, the list is empty.
+    pub fn load(git_dir: &str) -> Self {
+        let values = Config::load(git_dir)
+            .and_then(|config| config.get_values(CONFIG_SECTION, CONFIG_KEY))
+            .unwrap_or_default();
+        Self {
+            patterns: values
+                .iter()
+                .flat_map(|value| value.split_whitespace())
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+
+    /// Returns true if the path matches one of the patterns.
+    pub fn matches(&self, path: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| utils::matches_pattern(pattern, path))
+    }
+
+    /// Returns true if a file must be treated as binary, because of its path or because of its content.
+    pub fn is_binary_file(&self, path: &str, content: &[u8]) -> bool {
+        self.matches(path) || is_binary(content)
+    }
+
+    /// Same as `is_binary_file`, reading the content from the blob `hash`.
+    /// If the blob can't be read, only the path is checked.
+    pub fn is_binary_object(&self, path: &str, hash: &str, git_dir: &str) -> bool {
+        self.matches(path)
+            || cat_file::cat_file_return_bytes(hash, git_dir)
+                .map(|content| is_binary(&content))
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, io};
+
+    #[test]
+    fn test_nul_bytes_make_a_content_binary() {
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!is_binary("hola\nchau\n".as_bytes()));
+        assert!(!is_binary(b""));
+    }
+
+    #[test]
+    fn test_patterns_are_loaded_from_the_config() -> io::Result<()> {
+        let git_dir = "tests/binary_config/.mgit";
+        fs::create_dir_all(git_dir)?;
+        fs::write(
+            format!("{}/config", git_dir),
+            "[core]\n\trepositoryformatversion = 0\n\tfilemode = true\n\tbare = false\n\tlogallrefupdates = true\n[diff]\n\tbinary = *.png *.jpg\n\tbinary = assets/**\n",
+        )?;
+        let patterns = BinaryPatterns::load(git_dir);
+        fs::remove_dir_all("tests/binary_config")?;
+
+        assert_eq!(
+            patterns,
+            BinaryPatterns::new(&["*.png", "*.jpg", "assets/**"])
+        );
+        assert!(patterns.is_binary_file("img/logo.png", b"text"));
+        assert!(patterns.is_binary_file("assets/data.txt", b"text"));
+        assert!(!patterns.is_binary_file("src/main.rs", b"text"));
+        assert!(patterns.is_binary_file("src/main.rs", b"te\0xt"));
+        Ok(())
+    }
+}
@@ -0,0 +1,573 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::Path,
+    process::Command,
+};
+
+use crate::{
+    checkout,
+    configuration::LOGGER_COMMANDS_FILE,
+    log::{self, LogOptions, LogOrder},
+    logger::Logger,
+    show, utils,
+};
+
+/// File of the git directory with what HEAD was when the bisection started: a branch or a commit.
+const BISECT_START: &str = "BISECT_START";
+/// File of the git directory with the commands of the bisection, as `git bisect log` shows them.
+const BISECT_LOG: &str = "BISECT_LOG";
+/// Folder of the git directory with the references to the commits marked as good, bad or skipped.
+const BISECT_REFS: &str = "refs/bisect";
+/// Exit code of the command of `bisect run` that means the commit can't be tested.
+const SKIP_EXIT_CODE: i32 = 125;
+
+/// Logs the 'git bisect' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_bisect(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git bisect': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// How a commit was marked during the bisection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Term {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl Term {
+    /// Returns the name of the term, as it is written in the commands.
+    fn name(&self) -> &str {
+        match self {
+            Term::Good => "good",
+            Term::Bad => "bad",
+            Term::Skip => "skip",
+        }
+    }
+}
+
+/// The commits marked so far in a bisection, read from `refs/bisect`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BisectState {
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+impl BisectState {
+    /// Reads the marked commits. There is only one bad commit, the oldest one marked as bad so far.
+    pub fn load(git_dir: &str) -> io::Result<Self> {
+        let mut state = Self::default();
+        let refs_dir = Path::new(git_dir).join(BISECT_REFS);
+        if !refs_dir.is_dir() {
+            return Ok(state);
+        }
+        for entry in fs::read_dir(refs_dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let hash = fs::read_to_string(entry.path())?.trim().to_string();
+            if name == "bad" {
+                state.bad = Some(hash);
+            } else if name.starts_with("good-") {
+                state.good.push(hash);
+            } else if name.starts_with("skip-") {
+                state.skipped.push(hash);
+            }
+        }
+        state.good.sort();
+        state.skipped.sort();
+        Ok(state)
+    }
+}
+
+/// Returns an error if there is no bisection in progress.
+fn check_started(git_dir: &str) -> io::Result<()> {
+    if Path::new(git_dir).join(BISECT_START).is_file() {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "You need to start by \"git bisect start\"",
+    ))
+}
+
+/// Appends lines to the log of the bisection.
+fn append_log(git_dir: &str, lines: &str) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(Path::new(git_dir).join(BISECT_LOG))?;
+    file.write_all(lines.as_bytes())
+}
+
+/// Marks a commit as good, bad or skipped, and writes it in the log of the bisection.
+fn mark(git_dir: &str, revision: &str, term: Term) -> io::Result<()> {
+    let hash = utils::resolve_revision(revision, git_dir)?;
+    let refs_dir = Path::new(git_dir).join(BISECT_REFS);
+    fs::create_dir_all(&refs_dir)?;
+    let name = match term {
+        Term::Bad => "bad".to_string(),
+        term => format!("{}-{}", term.name(), hash),
+    };
+    fs::write(refs_dir.join(name), format!("{}\n", hash))?;
+    let subject = log::Log::load(Some(&hash), git_dir)?.subject().to_string();
+    append_log(
+        git_dir,
+        &format!(
+            "# {}: [{}] {}\ngit bisect {} {}\n",
+            term.name(),
+            hash,
+            subject,
+            term.name(),
+            hash
+        ),
+    )
+}
+
+/// Chooses the commit that splits the commits left to test in two halves as equal as possible.
+///
+/// For every candidate, the number of candidates reachable from it (itself included) are the commits
+/// that would be left if it turned out to be bad, and the rest the ones left if it were good. The best
+/// candidate is the one where the smaller of the two sides is the largest, so the graph is halved even
+/// when the history has merges. Skipped commits are never chosen.
+///
+/// # Arguments
+///
+/// * `commits` - The candidates as (hash, parents), newest first. Parents outside the candidates are ignored.
+/// * `skipped` - The commits that can't be tested.
+///
+pub fn choose_midpoint(commits: &[(String, Vec<String>)], skipped: &[String]) -> Option<String> {
+    let candidates: HashMap<&str, &Vec<String>> = commits
+        .iter()
+        .map(|(hash, parents)| (hash.as_str(), parents))
+        .collect();
+    let total = commits.len();
+    let mut best: Option<(usize, &str)> = None;
+    for (hash, _) in commits {
+        if skipped.contains(hash) {
+            continue;
+        }
+        let mut reachable: HashSet<&str> = HashSet::new();
+        let mut pending = vec![hash.as_str()];
+        while let Some(commit) = pending.pop() {
+            if !reachable.insert(commit) {
+                continue;
+            }
+            if let Some(parents) = candidates.get(commit) {
+                pending.extend(
+                    parents
+                        .iter()
+                        .map(|parent| parent.as_str())
+                        .filter(|parent| candidates.contains_key(parent)),
+                );
+            }
+        }
+        let score = reachable.len().min(total - reachable.len());
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, hash));
+        }
+    }
+    best.map(|(_, hash)| hash.to_string())
+}
+
+/// What the bisection has to do after the commits marked so far.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BisectStep {
+    /// There are not both good and bad commits yet.
+    Waiting,
+    /// The commit to test next, and how many commits would be left after it.
+    Test(String, usize),
+    /// The first bad commit was found.
+    Found(String),
+    /// Only skipped commits are left, so the first bad commit is any of these.
+    OnlySkipped(Vec<String>),
+}
+
+/// Returns the next step of the bisection: the commits left to test are the ones reachable from the
+/// bad commit but not from any good one, other than the bad commit itself.
+///
+/// # Errors
+///
+/// Returns an error if a commit can't be read.
+///
+pub fn next_step(git_dir: &str, state: &BisectState) -> io::Result<BisectStep> {
+    let bad = match (&state.bad, state.good.is_empty()) {
+        (Some(bad), false) => bad,
+        _ => return Ok(BisectStep::Waiting),
+    };
+    let mut revisions = vec![bad.clone()];
+    revisions.extend(state.good.iter().map(|good| format!("^{}", good)));
+    let options = LogOptions {
+        revisions,
+        order: LogOrder::Topo,
+        ..LogOptions::default()
+    };
+    let commits: Vec<(String, Vec<String>)> = log::log_with_options(git_dir, &options)?
+        .iter()
+        .map(|log| (log.hash().to_string(), log.parents().to_vec()))
+        .collect();
+    let untested: Vec<String> = commits
+        .iter()
+        .map(|(hash, _)| hash.clone())
+        .filter(|hash| hash != bad && !state.skipped.contains(hash))
+        .collect();
+    if untested.is_empty() {
+        let skipped: Vec<String> = commits
+            .iter()
+            .map(|(hash, _)| hash.clone())
+            .filter(|hash| state.skipped.contains(hash))
+            .collect();
+        if skipped.is_empty() {
+            return Ok(BisectStep::Found(bad.clone()));
+        }
+        let mut suspects = vec![bad.clone()];
+        suspects.extend(skipped);
+        return Ok(BisectStep::OnlySkipped(suspects));
+    }
+    let mut skipped = state.skipped.clone();
+    skipped.push(bad.clone());
+    match choose_midpoint(&commits, &skipped) {
+        Some(midpoint) => Ok(BisectStep::Test(midpoint, untested.len() / 2)),
+        None => Ok(BisectStep::Waiting),
+    }
+}
+
+/// Goes on with the bisection: checks out the next commit to test, or shows the first bad commit.
+/// Returns the step that was taken.
+fn advance(git_dir: &str, root_dir: &str, output: &mut impl Write) -> io::Result<BisectStep> {
+    let step = next_step(git_dir, &BisectState::load(git_dir)?)?;
+    match &step {
+        BisectStep::Waiting => {}
+        BisectStep::Test(hash, left) => {
+            checkout::checkout_commit_detached(Path::new(git_dir), root_dir, hash)?;
+            let steps = usize::BITS - left.leading_zeros();
+            writeln!(
+                output,
+                "Bisecting: {} revisions left to test after this (roughly {} steps)",
+                left, steps
+            )?;
+            let subject = log::Log::load(Some(hash), git_dir)?.subject().to_string();
+            writeln!(output, "[{}] {}", hash, subject)?;
+        }
+        BisectStep::Found(hash) => {
+            writeln!(output, "{} is the first bad commit", hash)?;
+            show::git_show(git_dir, &[hash.clone(), "--stat".to_string()], output)?;
+            append_log(git_dir, &format!("# first bad commit: [{}]\n", hash))?;
+        }
+        BisectStep::OnlySkipped(suspects) => {
+            writeln!(
+                output,
+                "There are only 'skip'ped commits left to test.\nThe first bad commit could be any of:"
+            )?;
+            for hash in suspects {
+                writeln!(output, "{}", hash)?;
+            }
+        }
+    }
+    Ok(step)
+}
+
+/// Starts a bisection, remembering where HEAD is to go back there with `bisect reset`.
+/// A bad commit and good ones may be given right away.
+fn start(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    if Path::new(git_dir).join(BISECT_START).is_file() {
+        reset_state(git_dir)?;
+    }
+    let head = fs::read_to_string(Path::new(git_dir).join("HEAD"))?;
+    let start = match head.trim().strip_prefix("ref: refs/heads/") {
+        Some(branch) => branch.to_string(),
+        None => utils::resolve_revision("HEAD", git_dir)?,
+    };
+    fs::write(
+        Path::new(git_dir).join(BISECT_START),
+        format!("{}\n", start),
+    )?;
+    append_log(git_dir, &format!("git bisect start {}\n", args.join(" ")))?;
+    let revisions: Vec<&String> = args.iter().filter(|arg| *arg != "--").collect();
+    if let Some((bad, good)) = revisions.split_first() {
+        mark(git_dir, bad, Term::Bad)?;
+        for revision in good {
+            mark(git_dir, revision, Term::Good)?;
+        }
+    }
+    advance(git_dir, root_dir, output)?;
+    Ok(())
+}
+
+/// Removes the state of the bisection.
+fn reset_state(git_dir: &str) -> io::Result<()> {
+    let git_dir = Path::new(git_dir);
+    for file in [BISECT_START, BISECT_LOG] {
+        if git_dir.join(file).is_file() {
+            fs::remove_file(git_dir.join(file))?;
+        }
+    }
+    if git_dir.join(BISECT_REFS).is_dir() {
+        fs::remove_dir_all(git_dir.join(BISECT_REFS))?;
+    }
+    Ok(())
+}
+
+/// Ends the bisection and goes back to the branch or commit where it started.
+fn reset(git_dir: &str, root_dir: &str) -> io::Result<()> {
+    let start_file = Path::new(git_dir).join(BISECT_START);
+    if !start_file.is_file() {
+        return Ok(());
+    }
+    let start = fs::read_to_string(start_file)?.trim().to_string();
+    if Path::new(git_dir).join("refs/heads").join(&start).is_file() {
+        checkout::checkout_branch(Path::new(git_dir), root_dir, &start)?;
+    } else {
+        checkout::checkout_commit_detached(Path::new(git_dir), root_dir, &start)?;
+    }
+    reset_state(git_dir)
+}
+
+/// Runs the command on every commit to test until the first bad commit is found.
+/// Exit code 0 means good, 125 means the commit can't be tested, and any other code up to 127 means bad.
+fn run(git_dir: &str, root_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    let (program, program_args) = args.split_first().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "bisect run failed: no command provided.",
+        )
+    })?;
+    let mut step = next_step(git_dir, &BisectState::load(git_dir)?)?;
+    if step == BisectStep::Waiting {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "bisect run failed: a good and a bad commit are needed",
+        ));
+    }
+    while let BisectStep::Test(_, _) = step {
+        let mut command = Command::new(program);
+        command.args(program_args);
+        if !root_dir.is_empty() {
+            command.current_dir(root_dir);
+        }
+        writeln!(output, "running {}", args.join(" "))?;
+        let term = match command.status()?.code() {
+            Some(0) => Term::Good,
+            Some(SKIP_EXIT_CODE) => Term::Skip,
+            Some(code) if (1..128).contains(&code) => Term::Bad,
+            code => {
+                return Err(io::Error::other(format!(
+                    "bisect run failed: exit code {:?} is not valid",
+                    code
+                )))
+            }
+        };
+        mark(git_dir, "HEAD", term)?;
+        step = advance(git_dir, root_dir, output)?;
+    }
+    if let BisectStep::Found(_) = step {
+        writeln!(output, "bisect found first bad commit")?;
+    }
+    Ok(())
+}
+
+/// Finds the commit that introduced a bug with a binary search over the history.
+///
+/// * `start [<bad> [<good>...]]` starts the bisection.
+/// * `bad [<rev>]`, `good [<rev>...]` and `skip [<rev>...]` mark commits (HEAD by default), and check out
+///   the next one to test, the one that splits the commits left in two halves.
+/// * `run <cmd> [<args>...]` tests the commits with a command until the first bad one is found.
+///   Exit code 0 means good, 125 means the commit can't be tested and any other code up to 127 means bad.
+/// * `log` shows the commands of the bisection and `reset` ends it, going back to where it started.
+///
+/// The state of the bisection is kept in the git directory: `BISECT_START`, `BISECT_LOG` and `refs/bisect`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The working directory, where the commits to test are checked out.
+/// * `args` - The arguments of the command, the ones after 'bisect'.
+/// * `output` - Where the progress of the bisection is written.
+///
+/// # Errors
+///
+/// Returns an error if the subcommand is unknown, there is no bisection in progress, a revision
+/// can't be resolved or a commit can't be checked out.
+///
+pub fn git_bisect(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    log_bisect(git_dir, args)?;
+    let (subcommand, args) = match args.split_first() {
+        Some((subcommand, args)) => (subcommand.as_str(), args),
+        None => ("", args),
+    };
+    if !matches!(subcommand, "start" | "reset") {
+        check_started(git_dir)?;
+    }
+    match subcommand {
+        "start" => start(git_dir, root_dir, args, output),
+        "reset" => reset(git_dir, root_dir),
+        "log" => output.write_all(fs::read(Path::new(git_dir).join(BISECT_LOG))?.as_slice()),
+        "run" => run(git_dir, root_dir, args, output),
+        "good" | "bad" | "skip" => {
+            let term = match subcommand {
+                "good" => Term::Good,
+                "bad" => Term::Bad,
+                _ => Term::Skip,
+            };
+            let revisions = if args.is_empty() {
+                vec!["HEAD".to_string()]
+            } else {
+                args.to_vec()
+            };
+            for revision in &revisions {
+                mark(git_dir, revision, term)?;
+            }
+            advance(git_dir, root_dir, output)?;
+            Ok(())
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: git bisect [start|bad|good|skip|run|log|reset]",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_file, create_repo};
+
+    fn run_bisect(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_bisect(git_dir, "", &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    fn commit(hash: &str, parents: &[&str]) -> (String, Vec<String>) {
+        (
+            hash.to_string(),
+            parents.iter().map(|parent| parent.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_midpoint_halves_the_graph_of_a_merge() {
+        let commits = vec![
+            commit("merge", &["a2", "b2"]),
+            commit("a2", &["a1"]),
+            commit("a1", &["base"]),
+            commit("b2", &["b1"]),
+            commit("b1", &["base"]),
+            commit("base", &["good"]),
+        ];
+        assert_eq!(
+            choose_midpoint(&commits, &["merge".to_string()]),
+            Some("a2".to_string())
+        );
+        assert_eq!(
+            choose_midpoint(&commits, &["merge".to_string(), "a2".to_string()]),
+            Some("b2".to_string())
+        );
+    }
+
+    /// Creates a repository with six commits of `file.txt`, where the fourth one adds the bug,
+    /// and returns its git directory, the path to the file and the commits.
+    fn create_history(root_dir: &str) -> io::Result<(String, String, Vec<String>)> {
+        let git_dir = create_repo(root_dir)?;
+        let file = format!("{}/file.txt", root_dir);
+        let mut hashes = Vec::new();
+        for (content, message) in [
+            ("ok\n", "Primero"),
+            ("ok\n1\n", "Segundo"),
+            ("ok\n1\n2\n", "Tercero"),
+            ("bug\n1\n2\n3\n", "Cuarto"),
+            ("bug\n1\n2\n3\n4\n", "Quinto"),
+            ("bug\n1\n2\n3\n4\n5\n", "Sexto"),
+        ] {
+            hashes.push(commit_file(&git_dir, &file, content, message)?);
+        }
+        Ok((git_dir, file, hashes))
+    }
+
+    #[test]
+    fn test_bisect_start_and_skip_check_out_the_midpoint() -> io::Result<()> {
+        let root_dir = "tests/bisect_skip";
+        let (git_dir, _, hashes) = create_history(root_dir)?;
+
+        let started = run_bisect(&git_dir, &["start", &hashes[5], &hashes[0]]);
+        let skipped = run_bisect(&git_dir, &["skip", &hashes[1]]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(started?.starts_with("Bisecting: 2 revisions left to test after this"));
+        assert!(skipped?.ends_with(&format!("[{}] Cuarto\n", hashes[3])));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bisect_run_finds_the_first_bad_commit() -> io::Result<()> {
+        let root_dir = "tests/bisect_run";
+        let (git_dir, file, hashes) = create_history(root_dir)?;
+
+        run_bisect(&git_dir, &["start", &hashes[5], &hashes[0]])?;
+        let ran = run_bisect(
+            &git_dir,
+            &["run", "sh", "-c", &format!("! grep -q bug {}", file)],
+        );
+        fs::remove_dir_all(root_dir)?;
+
+        let ran = ran?;
+        assert!(ran.contains(&format!("{} is the first bad commit", hashes[3])));
+        assert!(ran.ends_with("bisect found first bad commit\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bisect_log_and_reset() -> io::Result<()> {
+        let root_dir = "tests/bisect_log";
+        let (git_dir, file, hashes) = create_history(root_dir)?;
+
+        run_bisect(&git_dir, &["start", &hashes[5], &hashes[0]])?;
+        run_bisect(&git_dir, &["skip", &hashes[1]])?;
+        let log = run_bisect(&git_dir, &["log"]);
+        let reset = run_bisect(&git_dir, &["reset"]);
+        let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+        let content = fs::read_to_string(&file)?;
+        fs::remove_dir_all(root_dir)?;
+
+        let log = log?;
+        assert!(log.starts_with(&format!("git bisect start {} {}\n", hashes[5], hashes[0])));
+        assert!(log.contains(&format!("git bisect skip {}", hashes[1])));
+        reset?;
+        assert_eq!(head.trim(), "ref: refs/heads/master");
+        assert_eq!(content, "bug\n1\n2\n3\n4\n5\n");
+        Ok(())
+    }
+}
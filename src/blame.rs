@@ -0,0 +1,648 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use chrono::{FixedOffset, TimeZone};
+
+use crate::{
+    cat_file,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff::{self, Edit},
+    logger::Logger,
+    rename::{self, RenameOptions},
+    tree_handler::{self, Tree},
+    utils,
+};
+
+/// Length of the abbreviated hashes shown by `git blame`.
+const SHORT_HASH_LENGTH: usize = 8;
+
+/// Logs the 'git blame' command with the specified path and Git directory.
+///
+/// # Arguments
+///
+/// * `path` - The path of the blamed file.
+/// * `git_dir` - The path to the Git directory.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_blame(path: &str, git_dir: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git blame': Path '{}', Git Directory '{}', {}",
+        path,
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The person that signed a commit and when, as written in its `author` and `committer` lines:
+/// `<name> <email> <seconds since epoch> <timezone>`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    pub time: i64,
+    pub timezone: String,
+}
+
+impl Signature {
+    /// Parses the value of an `author`, `committer` or `tagger` line. The name may have spaces.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut fields: Vec<&str> = value.split(' ').collect();
+        if fields.len() < 4 {
+            return None;
+        }
+        let timezone = fields.pop()?.to_string();
+        let time = fields.pop()?.parse::<i64>().ok()?;
+        let email = fields.pop()?.trim_matches(|c| c == '<' || c == '>');
+        Some(Self {
+            name: fields.join(" "),
+            email: email.to_string(),
+            time,
+            timezone,
+        })
+    }
+
+    /// Returns the date in the timezone of the signature, for example `2023-11-02 18:30:00 -0300`.
+    pub fn formatted_date(&self) -> String {
+        self.format_date("%Y-%m-%d %H:%M:%S %z")
+    }
+
+    /// Returns the date in the timezone of the signature, with the given `chrono` format.
+    pub fn format_date(&self, format: &str) -> String {
+        let sign = if self.timezone.starts_with('-') {
+            -1
+        } else {
+            1
+        };
+        let digits = self.timezone.trim_start_matches(['+', '-']);
+        let hours = digits.get(..2).and_then(|h| h.parse::<i32>().ok());
+        let minutes = digits.get(2..4).and_then(|m| m.parse::<i32>().ok());
+        let seconds = sign * (hours.unwrap_or(0) * 3600 + minutes.unwrap_or(0) * 60);
+        match FixedOffset::east_opt(seconds).map(|offset| offset.timestamp_opt(self.time, 0)) {
+            Some(chrono::LocalResult::Single(date)) => date.format(format).to_string(),
+            _ => self.time.to_string(),
+        }
+    }
+}
+
+/// The information of a commit that is shown next to the lines it last changed.
+#[derive(Debug, Default, Clone)]
+pub struct BlameCommit {
+    pub hash: String,
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Signature,
+    pub committer: Signature,
+    pub summary: String,
+}
+
+impl BlameCommit {
+    /// Reads the commit from the objects folder.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the commit can't be read or if it has no tree.
+    pub fn load(hash: &str, git_dir: &str) -> io::Result<Self> {
+        let content = cat_file::cat_file_return_content(hash, git_dir)?;
+        let mut commit = Self {
+            hash: hash.to_string(),
+            ..Default::default()
+        };
+        let mut lines = content.lines();
+        for line in lines.by_ref().take_while(|line| !line.is_empty()) {
+            match line.split_once(' ') {
+                Some(("tree", tree)) => commit.tree = tree.to_string(),
+                Some(("parent", parent)) => commit.parents.push(parent.to_string()),
+                Some(("author", author)) => {
+                    commit.author = Signature::parse(author).unwrap_or_default()
+                }
+                Some(("committer", committer)) => {
+                    commit.committer = Signature::parse(committer).unwrap_or_default()
+                }
+                _ => {}
+            }
+        }
+        commit.summary = lines.next().unwrap_or_default().to_string();
+        if commit.tree.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("fatal: bad commit {}", hash),
+            ));
+        }
+        Ok(commit)
+    }
+
+    /// Returns true if the commit has no parents, so every line it has is blamed on it.
+    pub fn is_boundary(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+/// A line of the blamed file together with the commit that last changed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameLine {
+    /// The hash of the commit.
+    pub commit: String,
+    /// The path of the file in that commit, which differs from the blamed path if it was renamed afterwards.
+    pub original_path: String,
+    /// The number of the line in the file of that commit, starting at 1.
+    pub original_line: usize,
+    /// The number of the line in the blamed file, starting at 1.
+    pub final_line: usize,
+    pub content: String,
+}
+
+/// The result of blaming a file: each line with its commit, and the commits involved.
+#[derive(Debug, Default)]
+pub struct Blame {
+    pub path: String,
+    pub lines: Vec<BlameLine>,
+    pub commits: HashMap<String, BlameCommit>,
+}
+
+/// The lines of a version of the file that still have to be blamed.
+struct PendingLines {
+    path: String,
+    lines: Vec<String>,
+    /// Pairs of (index of the line in this version, index of the line in the blamed file).
+    tracked: Vec<(usize, usize)>,
+}
+
+/// Returns the lines of the file at `path` in the tree, or None if the tree doesn't have it.
+fn file_lines(tree: &Tree, path: &str, git_dir: &str) -> io::Result<Option<Vec<String>>> {
+    match tree.get_hash_from_path(path) {
+        Some(hash) => {
+            let content = cat_file::cat_file_return_content(&hash, git_dir)?;
+            Ok(Some(content.lines().map(|line| line.to_string()).collect()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the path the file had in the parent tree: the same one, or the old one if the commit renamed it.
+fn path_in_parent(path: &str, tree: &Tree, parent_tree: &Tree, git_dir: &str) -> Option<String> {
+    if parent_tree.get_hash_from_path(path).is_some() {
+        return Some(path.to_string());
+    }
+    rename::find_renamed_paths(
+        &parent_tree.get_files(),
+        &tree.get_files(),
+        git_dir,
+        RenameOptions::default(),
+    )
+    .into_iter()
+    .find(|(_, new_path)| new_path == path)
+    .map(|(old_path, _)| old_path)
+}
+
+/// Parses the argument of `-L`: `<start>,<end>` or `<start>,+<count>`, with lines starting at 1.
+/// Returns the first and the last line of the range.
+pub fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (start, end) = range.split_once(',')?;
+    let start = start.parse::<usize>().ok().filter(|start| *start > 0)?;
+    let end = match end.strip_prefix('+') {
+        Some(count) => start + count.parse::<usize>().ok().filter(|count| *count > 0)? - 1,
+        None => end.parse::<usize>().ok()?,
+    };
+    if end < start {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// Finds, for every line of the file, the commit that last changed it.
+///
+/// The history is walked from `revision` through the parents of each commit, newest commits first.
+/// Every commit compares its version of the file with the one of each parent using the line diff:
+/// the lines that are equal in a parent are passed on to it, and the rest are blamed on the commit.
+/// If a parent doesn't have the file, it's looked for as a rename. Root commits keep every line they get.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file in `revision`.
+/// * `revision` - The commit where the walk starts.
+/// * `range` - If given, only the lines between the first and the last one (both included) are blamed.
+/// * `git_dir` - The path to the git directory.
+///
+/// # Errors
+///
+/// Returns an error if the revision can't be resolved, if the file isn't in it, if the range is
+/// outside the file or if an object can't be read.
+///
+pub fn blame(
+    path: &str,
+    revision: &str,
+    range: Option<(usize, usize)>,
+    git_dir: &str,
+) -> io::Result<Blame> {
+    let head = utils::resolve_revision(revision, git_dir)?;
+    let mut commits: HashMap<String, BlameCommit> = HashMap::new();
+    let head_commit = BlameCommit::load(&head, git_dir)?;
+    let head_tree = tree_handler::load_tree_from_file(&head_commit.tree, git_dir)?;
+    let final_lines = match file_lines(&head_tree, path, git_dir)? {
+        Some(lines) => lines,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: no such path {} in {}", path, revision),
+            ))
+        }
+    };
+    let (start, end) = range.unwrap_or((1, final_lines.len()));
+    if range.is_some() && end > final_lines.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: file {} has only {} lines", path, final_lines.len()),
+        ));
+    }
+
+    let mut blamed: Vec<Option<BlameLine>> = vec![None; final_lines.len()];
+    let mut queue: BTreeMap<(i64, String), PendingLines> = BTreeMap::new();
+    queue.insert(
+        (head_commit.committer.time, head.clone()),
+        PendingLines {
+            path: path.to_string(),
+            lines: final_lines.clone(),
+            tracked: (start.saturating_sub(1)..end).map(|i| (i, i)).collect(),
+        },
+    );
+    commits.insert(head.clone(), head_commit);
+
+    while let Some(((_, hash), pending)) = queue.pop_last() {
+        let commit = match commits.get(&hash) {
+            Some(commit) => commit.clone(),
+            None => continue,
+        };
+        let tree = tree_handler::load_tree_from_file(&commit.tree, git_dir)?;
+        let mut tracked = pending.tracked;
+        for parent in &commit.parents {
+            if tracked.is_empty() {
+                break;
+            }
+            let parent_commit = match commits.get(parent) {
+                Some(parent_commit) => parent_commit.clone(),
+                None => BlameCommit::load(parent, git_dir)?,
+            };
+            let parent_tree = tree_handler::load_tree_from_file(&parent_commit.tree, git_dir)?;
+            let parent_path = match path_in_parent(&pending.path, &tree, &parent_tree, git_dir) {
+                Some(parent_path) => parent_path,
+                None => continue,
+            };
+            let parent_lines = match file_lines(&parent_tree, &parent_path, git_dir)? {
+                Some(lines) => lines,
+                None => continue,
+            };
+            let mut in_parent: HashMap<usize, usize> = HashMap::new();
+            for edit in diff::compute_edits(&parent_lines, &pending.lines) {
+                if let Edit::Equal(parent_index, index) = edit {
+                    in_parent.insert(index, parent_index);
+                }
+            }
+            let (passed, kept): (Vec<_>, Vec<_>) = tracked
+                .into_iter()
+                .partition(|(index, _)| in_parent.contains_key(index));
+            tracked = kept;
+            if passed.is_empty() {
+                continue;
+            }
+            let key = (parent_commit.committer.time, parent.clone());
+            commits.insert(parent.clone(), parent_commit);
+            let entry = queue.entry(key).or_insert_with(|| PendingLines {
+                path: parent_path,
+                lines: parent_lines,
+                tracked: Vec::new(),
+            });
+            entry.tracked.extend(
+                passed
+                    .into_iter()
+                    .map(|(index, final_index)| (in_parent[&index], final_index)),
+            );
+        }
+        for (index, final_index) in tracked {
+            blamed[final_index] = Some(BlameLine {
+                commit: hash.clone(),
+                original_path: pending.path.clone(),
+                original_line: index + 1,
+                final_line: final_index + 1,
+                content: final_lines[final_index].clone(),
+            });
+        }
+    }
+
+    let lines: Vec<BlameLine> = blamed.into_iter().flatten().collect();
+    commits.retain(|hash, _| lines.iter().any(|line| &line.commit == hash));
+    Ok(Blame {
+        path: path.to_string(),
+        lines,
+        commits,
+    })
+}
+
+/// Writes the blame in the default format: for each line, the abbreviated hash of the commit, the author,
+/// the date, the line number and the content. Lines of root commits have their hash prefixed with '^'.
+///
+/// ```text
+/// ^3b18e51 (Lionel 2023-11-02 18:30:00 -0300 1) fn main() {
+/// 9f2c4d07 (Angel  2023-11-05 10:12:44 -0300 2)     println!("Hola");
+/// ```
+pub fn write_blame(blame: &Blame, output: &mut impl Write) -> io::Result<()> {
+    let author_width = blame
+        .commits
+        .values()
+        .map(|commit| commit.author.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let line_width = blame
+        .lines
+        .last()
+        .map(|line| line.final_line.to_string().len())
+        .unwrap_or(1);
+    for line in &blame.lines {
+        let commit = match blame.commits.get(&line.commit) {
+            Some(commit) => commit,
+            None => continue,
+        };
+        let hash = match commit.is_boundary() {
+            true => format!("^{}", &line.commit[..SHORT_HASH_LENGTH - 1]),
+            false => line.commit[..SHORT_HASH_LENGTH].to_string(),
+        };
+        writeln!(
+            output,
+            "{} ({:<author_width$} {} {:>line_width$}) {}",
+            hash,
+            commit.author.name,
+            commit.author.formatted_date(),
+            line.final_line,
+            line.content
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the blame in the format of `git annotate`: the fields are separated by tabs.
+pub fn write_annotate(blame: &Blame, output: &mut impl Write) -> io::Result<()> {
+    for line in &blame.lines {
+        if let Some(commit) = blame.commits.get(&line.commit) {
+            writeln!(
+                output,
+                "{}\t({:>10}\t{}\t{}){}",
+                &line.commit[..SHORT_HASH_LENGTH],
+                commit.author.name,
+                commit.author.formatted_date(),
+                line.final_line,
+                line.content
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the blame in the porcelain format, meant to be read by other programs.
+///
+/// Every group of consecutive lines from the same commit starts with
+/// `<hash> <original line> <final line> <lines in the group>`, and the next lines of the group with
+/// `<hash> <original line> <final line>`. The first time a commit appears, its author, committer and
+/// summary follow, one per line. Each line of the file comes after its header, prefixed with a tab.
+pub fn write_porcelain(blame: &Blame, output: &mut impl Write) -> io::Result<()> {
+    let mut shown: Vec<&str> = Vec::new();
+    let mut previous: Option<&BlameLine> = None;
+    for (i, line) in blame.lines.iter().enumerate() {
+        let starts_group = match previous {
+            Some(previous) => {
+                previous.commit != line.commit
+                    || previous.original_line + 1 != line.original_line
+                    || previous.final_line + 1 != line.final_line
+            }
+            None => true,
+        };
+        if starts_group {
+            let group_length = blame.lines[i..]
+                .iter()
+                .enumerate()
+                .take_while(|(offset, other)| {
+                    other.commit == line.commit
+                        && other.original_line == line.original_line + offset
+                        && other.final_line == line.final_line + offset
+                })
+                .count();
+            writeln!(
+                output,
+                "{} {} {} {}",
+                line.commit, line.original_line, line.final_line, group_length
+            )?;
+        } else {
+            writeln!(
+                output,
+                "{} {} {}",
+                line.commit, line.original_line, line.final_line
+            )?;
+        }
+        if !shown.contains(&line.commit.as_str()) {
+            shown.push(&line.commit);
+            if let Some(commit) = blame.commits.get(&line.commit) {
+                for (role, signature) in
+                    [("author", &commit.author), ("committer", &commit.committer)]
+                {
+                    writeln!(output, "{} {}", role, signature.name)?;
+                    writeln!(output, "{}-mail <{}>", role, signature.email)?;
+                    writeln!(output, "{}-time {}", role, signature.time)?;
+                    writeln!(output, "{}-tz {}", role, signature.timezone)?;
+                }
+                writeln!(output, "summary {}", commit.summary)?;
+                if commit.is_boundary() {
+                    writeln!(output, "boundary")?;
+                }
+            }
+            writeln!(output, "filename {}", line.original_path)?;
+        }
+        writeln!(output, "\t{}", line.content)?;
+        previous = Some(line);
+    }
+    Ok(())
+}
+
+/// Shows, for each line of a file, the commit that last changed it.
+///
+/// Usage: `git blame [-L <start>,<end>] [--porcelain] [<rev>] [--] <file>`.
+/// Without a revision, the file is blamed as it is in HEAD.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments after `blame`.
+/// * `annotate` - If true, the output has the format of `git annotate`.
+/// * `output` - Where the blame is written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or if the file can't be blamed.
+///
+pub fn git_blame(
+    git_dir: &str,
+    args: &[String],
+    annotate: bool,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: git blame [-L <start>,<end>] [--porcelain] [<rev>] [--] <file>",
+        )
+    };
+    let mut range = None;
+    let mut porcelain = false;
+    let mut positional: Vec<String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-L" => {
+                let value = args.next().ok_or_else(usage)?;
+                range = Some(parse_range(value).ok_or_else(usage)?);
+            }
+            "--porcelain" | "-p" => porcelain = true,
+            "--" => {
+                let path = args.next().ok_or_else(usage)?;
+                positional.truncate(1);
+                positional.push(path.to_string());
+            }
+            value if value.starts_with("-L") => {
+                range = Some(parse_range(&value[2..]).ok_or_else(usage)?);
+            }
+            value if value.starts_with('-') => return Err(usage()),
+            value => positional.push(value.to_string()),
+        }
+    }
+    let (revision, path) = match positional.as_slice() {
+        [path] => ("HEAD", path),
+        [revision, path] => (revision.as_str(), path),
+        _ => return Err(usage()),
+    };
+    log_blame(path, git_dir)?;
+
+    let blame = blame(path, revision, range, git_dir)?;
+    match (porcelain, annotate) {
+        (true, _) => write_porcelain(&blame, output),
+        (false, true) => write_annotate(&blame, output),
+        (false, false) => write_blame(&blame, output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add, commit, rm};
+    use std::fs;
+
+    fn create_repo(root_dir: &str) -> String {
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir)).unwrap();
+        fs::create_dir_all(format!("{}/refs/heads", git_dir)).unwrap();
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master").unwrap();
+        fs::write(format!("{}/index", git_dir), "").unwrap();
+        git_dir
+    }
+
+    fn commit_file(git_dir: &str, path: &str, content: &str, message: &str) -> String {
+        fs::write(path, content).unwrap();
+        let index_path = format!("{}/index", git_dir);
+        add::add(path, &index_path, git_dir, "", None).unwrap();
+        commit::new_commit(git_dir, message, "").unwrap()
+    }
+
+    #[test]
+    fn test_ranges_are_parsed() {
+        assert_eq!(parse_range("2,5"), Some((2, 5)));
+        assert_eq!(parse_range("3,+2"), Some((3, 4)));
+        assert_eq!(parse_range("0,2"), None);
+        assert_eq!(parse_range("5,2"), None);
+        assert_eq!(parse_range("5"), None);
+    }
+
+    #[test]
+    fn test_signatures_are_parsed_with_spaces_in_the_name() {
+        let signature =
+            Signature::parse("Lionel Andres Messi lio@afa.com 1671321600 -0300").unwrap();
+        assert_eq!(signature.name, "Lionel Andres Messi");
+        assert_eq!(signature.email, "lio@afa.com");
+        assert_eq!(signature.formatted_date(), "2022-12-17 21:00:00 -0300");
+    }
+
+    #[test]
+    fn test_each_line_is_blamed_on_the_commit_that_last_changed_it() {
+        let root_dir = "tests/blame/test_blame_lines";
+        let git_dir = create_repo(root_dir);
+        let path = format!("{}/file.txt", root_dir);
+        let first = commit_file(&git_dir, &path, "uno\ndos\ntres\n", "First");
+        let second = commit_file(&git_dir, &path, "uno\nDOS\ntres\ncuatro\n", "Second");
+        let moved = format!("{}/moved.txt", root_dir);
+        rm::git_rm(&path, &format!("{}/index", git_dir), &git_dir, "").unwrap();
+        let third = commit_file(&git_dir, &moved, "cero\nuno\nDOS\ntres\ncuatro\n", "Third");
+
+        let result = blame(&moved, "HEAD", None, &git_dir).unwrap();
+        let commits: Vec<&str> = result.lines.iter().map(|l| l.commit.as_str()).collect();
+        assert_eq!(commits, vec![&third, &first, &second, &first, &second]);
+        assert_eq!(result.lines[1].original_path, path);
+        assert_eq!(result.lines[3].original_line, 3);
+
+        let ranged = blame(&moved, &second, Some((2, 3)), &git_dir);
+        assert!(ranged.is_err());
+        let ranged = blame(&path, &second, Some((2, 3)), &git_dir).unwrap();
+        let lines: Vec<usize> = ranged.lines.iter().map(|l| l.final_line).collect();
+        assert_eq!(lines, vec![2, 3]);
+
+        let mut output = Vec::new();
+        let args = vec!["-L".to_string(), "1,2".to_string(), moved.clone()];
+        git_blame(&git_dir, &args, false, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&format!("{} (no_user ", &third[..8])));
+        assert!(lines[0].ends_with(" 1) cero"));
+        assert!(lines[1].starts_with(&format!("^{} (no_user ", &first[..7])));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_porcelain_shows_the_commit_information_once() {
+        let root_dir = "tests/blame/test_blame_porcelain";
+        let git_dir = create_repo(root_dir);
+        let path = format!("{}/file.txt", root_dir);
+        let first = commit_file(&git_dir, &path, "uno\ndos\n", "First commit");
+        let second = commit_file(&git_dir, &path, "uno\ndos\ntres\n", "Second commit");
+
+        let mut output = Vec::new();
+        git_blame(
+            &git_dir,
+            &["--porcelain".to_string(), path.clone()],
+            false,
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        fs::remove_dir_all(root_dir).unwrap();
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], format!("{} 1 1 2", first));
+        assert_eq!(lines[1], "author no_user");
+        assert_eq!(lines[2], "author-mail <no_email>");
+        assert!(lines.contains(&"summary First commit"));
+        assert!(lines.contains(&"boundary"));
+        assert!(lines.contains(&format!("filename {}", path).as_str()));
+        assert!(lines.contains(&format!("{} 2 2", first).as_str()));
+        assert!(lines.contains(&format!("{} 3 3 1", second).as_str()));
+        assert_eq!(output.matches("author-mail").count(), 2);
+        assert!(output.ends_with(&format!("filename {}\n\ttres\n", path)));
+    }
+}
@@ -0,0 +1,641 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use sha1::{Digest, Sha1};
+
+use crate::{
+    cat_file, clone, config,
+    configuration::{GIT_DIR, INITIAL_BRANCH, LOGGER_COMMANDS_FILE, REMOTE},
+    fetch, init,
+    log::{self, LogOptions},
+    logger::Logger,
+    packfile::handler::{create_packfile, unpack_packfile},
+    server_utils::get_objects_tree_objects,
+    utils,
+};
+
+/// First line of the bundles of version 2.
+const BUNDLE_SIGNATURE: &str = "# v2 git bundle";
+
+/// Logs the 'git bundle' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_bundle(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git bundle': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// A bundle: the refs and the objects of a repository kept in a single file, so they can be moved
+/// without a connection to the repository.
+///
+/// The file has a header with a line for every prerequisite (`-<hash> <subject>`) and every
+/// reference (`<hash> <name>`), ended by an empty line, followed by a packfile.
+#[derive(Debug, Default, PartialEq)]
+pub struct Bundle {
+    /// The commits, as (hash, subject), that the repository must have before the bundle is unpacked in it.
+    pub prerequisites: Vec<(String, String)>,
+    /// The references of the bundle, as (hash, name).
+    pub references: Vec<(String, String)>,
+    /// The packfile with the objects of the bundle.
+    pub packfile: Vec<u8>,
+}
+
+impl Bundle {
+    /// Creates a bundle with the commits selected by the revisions, as `git log` would select them.
+    ///
+    /// The revisions may be refs (`master`, `v1.0`, `HEAD`), ranges (`v1.0..master`), exclusions
+    /// (`^v1.0`) or `--all`. The refs given (or the ends of the ranges) are the references of the
+    /// bundle, and the parents of the selected commits that were excluded are its prerequisites.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a revision can't be resolved, or no commit or no reference is selected.
+    ///
+    pub fn create(git_dir: &str, revisions: &[String]) -> io::Result<Bundle> {
+        let options = LogOptions {
+            all: revisions.iter().any(|revision| revision == "--all"),
+            revisions: revisions
+                .iter()
+                .filter(|revision| *revision != "--all")
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+        let commits = log::log_with_options(git_dir, &options)?;
+        let selected: HashSet<&str> = commits.iter().map(|commit| commit.hash()).collect();
+        let mut bundle = Bundle::default();
+        for parent in commits.iter().flat_map(|commit| commit.parents()) {
+            if !selected.contains(parent.as_str())
+                && !bundle.prerequisites.iter().any(|(hash, _)| hash == parent)
+            {
+                let subject = log::Log::load(Some(parent), git_dir)?.subject().to_string();
+                bundle.prerequisites.push((parent.clone(), subject));
+            }
+        }
+
+        let mut names: Vec<String> = options
+            .revisions
+            .iter()
+            .filter(|revision| !revision.starts_with('^'))
+            .map(|revision| match revision.split_once("..") {
+                Some((_, "")) => "HEAD".to_string(),
+                Some((_, to)) => to.to_string(),
+                None => revision.clone(),
+            })
+            .collect();
+        if options.all {
+            names.push("HEAD".to_string());
+            names.extend(utils::list_refs(git_dir)?.into_iter().map(|(name, _)| name));
+        }
+        for name in names {
+            if let Some(reference) = full_reference(&name, git_dir) {
+                if !bundle.references.contains(&reference) {
+                    bundle.references.push(reference);
+                }
+            }
+        }
+        if commits.is_empty() || bundle.references.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fatal: Refusing to create empty bundle.",
+            ));
+        }
+
+        let mut objects = HashSet::new();
+        for commit in &commits {
+            objects.insert(commit.hash().to_string());
+            objects.extend(get_objects_tree_objects(commit.tree_hash(), git_dir)?);
+        }
+        // The references to annotated tags need the objects of the tags too.
+        objects.extend(bundle.references.iter().map(|(hash, _)| hash.clone()));
+        let mut objects: Vec<String> = objects.into_iter().collect();
+        objects.sort();
+        bundle.packfile = create_packfile(&objects, git_dir)?;
+        Ok(bundle)
+    }
+
+    /// Reads a bundle from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or is not a bundle of version 2.
+    ///
+    pub fn read(path: &str) -> io::Result<Bundle> {
+        Bundle::from_bytes(&fs::read(path)?)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path, error)))
+    }
+
+    /// Parses the content of a bundle file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content doesn't start with the signature of a bundle of version 2,
+    /// or a line of the header is not valid.
+    ///
+    pub fn from_bytes(content: &[u8]) -> io::Result<Bundle> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut bundle = Bundle::default();
+        let mut position = 0;
+        let mut next_line = || -> io::Result<String> {
+            let end = content[position..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .ok_or_else(|| invalid("the bundle header is not complete"))?;
+            let line = String::from_utf8_lossy(&content[position..position + end]).to_string();
+            position += end + 1;
+            Ok(line)
+        };
+        if next_line()? != BUNDLE_SIGNATURE {
+            return Err(invalid("not a v2 bundle file"));
+        }
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+            let (line, is_prerequisite) = match line.strip_prefix('-') {
+                Some(prerequisite) => (prerequisite, true),
+                None => (line.as_str(), false),
+            };
+            let (hash, text) = line.split_once(' ').unwrap_or((line, ""));
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid(&format!("unrecognized header: {}", line)));
+            }
+            let entry = (hash.to_string(), text.to_string());
+            if is_prerequisite {
+                bundle.prerequisites.push(entry);
+            } else {
+                bundle.references.push(entry);
+            }
+        }
+        bundle.packfile = content[position..].to_vec();
+        Ok(bundle)
+    }
+
+    /// Returns the content of the bundle file: the header followed by the packfile.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut header = format!("{}\n", BUNDLE_SIGNATURE);
+        for (hash, subject) in &self.prerequisites {
+            header.push_str(&format!("-{} {}\n", hash, subject));
+        }
+        for (hash, name) in &self.references {
+            header.push_str(&format!("{} {}\n", hash, name));
+        }
+        header.push('\n');
+        [header.into_bytes(), self.packfile.clone()].concat()
+    }
+
+    /// Checks that the packfile is complete and the repository has every prerequisite of the bundle,
+    /// so it can be unpacked in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing the missing prerequisites, or if the packfile is corrupt.
+    ///
+    pub fn verify(&self, git_dir: &str) -> io::Result<()> {
+        let split = self.packfile.len().saturating_sub(20);
+        let (pack, checksum) = self.packfile.split_at(split);
+        if !pack.starts_with(b"PACK") || Sha1::digest(pack).as_slice() != checksum {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "error: the bundle has a corrupt packfile",
+            ));
+        }
+        let missing: Vec<String> = self
+            .prerequisites
+            .iter()
+            .filter(|(hash, _)| cat_file::cat_file_return_type(hash, git_dir).is_err())
+            .map(|(hash, subject)| format!("error: {} {}", hash, subject))
+            .collect();
+        if !missing.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "error: Repository lacks these prerequisite commits:\n{}",
+                    missing.join("\n")
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verifies the bundle and stores its objects in the repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bundle can't be verified or its packfile can't be unpacked.
+    ///
+    pub fn unbundle(&self, git_dir: &str) -> io::Result<()> {
+        self.verify(git_dir)?;
+        // The packfiles are unpacked as they come from the server, after the byte of their side-band.
+        unpack_packfile(&[vec![1], self.packfile.clone()].concat(), git_dir)
+    }
+}
+
+/// Returns the complete name of a reference and the hash it points to (without peeling annotated tags),
+/// or `None` if the name is not a reference.
+fn full_reference(name: &str, git_dir: &str) -> Option<(String, String)> {
+    if name == "HEAD" {
+        let hash = utils::resolve_revision(name, git_dir).ok()?;
+        return Some((hash, name.to_string()));
+    }
+    [
+        name.to_string(),
+        format!("refs/heads/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/remotes/{}", name),
+    ]
+    .into_iter()
+    .filter(|reference| reference.starts_with("refs/"))
+    .find_map(|reference| {
+        let hash = fs::read_to_string(Path::new(git_dir).join(&reference)).ok()?;
+        Some((hash.trim().to_string(), reference))
+    })
+}
+
+/// Unbundles a bundle and updates the refs of the repository with its references: the branches
+/// as remote-tracking branches of `remote` and the tags as tags. The fetched branches are written
+/// in FETCH_HEAD.
+///
+/// # Returns
+///
+/// Returns the names of the fetched branches.
+///
+fn store_bundle(
+    git_dir: &str,
+    bundle: &Bundle,
+    bundle_path: &str,
+    remote: &str,
+) -> io::Result<Vec<String>> {
+    bundle.unbundle(git_dir)?;
+    let mut branches = Vec::new();
+    let mut fetch_head = fetch::FetchHead::new();
+    for (hash, name) in &bundle.references {
+        let reference = if let Some(branch) = name.strip_prefix("refs/heads/") {
+            fetch_head.add_entry(fetch::FetchEntry::new(hash, branch, bundle_path));
+            branches.push(branch.to_string());
+            format!("refs/remotes/{}/{}", remote, branch)
+        } else if name.starts_with("refs/tags/") {
+            name.clone()
+        } else {
+            continue;
+        };
+        let path = Path::new(git_dir).join(reference);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{}\n", hash))?;
+    }
+    fetch_head.write_file(&format!("{}/FETCH_HEAD", git_dir))?;
+    Ok(branches)
+}
+
+/// Fetches the branches and tags of a bundle file into a repository, as `git fetch` does with a remote.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `bundle_path` - The path to the bundle file.
+///
+/// # Returns
+///
+/// Returns the names of the fetched branches, which are stored as remote-tracking branches of origin.
+///
+/// # Errors
+///
+/// Returns an error if the bundle can't be read or verified, or the refs can't be written.
+///
+pub fn fetch_bundle(git_dir: &str, bundle_path: &str) -> io::Result<Vec<String>> {
+    fetch::log_fetch(Some(bundle_path), "", git_dir)?;
+    let bundle = Bundle::read(bundle_path)?;
+    store_bundle(git_dir, &bundle, bundle_path, REMOTE)
+}
+
+/// Clones a bundle file into a new repository: the branches of the bundle are stored as
+/// remote-tracking branches of origin, whose URL is the bundle, and the branch HEAD points to
+/// is checked out (see `bundle_head`).
+///
+/// # Arguments
+///
+/// * `bundle_path` - The path to the bundle file.
+/// * `working_dir` - The path to the working tree of the new repository.
+///
+/// # Errors
+///
+/// Returns an error if the bundle can't be read or has no branch, or the repository can't be created.
+///
+pub fn clone_bundle(bundle_path: &str, working_dir: &str) -> io::Result<()> {
+    clone::log_clone(bundle_path, working_dir)?;
+    let bundle = Bundle::read(bundle_path)?;
+    let (branch, commit) = bundle_head(&bundle).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "warning: remote HEAD refers to nonexistent ref, unable to checkout",
+        )
+    })?;
+    init::git_init(working_dir, GIT_DIR, branch, None)?;
+    let git_dir = format!("{}/{}", working_dir, GIT_DIR);
+    store_bundle(&git_dir, &bundle, bundle_path, REMOTE)?;
+    clone::checkout_new_branch(&git_dir, working_dir, branch, commit)?;
+    let mut config_file = config::Config::load(&git_dir)?;
+    config_file.add_remote(
+        REMOTE.to_string(),
+        bundle_path.to_string(),
+        format!("+refs/heads/*:refs/remotes/{}/*", REMOTE),
+        &mut io::stdout(),
+    )?;
+    config_file.add_branch(
+        branch.to_string(),
+        REMOTE.to_string(),
+        format!("/refs/heads/{}", branch),
+        &mut io::stdout(),
+    )
+}
+
+/// Returns the branch of a bundle that is checked out when it is cloned, and its commit, as git
+/// guesses it: the branch at the same commit as the HEAD of the bundle (master if there are
+/// several), or the first branch if the bundle has no HEAD. Returns None if it has no branches.
+fn bundle_head(bundle: &Bundle) -> Option<(&str, &str)> {
+    let branches: Vec<(&str, &str)> = bundle
+        .references
+        .iter()
+        .filter_map(|(hash, name)| Some((name.strip_prefix("refs/heads/")?, hash.as_str())))
+        .collect();
+    let head = bundle
+        .references
+        .iter()
+        .find(|(_, name)| name == "HEAD")
+        .map(|(hash, _)| hash.as_str());
+    let at_head: Vec<(&str, &str)> = branches
+        .iter()
+        .copied()
+        .filter(|(_, hash)| Some(*hash) == head)
+        .collect();
+    at_head
+        .iter()
+        .find(|(branch, _)| *branch == INITIAL_BRANCH)
+        .or(at_head.first())
+        .or(branches.first())
+        .copied()
+}
+
+/// Writes the references of a bundle, one `<hash> <name>` per line. If names are given, only the
+/// references that end with one of them are written.
+fn list_heads(bundle: &Bundle, names: &[String], output: &mut impl Write) -> io::Result<()> {
+    for (hash, name) in &bundle.references {
+        if names.is_empty()
+            || names
+                .iter()
+                .any(|wanted| name == wanted || name.ends_with(&format!("/{}", wanted)))
+        {
+            writeln!(output, "{} {}", hash, name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates and reads bundles, to move the objects of a repository without a connection to it.
+///
+/// * `create <file> <revisions>...` writes a bundle with the commits selected by the revisions.
+/// * `verify <file>` checks that the bundle is valid and can be unpacked in the repository.
+/// * `list-heads <file> [<names>...]` lists the references of the bundle.
+/// * `unbundle <file>` stores the objects of the bundle in the repository and lists its references.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments of the command, the ones after 'bundle'.
+/// * `output` - Where the results are written.
+///
+/// # Errors
+///
+/// Returns an error if the subcommand is unknown, the bundle can't be created or read, or it is not valid.
+///
+pub fn git_bundle(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    log_bundle(git_dir, args)?;
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: git bundle (create <file> <git-rev-list args> | verify <file> | list-heads <file> [<refname>...] | unbundle <file>)",
+        )
+    };
+    let subcommand = args.first().ok_or_else(usage)?;
+    let file = args.get(1).ok_or_else(usage)?;
+    let rest = args.get(2..).unwrap_or_default();
+    match subcommand.as_str() {
+        "create" => {
+            let bundle = Bundle::create(git_dir, rest)?;
+            fs::write(file, bundle.to_bytes())
+        }
+        "verify" => {
+            let bundle = Bundle::read(file)?;
+            bundle.verify(git_dir)?;
+            let (count, plural) = match bundle.references.len() {
+                1 => ("this".to_string(), ""),
+                count => (format!("these {}", count), "s"),
+            };
+            writeln!(output, "The bundle contains {} ref{}:", count, plural)?;
+            list_heads(&bundle, &[], output)?;
+            if bundle.prerequisites.is_empty() {
+                writeln!(output, "The bundle records a complete history.")?;
+            } else {
+                let (count, plural) = match bundle.prerequisites.len() {
+                    1 => ("this".to_string(), ""),
+                    count => (format!("these {}", count), "s"),
+                };
+                writeln!(output, "The bundle requires {} ref{}:", count, plural)?;
+                for (hash, subject) in &bundle.prerequisites {
+                    writeln!(output, "{} {}", hash, subject)?;
+                }
+            }
+            writeln!(output, "{} is okay", file)
+        }
+        "list-heads" => list_heads(&Bundle::read(file)?, rest, output),
+        "unbundle" => {
+            let bundle = Bundle::read(file)?;
+            bundle.unbundle(git_dir)?;
+            list_heads(&bundle, rest, output)
+        }
+        _ => Err(usage()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        branch,
+        test_utils::{commit_file, create_repo},
+    };
+
+    fn run_bundle(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_bundle(git_dir, &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// Creates a repository with two commits, `full.bundle` with the first one and `range.bundle`
+    /// with the second one, and returns its git directory, both commits and both bundles.
+    fn create_bundles(root_dir: &str) -> io::Result<(String, [String; 2], [String; 2])> {
+        let git_dir = create_repo(root_dir)?;
+        let file = format!("{}/a.txt", root_dir);
+        let first = commit_file(&git_dir, &file, "uno\n", "Primero")?;
+        let full = format!("{}/full.bundle", root_dir);
+        run_bundle(&git_dir, &["create", &full, "master"])?;
+        let second = commit_file(&git_dir, &file, "uno\ndos\n", "Segundo")?;
+        let range = format!("{}/range.bundle", root_dir);
+        run_bundle(&git_dir, &["create", &range, "master~1..master"])?;
+        Ok((git_dir, [first, second], [full, range]))
+    }
+
+    #[test]
+    fn test_bundles_are_verified_and_list_their_heads() -> io::Result<()> {
+        let root_dir = "tests/bundle_verify";
+        let (git_dir, [first, second], [full, range]) = create_bundles(root_dir)?;
+
+        let verified = run_bundle(&git_dir, &["verify", &range]);
+        let heads = run_bundle(&git_dir, &["list-heads", &full]);
+        let empty = run_bundle(&git_dir, &["create", &range, "master..master"]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            verified?,
+            format!(
+                "The bundle contains this ref:\n{} refs/heads/master\nThe bundle requires this ref:\n{} Primero\n{} is okay\n",
+                second, first, range
+            )
+        );
+        assert_eq!(heads?, format!("{} refs/heads/master\n", first));
+        assert!(empty.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundles_are_cloned_and_fetched_without_a_server() -> io::Result<()> {
+        let root_dir = "tests/bundle_source";
+        let clone_dir = "tests/bundle_clone";
+        let _ = fs::remove_dir_all(clone_dir);
+        let (_, [first, second], [full, range]) = create_bundles(root_dir)?;
+
+        let cloned = clone_bundle(&full, clone_dir);
+        let clone_git_dir = format!("{}/{}", clone_dir, GIT_DIR);
+        let cloned_master = fs::read_to_string(format!("{}/refs/heads/master", clone_git_dir));
+        let fetched = fetch_bundle(&clone_git_dir, &range);
+        let tracking = fs::read_to_string(format!("{}/refs/remotes/origin/master", clone_git_dir));
+        let fetched_content = cat_file::cat_file_return_content(&second, &clone_git_dir);
+        fs::remove_dir_all(root_dir)?;
+        fs::remove_dir_all(clone_dir)?;
+
+        cloned?;
+        assert_eq!(cloned_master?, first);
+        assert_eq!(fetched?, vec!["master".to_string()]);
+        assert_eq!(tracking?, format!("{}\n", second));
+        assert!(fetched_content?.contains("Segundo"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cloned_bundles_check_out_the_branch_of_their_head() -> io::Result<()> {
+        let root_dir = "tests/bundle_head_source";
+        let clone_dir = "tests/bundle_head_clone";
+        let _ = fs::remove_dir_all(clone_dir);
+        let (git_dir, [first, second], _) = create_bundles(root_dir)?;
+        branch::update_branch_commit_hash("topic", &first, &git_dir)?;
+        let topic = format!("{}/topic.bundle", root_dir);
+        run_bundle(&git_dir, &["create", &topic, "topic"])?;
+        let with_head = format!("{}/head.bundle", root_dir);
+        run_bundle(&git_dir, &["create", &with_head, "topic", "master", "HEAD"])?;
+        let no_branches = format!("{}/tags.bundle", root_dir);
+        fs::write(format!("{}/refs/tags/v1", git_dir), &first)?;
+        run_bundle(&git_dir, &["create", &no_branches, "v1"])?;
+
+        let cloned_topic = clone_bundle(&topic, clone_dir);
+        let clone_git_dir = format!("{}/{}", clone_dir, GIT_DIR);
+        let topic_head = fs::read_to_string(format!("{}/HEAD", clone_git_dir));
+        let topic_branch = fs::read_to_string(format!("{}/refs/heads/topic", clone_git_dir));
+        let topic_file = fs::read_to_string(format!("{}/{}/a.txt", clone_dir, root_dir));
+        fs::remove_dir_all(clone_dir)?;
+        // The HEAD of the bundle is at master, not at its first branch.
+        let cloned_head = clone_bundle(&with_head, clone_dir);
+        let head_branch = fs::read_to_string(format!("{}/refs/heads/master", clone_git_dir));
+        fs::remove_dir_all(clone_dir)?;
+        let cloned_tags = clone_bundle(&no_branches, clone_dir);
+        fs::remove_dir_all(root_dir)?;
+        let _ = fs::remove_dir_all(clone_dir);
+
+        cloned_topic?;
+        assert!(topic_head?.contains("refs/heads/topic"));
+        assert_eq!(topic_branch?, first);
+        assert_eq!(topic_file?, "uno\n");
+        cloned_head?;
+        assert_eq!(head_branch?, second);
+        assert!(cloned_tags.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundles_of_other_versions_are_rejected() {
+        assert!(Bundle::from_bytes(b"# v3 git bundle\n\n").is_err());
+    }
+
+    #[test]
+    fn test_unbundle_requires_the_prerequisites() -> io::Result<()> {
+        let root_dir = "tests/bundle_prerequisites";
+        let other_dir = "tests/bundle_prerequisites_other";
+        let git_dir = create_repo(root_dir)?;
+        let other_git_dir = create_repo(other_dir)?;
+        let file = format!("{}/a.txt", root_dir);
+        commit_file(&git_dir, &file, "uno\n", "Primero")?;
+        let second = commit_file(&git_dir, &file, "uno\ndos\n", "Segundo")?;
+        let revisions = ["^master~1".to_string(), "master".to_string()];
+        let bundle = Bundle::create(&git_dir, &revisions);
+        let parsed = bundle
+            .as_ref()
+            .ok()
+            .map(|bundle| Bundle::from_bytes(&bundle.to_bytes()));
+        let unbundled = bundle
+            .as_ref()
+            .ok()
+            .map(|bundle| bundle.unbundle(&other_git_dir));
+        fs::remove_dir_all(root_dir)?;
+        fs::remove_dir_all(other_dir)?;
+
+        let bundle = bundle?;
+        assert_eq!(
+            bundle.references,
+            vec![(second, "refs/heads/master".to_string())]
+        );
+        assert_eq!(bundle.prerequisites.len(), 1);
+        assert_eq!(parsed.transpose()?, Some(bundle));
+        let error = unbundled.and_then(|result| result.err());
+        assert_eq!(
+            error.map(|error| error.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+        Ok(())
+    }
+}
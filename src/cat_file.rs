@@ -76,6 +76,62 @@ pub fn cat_file_return_content(hash: &str, directory: &str) -> io::Result<String
     }
 }
 
+/// Returns the content of an object as raw bytes, without the header.
+/// Unlike `cat_file_return_content`, it works for objects that are not valid UTF-8, such as images.
+///
+/// ## Parameters
+/// * `hash` - The complete hash of the object.
+/// * `directory` - The path to the git directory.
+pub fn cat_file_return_bytes(hash: &str, directory: &str) -> io::Result<Vec<u8>> {
+    if hash.len() < 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid object hash: {}", hash),
+        ));
+    }
+    let file = File::open(format!(
+        "{}/objects/{}/{}",
+        directory,
+        &hash[..2],
+        &hash[2..]
+    ))?;
+    let mut decompressor = ZlibDecoder::new(BufReader::new(file));
+    let mut content = Vec::new();
+    decompressor.read_to_end(&mut content)?;
+    match content.iter().position(|byte| *byte == 0) {
+        Some(header_end) => Ok(content.split_off(header_end + 1)),
+        None => Ok(content),
+    }
+}
+
+/// Returns the type of an object as written in its header: "blob", "tree", "commit" or "tag".
+///
+/// ## Parameters
+/// * `hash` - The complete hash of the object.
+/// * `directory` - The path to the git directory.
+pub fn cat_file_return_type(hash: &str, directory: &str) -> io::Result<String> {
+    if hash.len() < 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid object hash: {}", hash),
+        ));
+    }
+    let file = File::open(format!(
+        "{}/objects/{}/{}",
+        directory,
+        &hash[..2],
+        &hash[2..]
+    ))?;
+    let content = decompress_into_bytes(file)?;
+    match content.iter().position(|byte| *byte == b' ') {
+        Some(type_end) => Ok(String::from_utf8_lossy(&content[..type_end]).to_string()),
+        None => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid object header: {}", hash),
+        )),
+    }
+}
+
 /// Extracts and parses the content of a Git tree object.
 ///
 /// This function reads and parses the content of a Git tree object identified by its `hash` from the local
@@ -198,6 +254,13 @@ mod tests {
         assert_eq!(content, "Hello World!");
     }
 
+    #[test]
+    fn test_cat_file_return_type() {
+        let hash = "c57eff55ebc0c54973903af5f72bac72762cf4f4";
+        let object_type = cat_file_return_type(hash, "tests/cat_file").unwrap();
+        assert_eq!(object_type, "blob");
+    }
+
     #[test]
     fn test_decompress_file() {
         let file =
@@ -1,8 +1,10 @@
 use crate::branch;
 use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::hooks;
 use crate::logger::Logger;
+use crate::restore::{self, RestoreOptions};
 use crate::tree_handler;
-use crate::utils::get_current_time;
+use crate::utils::{self, get_current_time};
 use std::fs;
 use std::io;
 use std::io::Write;
@@ -135,7 +137,7 @@ fn checkout_branch_references(git_dir: &Path, branch_name: &str) -> io::Result<S
         ));
     }
 
-    let old_commit_id = branch::get_current_branch_commit(git_dir_str)?;
+    let old_commit_id = head_commit(git_dir_str)?;
     let head_file = git_dir.join("HEAD");
     let new_head_content = format!("ref: refs/heads/{}\n", branch_name);
     fs::write(head_file, new_head_content)?;
@@ -295,6 +297,21 @@ pub fn checkout_commit_detached(git_dir: &Path, root_dir: &str, commit_id: &str)
     }
 }
 
+/// Returns the commit HEAD points to, either through the current branch or directly if it's detached.
+fn head_commit(git_dir_str: &str) -> io::Result<String> {
+    let head = fs::read_to_string(Path::new(git_dir_str).join("HEAD"))?;
+    if head.starts_with("ref: ") {
+        return branch::get_current_branch_commit(git_dir_str);
+    }
+    match head.split_whitespace().next() {
+        Some(commit_id) => Ok(commit_id.to_string()),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "HEAD file is empty\n",
+        )),
+    }
+}
+
 /// Checks out a specific commit in detached HEAD mode in the local Git repository.
 ///
 /// This function enables checking out a specific Git commit identified by `commit_id` in detached HEAD mode within
@@ -313,7 +330,7 @@ pub fn checkout_commit_detached(git_dir: &Path, root_dir: &str, commit_id: &str)
 ///
 fn checkout_commit_detached_references(git_dir_str: &str, commit_id: &str) -> io::Result<String> {
     let head_file = git_dir_str.to_string() + "/HEAD";
-    let old_commit_id = branch::get_current_branch_commit(git_dir_str)?;
+    let old_commit_id = head_commit(git_dir_str)?;
     let new_head_content = format!("{} (commit)\n", commit_id);
     fs::write(head_file, new_head_content)?;
     Ok(old_commit_id)
@@ -353,6 +370,72 @@ fn replace_working_tree(
     Ok(())
 }
 
+/// Checks out some paths, leaving HEAD and every other path as they are (`git checkout [<rev>] -- <paths>`).
+///
+/// With a revision, the files of the paths are taken from its tree into both the index and the working
+/// tree. Without one, the files of the working tree are restored from the index. As in git, the files of
+/// the paths that the revision doesn't have are kept.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `revision` - The revision the files are taken from, if any.
+/// * `paths` - The paths to check out.
+///
+/// # Errors
+///
+/// Returns an error if the revision can't be resolved, a path doesn't match any known file, or a file
+/// can't be written.
+///
+pub fn checkout_paths(
+    git_dir: &str,
+    root_dir: &str,
+    revision: Option<&str>,
+    paths: &[String],
+) -> io::Result<()> {
+    let current_branch = branch::get_current_branch_path(git_dir).unwrap_or_default();
+    log_checkout(
+        &current_branch,
+        revision.unwrap_or("index"),
+        &format!(" -- {}", paths.join(" ")),
+        Path::new(git_dir),
+    )?;
+    let options = RestoreOptions {
+        staged: revision.is_some(),
+        worktree: true,
+        source: revision.map(str::to_string),
+        overlay: true,
+        paths: paths.to_vec(),
+    };
+    restore::restore(git_dir, root_dir, &options)
+}
+
+/// Runs the `post-checkout` hook after a checkout, with the commit HEAD pointed to before it, the
+/// one it points to now, and whether branches (`1`) or only paths (`0`) were checked out. As in git,
+/// its result doesn't change the outcome of the checkout.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `previous_head` - The commit HEAD pointed to before the checkout, if there was one.
+/// * `branch_checkout` - True if HEAD was moved, false if only some paths were checked out.
+///
+pub fn run_post_checkout_hook(git_dir: &str, previous_head: Option<&str>, branch_checkout: bool) {
+    let new_head = utils::resolve_revision("HEAD", git_dir).unwrap_or_default();
+    let new_head = match new_head.is_empty() {
+        true => hooks::ZERO_HASH,
+        false => new_head.as_str(),
+    };
+    let flag = if branch_checkout { "1" } else { "0" };
+    let _ = hooks::run_hook(
+        git_dir,
+        "post-checkout",
+        &[previous_head.unwrap_or(hooks::ZERO_HASH), new_head, flag],
+        None,
+    );
+}
+
 /// Forcefully switch to a specific branch or commit in a Git-like repository.
 ///
 /// This function allows you to forcibly switch to a specific branch or commit in a Git-like
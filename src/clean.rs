@@ -0,0 +1,294 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    configuration::{GIT_IGNORE, LOGGER_COMMANDS_FILE},
+    ignorer::{is_subpath, Ignorer},
+    index::Index,
+    logger::Logger,
+    status, utils,
+};
+
+/// Logs the 'git clean' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_clean(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git clean': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git clean` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CleanOptions {
+    /// If true (`-n`), the paths that would be removed are listed but nothing is removed.
+    pub dry_run: bool,
+    /// If true (`-f`), the paths are removed.
+    pub force: bool,
+    /// If true (`-d`), the untracked directories are removed too.
+    pub directories: bool,
+    /// If true (`-x`), the ignored paths are removed too.
+    pub remove_ignored: bool,
+    /// If true (`-X`), only the ignored paths are removed.
+    pub only_ignored: bool,
+    /// Only the paths inside these ones are removed. If it's empty, every path can be.
+    pub paths: Vec<String>,
+}
+
+impl CleanOptions {
+    /// Parses the arguments of `git clean` (the ones after 'clean').
+    /// Short options can be combined, as in `-fdx`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<CleanOptions> {
+        let mut options = CleanOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    options.paths.extend(args.by_ref().cloned());
+                }
+                "--dry-run" => options.dry_run = true,
+                "--force" => options.force = true,
+                _ if arg.starts_with('-') && arg.len() > 1 && !arg.starts_with("--") => {
+                    for flag in arg[1..].chars() {
+                        match flag {
+                            'n' => options.dry_run = true,
+                            'f' => options.force = true,
+                            'd' => options.directories = true,
+                            'x' => options.remove_ignored = true,
+                            'X' => options.only_ignored = true,
+                            _ => {
+                                return Err(io::Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    format!("error: unknown switch `{}'", flag),
+                                ))
+                            }
+                        }
+                    }
+                }
+                _ if arg.starts_with('-') => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("error: unknown option `{}'", arg.trim_start_matches('-')),
+                    ))
+                }
+                _ => options.paths.push(arg.clone()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Returns the untracked paths `git clean` removes, relative to the working tree and sorted.
+/// The directories end with '/'.
+///
+/// Without `-d`, the untracked directories (the ones without tracked files) are left as they are, with
+/// everything inside them. With `-d`, they are removed as a whole when everything inside them has to
+/// be removed, and otherwise the paths inside them are looked at one by one.
+///
+/// # Arguments
+///
+/// * `root_dir` - The path to the working tree.
+/// * `index` - The index with the tracked files.
+/// * `ignorer` - Tells which paths are ignored.
+/// * `options` - Which kinds of paths are removed, and inside which paths.
+///
+/// # Errors
+///
+/// Returns an error if a directory can't be read.
+///
+pub fn find_paths_to_clean(
+    root_dir: &str,
+    index: &Index,
+    ignorer: &Ignorer,
+    options: &CleanOptions,
+) -> io::Result<Vec<String>> {
+    let root = Path::new(root_dir);
+    let mut untracked: Vec<(String, bool)> = status::find_untracked_paths(root, root, index)?
+        .into_iter()
+        .map(|path| {
+            (
+                path.to_string_lossy().to_string(),
+                root.join(&path).is_dir(),
+            )
+        })
+        .collect();
+    untracked.sort();
+
+    let is_selected = |path: &str| {
+        let ignored = ignorer.ignore(path);
+        let kind_selected = match options.only_ignored {
+            true => ignored,
+            false => options.remove_ignored || !ignored,
+        };
+        kind_selected
+            && (options.paths.is_empty()
+                || options
+                    .paths
+                    .iter()
+                    .any(|selected| is_subpath(path, selected)))
+    };
+    let mut paths = Vec::new();
+    let mut skipped_dirs: Vec<&str> = Vec::new();
+    for (path, is_dir) in &untracked {
+        if skipped_dirs.iter().any(|dir| is_subpath(path, dir)) {
+            continue;
+        }
+        if !is_dir {
+            if is_selected(path) {
+                paths.push(path.clone());
+            }
+            continue;
+        }
+        if index.iter().any(|(tracked, _)| is_subpath(tracked, path)) {
+            continue;
+        }
+        if !options.directories {
+            skipped_dirs.push(path);
+            continue;
+        }
+        let whole = untracked
+            .iter()
+            .filter(|(inner, _)| is_subpath(inner, path))
+            .all(|(inner, _)| is_selected(inner));
+        if whole {
+            paths.push(format!("{}/", path));
+            skipped_dirs.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Removes the untracked files of the working tree, or lists them with `-n`.
+///
+/// `-d` removes the untracked directories too, `-x` also the ignored files and `-X` only the ignored
+/// files. The paths after the options limit what is removed. Without `-f` or `-n` nothing is done,
+/// so the files can't be removed by mistake.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `args` - The arguments of the command, the ones after 'clean'.
+/// * `output` - Where the removed paths are written.
+///
+/// # Errors
+///
+/// Returns an error if there is no `-f` or `-n`, an option is invalid, or a path can't be removed.
+///
+pub fn git_clean(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    log_clean(git_dir, args)?;
+    let options = CleanOptions::parse(args)?;
+    if !options.force && !options.dry_run {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: clean.requireForce defaults to true and neither -n nor -f given; refusing to clean",
+        ));
+    }
+    let index_path = utils::get_index_file_path(git_dir);
+    let index = Index::load(&index_path, git_dir, "")?;
+    let ignorer = Ignorer::load(&Path::new(root_dir).join(GIT_IGNORE).to_string_lossy());
+    for path in find_paths_to_clean(root_dir, &index, &ignorer, &options)? {
+        if options.dry_run {
+            writeln!(output, "Would remove {}", path)?;
+            continue;
+        }
+        writeln!(output, "Removing {}", path)?;
+        let full_path = Path::new(root_dir).join(&path);
+        if path.ends_with('/') {
+            fs::remove_dir_all(full_path)?;
+        } else {
+            fs::remove_file(full_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_object;
+
+    fn run_clean(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_clean(git_dir, root_dir, &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    #[test]
+    fn test_clean_removes_untracked_and_ignored_paths() -> io::Result<()> {
+        let root_dir = "tests/clean_repo";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/src", root_dir))?;
+        fs::create_dir_all(format!("{}/build/debug", root_dir))?;
+        fs::create_dir_all(format!("{}/notas", root_dir))?;
+        fs::write(format!("{}/index", git_dir), "")?;
+        fs::write(format!("{}/{}", root_dir, GIT_IGNORE), "build\n")?;
+        let mut index = Index::load(&format!("{}/index", git_dir), &git_dir, "")?;
+        for path in ["a.txt", "src/main.rs"] {
+            fs::write(format!("{}/{}", root_dir, path), "tracked")?;
+            index.add_file(path, &hash_object::hash_content(b"tracked", "blob"))?;
+        }
+        index.write_file()?;
+        for path in ["b.txt", "src/tmp.rs", "notas/todo.txt", "build/debug/app"] {
+            fs::write(format!("{}/{}", root_dir, path), "untracked")?;
+        }
+
+        let refused = run_clean(&git_dir, root_dir, &[]);
+        let dry_run = run_clean(&git_dir, root_dir, &["-n"]);
+        let only_ignored = run_clean(&git_dir, root_dir, &["-n", "-dX"]);
+        let in_path = run_clean(&git_dir, root_dir, &["-f", "--", "src"]);
+        let removed = run_clean(&git_dir, root_dir, &["-fdx"]);
+        let mut remaining: Vec<String> = fs::read_dir(root_dir)?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        remaining.sort();
+        let src_remaining = fs::read_dir(format!("{}/src", root_dir))?.count();
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(refused.is_err());
+        assert_eq!(dry_run?, "Would remove b.txt\nWould remove src/tmp.rs\n");
+        assert_eq!(only_ignored?, "Would remove build/\n");
+        assert_eq!(in_path?, "Removing src/tmp.rs\n");
+        assert_eq!(
+            removed?,
+            "Removing b.txt\nRemoving build/\nRemoving notas/\n"
+        );
+        assert_eq!(remaining, vec![".mgit", GIT_IGNORE, "a.txt", "src"]);
+        assert_eq!(src_remaining, 1);
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use std::{
     path::PathBuf,
 };
 
-use crate::{packfile, server_utils::*};
+use crate::{hooks, packfile, server_utils::*};
 
 const VERSION: &str = "1";
 const GIT_UPLOAD_PACK: &str = "git-upload-pack";
@@ -24,6 +24,7 @@ pub struct Client {
     git_dir: String,
     remote: String,
     server_refs: HashMap<String, String>,
+    pre_push: Option<(String, String)>,
 }
 
 /// This is a git client that is able to connect to a git server
@@ -49,6 +50,19 @@ impl Client {
         }
     }
 
+    /// Makes the pushes run the `pre-push` hook before sending anything, with the name and the url
+    /// of the remote as arguments and the pushed ref in its standard input. If the hook fails, the
+    /// push is aborted.
+    ///
+    /// # Arguments
+    ///
+    /// - `remote_name`: The name of the remote, for example `origin`.
+    /// - `remote_url`: The url of the remote.
+    ///
+    pub fn set_pre_push_hook(&mut self, remote_name: &str, remote_url: &str) {
+        self.pre_push = Some((remote_name.to_string(), remote_url.to_string()));
+    }
+
     // Establish a connection with the server and asks for the refs in the remote.
     // A hashmap with the path of the refs as keys and the last commit hash as values is returned.
     //
@@ -185,6 +199,22 @@ impl Client {
             log("Already up to date.")?;
             return Ok(());
         }
+        if let Some((remote_name, remote_url)) = &self.pre_push {
+            let remote_hash = match prev_hash.is_empty() {
+                true => ZERO_HASH,
+                false => &prev_hash,
+            };
+            let input = format!(
+                "{} {} {} {}\n",
+                pushing_ref, new_hash, pushing_ref, remote_hash
+            );
+            hooks::run_hook(
+                git_dir,
+                "pre-push",
+                &[remote_name, remote_url],
+                Some(&input),
+            )?;
+        }
         if prev_hash.is_empty() {
             self.receive_pack_create(&pushing_ref, new_hash)?;
         } else {
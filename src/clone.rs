@@ -105,12 +105,28 @@ fn get_clean_refs(refs: HashMap<String, String>) -> Vec<String> {
 /// - If there is an issue while loading the default branch commit or the commit tree.
 /// - If there is an issue while creating directories or building the index file.
 ///
-fn create_working_dir(local_git_dir: &str, working_dir: &str) -> io::Result<()> {
+pub fn create_working_dir(local_git_dir: &str, working_dir: &str) -> io::Result<()> {
     let default_branch_commit = get_default_branch_commit(local_git_dir)?;
-    let commit_tree = tree_handler::load_tree_from_commit(&default_branch_commit, local_git_dir)?;
-    let branch_file_path = local_git_dir.to_string() + "/refs/heads/master";
+    checkout_new_branch(
+        local_git_dir,
+        working_dir,
+        INITIAL_BRANCH,
+        &default_branch_commit,
+    )
+}
+
+/// Creates the branch `branch` at `commit` and writes the files of the commit to the working
+/// tree and the index of a new repository.
+pub fn checkout_new_branch(
+    local_git_dir: &str,
+    working_dir: &str,
+    branch: &str,
+    commit: &str,
+) -> io::Result<()> {
+    let commit_tree = tree_handler::load_tree_from_commit(commit, local_git_dir)?;
+    let branch_file_path = local_git_dir.to_string() + "/refs/heads/" + branch;
     let mut branch_file = std::fs::File::create(branch_file_path)?;
-    branch_file.write_all(default_branch_commit.as_bytes())?;
+    branch_file.write_all(commit.as_bytes())?;
     commit_tree.create_directories(working_dir, local_git_dir)?;
     let index_path = local_git_dir.to_string() + "/index";
     let gitignore_path = working_dir.to_string() + "/.gitignore";
@@ -4,12 +4,17 @@ use crate::configuration::EMAIL;
 use crate::configuration::LOGGER_COMMANDS_FILE;
 use crate::configuration::USER;
 use crate::hash_object;
+use crate::hooks;
+use crate::index::Index;
 use crate::logger::Logger;
+use crate::status;
 use crate::tree_handler;
 use crate::tree_handler::has_tree_changed_since_last_commit;
 use crate::tree_handler::Tree;
 use crate::utils;
 use crate::utils::get_current_time;
+use chrono::DateTime;
+use std::env;
 use std::fs;
 use std::io;
 use std::io::Read;
@@ -18,6 +23,10 @@ use std::path::Path;
 
 const NO_PARENT: &str = "0000000000000000000000000000000000000000";
 const INDEX_FILE_NAME: &str = "index";
+const INDEX_LOCK_FILE_NAME: &str = "index.lock";
+pub const COMMIT_EDITMSG: &str = "COMMIT_EDITMSG";
+/// Message of the error returned when a commit would have the same tree as its parent.
+pub const NO_CHANGES: &str = "No changes were made";
 
 /// Logs the 'git commit' command with the specified Git directory, commit message, and Git ignore path.
 ///
@@ -51,59 +60,129 @@ pub fn log_commit(git_dir_path: &str, message: &str, git_ignore_path: &str) -> i
 }
 
 /// Creates a new commit file.
-/// With the given tree hash, parent commit and message. Adds the author and date.
-/// If no changes were made, it will not create a new commit and will return an error.
-/// If the index file doesn't exist, it will return an error.
+/// With the given tree, parents and message, and the given author and committer, both written as
+/// `<name> <email> <timestamp> <timezone>`.
+/// If no changes were made since the first parent, it will not create a new commit and will return an
+/// error, unless `allow_empty` is true.
 /// If the commit file was created successfully, it will return the hash of the new commit.
 fn create_new_commit_file(
     directory: &str,
+    commit_tree: &Tree,
     message: &str,
-    parent_commit: &str,
-    git_ignore_path: &str,
+    parents: &[String],
+    author: &str,
+    committer: &str,
+    allow_empty: bool,
 ) -> io::Result<String> {
-    let index_path = directory.to_string() + "/" + INDEX_FILE_NAME;
-    let commit_tree = tree_handler::build_tree_from_index(&index_path, directory, git_ignore_path)?;
-    let (tree_hash, _) = tree_handler::write_tree(&commit_tree, directory)?;
+    let (tree_hash, _) = tree_handler::write_tree(commit_tree, directory)?;
 
-    if !has_tree_changed_since_last_commit(&tree_hash, parent_commit, directory) {
-        return Err(io::Error::new(io::ErrorKind::Other, "No changes were made"));
+    let first_parent = parents.first().map(String::as_str).unwrap_or(NO_PARENT);
+    if !allow_empty && !has_tree_changed_since_last_commit(&tree_hash, first_parent, directory) {
+        return Err(io::Error::new(io::ErrorKind::Other, NO_CHANGES));
     }
 
-    let (timestamp, offset) = utils::get_timestamp()?;
-    let time = format!("{} {}", timestamp, offset);
-    let mut user = USER.to_string();
-    let mut email = EMAIL.to_string();
-    let config = Config::load(directory);
+    let parent_lines: String = parents
+        .iter()
+        .map(|parent| format!("parent {}\n", parent))
+        .collect();
+    let commit_content = format!(
+        "tree {tree_hash}\n{parent_lines}author {author}\ncommitter {committer}\n\n{message}\0",
+        author = author,
+        committer = committer,
+        message = message,
+        tree_hash = tree_hash,
+        parent_lines = parent_lines
+    );
+    let commit_hash = hash_object::store_string_to_file(&commit_content, directory, "commit")?;
+    Ok(commit_hash)
+}
 
-    if let Ok(config) = config {
-        if let Ok(result) = config.get_user_name_and_email() {
-            (user, email) = result;
+/// Parses a date given to `--date` or in the `GIT_AUTHOR_DATE` and `GIT_COMMITTER_DATE` variables.
+///
+/// The accepted formats are the one of git (`<timestamp> <timezone>`, or `@<timestamp>`), RFC 2822
+/// (`Mon, 2 Jan 2023 10:00:00 -0300`) and ISO 8601 (`2023-01-02T10:00:00-03:00` or `2023-01-02 10:00:00 -0300`).
+///
+/// # Returns
+///
+/// Returns the date as it's written in a commit: `<timestamp> <timezone>`.
+///
+/// # Errors
+///
+/// Returns an error if the date has none of the formats.
+///
+pub fn parse_date(value: &str) -> io::Result<String> {
+    let value = value.trim();
+    if let Some(timestamp) = value.strip_prefix('@') {
+        if let Ok(timestamp) = timestamp.parse::<i64>() {
+            return Ok(format!("{} +0000", timestamp));
+        }
+    }
+    if let Some((timestamp, timezone)) = value.split_once(' ') {
+        let is_timezone = timezone.len() == 5
+            && timezone.starts_with(['+', '-'])
+            && timezone[1..].chars().all(|c| c.is_ascii_digit());
+        if timestamp.parse::<i64>().is_ok() && is_timezone {
+            return Ok(value.to_string());
         }
     }
+    DateTime::parse_from_rfc2822(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z"))
+        .map(|date| date.format("%s %z").to_string())
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: invalid date format: {}", value),
+            )
+        })
+}
 
-    if parent_commit == NO_PARENT {
-        let commit_content = format!(
-            "tree {tree_hash}\nauthor {} {} {time}\ncommitter {} {} {time}\n\n{message}\0",
-            user,
-            email,
-            user,
-            email,
-            message = message,
-            time = time,
-            tree_hash = tree_hash
-        );
-        let commit_hash = hash_object::store_string_to_file(&commit_content, directory, "commit")?;
-        Ok(commit_hash)
-    } else {
-        let commit_content = format!(
-            "tree {tree_hash}\nparent {parent_commit}\nauthor {} {} {time}\ncommitter {} {} {time}\n\n{message}\0", user, email, user, email,
-            message = message,
-            time = time,
-            tree_hash = tree_hash,
-            parent_commit = parent_commit
-        );
-        let commit_hash = hash_object::store_string_to_file(&commit_content, directory, "commit")?;
-        Ok(commit_hash)
+/// Returns the user name and email of the configuration, or the default ones if there are none.
+fn configured_user(directory: &str) -> (String, String) {
+    Config::load(directory)
+        .and_then(|config| config.get_user_name_and_email())
+        .unwrap_or((USER.to_string(), EMAIL.to_string()))
+}
+
+/// Returns the author or committer (the `role`, `AUTHOR` or `COMMITTER`) of a new commit, as
+/// `<name> <email> <timestamp> <timezone>`.
+///
+/// The name, email and date are taken from the `GIT_<role>_NAME`, `GIT_<role>_EMAIL` and
+/// `GIT_<role>_DATE` variables found by `variable`, and otherwise they are the configured user and the current time.
+fn identity_with(
+    directory: &str,
+    role: &str,
+    variable: impl Fn(&str) -> Option<String>,
+) -> io::Result<String> {
+    let (mut name, mut email) = configured_user(directory);
+    if let Some(value) = variable(&format!("GIT_{}_NAME", role)) {
+        name = value;
+    }
+    if let Some(value) = variable(&format!("GIT_{}_EMAIL", role)) {
+        email = value;
+    }
+    let time = match variable(&format!("GIT_{}_DATE", role)) {
+        Some(date) => parse_date(&date)?,
+        None => {
+            let (timestamp, offset) = utils::get_timestamp()?;
+            format!("{} {}", timestamp, offset)
+        }
+    };
+    Ok(format!("{} {} {}", name, email, time))
+}
+
+/// Returns the author or committer (`AUTHOR` or `COMMITTER`) of a new commit, taking the
+/// `GIT_AUTHOR_*` or `GIT_COMMITTER_*` environment variables into account.
+fn identity(directory: &str, role: &str) -> io::Result<String> {
+    identity_with(directory, role, |name| env::var(name).ok())
+}
+
+/// Splits a signature, `<name> <email> <timestamp> <timezone>`, in the person and the date.
+fn split_signature(signature: &str) -> (&str, String) {
+    let fields: Vec<&str> = signature.rsplitn(3, ' ').collect();
+    match fields.as_slice() {
+        [timezone, timestamp, person] => (person, format!("{} {}", timestamp, timezone)),
+        _ => (signature, String::new()),
     }
 }
 
@@ -166,23 +245,587 @@ pub fn get_branch_name(git_dir_path: &str) -> io::Result<String> {
 /// The hash of the new commit.
 ///
 pub fn new_commit(git_dir_path: &str, message: &str, git_ignore_path: &str) -> io::Result<String> {
+    new_commit_with_author(git_dir_path, message, None, git_ignore_path)
+}
+
+/// Creates a new commit, as `new_commit` does, keeping the given author instead of the committer.
+/// It's used when the changes come from someone else, for example from a patch.
+///
+/// ## Parameters
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `message` - The commit message.
+/// * `author` - The author and date of the changes, as `<name> <email> <timestamp> <timezone>`.
+///   If it's `None`, the committer is the author.
+/// * `git_ignore_path` - The path to the git ignore file.
+///
+/// ## Returns
+///
+/// The hash of the new commit.
+///
+pub fn new_commit_with_author(
+    git_dir_path: &str,
+    message: &str,
+    author: Option<&str>,
+    git_ignore_path: &str,
+) -> io::Result<String> {
     let branch_name = get_branch_name(git_dir_path)?;
     let branch_path = git_dir_path.to_string() + "/refs/heads/" + &branch_name;
-    let parent_hash = match std::fs::File::open(&branch_path) {
+    let parents = match std::fs::File::open(&branch_path) {
         Ok(mut file) => {
             let mut parent_hash = String::new();
             file.read_to_string(&mut parent_hash)?;
-            parent_hash
+            vec![parent_hash]
         }
-        Err(_) => NO_PARENT.to_string(),
+        Err(_) => Vec::new(),
     };
-    let commit_hash = create_new_commit_file(git_dir_path, message, &parent_hash, git_ignore_path)?;
+    let author = match author {
+        Some(author) => author.to_string(),
+        None => identity(git_dir_path, "AUTHOR")?,
+    };
+    let committer = identity(git_dir_path, "COMMITTER")?;
+    let index_path = git_dir_path.to_string() + "/" + INDEX_FILE_NAME;
+    let commit_tree =
+        tree_handler::build_tree_from_index(&index_path, git_dir_path, git_ignore_path)?;
+    let commit_hash = create_new_commit_file(
+        git_dir_path,
+        &commit_tree,
+        message,
+        &parents,
+        &author,
+        &committer,
+        false,
+    )?;
     let mut branch_file = std::fs::File::create(&branch_path)?;
     branch_file.write_all(commit_hash.as_bytes())?;
     log_commit(git_dir_path, message, git_ignore_path)?;
     Ok(commit_hash)
 }
 
+/// The options of the `git commit` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CommitOptions {
+    /// The paragraphs of the message (`-m`). Each one is separated from the next by a blank line.
+    pub messages: Vec<String>,
+    /// The file the message is read from (`-F`), or `-` for the standard input.
+    pub message_file: Option<String>,
+    /// If true (`--amend`), the commit replaces HEAD, with its parents, author and, if no other is given, message.
+    pub amend: bool,
+    /// If true (`-a`), the changes of the tracked files are staged before committing.
+    pub all: bool,
+    /// The author (`--author`), as `Name <email>`, instead of the configured user.
+    pub author: Option<String>,
+    /// The date of the author (`--date`), instead of the current time.
+    pub date: Option<String>,
+    /// If true (`--allow-empty`), the commit is made even if it doesn't change anything.
+    pub allow_empty: bool,
+    /// Whether the message is opened in the editor (`-e`, `--no-edit`). By default it is only
+    /// opened if there is no `-m` or `-F`.
+    pub edit: Option<bool>,
+    /// If true (`-s`), a `Signed-off-by` trailer with the committer is added to the message.
+    pub signoff: bool,
+    /// The trailers added to the message (`--trailer`), as `key=value` or `key:value`.
+    pub trailers: Vec<String>,
+    /// If true (`-n`), the `pre-commit` and `commit-msg` hooks are not run.
+    pub no_verify: bool,
+}
+
+impl CommitOptions {
+    /// Parses the arguments of `git commit` (the ones after 'commit').
+    /// Short options can be combined, as in `-am <message>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or a value is missing.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<CommitOptions> {
+        let invalid_option = |arg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: invalid option: {}", arg),
+            )
+        };
+        let mut options = CommitOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| -> io::Result<String> {
+                match arg
+                    .strip_prefix(name)
+                    .and_then(|rest| rest.strip_prefix('='))
+                {
+                    Some(value) => Ok(value.to_string()),
+                    None => args.next().cloned().ok_or_else(|| invalid_option(arg)),
+                }
+            };
+            match arg.split('=').next().unwrap_or_default() {
+                "--message" => options.messages.push(value("--message")?),
+                "--file" => options.message_file = Some(value("--file")?),
+                "--author" => options.author = Some(value("--author")?),
+                "--date" => options.date = Some(value("--date")?),
+                "--amend" => options.amend = true,
+                "--all" => options.all = true,
+                "--allow-empty" => options.allow_empty = true,
+                "--trailer" => options.trailers.push(value("--trailer")?),
+                "--signoff" => options.signoff = true,
+                "--edit" => options.edit = Some(true),
+                "--no-edit" => options.edit = Some(false),
+                "--no-verify" => options.no_verify = true,
+                _ if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 1 => {
+                    for (i, flag) in arg[1..].char_indices() {
+                        let rest = &arg[1 + i + flag.len_utf8()..];
+                        match flag {
+                            'a' => options.all = true,
+                            's' => options.signoff = true,
+                            'n' => options.no_verify = true,
+                            'e' => options.edit = Some(true),
+                            'm' | 'F' => {
+                                let value = match rest.is_empty() {
+                                    true => value("")?,
+                                    false => rest.to_string(),
+                                };
+                                match flag {
+                                    'm' => options.messages.push(value),
+                                    _ => options.message_file = Some(value),
+                                }
+                                break;
+                            }
+                            _ => return Err(invalid_option(arg)),
+                        }
+                    }
+                }
+                _ => return Err(invalid_option(arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Stages the changes of the tracked files: the modified ones are updated in the index and the
+/// deleted ones are removed from it. The files that are not tracked are left out.
+///
+/// The staged index is written to `index.lock` instead of the index, so it is only moved over it
+/// once the commit is created.
+fn stage_tracked_changes(git_dir_path: &str, git_ignore_path: &str) -> io::Result<Index> {
+    let index_path = git_dir_path.to_string() + "/" + INDEX_FILE_NAME;
+    let lock_path = git_dir_path.to_string() + "/" + INDEX_LOCK_FILE_NAME;
+    fs::copy(&index_path, &lock_path)?;
+    let mut index = Index::load(&lock_path, git_dir_path, git_ignore_path)?;
+    let paths: Vec<String> = index.iter().map(|(path, _)| path.clone()).collect();
+    for path in paths {
+        index.add_path(&path)?;
+    }
+    index.write_file()?;
+    Ok(index)
+}
+
+/// Returns the parents and the author of a commit.
+fn read_parents_and_author(
+    commit_hash: &str,
+    git_dir_path: &str,
+) -> io::Result<(Vec<String>, String)> {
+    let content = cat_file::cat_file_return_content(commit_hash, git_dir_path)?;
+    let mut parents = Vec::new();
+    let mut author = String::new();
+    for line in content.lines().take_while(|line| !line.is_empty()) {
+        if let Some(parent) = line.strip_prefix("parent ") {
+            parents.push(parent.to_string());
+        } else if let Some(value) = line.strip_prefix("author ") {
+            author = value.to_string();
+        }
+    }
+    Ok((parents, author))
+}
+
+/// Returns the message given with `-m` or `-F`, if there is one.
+fn given_message(options: &CommitOptions) -> io::Result<Option<String>> {
+    match &options.message_file {
+        Some(_) if !options.messages.is_empty() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: Option -m cannot be combined with -F.",
+        )),
+        _ if !options.messages.is_empty() => Ok(Some(options.messages.join("\n\n"))),
+        Some(file) if file == "-" => {
+            let mut message = String::new();
+            io::stdin().read_to_string(&mut message)?;
+            Ok(Some(message))
+        }
+        Some(file) => Ok(Some(fs::read_to_string(file)?)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the content of the file set in `commit.template`, if there is one.
+fn commit_template(git_dir_path: &str) -> io::Result<Option<String>> {
+    let Some(path) = Config::load(git_dir_path)
+        .and_then(|config| config.get_values("commit", "template"))
+        .ok()
+        .and_then(|values| values.last().cloned())
+    else {
+        return Ok(None);
+    };
+    let path = match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path,
+    };
+    match fs::read_to_string(&path) {
+        Ok(template) => Ok(Some(template)),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: could not read '{}'", path),
+        )),
+    }
+}
+
+/// Returns the editor of the commit messages: the one of the `GIT_EDITOR` variable found by
+/// `variable`, `core.editor`, or the `VISUAL` or `EDITOR` variables, in that order, or `vi`.
+fn commit_editor_with(git_dir_path: &str, variable: impl Fn(&str) -> Option<String>) -> String {
+    let configured = Config::load(git_dir_path)
+        .and_then(|config| config.get_values("core", "editor"))
+        .ok()
+        .and_then(|values| values.last().cloned());
+    [
+        variable("GIT_EDITOR"),
+        configured,
+        variable("VISUAL"),
+        variable("EDITOR"),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|editor| !editor.trim().is_empty())
+    .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Returns the editor of the commit messages, taking the environment variables into account.
+fn commit_editor(git_dir_path: &str) -> String {
+    commit_editor_with(git_dir_path, |name| env::var(name).ok())
+}
+
+/// Removes the comments (lines starting with '#'), the trailing whitespace of every line, the
+/// blank lines at the start and the end, and the repeated blank lines of a message.
+pub fn cleanup_message(message: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+    {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Returns true if a line is a trailer, `<key>: <value>`.
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+/// Adds trailers, given as `key=value` or `key:value`, at the end of a message.
+///
+/// They go in the last paragraph if it's already made of trailers, and in a new one otherwise. A
+/// trailer equal to the last one of the message is not repeated.
+pub fn add_trailers(message: &str, trailers: &[String]) -> String {
+    let mut message = message.trim_end().to_string();
+    for trailer in trailers {
+        let separator = trailer.find(['=', ':']).unwrap_or(trailer.len());
+        let (key, value) = trailer.split_at(separator);
+        let line = format!(
+            "{}: {}",
+            key.trim(),
+            value.get(1..).unwrap_or_default().trim()
+        );
+        let mut paragraphs = message.rsplit("\n\n");
+        let last_paragraph = paragraphs.next().unwrap_or_default();
+        let in_trailers = paragraphs.next().is_some() && last_paragraph.lines().all(is_trailer);
+        if message.lines().last() == Some(line.as_str()) && in_trailers {
+            continue;
+        }
+        let separator = if in_trailers { "\n" } else { "\n\n" };
+        message = format!("{}{}{}", message, separator, line);
+    }
+    message
+}
+
+/// Returns the `Signed-off-by` trailer with the committer, as `Signed-off-by=Name <email>`.
+fn signoff_trailer(git_dir_path: &str) -> io::Result<String> {
+    let committer = identity(git_dir_path, "COMMITTER")?;
+    let person = split_signature(&committer).0;
+    let person = match person.rsplit_once(' ') {
+        Some((name, email)) if !email.starts_with('<') => format!("{} <{}>", name, email),
+        _ => person.to_string(),
+    };
+    Ok(format!("Signed-off-by={}", person))
+}
+
+/// Opens a message in the editor, in `COMMIT_EDITMSG` of the git directory, under a commented
+/// summary of the changes to be committed, and returns it after `cleanup_message`.
+fn edit_message(
+    git_dir_path: &str,
+    message: &str,
+    index: &Index,
+    parent: Option<&String>,
+    branch_name: &str,
+    editor: &str,
+) -> io::Result<String> {
+    let tree =
+        parent.and_then(|parent| tree_handler::load_tree_from_commit(parent, git_dir_path).ok());
+    let mut content = format!(
+        "{}\n\n# Please enter the commit message for your changes. Lines starting\n# with '#' will be ignored, and an empty message aborts the commit.\n#\n# On branch {}\n# Changes to be committed:\n",
+        message.trim_end(),
+        branch_name
+    );
+    for change in status::staged_file_changes(index, tree.as_ref(), git_dir_path) {
+        content += &format!(
+            "#\t{}:   {}\n",
+            status::status_label(&change),
+            status::status_name(&change)
+        );
+    }
+    content += "#\n";
+    let path = Path::new(git_dir_path).join(COMMIT_EDITMSG);
+    fs::write(&path, content)?;
+    utils::launch_editor(editor, &path.to_string_lossy())?;
+    Ok(cleanup_message(&fs::read_to_string(&path)?))
+}
+
+/// Creates a new commit, or replaces HEAD with `--amend`, with the options of `git commit`.
+///
+/// The author is the configured user, or the one of `--author`, at the current time or at `--date`.
+/// With `--amend`, the parents and the author are the ones of HEAD, and also its message if there
+/// is no other. The `GIT_AUTHOR_*` and `GIT_COMMITTER_*` environment variables change the default
+/// name, email and date of the author and the committer.
+///
+/// Without `-m` or `-F`, or with `-e`, the message is written in the editor, starting from the
+/// `commit.template` file or the amended message. The trailers of `--trailer` and `--signoff` are
+/// added at the end of the message.
+///
+/// With `-a`, the changes of the tracked files are committed too. They are staged in `index.lock`,
+/// which the hooks see through `GIT_INDEX_FILE`, and it only replaces the index once the commit is
+/// created, so a rejected commit leaves the index as it was.
+///
+/// Unless `--no-verify` is given, the `pre-commit` hook runs before the commit and the `commit-msg`
+/// hook can change or reject the message. The `post-commit` hook runs after it.
+///
+/// ## Parameters
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `options` - The options of the commit.
+/// * `git_ignore_path` - The path to the git ignore file.
+///
+/// ## Returns
+///
+/// The hash of the new commit.
+///
+/// ## Errors
+///
+/// Returns an error if a hook rejects the commit, the message is empty or the template wasn't edited, there is nothing to
+/// amend, an invalid author or date, or no changes to commit without `--allow-empty`.
+///
+pub fn new_commit_with_options(
+    git_dir_path: &str,
+    options: &CommitOptions,
+    git_ignore_path: &str,
+) -> io::Result<String> {
+    let branch_name = get_branch_name(git_dir_path)?;
+    let branch_path = git_dir_path.to_string() + "/refs/heads/" + &branch_name;
+    let head = fs::read_to_string(&branch_path).ok();
+    let amended = match (options.amend, &head) {
+        (true, Some(head)) => Some(head.trim()),
+        (true, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "fatal: You have nothing to amend.",
+            ))
+        }
+        (false, _) => None,
+    };
+    let given = given_message(options)?;
+
+    let (parents, mut author) = match amended {
+        Some(amended) => read_parents_and_author(amended, git_dir_path)?,
+        None => (
+            head.clone().into_iter().collect(),
+            identity(git_dir_path, "AUTHOR")?,
+        ),
+    };
+    if let Some(person) = &options.author {
+        if !person.contains('<') || !person.ends_with('>') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "fatal: --author '{}' is not 'Name <email>' and matches no existing author",
+                    person
+                ),
+            ));
+        }
+        author = format!("{} {}", person, split_signature(&author).1);
+    }
+    if let Some(date) = &options.date {
+        author = format!("{} {}", split_signature(&author).0, parse_date(date)?);
+    }
+    let committer = identity(git_dir_path, "COMMITTER")?;
+
+    let index_path = git_dir_path.to_string() + "/" + INDEX_FILE_NAME;
+    let lock_path = git_dir_path.to_string() + "/" + INDEX_LOCK_FILE_NAME;
+    let staged = match options.all {
+        true => Some(
+            stage_tracked_changes(git_dir_path, git_ignore_path).inspect_err(|_| {
+                let _ = fs::remove_file(&lock_path);
+            })?,
+        ),
+        false => None,
+    };
+    let created = (|| {
+        let index_file = fs::canonicalize(git_dir_path)?.join(match staged {
+            Some(_) => INDEX_LOCK_FILE_NAME,
+            None => INDEX_FILE_NAME,
+        });
+        if !options.no_verify {
+            hooks::run_hook_with_index(git_dir_path, "pre-commit", &[], &index_file)?;
+        }
+
+        let edit = options.edit.unwrap_or(given.is_none());
+        let template = match (&given, amended) {
+            (None, None) => commit_template(git_dir_path)?,
+            _ => None,
+        };
+        let mut message = match (given, amended) {
+            (Some(message), _) => message,
+            (None, Some(amended)) => get_commit_full_message(amended, git_dir_path)?
+                .trim_end_matches('\0')
+                .to_string(),
+            (None, None) => template.clone().unwrap_or_default(),
+        };
+        let mut trailers = options.trailers.clone();
+        if options.signoff {
+            trailers.push(signoff_trailer(git_dir_path)?);
+        }
+        if !trailers.is_empty() {
+            message = add_trailers(&message, &trailers);
+        }
+        if edit {
+            let on_disk;
+            let index = match &staged {
+                Some(index) => index,
+                None => {
+                    on_disk = Index::load(&index_path, git_dir_path, git_ignore_path)?;
+                    &on_disk
+                }
+            };
+            let edited = edit_message(
+                git_dir_path,
+                &message,
+                index,
+                parents.first(),
+                &branch_name,
+                &commit_editor(git_dir_path),
+            )?;
+            if template.is_some() && edited == cleanup_message(&message) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Aborting commit; you did not edit the message.",
+                ));
+            }
+            message = edited;
+        }
+        if message.trim().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Aborting commit due to empty commit message.",
+            ));
+        }
+        if !options.no_verify && hooks::find_hook(git_dir_path, "commit-msg").is_some() {
+            message = run_commit_msg_hook(git_dir_path, &message, &index_file)?;
+        }
+
+        let commit_tree = match &staged {
+            Some(index) => tree_handler::build_tree_from_entries(index)?,
+            None => {
+                tree_handler::build_tree_from_index(&index_path, git_dir_path, git_ignore_path)?
+            }
+        };
+        let commit_hash = create_new_commit_file(
+            git_dir_path,
+            &commit_tree,
+            &message,
+            &parents,
+            &author,
+            &committer,
+            options.allow_empty,
+        )?;
+        Ok((commit_hash, message))
+    })();
+    if staged.is_some() {
+        match &created {
+            Ok(_) => fs::rename(&lock_path, &index_path)?,
+            Err(_) => {
+                let _ = fs::remove_file(&lock_path);
+            }
+        }
+    }
+    let (commit_hash, message) = created?;
+    fs::write(&branch_path, &commit_hash)?;
+    log_commit(git_dir_path, &message, git_ignore_path)?;
+    // As in git, the result of the post-commit hook doesn't change the outcome of the commit.
+    let _ = hooks::run_hook(git_dir_path, "post-commit", &[], None);
+    Ok(commit_hash)
+}
+
+/// Runs the `commit-msg` hook with the message in `COMMIT_EDITMSG`, and returns the message as
+/// the hook leaves it.
+fn run_commit_msg_hook(git_dir_path: &str, message: &str, index_file: &Path) -> io::Result<String> {
+    let path = Path::new(git_dir_path).join(COMMIT_EDITMSG);
+    fs::write(&path, format!("{}\n", message.trim_end()))?;
+    let path = fs::canonicalize(path)?;
+    hooks::run_hook_with_index(
+        git_dir_path,
+        "commit-msg",
+        &[&path.to_string_lossy()],
+        index_file,
+    )?;
+    fs::read_to_string(path)
+}
+
+/// Records the changes staged in the index in a new commit, with the options of `git commit`
+/// (see `new_commit_with_options`), and writes its branch, hash and subject.
+///
+/// ## Parameters
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `git_ignore_path` - The path to the git ignore file.
+/// * `args` - The arguments of the command, the ones after 'commit'.
+/// * `output` - Where the summary of the commit is written.
+///
+/// ## Errors
+///
+/// Returns an error if an option is invalid or the commit can't be made.
+///
+pub fn git_commit(
+    git_dir_path: &str,
+    git_ignore_path: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<String> {
+    let options = CommitOptions::parse(args)?;
+    let commit_hash = new_commit_with_options(git_dir_path, &options, git_ignore_path)?;
+    let message = get_commit_message(&commit_hash, git_dir_path).unwrap_or_default();
+    writeln!(
+        output,
+        "[{} {}] {}",
+        get_branch_name(git_dir_path)?,
+        &commit_hash[..7],
+        message.lines().next().unwrap_or_default()
+    )?;
+    Ok(commit_hash)
+}
+
 /// Creates a new merge commit. merge commits are special as they have two parents. This function should only be used when merging two branches.
 ///
 /// The commit file will be created with the following format:
@@ -200,17 +843,63 @@ pub fn new_merge_commit(
     parent_hash2: &str,
     git_ignore_path: &str,
 ) -> io::Result<String> {
+    let parents = vec![parent_hash.to_string(), parent_hash2.to_string()];
+    new_octopus_merge_commit(git_dir_path, message, &parents, git_ignore_path)
+}
+
+/// Creates a new merge commit with any number of parents, using the index file as the tree of the commit.
+/// The first parent must be the commit the current branch points to. The branch is updated with the new commit.
+///
+/// The commit file will be created with the following format:
+/// tree <tree_hash>
+/// parent <parent_hash>
+/// parent <parent_hash>
+/// ...
+/// author <author>
+///
+/// <message>
+///
+/// ## Parameters
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `message` - The commit message.
+/// * `parents` - The hashes of the parents of the commit, in order.
+/// * `git_ignore_path` - The path to the git ignore file.
+///
+/// ## Returns
+///
+/// The hash of the new commit. If the tree didn't change since the first parent, it returns an error.
+///
+pub fn new_octopus_merge_commit(
+    git_dir_path: &str,
+    message: &str,
+    parents: &[String],
+    git_ignore_path: &str,
+) -> io::Result<String> {
+    let first_parent = match parents.first() {
+        Some(parent) => parent,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "A merge commit needs at least one parent",
+            ))
+        }
+    };
     let index_path = git_dir_path.to_string() + "/" + INDEX_FILE_NAME;
     let commit_tree =
         tree_handler::build_tree_from_index(&index_path, git_dir_path, git_ignore_path)?;
     let (tree_hash, _) = tree_handler::write_tree(&commit_tree, git_dir_path)?;
-    if !has_tree_changed_since_last_commit(&tree_hash, parent_hash, git_dir_path) {
-        return Err(io::Error::new(io::ErrorKind::Other, "No changes were made"));
+    if !has_tree_changed_since_last_commit(&tree_hash, first_parent, git_dir_path) {
+        return Err(io::Error::new(io::ErrorKind::Other, NO_CHANGES));
     }
 
     let (timestamp, offset) = utils::get_timestamp()?;
     let time = format!("{} {}", timestamp, offset);
-    let commit_content = format!("tree {tree_hash}\nparent {parent_hash}\nparent {parent_hash2}\nauthor {} {} {time}\ncommitter {} {} {time}\n\n{message}\0", "user", "email@email", "user", "email@email"
+    let parent_lines: String = parents
+        .iter()
+        .map(|parent| format!("parent {}\n", parent))
+        .collect();
+    let commit_content = format!("tree {tree_hash}\n{parent_lines}author {} {} {time}\ncommitter {} {} {time}\n\n{message}\0", "user", "email@email", "user", "email@email"
     );
     let commit_hash = hash_object::store_string_to_file(&commit_content, git_dir_path, "commit")?;
     let branch_name = get_branch_name(git_dir_path)?;
@@ -307,26 +996,32 @@ pub fn get_parent_hash(commit_hash: &str, git_dir_path: &str) -> io::Result<Stri
 /// * `git_dir_path` - The path to the git directory.
 pub fn get_commit_message(commit_hash: &str, git_dir_path: &str) -> io::Result<String> {
     let commit_file = cat_file::cat_file_return_content(commit_hash, git_dir_path)?;
-    let commit_parent = get_parent_hash(commit_hash, git_dir_path)?;
-
-    let message = if is_merge_commit(commit_hash, git_dir_path)? {
-        match commit_file.split('\n').nth(6) {
-            Some(message) => message,
-            None => return Err(io::Error::new(io::ErrorKind::NotFound, "Message not found")),
-        }
-    } else if commit_parent == NO_PARENT {
-        match commit_file.split('\n').nth(4) {
-            Some(message) => message,
-            None => return Err(io::Error::new(io::ErrorKind::NotFound, "Message not found")),
-        }
-    } else {
-        match commit_file.split('\n').nth(5) {
-            Some(message) => message,
-            None => return Err(io::Error::new(io::ErrorKind::NotFound, "Message not found")),
+    let mut lines = commit_file.split('\n');
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
         }
-    };
-    Ok(message.to_string())
+    }
+    match lines.next() {
+        Some(message) => Ok(message.to_string()),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "Message not found")),
+    }
+}
+/// Returns the complete commit message of the given commit hash, including every line after the header.
+/// If the commit is not found, it returns an error.
+///
+/// ## Parameters
+///
+/// * `commit_hash` - The hash of the commit that you want the message of.
+/// * `git_dir_path` - The path to the git directory.
+pub fn get_commit_full_message(commit_hash: &str, git_dir_path: &str) -> io::Result<String> {
+    let commit_file = cat_file::cat_file_return_content(commit_hash, git_dir_path)?;
+    match commit_file.split_once("\n\n") {
+        Some((_, message)) => Ok(message.to_string()),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "Message not found")),
+    }
 }
+
 /// Reads and returns the commit hash referred to by the HEAD reference in a Git repository.
 ///
 /// This function reads the contents of the Git repository's "HEAD" file to determine the commit hash
@@ -371,34 +1066,16 @@ pub fn read_head_commit_hash(git_dir: &str) -> io::Result<String> {
 /// * `git_dir_path` - The path to the git directory.
 pub fn get_commit_time(commit_hash: &str, git_dir_path: &str) -> io::Result<String> {
     let commit_file = cat_file::cat_file_return_content(commit_hash, git_dir_path)?;
-    let commit_parent = get_parent_hash(commit_hash, git_dir_path)?;
-
-    let time = if is_merge_commit(commit_hash, git_dir_path)? {
-        match commit_file.split('\n').nth(4) {
-            Some(time) => {
-                let time: Vec<&str> = time.split(' ').collect();
-                time[3..].join(" ")
-            }
-            None => return Err(io::Error::new(io::ErrorKind::NotFound, "Time not found")),
-        }
-    } else if commit_parent == NO_PARENT {
-        match commit_file.split('\n').nth(2) {
-            Some(time) => {
-                let time: Vec<&str> = time.split(' ').collect();
-                time[3..].join(" ")
-            }
-            None => return Err(io::Error::new(io::ErrorKind::NotFound, "Time not found")),
+    match commit_file
+        .split('\n')
+        .find(|line| line.starts_with("committer "))
+    {
+        Some(time) => {
+            let time: Vec<&str> = time.split(' ').collect();
+            Ok(time[3..].join(" "))
         }
-    } else {
-        match commit_file.split('\n').nth(3) {
-            Some(time) => {
-                let time: Vec<&str> = time.split(' ').collect();
-                time[3..].join(" ")
-            }
-            None => return Err(io::Error::new(io::ErrorKind::NotFound, "Time not found")),
-        }
-    };
-    Ok(time)
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "Time not found")),
+    }
 }
 
 /// Returns true if the given commit hash is a merge commit. False otherwise.
@@ -420,6 +1097,7 @@ pub fn is_merge_commit(commit_hash: &str, git_dir_path: &str) -> io::Result<bool
 /// Returns the parents of the given commit hash.
 /// If the commit is not found, it returns an error.
 ///
+/// All the parents are returned in order, so octopus merges (more than two parents) are supported.
 /// If the commit is not a merge commit, it will return an error.
 ///
 /// ## Parameters
@@ -428,26 +1106,19 @@ pub fn is_merge_commit(commit_hash: &str, git_dir_path: &str) -> io::Result<bool
 /// * `git_dir_path` - The path to the git directory.
 pub fn get_merge_parents(commit_hash: &str, git_dir_path: &str) -> io::Result<Vec<String>> {
     let commit_file = cat_file::cat_file_return_content(commit_hash, git_dir_path)?;
-    let lines: Vec<&str> = commit_file.split('\n').collect();
-    let parent1: &str = match lines[1].split(' ').nth(1) {
-        Some(parent1) => parent1,
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Parent hash not found",
-            ))
-        }
-    };
-    let parent2: &str = match lines[2].split(' ').nth(1) {
-        Some(parent2) => parent2,
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Parent hash not found",
-            ))
-        }
-    };
-    Ok(vec![parent1.to_string(), parent2.to_string()])
+    let parents: Vec<String> = commit_file
+        .split('\n')
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.strip_prefix("parent "))
+        .map(|parent| parent.to_string())
+        .collect();
+    if parents.len() < 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Parent hash not found",
+        ));
+    }
+    Ok(parents)
 }
 
 /// This function will not use the index file. It will use the tree provided as a parameter.
@@ -649,4 +1320,260 @@ mod tests {
         assert_eq!(commit_message, message);
         let _ = std::fs::remove_dir_all(git_dir_path);
     }
+
+    #[test]
+    fn test_commit_options_parse_short_flags() {
+        let parse = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            CommitOptions::parse(&args)
+        };
+        let combined = parse(&["-am", "Mensaje"]).unwrap();
+        let attached = parse(&["-sméxico"]).unwrap();
+
+        assert!(combined.all);
+        assert_eq!(combined.messages, vec!["Mensaje"]);
+        assert!(attached.signoff);
+        assert_eq!(attached.messages, vec!["éxico"]);
+        assert!(parse(&["-é"]).is_err());
+        assert!(parse(&["-aé", "Mensaje"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_and_identity_variables() {
+        let variables = |name: &str| match name {
+            "GIT_AUTHOR_NAME" => Some("Ada".to_string()),
+            "GIT_AUTHOR_DATE" => Some("2023-11-20 10:30:00 -0300".to_string()),
+            _ => None,
+        };
+        let author = identity_with("tests/commit/no_repo", "AUTHOR", variables).unwrap();
+
+        assert_eq!(parse_date("@1700000000").unwrap(), "1700000000 +0000");
+        assert_eq!(parse_date("1700000000 -0300").unwrap(), "1700000000 -0300");
+        assert_eq!(
+            parse_date("Mon, 20 Nov 2023 10:30:00 -0300").unwrap(),
+            "1700487000 -0300"
+        );
+        assert_eq!(
+            parse_date("2023-11-20T10:30:00-03:00").unwrap(),
+            "1700487000 -0300"
+        );
+        assert!(parse_date("ayer").is_err());
+        assert_eq!(author, format!("Ada {} 1700487000 -0300", EMAIL));
+        assert_eq!(
+            split_signature("Ada <ada@mail.com> 1700487000 -0300"),
+            ("Ada <ada@mail.com>", "1700487000 -0300".to_string())
+        );
+    }
+
+    #[test]
+    fn test_git_commit_options() -> io::Result<()> {
+        let root_dir = "tests/commit_options_repo";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        fs::write(format!("{}/index", git_dir), "")?;
+        let file = format!("{}/a.txt", root_dir);
+        let message_file = format!("{}/mensaje.txt", root_dir);
+        fs::write(&file, "uno\n")?;
+        fs::write(&message_file, "Desde archivo\n\nCon cuerpo.\n")?;
+        let run = |args: &[&str]| -> io::Result<(String, String)> {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let mut output: Vec<u8> = Vec::new();
+            let hash = git_commit(&git_dir, "", &args, &mut output)?;
+            Ok((hash, String::from_utf8_lossy(&output).to_string()))
+        };
+        let content = |hash: &str| cat_file::cat_file_return_content(hash, &git_dir);
+
+        let nothing_to_amend = run(&["--amend", "-m", "Nada"]);
+        let index_path = format!("{}/index", git_dir);
+        let mut index = Index::load(&index_path, &git_dir, "")?;
+        index.add_path(&file)?;
+        index.write_file()?;
+        let (first, first_output) = run(&[
+            "-m",
+            "Primero",
+            "--author",
+            "Ada <ada@mail.com>",
+            "--date=@1700000000",
+        ])?;
+        let no_changes = run(&["-m", "Igual"]);
+        let empty_message = run(&["--allow-empty", "-m", " "]);
+        fs::write(&file, "dos\n")?;
+        let (second, _) = run(&["-aF", &message_file])?;
+        let (amended, _) = run(&[
+            "--amend",
+            "--no-edit",
+            "--allow-empty",
+            "--date=@1700000100",
+        ])?;
+        let (empty, _) = run(&["--allow-empty", "-m", "Vacio", "-m", "Segundo parrafo"])?;
+        let first_content = content(&first)?;
+        let second_content = content(&second)?;
+        let amended_content = content(&amended)?;
+        let empty_content = content(&empty)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(nothing_to_amend.is_err());
+        assert_eq!(first_output, format!("[master {}] Primero\n", &first[..7]));
+        assert!(first_content.contains("\nauthor Ada <ada@mail.com> 1700000000 +0000\n"));
+        assert!(!first_content.contains("parent "));
+        assert!(no_changes.is_err());
+        assert!(empty_message.is_err());
+        assert!(second_content.contains(&format!("parent {}", first)));
+        assert!(second_content.ends_with("Desde archivo\n\nCon cuerpo.\n"));
+        assert_ne!(amended, second);
+        assert!(amended_content.contains(&format!("parent {}", first)));
+        assert!(amended_content.contains(" 1700000100 +0000\ncommitter "));
+        assert!(amended_content.ends_with("Desde archivo\n\nCon cuerpo.\n"));
+        assert!(empty_content.contains(&format!("parent {}", amended)));
+        assert!(empty_content.ends_with("Vacio\n\nSegundo parrafo"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejected_commit_all_leaves_the_index_untouched() -> io::Result<()> {
+        let root_dir = "tests/commit_all_repo";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        let index_path = format!("{}/index", git_dir);
+        fs::write(&index_path, "")?;
+        let file = format!("{}/a.txt", root_dir);
+        fs::write(&file, "uno\n")?;
+        let mut index = Index::load(&index_path, &git_dir, "")?;
+        index.add_path(&file)?;
+        index.write_file()?;
+        let run = |args: &[&str]| -> io::Result<String> {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            git_commit(&git_dir, "", &args, &mut Vec::new())
+        };
+        run(&["-m", "Primero"])?;
+        fs::write(&file, "dos\n")?;
+        let before = fs::read_to_string(&index_path)?;
+
+        let invalid_author = run(&["-a", "--author", "Nadie", "-m", "Segundo"]);
+        let invalid_date = run(&["-a", "--date", "ayer", "-m", "Segundo"]);
+        let empty_message = run(&["-a", "-m", " "]);
+        let after_rejected = fs::read_to_string(&index_path)?;
+        let second = run(&["-am", "Segundo"])?;
+        let after_commit = fs::read_to_string(&index_path)?;
+        let second_tree = tree_handler::load_tree_from_commit(&second, &git_dir)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(invalid_author.is_err());
+        assert!(invalid_date.is_err());
+        assert!(empty_message.is_err());
+        assert_eq!(after_rejected, before);
+        assert_ne!(after_commit, before);
+        assert_eq!(
+            second_tree.get_hash_from_path("tests/commit_all_repo/a.txt"),
+            Some(hash_object::hash_content(b"dos\n", "blob"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cleanup_message_and_add_trailers() {
+        let trailers = ["Reviewed-by=Ada <ada@mail.com>".to_string()];
+        let signoff = ["Signed-off-by: Ada <ada@mail.com>".to_string()];
+
+        assert_eq!(
+            cleanup_message("\n# comentario\nAsunto  \n\n\n\nCuerpo\n# otro\n\n"),
+            "Asunto\n\nCuerpo"
+        );
+        assert_eq!(
+            add_trailers("Asunto\n\nCuerpo\n", &trailers),
+            "Asunto\n\nCuerpo\n\nReviewed-by: Ada <ada@mail.com>"
+        );
+        assert_eq!(
+            add_trailers("Fix: parser", &signoff),
+            "Fix: parser\n\nSigned-off-by: Ada <ada@mail.com>"
+        );
+        assert_eq!(
+            add_trailers("Asunto\n\nReviewed-by: Ada <ada@mail.com>", &signoff),
+            "Asunto\n\nReviewed-by: Ada <ada@mail.com>\nSigned-off-by: Ada <ada@mail.com>"
+        );
+        assert_eq!(
+            add_trailers("Asunto\n\nSigned-off-by: Ada <ada@mail.com>", &signoff),
+            "Asunto\n\nSigned-off-by: Ada <ada@mail.com>"
+        );
+    }
+
+    #[test]
+    fn test_commit_editor_template_and_signoff() -> io::Result<()> {
+        let root_dir = "tests/commit_editor_repo";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        fs::write(format!("{}/index", git_dir), "")?;
+        let file = format!("{}/a.txt", root_dir);
+        let template = format!("{}/plantilla.txt", root_dir);
+        let seen = format!("{}/visto.txt", root_dir);
+        let editor = format!("{}/editor.sh", root_dir);
+        fs::write(&file, "uno\n")?;
+        fs::write(&template, "Asunto\n\n# Explicar el cambio\n")?;
+        fs::write(
+            &editor,
+            format!(
+                "cp \"$1\" {}\nsed -i 's/^Asunto$/Arreglo del parser/' \"$1\"\n",
+                seen
+            ),
+        )?;
+        fs::write(
+            format!("{}/config", git_dir),
+            format!(
+                "[core]\n\teditor = sh {}\n[commit]\n\ttemplate = {}\n",
+                editor, template
+            ),
+        )?;
+        let mut index = Index::load(&format!("{}/index", git_dir), &git_dir, "")?;
+        index.add_path(&file)?;
+        index.write_file()?;
+
+        let configured_editor = commit_editor_with(&git_dir, |_| None);
+        let git_editor = commit_editor_with(&git_dir, |name| match name {
+            "GIT_EDITOR" => Some("nano".to_string()),
+            _ => None,
+        });
+        let message = add_trailers(
+            &commit_template(&git_dir)?.unwrap_or_default(),
+            &[signoff_trailer(&git_dir)?],
+        );
+        let index = Index::load(&format!("{}/index", git_dir), &git_dir, "")?;
+        let edited = edit_message(
+            &git_dir,
+            &message,
+            &index,
+            None,
+            "master",
+            &configured_editor,
+        )?;
+        let seen_content = fs::read_to_string(&seen)?;
+        let args: Vec<String> = ["-m", "Arreglo", "-s", "--trailer", "Reviewed-by=Ada"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let commit_hash = git_commit(&git_dir, "", &args, &mut Vec::new())?;
+        let content = cat_file::cat_file_return_content(&commit_hash, &git_dir)?;
+        fs::remove_dir_all(root_dir)?;
+
+        let signoff = format!("Signed-off-by: {} <{}>", USER, EMAIL);
+        assert_eq!(configured_editor, format!("sh {}", editor));
+        assert_eq!(git_editor, "nano");
+        assert_eq!(edited, format!("Arreglo del parser\n\n{}", signoff));
+        assert!(seen_content.starts_with(&format!(
+            "Asunto\n\n# Explicar el cambio\n\n{}\n\n# Please enter",
+            signoff
+        )));
+        assert!(seen_content.contains("\n# On branch master\n# Changes to be committed:\n"));
+        assert!(seen_content.contains(&format!("\n#\tnew file:   {}\n", file)));
+        assert!(content.ends_with(&format!("\n\nArreglo\n\nReviewed-by: Ada\n{}", signoff)));
+        Ok(())
+    }
 }
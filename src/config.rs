@@ -87,24 +87,55 @@ impl Config {
     /// - `lines`: A vector of strings containing the relevant lines from the configuration file.
     ///
     fn process_lines(config: &mut Config, lines: Vec<String>) {
-        let mut buffer: Vec<String> = Vec::new();
-        let mut count = 0;
-
+        let mut sections: Vec<Vec<String>> = Vec::new();
         for line in lines {
-            buffer.push(line);
-            count += 1;
-
-            if count == 3 {
-                if let Some(remote) = Self::parse_remote(&buffer) {
-                    config.remotes.push(remote);
-                } else if let Some(branch) = Self::parse_branch(&buffer) {
-                    config.branches.push(branch);
-                }
+            match sections.last_mut() {
+                Some(section) if !line.starts_with('[') => section.push(line),
+                _ => sections.push(vec![line]),
+            }
+        }
 
-                buffer.clear();
-                count = 0;
+        for buffer in sections.iter().filter(|section| section.len() >= 3) {
+            if let Some(remote) = Self::parse_remote(buffer) {
+                config.remotes.push(remote);
+            } else if let Some(branch) = Self::parse_branch(buffer) {
+                config.branches.push(branch);
+            }
+        }
+    }
+
+    /// Returns every value of a key of the configuration, in the order they appear in the file.
+    ///
+    /// The key is looked up in the sections named `section`, for example `diff` for the lines
+    /// `binary = *.png` under `[diff]`.
+    ///
+    /// # Arguments
+    ///
+    /// - `section`: The name of the section, without the brackets.
+    /// - `key`: The name of the key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration file can't be read.
+    ///
+    pub fn get_values(&self, section: &str, key: &str) -> io::Result<Vec<String>> {
+        let file = File::open(&self.config_file_path)?;
+        let mut in_section = false;
+        let mut values = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.starts_with('[') {
+                in_section = line.trim_matches(|c| c == '[' || c == ']') == section;
+            } else if in_section {
+                if let Some((name, value)) = line.split_once('=') {
+                    if name.trim() == key {
+                        values.push(value.trim().to_string());
+                    }
+                }
             }
         }
+        Ok(values)
     }
 
     /// Parses relevant lines to extract information about a remote repository.
@@ -1,9 +1,26 @@
-use crate::cat_file::cat_file_return_content;
+use crate::{
+    attributes::Attributes,
+    cat_file::{cat_file_return_bytes, cat_file_return_content},
+    configuration::LOGGER_COMMANDS_FILE,
+    hash_object,
+    index::Index,
+    logger::Logger,
+    rename::{self, RenameOptions},
+    tree_handler, utils,
+};
 use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
 };
 
+/// Number of lines of context shown around the changes when it's not specified.
+pub const DEFAULT_CONTEXT: usize = 3;
+const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+const FILE_MODE: &str = "100644";
+
 /// Prints the difference between two text files using line-by-line comparison.
 ///
 /// This function reads the contents of two text files specified by their file paths and compares them
@@ -32,7 +49,7 @@ pub fn print_diff(path_a: &str, path_b: &str) {
         }
     };
 
-    show_diff(&archivo_a, &archivo_b, archivo_a.len(), archivo_b.len());
+    show_diff(&archivo_a, &archivo_b);
 }
 
 /// Reads the lines from a file and returns them as a vector of strings.
@@ -60,64 +77,16 @@ fn read_file_lines(path: &str) -> Result<Vec<String>, String> {
     }
 }
 
-/// Computes the matrix for the Longest Common Subsequence (LCS) problem.
-///
-/// This function calculates the matrix for the Longest Common Subsequence problem
-/// given two vectors of strings. The matrix represents the length of the LCS between
-/// the elements of the two vectors.
-///
-/// # Arguments
-///
-/// * `a` - A reference to a vector of strings.
-/// * `b` - A reference to another vector of strings.
-///
-/// # Returns
-///
-/// Returns a 2D vector (matrix) of integers representing the LCS lengths.
-///
-fn compute_longest_common_subsequence_matrix(a: &Vec<String>, b: &Vec<String>) -> Vec<Vec<i32>> {
-    let mut matrix = vec![vec![0; b.len() + 1]; a.len() + 1];
-    for i in matrix.iter_mut() {
-        for j in i.iter_mut() {
-            *j = 0;
+/// Prints the differences between two sequences of lines: unchanged lines are indented,
+/// lines that are only in `x` are marked with `<<` and lines that are only in `y` with `>>`.
+fn show_diff(x: &[String], y: &[String]) {
+    for edit in compute_edits(x, y) {
+        match edit {
+            Edit::Equal(i, _) => println!("  {}", x[i]),
+            Edit::Delete(i) => println!("<< {}", x[i]),
+            Edit::Insert(j) => println!(">> {}", y[j]),
         }
     }
-
-    for (i_pos, i) in a.iter().enumerate() {
-        for (j_pos, j) in b.iter().enumerate() {
-            if i == j {
-                matrix[i_pos + 1][j_pos + 1] = matrix[i_pos][j_pos] + 1;
-            } else {
-                matrix[i_pos + 1][j_pos + 1] =
-                    std::cmp::max(matrix[i_pos + 1][j_pos], matrix[i_pos][j_pos + 1]);
-            }
-        }
-    }
-    matrix
-}
-
-/// i y j len(x) y len(y) respectivamente
-fn show_diff(x: &Vec<String>, y: &Vec<String>, i: usize, j: usize) {
-    // Enunciado
-    // C es la grilla computada por lcs()
-    // X e Y son las secuencias
-    // i y j especifican la ubicacion dentro de C que se quiere buscar cuando
-    // se lee el diff. Al llamar a estar funcion inicialmente, pasarle
-    // i=len(X) y j=len(Y)
-    let c: Vec<Vec<i32>> = compute_longest_common_subsequence_matrix(x, y);
-
-    if i > 0 && j > 0 && x[i - 1] == y[j - 1] {
-        show_diff(x, y, i - 1, j - 1);
-        println!("  {}", x[i - 1]);
-    } else if j > 0 && (i == 0 || c[i][j - 1] >= c[i - 1][j]) {
-        show_diff(x, y, i, j - 1);
-        println!(">> {}", y[j - 1]);
-    } else if i > 0 && (j == 0 || c[i][j - 1] < c[i - 1][j]) {
-        show_diff(x, y, i - 1, j);
-        println!("<< {}", x[i - 1]);
-    } else {
-        println!();
-    }
 }
 
 /// Returns the difference between two files as a vector of strings.
@@ -141,93 +110,2024 @@ pub fn return_diff(path_a: &str, path_b: &str) -> Result<Vec<String>, String> {
     let archivo_a = read_file_lines(path_a)?;
     let archivo_b = read_file_lines(path_b)?;
 
-    Ok(diff_to_vec(
-        &archivo_a,
-        &archivo_b,
-        archivo_a.len(),
-        archivo_b.len(),
-    ))
+    Ok(diff_to_vec(&archivo_a, &archivo_b))
 }
 
-/// Converts the result of a longest common subsequence matrix computation into a vector of strings representing the differences.
+/// Converts the differences between two sequences of lines into a vector of strings, one per line.
 ///
-/// This function takes two sequences represented by vectors (`x` and `y`) and their corresponding longest common subsequence matrix (`c`).
-/// It recursively processes the matrix to identify and generate a vector of strings representing the differences between the two sequences.
+/// Unchanged lines are kept as they are, lines that are only in `x` are prefixed with `<<<<<<< `
+/// and lines that are only in `y` with `>>>>>>> `. Every line ends with a newline.
 ///
 /// # Arguments
 ///
 /// * `x` - A reference to the first sequence as a vector of strings.
 /// * `y` - A reference to the second sequence as a vector of strings.
-/// * `i` - The index in the first sequence.
-/// * `j` - The index in the second sequence.
 ///
 /// # Returns
 ///
 /// Returns a vector of strings representing the differences between the two sequences.
 ///
-fn diff_to_vec(x: &Vec<String>, y: &Vec<String>, i: usize, j: usize) -> Vec<String> {
-    let c: Vec<Vec<i32>> = compute_longest_common_subsequence_matrix(x, y);
-    let mut output: Vec<String> = Vec::new();
-
-    if i > 0 && j > 0 && x[i - 1] == y[j - 1] {
-        output.append(&mut diff_to_vec(x, y, i - 1, j - 1));
-        output.push(format!("{}\n", x[i - 1]));
-    } else if j > 0 && (i == 0 || c[i][j - 1] >= c[i - 1][j]) {
-        output.append(&mut diff_to_vec(x, y, i, j - 1));
-        output.push(format!(">>>>>>> {}\n", y[j - 1]));
-    } else if i > 0 && (j == 0 || c[i][j - 1] < c[i - 1][j]) {
-        output.append(&mut diff_to_vec(x, y, i - 1, j));
-        output.push(format!("<<<<<<< {}\n", x[i - 1]));
-    }
-    output
+fn diff_to_vec(x: &[String], y: &[String]) -> Vec<String> {
+    compute_edits(x, y)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal(i, _) => format!("{}\n", x[i]),
+            Edit::Delete(i) => format!("<<<<<<< {}\n", x[i]),
+            Edit::Insert(j) => format!(">>>>>>> {}\n", y[j]),
+        })
+        .collect()
 }
 
-/// Returns a string representing the differences between two Git objects identified by their commit hashes.
-///
-/// This function takes two commit hashes (`hash_a` and `hash_b`) and the Git directory path (`git_dir`).
-/// It retrieves the content of the Git objects corresponding to the given hashes, then computes the differences
-/// between their content using the `diff_to_vec` function.
+/// Returns the lines of both Git objects, without conflict markers. The lines only in one of them
+/// are kept in the order the diff places them, first the ones of `hash_a` and then the ones of `hash_b`.
+/// It is the content written for the files merged with the `union` driver.
 ///
 /// # Arguments
 ///
-/// * `hash_a` - The commit hash of the first Git object.
-/// * `hash_b` - The commit hash of the second Git object.
+/// * `hash_a` - The hash of the first Git object.
+/// * `hash_b` - The hash of the second Git object.
 /// * `git_dir` - The path to the Git directory.
 ///
 /// # Returns
 ///
-/// Returns a `Result` where `Ok` contains a string representing the differences between the two Git objects,
-/// and `Err` contains an error message if any error occurs during the process.
+/// Returns a `Result` where `Ok` contains the union of the lines of both objects,
+/// and `Err` contains an error message if any of the objects can't be read.
 ///
-pub fn return_object_diff_string(
+pub fn return_object_union_string(
     hash_a: &str,
     hash_b: &str,
     git_dir: &str,
 ) -> Result<String, String> {
-    let object_a = match cat_file_return_content(hash_a, git_dir) {
-        Ok(content) => content,
-        Err(error) => return Err(error.to_string()),
+    let object_a = cat_file_return_content(hash_a, git_dir).map_err(|error| error.to_string())?;
+    let object_b = cat_file_return_content(hash_b, git_dir).map_err(|error| error.to_string())?;
+    let lines_a: Vec<String> = object_a.lines().map(|s| s.to_string()).collect();
+    let lines_b: Vec<String> = object_b.lines().map(|s| s.to_string()).collect();
+    Ok(compute_edits(&lines_a, &lines_b)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal(i, _) | Edit::Delete(i) => format!("{}\n", lines_a[i]),
+            Edit::Insert(j) => format!("{}\n", lines_b[j]),
+        })
+        .collect())
+}
+
+/// Returns the hunks of the unified diff between two blobs.
+/// This is the structured form of the diff, meant for the code that needs to inspect the changes
+/// (merges, rebases or the graphical interface) instead of printing them.
+///
+/// # Arguments
+///
+/// * `hash_a` - The hash of the old blob.
+/// * `hash_b` - The hash of the new blob.
+/// * `git_dir` - The path to the Git directory.
+/// * `context` - The number of lines of context of each hunk.
+/// * `algorithm` - The algorithm used to compute the differences.
+///
+/// # Errors
+///
+/// Returns an error if one of the objects can't be read.
+///
+pub fn diff_objects(
+    hash_a: &str,
+    hash_b: &str,
+    git_dir: &str,
+    context: usize,
+    algorithm: DiffAlgorithm,
+) -> io::Result<Vec<Hunk>> {
+    let object_a = cat_file_return_content(hash_a, git_dir)?;
+    let object_b = cat_file_return_content(hash_b, git_dir)?;
+    Ok(diff_hunks(&object_a, &object_b, context, algorithm))
+}
+
+/// An operation of the edit script that transforms a sequence of lines into another one.
+/// Each variant keeps the position of the line in the old sequence, the new one, or both.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// A line of a hunk, with its line terminator if it has one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Context(String),
+    Deletion(String),
+    Addition(String),
+}
+
+/// A group of changes together with the lines of context around them.
+/// The starts are 1-based, as in the `@@ -old_start,old_lines +new_start,new_lines @@` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Formats the range of a hunk header. The length is omitted when it is 1.
+fn format_range(start: usize, lines: usize) -> String {
+    if lines == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, lines)
+    }
+}
+
+impl fmt::Display for Hunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "@@ -{} +{} @@",
+            format_range(self.old_start, self.old_lines),
+            format_range(self.new_start, self.new_lines)
+        )?;
+        for line in &self.lines {
+            let (prefix, content) = match line {
+                DiffLine::Context(content) => (' ', content),
+                DiffLine::Deletion(content) => ('-', content),
+                DiffLine::Addition(content) => ('+', content),
+            };
+            write!(f, "{}{}", prefix, content)?;
+            if !content.ends_with('\n') {
+                write!(f, "\n\\ No newline at end of file\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits a text into lines, keeping the line terminators so that a missing newline
+/// at the end of the file is also seen as a change.
+pub fn split_lines(content: &str) -> Vec<String> {
+    content.split_inclusive('\n').map(String::from).collect()
+}
+
+/// The algorithms that can be used to compute the differences between two texts.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DiffAlgorithm {
+    /// The O(ND) algorithm by Eugene Myers, in its linear space version. It finds a minimal edit script.
+    #[default]
+    Myers,
+    /// Matches first the lines that appear exactly once in both texts and diffs the regions between them.
+    Patience,
+    /// Like patience, but uses the lines that appear the least instead of only the unique ones.
+    Histogram,
+}
+
+impl DiffAlgorithm {
+    /// Parses the name of an algorithm, as given to `--diff-algorithm`.
+    pub fn parse(name: &str) -> Option<DiffAlgorithm> {
+        match name {
+            "myers" | "default" | "minimal" => Some(DiffAlgorithm::Myers),
+            "patience" => Some(DiffAlgorithm::Patience),
+            "histogram" => Some(DiffAlgorithm::Histogram),
+            _ => None,
+        }
+    }
+}
+
+/// Lines that appear more times than this in the old text are not used as anchors by the histogram algorithm.
+const MAX_HISTOGRAM_OCCURRENCES: usize = 64;
+
+/// A pending piece of work of the diff: a region of both sequences to compare,
+/// or a run of equal lines that was already found.
+enum DiffTask {
+    Region(usize, usize, usize, usize),
+    Equal(usize, usize, usize),
+}
+
+/// Computes the shortest edit script that transforms `a` into `b`, using the Myers algorithm.
+/// Within a block of changes, the deletions come before the insertions.
+pub fn compute_edits(a: &[String], b: &[String]) -> Vec<Edit> {
+    compute_edits_with(a, b, DiffAlgorithm::Myers)
+}
+
+/// Computes an edit script that transforms `a` into `b` with the given algorithm.
+/// Within a block of changes, the deletions come before the insertions.
+///
+/// The work is kept in an explicit stack instead of recursive calls, so big files can't overflow the stack.
+///
+/// # Arguments
+///
+/// * `a` - The old lines.
+/// * `b` - The new lines.
+/// * `algorithm` - The algorithm used to find the common lines.
+///
+pub fn compute_edits_with(a: &[String], b: &[String], algorithm: DiffAlgorithm) -> Vec<Edit> {
+    let (a, b) = intern_lines(a, b);
+    let mut matches: Vec<(usize, usize)> = Vec::new();
+    let mut tasks = vec![DiffTask::Region(0, a.len(), 0, b.len())];
+    while let Some(task) = tasks.pop() {
+        let (mut a_start, mut a_end, mut b_start, mut b_end) = match task {
+            DiffTask::Equal(a_start, b_start, length) => {
+                matches.extend((0..length).map(|i| (a_start + i, b_start + i)));
+                continue;
+            }
+            DiffTask::Region(a_start, a_end, b_start, b_end) => (a_start, a_end, b_start, b_end),
+        };
+        let prefix_start = (a_start, b_start);
+        while a_start < a_end && b_start < b_end && a[a_start] == b[b_start] {
+            a_start += 1;
+            b_start += 1;
+        }
+        let prefix = a_start - prefix_start.0;
+        matches.extend((0..prefix).map(|i| (prefix_start.0 + i, prefix_start.1 + i)));
+        let mut suffix = 0;
+        while a_start < a_end && b_start < b_end && a[a_end - 1] == b[b_end - 1] {
+            a_end -= 1;
+            b_end -= 1;
+            suffix += 1;
+        }
+        if suffix > 0 {
+            tasks.push(DiffTask::Equal(a_end, b_end, suffix));
+        }
+        if a_start == a_end || b_start == b_end {
+            continue;
+        }
+
+        let anchors = match algorithm {
+            DiffAlgorithm::Myers => Vec::new(),
+            DiffAlgorithm::Patience => {
+                patience_anchors(&a[a_start..a_end], &b[b_start..b_end], a_start, b_start)
+            }
+            DiffAlgorithm::Histogram => {
+                histogram_anchors(&a[a_start..a_end], &b[b_start..b_end], a_start, b_start)
+            }
+        };
+        if anchors.is_empty() {
+            let (x_start, y_start, x_end, y_end) =
+                middle_snake(&a[a_start..a_end], &b[b_start..b_end]);
+            tasks.push(DiffTask::Region(
+                a_start + x_end,
+                a_end,
+                b_start + y_end,
+                b_end,
+            ));
+            tasks.push(DiffTask::Equal(
+                a_start + x_start,
+                b_start + y_start,
+                x_end - x_start,
+            ));
+            tasks.push(DiffTask::Region(
+                a_start,
+                a_start + x_start,
+                b_start,
+                b_start + y_start,
+            ));
+            continue;
+        }
+        // The regions are pushed from the last one to the first one, so they are solved in order
+        let (mut next_a, mut next_b) = (a_end, b_end);
+        for &(anchor_a, anchor_b, length) in anchors.iter().rev() {
+            tasks.push(DiffTask::Region(
+                anchor_a + length,
+                next_a,
+                anchor_b + length,
+                next_b,
+            ));
+            tasks.push(DiffTask::Equal(anchor_a, anchor_b, length));
+            next_a = anchor_a;
+            next_b = anchor_b;
+        }
+        tasks.push(DiffTask::Region(a_start, next_a, b_start, next_b));
+    }
+    matches_to_edits(&matches, a.len(), b.len())
+}
+
+/// Replaces every line with a number, equal lines getting the same number, so comparing and hashing them is cheap.
+fn intern_lines(a: &[String], b: &[String]) -> (Vec<usize>, Vec<usize>) {
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut interned = (Vec::with_capacity(a.len()), Vec::with_capacity(b.len()));
+    for (lines, result) in [(a, &mut interned.0), (b, &mut interned.1)] {
+        for line in lines {
+            let next_id = ids.len();
+            result.push(*ids.entry(line.as_str()).or_insert(next_id));
+        }
+    }
+    interned
+}
+
+/// Turns the pairs of equal lines, sorted, into an edit script.
+fn matches_to_edits(matches: &[(usize, usize)], a_len: usize, b_len: usize) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(a_len + b_len - matches.len());
+    let (mut i, mut j) = (0, 0);
+    for &(x, y) in matches.iter().chain([(a_len, b_len)].iter()) {
+        edits.extend((i..x).map(Edit::Delete));
+        edits.extend((j..y).map(Edit::Insert));
+        if x < a_len {
+            edits.push(Edit::Equal(x, y));
+        }
+        i = x + 1;
+        j = y + 1;
+    }
+    edits
+}
+
+/// Finds the middle snake of the Myers algorithm: a run of equal lines (possibly empty) that is
+/// in the middle of a shortest edit script. The sequences must not be empty and must not start
+/// or end with the same line.
+/// Returns the start and the end of the snake, as `(x_start, y_start, x_end, y_end)`.
+fn middle_snake(a: &[usize], b: &[usize]) -> (usize, usize, usize, usize) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let offset = max + 1;
+    let mut forward = vec![0isize; (2 * max + 3) as usize];
+    let mut backward = vec![0isize; (2 * max + 3) as usize];
+    let index = |k: isize| (k + offset) as usize;
+
+    for d in 0..=(max + 1) / 2 {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+            let reverse_k = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[index(reverse_k)] >= n {
+                return (x_start as usize, y_start as usize, x as usize, y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)]) {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(k)] = x;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[index(forward_k)] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x_start) as usize,
+                    (m - y_start) as usize,
+                );
+            }
+        }
+    }
+    // Unreachable: both searches always meet before d goes over (n + m) / 2
+    (0, 0, 0, 0)
+}
+
+/// Returns the anchors of the patience algorithm: the longest increasing sequence of lines
+/// that appear exactly once in both `a` and `b`, as `(position in a, position in b, 1)`.
+/// The positions are moved by `a_offset` and `b_offset`.
+fn patience_anchors(
+    a: &[usize],
+    b: &[usize],
+    a_offset: usize,
+    b_offset: usize,
+) -> Vec<(usize, usize, usize)> {
+    // For every line: how many times it appears in `a`, in `b`, and its first position in `a`
+    let mut occurrences: HashMap<usize, (usize, usize, usize)> = HashMap::new();
+    for (i, line) in a.iter().enumerate() {
+        occurrences.entry(*line).or_insert((0, 0, i)).0 += 1;
+    }
+    for line in b {
+        if let Some(entry) = occurrences.get_mut(line) {
+            entry.1 += 1;
+        }
+    }
+    let unique: Vec<(usize, usize)> = b
+        .iter()
+        .enumerate()
+        .filter_map(|(j, line)| match occurrences.get(line) {
+            Some(&(1, 1, i)) => Some((i, j)),
+            _ => None,
+        })
+        .collect();
+
+    // Patience sorting: the longest increasing subsequence of the positions in `a`
+    let mut piles: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; unique.len()];
+    for (position, &(i, _)) in unique.iter().enumerate() {
+        let pile = piles.partition_point(|&top| unique[top].0 < i);
+        if pile > 0 {
+            previous[position] = Some(piles[pile - 1]);
+        }
+        if pile == piles.len() {
+            piles.push(position);
+        } else {
+            piles[pile] = position;
+        }
+    }
+    let mut anchors = Vec::new();
+    let mut current = piles.last().copied();
+    while let Some(position) = current {
+        let (i, j) = unique[position];
+        anchors.push((i + a_offset, j + b_offset, 1));
+        current = previous[position];
+    }
+    anchors.reverse();
+    anchors
+}
+
+/// Returns the anchor of the histogram algorithm: the longest run of equal lines around the lines of `b`
+/// that appear the least in `a`, as `(position in a, position in b, length)`.
+/// The positions are moved by `a_offset` and `b_offset`.
+///
+/// As in git, `b` is read once: a line is only tried if it doesn't appear more times than the best
+/// run so far, and the lines of a run are skipped once it is found. Nothing is returned if every line
+/// appears more than `MAX_HISTOGRAM_OCCURRENCES` times, so the region is diffed with Myers.
+fn histogram_anchors(
+    a: &[usize],
+    b: &[usize],
+    a_offset: usize,
+    b_offset: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut positions: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, line) in a.iter().enumerate() {
+        positions.entry(*line).or_default().push(i);
+    }
+    let count = |line: usize| positions.get(&line).map_or(0, Vec::len);
+    // The best run so far, as `(count, start in a, start in b, length)`
+    let mut best: Option<(usize, usize, usize, usize)> = None;
+    let mut j = 0;
+    while j < b.len() {
+        let mut next_j = j + 1;
+        let max_count = best.map_or(MAX_HISTOGRAM_OCCURRENCES, |(count, _, _, _)| count);
+        let candidates = match positions.get(&b[j]) {
+            Some(candidates) if candidates.len() <= max_count => candidates,
+            _ => {
+                j = next_j;
+                continue;
+            }
+        };
+        for &i in candidates {
+            // The count of a run is the one of its line that appears the least in `a`
+            let mut run_count = candidates.len();
+            let (mut start_a, mut start_b) = (i, j);
+            while start_a > 0 && start_b > 0 && a[start_a - 1] == b[start_b - 1] {
+                start_a -= 1;
+                start_b -= 1;
+                run_count = run_count.min(count(a[start_a]));
+            }
+            let (mut end_a, mut end_b) = (i + 1, j + 1);
+            while end_a < a.len() && end_b < b.len() && a[end_a] == b[end_b] {
+                run_count = run_count.min(count(a[end_a]));
+                end_a += 1;
+                end_b += 1;
+            }
+            next_j = next_j.max(end_b);
+            let length = end_a - start_a;
+            let better = match best {
+                None => true,
+                Some((best_count, _, _, best_length)) => {
+                    run_count < best_count || (run_count == best_count && length > best_length)
+                }
+            };
+            if better {
+                best = Some((run_count, start_a, start_b, length));
+            }
+        }
+        j = next_j;
+    }
+    match best {
+        Some((_, i, j, length)) => vec![(i + a_offset, j + b_offset, length)],
+        None => Vec::new(),
+    }
+}
+
+/// Groups an edit script into hunks with `context` lines of context around each change.
+/// Changes separated by at most twice the context go in the same hunk.
+///
+/// # Arguments
+///
+/// * `a` - The old lines.
+/// * `b` - The new lines.
+/// * `edits` - The edit script that transforms `a` into `b`.
+/// * `context` - The number of unchanged lines shown before and after each change.
+///
+pub fn build_hunks(a: &[String], b: &[String], edits: &[Edit], context: usize) -> Vec<Hunk> {
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut old_position, mut new_position) = (0, 0);
+    for edit in edits {
+        positions.push((old_position, new_position));
+        match edit {
+            Edit::Equal(_, _) => {
+                old_position += 1;
+                new_position += 1;
+            }
+            Edit::Delete(_) => old_position += 1,
+            Edit::Insert(_) => new_position += 1,
+        }
+    }
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(_, _)))
+        .map(|(position, _)| position)
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut k = 0;
+    while k < changes.len() {
+        let first = changes[k];
+        let mut last = first;
+        while k + 1 < changes.len() && changes[k + 1] - last <= 2 * context + 1 {
+            k += 1;
+            last = changes[k];
+        }
+        k += 1;
+
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        let mut hunk = Hunk {
+            old_start: 0,
+            old_lines: 0,
+            new_start: 0,
+            new_lines: 0,
+            lines: Vec::new(),
+        };
+        for edit in &edits[start..end] {
+            match *edit {
+                Edit::Equal(i, _) => {
+                    hunk.lines.push(DiffLine::Context(a[i].clone()));
+                    hunk.old_lines += 1;
+                    hunk.new_lines += 1;
+                }
+                Edit::Delete(i) => {
+                    hunk.lines.push(DiffLine::Deletion(a[i].clone()));
+                    hunk.old_lines += 1;
+                }
+                Edit::Insert(j) => {
+                    hunk.lines.push(DiffLine::Addition(b[j].clone()));
+                    hunk.new_lines += 1;
+                }
+            }
+        }
+        let (old_position, new_position) = positions[start];
+        hunk.old_start = old_position + usize::from(hunk.old_lines > 0);
+        hunk.new_start = new_position + usize::from(hunk.new_lines > 0);
+        hunks.push(hunk);
+    }
+    hunks
+}
+
+/// Computes the hunks of the unified diff between two texts with the given algorithm.
+pub fn diff_hunks(
+    old_content: &str,
+    new_content: &str,
+    context: usize,
+    algorithm: DiffAlgorithm,
+) -> Vec<Hunk> {
+    let a = split_lines(old_content);
+    let b = split_lines(new_content);
+    let edits = compute_edits_with(&a, &b, algorithm);
+    build_hunks(&a, &b, &edits, context)
+}
+
+/// A change one side of a merge made to the base: the lines `start..end` of the base are replaced by `lines`.
+struct MergeChange {
+    ours: bool,
+    start: usize,
+    end: usize,
+    lines: Vec<String>,
+}
+
+/// Returns the changes that turn `base` into `side`, taken from the hunks without context between them.
+fn merge_changes(base: &str, side: &str, ours: bool) -> Vec<MergeChange> {
+    diff_hunks(base, side, 0, DiffAlgorithm::default())
+        .into_iter()
+        .map(|hunk| {
+            let start = hunk.old_start - usize::from(hunk.old_lines > 0);
+            let lines = hunk
+                .lines
+                .into_iter()
+                .filter_map(|line| match line {
+                    DiffLine::Addition(content) => Some(content),
+                    _ => None,
+                })
+                .collect();
+            MergeChange {
+                ours,
+                start,
+                end: start + hunk.old_lines,
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// Merges the changes that two versions of a file made to a common base, as diff3 does.
+///
+/// The changes of both sides are grouped when they touch the same lines of the base. A group changed
+/// by one side only, or by both sides in the same way, is taken as it is. Otherwise both versions are
+/// kept between conflict markers, leaving out the lines they start and end with in common.
+///
+/// # Arguments
+///
+/// * `base` - The content of the common ancestor, empty if there is none.
+/// * `ours` - Our version of the content.
+/// * `theirs` - Their version of the content.
+/// * `labels` - The names of both sides, written after the `<<<<<<<` and `>>>>>>>` markers.
+///
+/// # Returns
+///
+/// Returns the merged content and true if there are conflicts.
+///
+pub fn merge_three_way(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    labels: (&str, &str),
+) -> (String, bool) {
+    let base_lines = split_lines(base);
+    let mut changes = merge_changes(base, ours, true);
+    changes.extend(merge_changes(base, theirs, false));
+    changes.sort_by_key(|change| (change.start, !change.ours));
+
+    let mut merged: Vec<String> = Vec::new();
+    let mut conflicts = false;
+    let mut position = 0;
+    let mut i = 0;
+    while i < changes.len() {
+        let (start, mut end) = (changes[i].start, changes[i].end);
+        let mut j = i + 1;
+        while j < changes.len() && changes[j].start <= end {
+            end = end.max(changes[j].end);
+            j += 1;
+        }
+        let group = &changes[i..j];
+        let version = |ours: bool| {
+            let mut lines: Vec<String> = Vec::new();
+            let mut line = start;
+            for change in group.iter().filter(|change| change.ours == ours) {
+                lines.extend_from_slice(&base_lines[line..change.start]);
+                lines.extend(change.lines.iter().cloned());
+                line = change.end;
+            }
+            lines.extend_from_slice(&base_lines[line..end]);
+            lines
+        };
+        merged.extend_from_slice(&base_lines[position..start]);
+        let changed_by = |ours: bool| group.iter().any(|change| change.ours == ours);
+        let (our_part, their_part) = (version(true), version(false));
+        if !changed_by(false) || our_part == their_part {
+            merged.extend(our_part);
+        } else if !changed_by(true) {
+            merged.extend(their_part);
+        } else {
+            conflicts = true;
+            let prefix = our_part
+                .iter()
+                .zip(&their_part)
+                .take_while(|(ours, theirs)| ours == theirs)
+                .count();
+            let suffix = our_part[prefix..]
+                .iter()
+                .rev()
+                .zip(their_part[prefix..].iter().rev())
+                .take_while(|(ours, theirs)| ours == theirs)
+                .count();
+            merged.extend_from_slice(&our_part[..prefix]);
+            let markers = [
+                (format!("<<<<<<< {}\n", labels.0), &our_part),
+                ("=======\n".to_string(), &their_part),
+            ];
+            for (marker, part) in markers {
+                merged.push(marker);
+                merged.extend_from_slice(&part[prefix..part.len() - suffix]);
+                if let Some(last) = merged.last_mut() {
+                    if !last.ends_with('\n') {
+                        last.push('\n');
+                    }
+                }
+            }
+            merged.push(format!(">>>>>>> {}\n", labels.1));
+            merged.extend_from_slice(&our_part[our_part.len() - suffix..]);
+        }
+        position = end;
+        i = j;
+    }
+    merged.extend_from_slice(&base_lines[position..]);
+    (merged.concat(), conflicts)
+}
+
+/// Merges two blobs with `merge_three_way`, using the blob of their common ancestor as the base.
+/// Without a base, as for a file both sides added, the versions are merged from an empty file.
+///
+/// # Arguments
+///
+/// * `base_hash` - The hash of the blob of the common ancestor, if there is one.
+/// * `our_hash` - The hash of our blob.
+/// * `their_hash` - The hash of their blob.
+/// * `git_dir` - The path to the Git directory.
+/// * `labels` - The names of both sides, written after the conflict markers.
+///
+/// # Errors
+///
+/// Returns an error if one of the objects can't be read.
+///
+pub fn merge_objects(
+    base_hash: Option<&str>,
+    our_hash: &str,
+    their_hash: &str,
+    git_dir: &str,
+    labels: (&str, &str),
+) -> io::Result<(String, bool)> {
+    let base = match base_hash {
+        Some(base_hash) => cat_file_return_content(base_hash, git_dir)?,
+        None => String::new(),
+    };
+    let ours = cat_file_return_content(our_hash, git_dir)?;
+    let theirs = cat_file_return_content(their_hash, git_dir)?;
+    Ok(merge_three_way(&base, &ours, &theirs, labels))
+}
+
+/// How the `git diff` command shows the changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffFormat {
+    /// The unified diff of every file.
+    Patch,
+    /// A histogram of the added and removed lines of every file (`--stat`).
+    Stat,
+    /// Only the names of the changed files (`--name-only`).
+    NameOnly,
+    /// The names of the changed files with their status (`--name-status`).
+    NameStatus,
+}
+
+/// The options of the `git diff` command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    pub context: usize,
+    pub algorithm: DiffAlgorithm,
+    pub format: DiffFormat,
+    pub renames: Option<RenameOptions>,
+    pub cached: bool,
+    pub revisions: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context: DEFAULT_CONTEXT,
+            algorithm: DiffAlgorithm::Myers,
+            format: DiffFormat::Patch,
+            renames: None,
+            cached: false,
+            revisions: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Parses the arguments of `git diff` (the ones after 'diff').
+    /// Everything after `--` is taken as a path. `<rev1>..<rev2>` is the same as `<rev1> <rev2>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or the context is not a number.
+    pub fn parse(args: &[String]) -> io::Result<DiffOptions> {
+        let mut options = DiffOptions::default();
+        let invalid_option = |arg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: invalid option: {}", arg),
+            )
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    options.paths.extend(args.by_ref().cloned());
+                }
+                "--cached" | "--staged" => options.cached = true,
+                "--stat" => options.format = DiffFormat::Stat,
+                "--name-only" => options.format = DiffFormat::NameOnly,
+                "--name-status" => options.format = DiffFormat::NameStatus,
+                "--patience" => options.algorithm = DiffAlgorithm::Patience,
+                "--histogram" => options.algorithm = DiffAlgorithm::Histogram,
+                "--no-renames" => options.renames = None,
+                _ if arg.starts_with("-M") || arg.starts_with("--find-renames") => {
+                    let score = arg
+                        .trim_start_matches("-M")
+                        .trim_start_matches("--find-renames")
+                        .trim_start_matches('=');
+                    options.renames = Some(
+                        RenameOptions::with_score(score, false)
+                            .ok_or_else(|| invalid_option(arg))?,
+                    );
+                }
+                _ if arg.starts_with("-C") || arg.starts_with("--find-copies") => {
+                    let score = arg
+                        .trim_start_matches("-C")
+                        .trim_start_matches("--find-copies")
+                        .trim_start_matches('=');
+                    options.renames = Some(
+                        RenameOptions::with_score(score, true)
+                            .ok_or_else(|| invalid_option(arg))?,
+                    );
+                }
+                _ if arg.starts_with("--diff-algorithm=") => {
+                    let name = arg.trim_start_matches("--diff-algorithm=");
+                    options.algorithm =
+                        DiffAlgorithm::parse(name).ok_or_else(|| invalid_option(arg))?;
+                }
+                _ if arg.starts_with("-U") || arg.starts_with("--unified=") => {
+                    let value = arg
+                        .trim_start_matches("-U")
+                        .trim_start_matches("--unified=");
+                    options.context = value.parse().map_err(|_| invalid_option(arg))?;
+                }
+                _ if arg.starts_with('-') => return Err(invalid_option(arg)),
+                _ => match arg.split_once("..") {
+                    Some((from, to)) => {
+                        let from = if from.is_empty() { "HEAD" } else { from };
+                        let to = if to.is_empty() { "HEAD" } else { to };
+                        options.revisions.push(from.to_string());
+                        options.revisions.push(to.to_string());
+                    }
+                    None => options.revisions.push(arg.to_string()),
+                },
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A file that is different in the two sides of a comparison.
+/// `status` is 'A' for added files, 'D' for deleted files, 'M' for modified files,
+/// 'R' for renamed files and 'C' for copied files.
+/// Renamed and copied files keep the path they come from and how similar they are to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub path: String,
+    pub status: char,
+    pub old_hash: Option<String>,
+    pub new_hash: Option<String>,
+    pub old_path: Option<String>,
+    pub similarity: Option<usize>,
+}
+
+impl FileChange {
+    /// Marks the file as renamed ('R') or copied ('C') from `source`.
+    pub fn set_source(&mut self, source: &FileChange, status: char, similarity: usize) {
+        self.status = status;
+        self.old_hash = source.old_hash.clone();
+        self.old_path = Some(source.path.clone());
+        self.similarity = Some(similarity);
+    }
+
+    /// Returns the path of the file in the old side of the comparison.
+    pub fn source_path(&self) -> &str {
+        self.old_path.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Returns the name shown for the file: 'old => new' for renames and copies, otherwise the path.
+    pub fn display_name(&self) -> String {
+        match &self.old_path {
+            Some(old_path) => format!("{} => {}", old_path, self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Returns true if `path` is one of `paths` or is inside one of them. An empty list matches every path.
+pub fn path_is_selected(path: &str, paths: &[String]) -> bool {
+    paths.is_empty()
+        || paths.iter().any(|selected| {
+            let selected = selected.trim_end_matches('/');
+            selected.is_empty()
+                || selected == "."
+                || path == selected
+                || path.starts_with(&format!("{}/", selected))
+        })
+}
+
+/// Compares two lists of files (path, hash) and returns the files that changed, sorted by path.
+///
+/// # Arguments
+///
+/// * `old_files` - The files of the old side, for example the ones of a tree.
+/// * `new_files` - The files of the new side, for example the ones of the index.
+/// * `paths` - Only the files inside these paths are compared. If it's empty, every file is.
+///
+pub fn compare_files(
+    old_files: &[(String, String)],
+    new_files: &[(String, String)],
+    paths: &[String],
+) -> Vec<FileChange> {
+    let mut sides: BTreeMap<&str, (Option<&String>, Option<&String>)> = BTreeMap::new();
+    for (path, hash) in old_files {
+        sides.entry(path).or_default().0 = Some(hash);
+    }
+    for (path, hash) in new_files {
+        sides.entry(path).or_default().1 = Some(hash);
+    }
+    sides
+        .into_iter()
+        .filter(|(path, _)| path_is_selected(path, paths))
+        .filter_map(|(path, (old_hash, new_hash))| {
+            let status = match (old_hash, new_hash) {
+                (Some(old), Some(new)) if old == new => return None,
+                (Some(_), Some(_)) => 'M',
+                (None, Some(_)) => 'A',
+                (Some(_), None) => 'D',
+                (None, None) => return None,
+            };
+            Some(FileChange {
+                path: path.to_string(),
+                status,
+                old_hash: old_hash.cloned(),
+                new_hash: new_hash.cloned(),
+                old_path: None,
+                similarity: None,
+            })
+        })
+        .collect()
+}
+
+/// Returns the files of the index as tuples (path, hash).
+pub fn index_files(index: &Index) -> Vec<(String, String)> {
+    index
+        .iter()
+        .map(|(path, hash)| (path.to_string(), hash.to_string()))
+        .collect()
+}
+
+/// Returns the files of the working tree that are tracked in the index, with the hash of their current content.
+/// Files that were deleted from the working tree are left out.
+/// The content is hashed after the clean conversion of the attributes, as it would be stored.
+///
+/// # Arguments
+///
+/// * `index` - The index with the tracked files.
+/// * `root_dir` - The path to the working tree.
+/// * `attributes` - The attributes of the repository.
+///
+pub fn working_tree_files(
+    index: &Index,
+    root_dir: &str,
+    attributes: &Attributes,
+) -> io::Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    for (path, _) in index.iter() {
+        let file_path = Path::new(root_dir).join(path);
+        if file_path.is_file() {
+            let content = attributes.clean(path, fs::read(&file_path)?);
+            files.push((
+                path.to_string(),
+                hash_object::hash_content(&content, "blob"),
+            ));
+        }
+    }
+    Ok(files)
+}
+
+/// Returns the files of the tree of a revision. If the revision is `HEAD` and there are no commits yet, the list is empty.
+fn revision_files(revision: &str, git_dir: &str) -> io::Result<Vec<(String, String)>> {
+    let commit_hash = match utils::resolve_revision(revision, git_dir) {
+        Ok(hash) => hash,
+        Err(_) if revision == "HEAD" => return Ok(Vec::new()),
+        Err(error) => return Err(error),
     };
-    let object_b = match cat_file_return_content(hash_b, git_dir) {
-        Ok(content) => content,
-        Err(error) => return Err(error.to_string()),
+    Ok(tree_handler::load_tree_from_commit(&commit_hash, git_dir)?.get_files())
+}
+
+/// Reads the raw content of one side of a changed file.
+/// If `working_dir` is given, the content is read from the working tree instead of the object database,
+/// and it goes through the clean conversion of the attributes.
+pub fn read_side(
+    hash: &Option<String>,
+    path: &str,
+    git_dir: &str,
+    working_dir: Option<&str>,
+    attributes: &Attributes,
+) -> io::Result<Vec<u8>> {
+    match (hash, working_dir) {
+        (None, _) => Ok(Vec::new()),
+        (Some(_), Some(working_dir)) => {
+            let content = fs::read(Path::new(working_dir).join(path))?;
+            Ok(attributes.clean(path, content))
+        }
+        (Some(hash), None) => cat_file_return_bytes(hash, git_dir),
+    }
+}
+
+/// Returns the abbreviated form of a hash used in the `index` line of a patch.
+fn abbreviate(hash: &Option<String>) -> &str {
+    let hash = hash.as_deref().unwrap_or(NULL_HASH);
+    hash.get(..7).unwrap_or(hash)
+}
+
+/// Writes the unified diff of every changed file.
+///
+/// # Arguments
+///
+/// * `changes` - The changed files.
+/// * `git_dir` - The path to the git directory.
+/// * `working_dir` - If given, the new side of the files is read from this working tree.
+/// * `options` - The context of each hunk and the algorithm to use.
+/// * `output` - Where the diff is written.
+///
+/// Binary files, either by content or because their attributes or the configuration say so,
+/// are shown as 'Binary files a/<path> and b/<path> differ' instead of a list of hunks.
+///
+pub fn write_patch(
+    changes: &[FileChange],
+    git_dir: &str,
+    working_dir: Option<&str>,
+    options: &DiffOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let attributes = Attributes::load(git_dir);
+    for change in changes {
+        let old_path = change.source_path();
+        let old_content = read_side(&change.old_hash, old_path, git_dir, None, &attributes)?;
+        let new_content = read_side(
+            &change.new_hash,
+            &change.path,
+            git_dir,
+            working_dir,
+            &attributes,
+        )?;
+        writeln!(output, "diff --git a/{} b/{}", old_path, change.path)?;
+        match change.status {
+            'A' => writeln!(output, "new file mode {}", FILE_MODE)?,
+            'D' => writeln!(output, "deleted file mode {}", FILE_MODE)?,
+            'R' | 'C' => {
+                let action = if change.status == 'R' {
+                    "rename"
+                } else {
+                    "copy"
+                };
+                writeln!(
+                    output,
+                    "similarity index {}%",
+                    change.similarity.unwrap_or(100)
+                )?;
+                writeln!(output, "{} from {}", action, old_path)?;
+                writeln!(output, "{} to {}", action, change.path)?;
+            }
+            _ => {}
+        }
+        if change.old_hash == change.new_hash {
+            continue;
+        }
+        let mode = if matches!(change.status, 'M' | 'R' | 'C') {
+            format!(" {}", FILE_MODE)
+        } else {
+            String::new()
+        };
+        writeln!(
+            output,
+            "index {}..{}{}",
+            abbreviate(&change.old_hash),
+            abbreviate(&change.new_hash),
+            mode
+        )?;
+        if attributes.is_binary_file(old_path, &old_content)
+            || attributes.is_binary_file(&change.path, &new_content)
+        {
+            let old_name = match change.old_hash {
+                Some(_) => format!("a/{}", old_path),
+                None => "/dev/null".to_string(),
+            };
+            let new_name = match change.new_hash {
+                Some(_) => format!("b/{}", change.path),
+                None => "/dev/null".to_string(),
+            };
+            writeln!(output, "Binary files {} and {} differ", old_name, new_name)?;
+            continue;
+        }
+        let hunks = diff_hunks(
+            &String::from_utf8_lossy(&old_content),
+            &String::from_utf8_lossy(&new_content),
+            options.context,
+            options.algorithm,
+        );
+        if hunks.is_empty() {
+            continue;
+        }
+        match change.old_hash {
+            Some(_) => writeln!(output, "--- a/{}", old_path)?,
+            None => writeln!(output, "--- /dev/null")?,
+        }
+        match change.new_hash {
+            Some(_) => writeln!(output, "+++ b/{}", change.path)?,
+            None => writeln!(output, "+++ /dev/null")?,
+        }
+        for hunk in hunks {
+            write!(output, "{}", hunk)?;
+        }
+    }
+    Ok(())
+}
+
+/// A file of a merge commit that is different from the one of every parent.
+/// `parent_hashes` has the hash of the file in each parent, or None if the parent doesn't have it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombinedChange {
+    pub path: String,
+    pub parent_hashes: Vec<Option<String>>,
+    pub hash: String,
+}
+
+/// Compares the files of a merge commit with the ones of its parents and returns the files
+/// that differ from every parent, sorted by path. Files the merge took as they were in one of the parents are left out.
+///
+/// # Arguments
+///
+/// * `parent_files` - The files (path, hash) of each parent.
+/// * `files` - The files of the merge commit.
+/// * `paths` - Only the files inside these paths are compared. If it's empty, every file is.
+///
+pub fn compare_merge_files(
+    parent_files: &[Vec<(String, String)>],
+    files: &[(String, String)],
+    paths: &[String],
+) -> Vec<CombinedChange> {
+    let parent_files: Vec<HashMap<&str, &str>> = parent_files
+        .iter()
+        .map(|files| {
+            files
+                .iter()
+                .map(|(path, hash)| (path.as_str(), hash.as_str()))
+                .collect()
+        })
+        .collect();
+    let mut changes: Vec<CombinedChange> = files
+        .iter()
+        .filter(|(path, _)| path_is_selected(path, paths))
+        .filter_map(|(path, hash)| {
+            let parent_hashes: Vec<Option<String>> = parent_files
+                .iter()
+                .map(|files| files.get(path.as_str()).map(|hash| hash.to_string()))
+                .collect();
+            if parent_hashes
+                .iter()
+                .any(|parent| parent == &Some(hash.clone()))
+            {
+                return None;
+            }
+            Some(CombinedChange {
+                path: path.to_string(),
+                parent_hashes,
+                hash: hash.to_string(),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// A line of a combined diff: a line of the merged file, or a line some parents had and the merge lost.
+enum CombinedLine {
+    Merged(usize),
+    Lost(Vec<usize>, String),
+}
+
+/// Writes the combined diff (`diff --cc`) of the files of a merge commit.
+///
+/// Each line has one column per parent: '+' if the line is not in that parent, '-' if the line is only in that parent.
+/// As git does by default, only the hunks that differ from every parent are shown, so the hunks where the merge
+/// simply took the lines of one of the parents are left out.
+///
+/// # Arguments
+///
+/// * `changes` - The files that differ from every parent.
+/// * `git_dir` - The path to the git directory.
+/// * `options` - The context of each hunk and the algorithm to use.
+/// * `output` - Where the diff is written.
+///
+pub fn write_combined_patch(
+    changes: &[CombinedChange],
+    git_dir: &str,
+    options: &DiffOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let attributes = Attributes::load(git_dir);
+    for change in changes {
+        let content = cat_file_return_bytes(&change.hash, git_dir)?;
+        let mut parent_contents = Vec::new();
+        for hash in &change.parent_hashes {
+            parent_contents.push(read_side(hash, &change.path, git_dir, None, &attributes)?);
+        }
+        writeln!(output, "diff --cc {}", change.path)?;
+        let parent_hashes: Vec<&str> = change.parent_hashes.iter().map(abbreviate).collect();
+        writeln!(
+            output,
+            "index {}..{}",
+            parent_hashes.join(","),
+            abbreviate(&Some(change.hash.clone()))
+        )?;
+        if attributes.is_binary_file(&change.path, &content)
+            || parent_contents
+                .iter()
+                .any(|parent| attributes.is_binary_file(&change.path, parent))
+        {
+            writeln!(output, "Binary files differ")?;
+            continue;
+        }
+        let lines = split_lines(&String::from_utf8_lossy(&content));
+        let parents: Vec<Vec<String>> = parent_contents
+            .iter()
+            .map(|parent| split_lines(&String::from_utf8_lossy(parent)))
+            .collect();
+        writeln!(output, "--- a/{}", change.path)?;
+        writeln!(output, "+++ b/{}", change.path)?;
+        write_combined_hunks(&parents, &lines, options, output)?;
+    }
+    Ok(())
+}
+
+/// Writes the hunks of the combined diff between the lines of the parents and the lines of the merged file.
+fn write_combined_hunks(
+    parents: &[Vec<String>],
+    lines: &[String],
+    options: &DiffOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    // For each parent: where each merged line comes from, and which of its lines were lost before each merged line.
+    let mut origins = vec![vec![false; lines.len()]; parents.len()];
+    let mut lost = vec![vec![Vec::new(); lines.len() + 1]; parents.len()];
+    for (parent, parent_lines) in parents.iter().enumerate() {
+        let mut position = 0;
+        for edit in compute_edits_with(parent_lines, lines, options.algorithm) {
+            match edit {
+                Edit::Equal(_, j) => {
+                    origins[parent][j] = true;
+                    position = j + 1;
+                }
+                Edit::Delete(i) => lost[parent][position].push(i),
+                Edit::Insert(j) => position = j + 1,
+            }
+        }
+    }
+
+    let mut rows: Vec<CombinedLine> = Vec::new();
+    for position in 0..=lines.len() {
+        let first_lost = rows.len();
+        for (parent, parent_lost) in lost.iter().enumerate() {
+            for &i in &parent_lost[position] {
+                let content = &parents[parent][i];
+                let same_line = rows[first_lost..].iter_mut().find(|row| {
+                    matches!(row, CombinedLine::Lost(lost_by, lost_content)
+                        if lost_content == content && !lost_by.contains(&parent))
+                });
+                match same_line {
+                    Some(CombinedLine::Lost(lost_by, _)) => lost_by.push(parent),
+                    _ => rows.push(CombinedLine::Lost(vec![parent], content.clone())),
+                }
+            }
+        }
+        if position < lines.len() {
+            rows.push(CombinedLine::Merged(position));
+        }
+    }
+
+    let changes_parent = |row: &CombinedLine, parent: usize| match row {
+        CombinedLine::Merged(j) => !origins[parent][*j],
+        CombinedLine::Lost(lost_by, _) => lost_by.contains(&parent),
     };
-    let object_a_vec = object_a
-        .split('\n')
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
-    let object_b_vec = object_b
-        .split('\n')
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
-    let mut output: Vec<String> = Vec::new();
-    for line in diff_to_vec(
-        &object_a_vec,
-        &object_b_vec,
-        object_a_vec.len(),
-        object_b_vec.len(),
-    ) {
-        output.push(line);
-    }
-    Ok(output.join(""))
+    let is_change =
+        |row: &CombinedLine| (0..parents.len()).any(|parent| changes_parent(row, parent));
+    // How many lines of each parent, and of the merged file (the last one), come before each row.
+    let mut positions = vec![vec![0; parents.len() + 1]];
+    for row in &rows {
+        let mut next = positions[positions.len() - 1].clone();
+        for (parent, position) in next.iter_mut().take(parents.len()).enumerate() {
+            let in_parent = match row {
+                CombinedLine::Merged(j) => origins[parent][*j],
+                CombinedLine::Lost(lost_by, _) => lost_by.contains(&parent),
+            };
+            if in_parent {
+                *position += 1;
+            }
+        }
+        if let CombinedLine::Merged(_) = row {
+            next[parents.len()] += 1;
+        }
+        positions.push(next);
+    }
+
+    let changes: Vec<usize> = (0..rows.len()).filter(|&r| is_change(&rows[r])).collect();
+    let context = options.context;
+    let marker = "@".repeat(parents.len() + 1);
+    let mut k = 0;
+    while k < changes.len() {
+        let first = changes[k];
+        let mut last = first;
+        while k + 1 < changes.len() && changes[k + 1] - last <= 2 * context + 1 {
+            k += 1;
+            last = changes[k];
+        }
+        k += 1;
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(rows.len());
+        let hunk_rows = &rows[start..end];
+        if !(0..parents.len()).all(|parent| hunk_rows.iter().any(|row| changes_parent(row, parent)))
+        {
+            continue;
+        }
+
+        write!(output, "{}", marker)?;
+        for (side, (before, after)) in positions[start].iter().zip(&positions[end]).enumerate() {
+            let count = after - before;
+            let sign = if side == parents.len() { '+' } else { '-' };
+            let range = format_range(before + usize::from(count > 0), count);
+            write!(output, " {}{}", sign, range)?;
+        }
+        writeln!(output, " {}", marker)?;
+        for row in hunk_rows {
+            let (columns, content): (String, &String) = match row {
+                CombinedLine::Merged(j) => (
+                    (0..parents.len())
+                        .map(|parent| if origins[parent][*j] { ' ' } else { '+' })
+                        .collect(),
+                    &lines[*j],
+                ),
+                CombinedLine::Lost(lost_by, content) => (
+                    (0..parents.len())
+                        .map(|parent| if lost_by.contains(&parent) { '-' } else { ' ' })
+                        .collect(),
+                    content,
+                ),
+            };
+            write!(output, "{}{}", columns, content)?;
+            if !content.ends_with('\n') {
+                write!(output, "\n\\ No newline at end of file\n")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes, for every changed file, the number of changed lines and a histogram of additions and deletions,
+/// followed by a summary line. Binary files show their size before and after the change instead.
+pub fn write_stat(
+    changes: &[FileChange],
+    git_dir: &str,
+    working_dir: Option<&str>,
+    algorithm: DiffAlgorithm,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let attributes = Attributes::load(git_dir);
+    let mut stats = Vec::new();
+    let mut binary_sizes = Vec::new();
+    for change in changes {
+        let old_path = change.source_path();
+        let old_content = read_side(&change.old_hash, old_path, git_dir, None, &attributes)?;
+        let new_content = read_side(
+            &change.new_hash,
+            &change.path,
+            git_dir,
+            working_dir,
+            &attributes,
+        )?;
+        if attributes.is_binary_file(old_path, &old_content)
+            || attributes.is_binary_file(&change.path, &new_content)
+        {
+            binary_sizes.push(Some((old_content.len(), new_content.len())));
+            stats.push((change.display_name(), 0, 0));
+            continue;
+        }
+        let (old_content, new_content) = (
+            String::from_utf8_lossy(&old_content),
+            String::from_utf8_lossy(&new_content),
+        );
+        binary_sizes.push(None);
+        let (mut insertions, mut deletions): (usize, usize) = (0, 0);
+        for hunk in diff_hunks(&old_content, &new_content, 0, algorithm) {
+            for line in hunk.lines {
+                match line {
+                    DiffLine::Addition(_) => insertions += 1,
+                    DiffLine::Deletion(_) => deletions += 1,
+                    DiffLine::Context(_) => {}
+                }
+            }
+        }
+        stats.push((change.display_name(), insertions, deletions));
+    }
+    if stats.is_empty() {
+        return Ok(());
+    }
+
+    let name_width = stats
+        .iter()
+        .map(|(path, _, _)| path.len())
+        .max()
+        .unwrap_or(0);
+    let max_changes = stats.iter().map(|(_, i, d)| i + d).max().unwrap_or(0);
+    let count_width = max_changes.to_string().len();
+    let bar_width: usize = 50;
+    let (mut total_insertions, mut total_deletions) = (0, 0);
+    for ((path, insertions, deletions), sizes) in stats.iter().zip(binary_sizes) {
+        if let Some((old_size, new_size)) = sizes {
+            writeln!(
+                output,
+                " {:<name_width$} | Bin {} -> {} bytes",
+                path, old_size, new_size
+            )?;
+            continue;
+        }
+        let (mut plus, mut minus) = (*insertions, *deletions);
+        if max_changes > bar_width {
+            plus = (plus * bar_width).div_ceil(max_changes);
+            minus = (minus * bar_width).div_ceil(max_changes);
+        }
+        writeln!(
+            output,
+            " {:<name_width$} | {:>count_width$} {}{}",
+            path,
+            insertions + deletions,
+            "+".repeat(plus),
+            "-".repeat(minus)
+        )?;
+        total_insertions += insertions;
+        total_deletions += deletions;
+    }
+
+    let plural = |count: usize, singular: &str, plural: &str| {
+        if count == 1 {
+            format!("{} {}", count, singular)
+        } else {
+            format!("{} {}", count, plural)
+        }
+    };
+    let mut summary = format!(" {}", plural(stats.len(), "file changed", "files changed"));
+    if total_insertions > 0 || total_deletions == 0 {
+        summary.push_str(&format!(
+            ", {}",
+            plural(total_insertions, "insertion(+)", "insertions(+)")
+        ));
+    }
+    if total_deletions > 0 || total_insertions == 0 {
+        summary.push_str(&format!(
+            ", {}",
+            plural(total_deletions, "deletion(-)", "deletions(-)")
+        ));
+    }
+    writeln!(output, "{}", summary)
+}
+
+/// Writes the changed files in the given format.
+///
+/// # Arguments
+///
+/// * `changes` - The changed files.
+/// * `git_dir` - The path to the git directory.
+/// * `working_dir` - If given, the new side of the files is read from this working tree.
+/// * `options` - The format, the context and the algorithm to use.
+/// * `output` - Where the changes are written.
+///
+pub fn write_changes(
+    changes: &[FileChange],
+    git_dir: &str,
+    working_dir: Option<&str>,
+    options: &DiffOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    match options.format {
+        DiffFormat::Patch => write_patch(changes, git_dir, working_dir, options, output),
+        DiffFormat::Stat => write_stat(changes, git_dir, working_dir, options.algorithm, output),
+        DiffFormat::NameOnly => {
+            for change in changes {
+                writeln!(output, "{}", change.path)?;
+            }
+            Ok(())
+        }
+        DiffFormat::NameStatus => {
+            for change in changes {
+                match (&change.old_path, change.similarity) {
+                    (Some(old_path), Some(similarity)) => writeln!(
+                        output,
+                        "{}{:03}\t{}\t{}",
+                        change.status, similarity, old_path, change.path
+                    )?,
+                    _ => writeln!(output, "{}\t{}", change.status, change.path)?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Logs the 'git diff' command with the specified Git directory and arguments.
+///
+/// This function logs the 'git diff' command with the provided Git directory and arguments
+/// to a file named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_diff(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git diff': Git Directory '{}', Args '{}', {}",
+        git_dir,
+        args.join(" "),
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Shows the changes between the working tree, the index and commits.
+///
+/// * `git diff` shows the changes in the working tree that are not staged.
+/// * `git diff --cached [<rev>]` shows the staged changes, compared with `<rev>` or HEAD.
+/// * `git diff <rev>` shows the changes in the working tree compared with `<rev>`.
+/// * `git diff <rev1> <rev2>` shows the changes between two commits.
+///
+/// Every form accepts `-U<n>`, `--diff-algorithm=<myers|patience|histogram>`, `-M[<n>%]` to detect renames,
+/// `-C[<n>%]` to also detect copies, `--stat`, `--name-only`, `--name-status` and `-- <paths>`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `line` - The command line, starting with 'git diff'.
+/// * `output` - Where the diff is written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, a revision can't be resolved or an object can't be read.
+///
+pub fn git_diff(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let args = line.get(2..).unwrap_or_default();
+    let options = DiffOptions::parse(args)?;
+    log_diff(git_dir, args)?;
+
+    let index_path = utils::get_index_file_path(git_dir);
+    let index = Index::load(&index_path, git_dir, "")?;
+    let attributes = Attributes::load(git_dir);
+    let (old_files, new_files, working_dir) = match (options.cached, options.revisions.as_slice()) {
+        (false, []) => (
+            index_files(&index),
+            working_tree_files(&index, root_dir, &attributes)?,
+            Some(root_dir),
+        ),
+        (true, []) => (revision_files("HEAD", git_dir)?, index_files(&index), None),
+        (true, [revision]) => (
+            revision_files(revision, git_dir)?,
+            index_files(&index),
+            None,
+        ),
+        (false, [revision]) => (
+            revision_files(revision, git_dir)?,
+            working_tree_files(&index, root_dir, &attributes)?,
+            Some(root_dir),
+        ),
+        (false, [old, new]) => (
+            revision_files(old, git_dir)?,
+            revision_files(new, git_dir)?,
+            None,
+        ),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: git diff [--cached] [<rev> [<rev>]] [-- <paths>...]",
+            ))
+        }
+    };
+    let mut changes = compare_files(&old_files, &new_files, &options.paths);
+    if let Some(renames) = options.renames {
+        changes = rename::detect_renames(changes, git_dir, working_dir, renames);
+    }
+    write_changes(&changes, git_dir, working_dir, &options, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add, commit};
+
+    fn create_mock_git_dir(git_dir: &str) {
+        let _ = fs::remove_dir_all(git_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir)).unwrap();
+        fs::create_dir_all(format!("{}/refs/heads", git_dir)).unwrap();
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master").unwrap();
+        fs::write(format!("{}/index", git_dir), "").unwrap();
+    }
+
+    fn add_file(git_dir: &str, path: &str, content: &str) {
+        fs::write(path, content).unwrap();
+        let index_path = format!("{}/index", git_dir);
+        add::add(path, &index_path, git_dir, "", None).unwrap();
+    }
+
+    fn run_diff(git_dir: &str, args: &[&str]) -> String {
+        let mut line = vec!["git".to_string(), "diff".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output: Vec<u8> = Vec::new();
+        git_diff(git_dir, "", line, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_changes_far_apart_go_in_different_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\ntwo\n3\n4\n5\n6\n7\n8\n9\nten\n";
+
+        let hunks = diff_hunks(old, new, 1, DiffAlgorithm::Myers);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].to_string(), "@@ -1,3 +1,3 @@\n 1\n-2\n+two\n 3\n");
+        assert_eq!(hunks[1].to_string(), "@@ -9,2 +9,2 @@\n 9\n-10\n+ten\n");
+
+        let hunks = diff_hunks(old, new, 4, DiffAlgorithm::Myers);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!((hunks[0].old_start, hunks[0].old_lines), (1, 10));
+    }
+
+    #[test]
+    fn test_missing_newline_at_end_of_file_is_a_change() {
+        let hunks = diff_hunks("a\nb", "a\nb\n", 0, DiffAlgorithm::Myers);
+        assert_eq!(
+            hunks[0].to_string(),
+            "@@ -2 +2 @@\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        let hunks = diff_hunks("", "a\n", 3, DiffAlgorithm::Myers);
+        assert_eq!(hunks[0].to_string(), "@@ -0,0 +1 @@\n+a\n");
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.chars().map(|c| c.to_string()).collect()
+    }
+
+    /// Length of the longest common subsequence, computed with the classic quadratic table.
+    fn longest_common_subsequence(a: &[String], b: &[String]) -> usize {
+        let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                table[i + 1][j + 1] = if a[i] == b[j] {
+                    table[i][j] + 1
+                } else {
+                    table[i + 1][j].max(table[i][j + 1])
+                };
+            }
+        }
+        table[a.len()][b.len()]
+    }
+
+    #[test]
+    fn test_every_algorithm_produces_a_valid_edit_script() {
+        let cases = [
+            ("abcabba", "cbabac"),
+            ("", "abc"),
+            ("abc", ""),
+            ("abcdef", "abcdef"),
+            ("xaxbxcx", "axbxcxa"),
+            ("{a}{b}{c}", "{a}{x}{b}{c}{y}"),
+            ("aaaabaaaa", "aaaacaaaa"),
+        ];
+        for (old, new) in cases {
+            let (a, b) = (lines(old), lines(new));
+            for algorithm in [
+                DiffAlgorithm::Myers,
+                DiffAlgorithm::Patience,
+                DiffAlgorithm::Histogram,
+            ] {
+                let edits = compute_edits_with(&a, &b, algorithm);
+                let (mut rebuilt_a, mut rebuilt_b) = (Vec::new(), Vec::new());
+                for edit in &edits {
+                    match *edit {
+                        Edit::Equal(i, j) => {
+                            assert_eq!(a[i], b[j]);
+                            rebuilt_a.push(a[i].clone());
+                            rebuilt_b.push(b[j].clone());
+                        }
+                        Edit::Delete(i) => rebuilt_a.push(a[i].clone()),
+                        Edit::Insert(j) => rebuilt_b.push(b[j].clone()),
+                    }
+                }
+                assert_eq!((&rebuilt_a, &rebuilt_b), (&a, &b), "{:?}", algorithm);
+                if algorithm == DiffAlgorithm::Myers {
+                    let equal = edits
+                        .iter()
+                        .filter(|edit| matches!(edit, Edit::Equal(_, _)))
+                        .count();
+                    assert_eq!(equal, longest_common_subsequence(&a, &b), "{}", old);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_big_files_are_diffed_quickly() {
+        let old: String = (0..2000).map(|i| format!("line {}\n", i % 150)).collect();
+        let new = old
+            .replacen("line 10\n", "changed\n", 1)
+            .replace("line 149\n", "")
+            + "end\n";
+        for algorithm in [
+            DiffAlgorithm::Myers,
+            DiffAlgorithm::Patience,
+            DiffAlgorithm::Histogram,
+        ] {
+            let hunks = diff_hunks(&old, &new, 0, algorithm);
+            let changed: usize = hunks.iter().map(|hunk| hunk.lines.len()).sum();
+            assert_eq!(changed, 2 + 13 + 1, "{:?}", algorithm);
+        }
+
+        let old: String = (0..20000).map(|i| format!("line {}\n", i)).collect();
+        let new = "start\n".to_string() + &old.replacen("line 10000\n", "changed\n", 1) + "end\n";
+        for algorithm in [
+            DiffAlgorithm::Myers,
+            DiffAlgorithm::Patience,
+            DiffAlgorithm::Histogram,
+        ] {
+            let hunks = diff_hunks(&old, &new, 0, algorithm);
+            let changed: usize = hunks.iter().map(|hunk| hunk.lines.len()).sum();
+            assert_eq!(changed, 1 + 2 + 1, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_merge_three_way_takes_the_changes_of_both_sides() {
+        let base = "uno\ndos\ntres\n";
+        let labels = ("ours", "theirs");
+        assert_eq!(
+            merge_three_way(base, "UNO\ndos\ntres\n", "uno\ndos\nTRES\n", labels),
+            ("UNO\ndos\nTRES\n".to_string(), false)
+        );
+        assert_eq!(
+            merge_three_way(
+                base,
+                "uno\ndos\ntres\ncuatro\n",
+                "cero\nuno\ndos\ntres\n",
+                labels
+            ),
+            ("cero\nuno\ndos\ntres\ncuatro\n".to_string(), false)
+        );
+        assert_eq!(
+            merge_three_way(base, "uno\nDOS\ntres\n", "uno\nDOS\ntres\n", labels),
+            ("uno\nDOS\ntres\n".to_string(), false)
+        );
+        assert_eq!(
+            merge_three_way(base, "uno\ndos\n", "uno\ndos\ntres\n", labels),
+            ("uno\ndos\n".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn test_merge_three_way_marks_the_conflicts() {
+        let base = "uno\ndos\ntres\n";
+        assert_eq!(
+            merge_three_way(base, "uno\n2\ntres\n", "uno\nDOS\ntres\n", ("HEAD", "rama")),
+            (
+                "uno\n<<<<<<< HEAD\n2\n=======\nDOS\n>>>>>>> rama\ntres\n".to_string(),
+                true
+            )
+        );
+        assert_eq!(
+            merge_three_way("", "a\nb\nc", "a\nx\nc", ("ours", "theirs")),
+            (
+                "a\n<<<<<<< ours\nb\n=======\nx\n>>>>>>> theirs\nc".to_string(),
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn test_lines_only_in_one_side_are_marked() {
+        let a = lines("abc");
+        let b = lines("axc");
+        assert_eq!(
+            diff_to_vec(&a, &b),
+            vec!["a\n", "<<<<<<< b\n", ">>>>>>> x\n", "c\n"]
+        );
+    }
+
+    #[test]
+    fn test_options_are_parsed() {
+        let args: Vec<String> = [
+            "--cached",
+            "-U1",
+            "--stat",
+            "--diff-algorithm=histogram",
+            "HEAD~1..HEAD",
+            "--",
+            "src",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let options = DiffOptions::parse(&args).unwrap();
+        assert!(options.cached);
+        assert_eq!(options.context, 1);
+        assert_eq!(options.format, DiffFormat::Stat);
+        assert_eq!(options.algorithm, DiffAlgorithm::Histogram);
+        assert_eq!(options.revisions, vec!["HEAD~1", "HEAD"]);
+        assert_eq!(options.paths, vec!["src"]);
+        assert!(DiffOptions::parse(&["--bogus".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_diff_of_working_tree_index_and_commits() {
+        let root_dir = "tests/diff/test_diff";
+        let git_dir = format!("{}/.mgit", root_dir);
+        create_mock_git_dir(&git_dir);
+        let src_dir = format!("{}/src", root_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        let main_path = format!("{}/main.c", src_dir);
+        let other_path = format!("{}/other.c", src_dir);
+
+        add_file(&git_dir, &main_path, "int main() {\n    return 0;\n}\n");
+        let old_hash = hash_object::hash_file_content(&main_path, "blob").unwrap();
+        let first = commit::new_commit(&git_dir, "First", "").unwrap();
+        fs::write(&main_path, "int main() {\n    return 1;\n}\n").unwrap();
+        let new_hash = hash_object::hash_file_content(&main_path, "blob").unwrap();
+
+        assert_eq!(
+            run_diff(&git_dir, &[]),
+            format!(
+                "diff --git a/{0} b/{0}\nindex {1}..{2} 100644\n--- a/{0}\n+++ b/{0}\n@@ -1,3 +1,3 @@\n int main() {{\n-    return 0;\n+    return 1;\n }}\n",
+                main_path,
+                &old_hash[..7],
+                &new_hash[..7]
+            )
+        );
+        assert_eq!(run_diff(&git_dir, &["--cached"]), "");
+
+        add_file(&git_dir, &main_path, "int main() {\n    return 1;\n}\n");
+        add_file(&git_dir, &other_path, "other\n");
+        assert_eq!(run_diff(&git_dir, &[]), "");
+        assert_eq!(
+            run_diff(&git_dir, &["--cached", "--name-status"]),
+            format!("M\t{}\nA\t{}\n", main_path, other_path)
+        );
+        let second = commit::new_commit(&git_dir, "Second", "").unwrap();
+
+        assert_eq!(
+            run_diff(
+                &git_dir,
+                &[&first, &second, "--name-only", "--", &other_path]
+            ),
+            format!("{}\n", other_path)
+        );
+        let stat = run_diff(&git_dir, &["--stat", &format!("{}..{}", first, second)]);
+        let width = other_path.len();
+        assert!(stat.contains(&format!(" {:<width$} | 2 +-\n", main_path)));
+        assert!(stat.contains(&format!(" {} | 1 +\n", other_path)));
+        assert!(stat.ends_with(" 2 files changed, 2 insertions(+), 1 deletion(-)\n"));
+        let patch = run_diff(&git_dir, &["HEAD~1", "--", &other_path]);
+        assert!(patch.contains("new file mode 100644\n"));
+        assert!(patch.contains("--- /dev/null\n"));
+        assert!(patch.ends_with("@@ -0,0 +1 @@\n+other\n"));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_binary_files_are_not_diffed_line_by_line() {
+        let root_dir = "tests/diff/test_diff_binary";
+        let git_dir = format!("{}/.mgit", root_dir);
+        create_mock_git_dir(&git_dir);
+        fs::write(
+            format!("{}/config", git_dir),
+            "[core]\n\trepositoryformatversion = 0\n\tfilemode = true\n\tbare = false\n\tlogallrefupdates = true\n[diff]\n\tbinary = *.dat\n",
+        )
+        .unwrap();
+        let image_path = format!("{}/logo.png", root_dir);
+        let data_path = format!("{}/table.dat", root_dir);
+        fs::write(&image_path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\xff").unwrap();
+        add::add(
+            &image_path,
+            &format!("{}/index", git_dir),
+            &git_dir,
+            "",
+            None,
+        )
+        .unwrap();
+        add_file(&git_dir, &data_path, "1,2,3\n");
+        commit::new_commit(&git_dir, "First", "").unwrap();
+        fs::write(&image_path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\xfe\xfe").unwrap();
+        fs::write(&data_path, "1,2,4\n").unwrap();
+
+        let patch = run_diff(&git_dir, &[]);
+        assert!(patch.contains(&format!(
+            "Binary files a/{0} and b/{0} differ\n",
+            image_path
+        )));
+        assert!(patch.contains(&format!("Binary files a/{0} and b/{0} differ\n", data_path)));
+        assert!(!patch.contains("@@"));
+        let stat = run_diff(&git_dir, &["--stat"]);
+        assert!(stat.contains(&format!(" {}  | Bin 17 -> 18 bytes\n", image_path)));
+        assert!(stat.contains(&format!(" {} | Bin 6 -> 6 bytes\n", data_path)));
+        assert!(stat.contains(" 2 files changed, 0 insertions(+), 0 deletions(-)\n"));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_attributes_normalize_line_endings_and_force_binary_diffs() {
+        let root_dir = "tests/diff/test_diff_attributes";
+        let git_dir = format!("{}/.mgit", root_dir);
+        create_mock_git_dir(&git_dir);
+        fs::write(
+            format!("{}/.gitattributes", root_dir),
+            "*.txt text\nnotes.md -diff\n",
+        )
+        .unwrap();
+        let text_path = format!("{}/a.txt", root_dir);
+        let notes_path = format!("{}/notes.md", root_dir);
+        add_file(&git_dir, &text_path, "uno\r\ndos\r\n");
+        add_file(&git_dir, &notes_path, "hola\n");
+        commit::new_commit(&git_dir, "First", "").unwrap();
+        let tree = tree_handler::load_tree_from_commit(
+            &utils::resolve_revision("HEAD", &git_dir).unwrap(),
+            &git_dir,
+        )
+        .unwrap();
+        let stored = tree.get_hash_from_path(&text_path).unwrap();
+        assert_eq!(
+            cat_file_return_content(&stored, &git_dir).unwrap(),
+            "uno\ndos\n"
+        );
+
+        fs::write(&text_path, "uno\ndos\n").unwrap();
+        assert_eq!(run_diff(&git_dir, &[]), "");
+
+        fs::write(&text_path, "uno\r\ndos\r\ntres\r\n").unwrap();
+        fs::write(&notes_path, "chau\n").unwrap();
+        let patch = run_diff(&git_dir, &[]);
+        assert!(patch.contains(" uno\n dos\n+tres\n"));
+        assert!(patch.contains(&format!(
+            "Binary files a/{0} and b/{0} differ\n",
+            notes_path
+        )));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_renames_are_shown_with_find_renames() {
+        let root_dir = "tests/diff/test_diff_renames";
+        let git_dir = format!("{}/.mgit", root_dir);
+        create_mock_git_dir(&git_dir);
+        let old_path = format!("{}/old.txt", root_dir);
+        let new_path = format!("{}/new.txt", root_dir);
+        let content: String = (1..=10).map(|i| format!("line {:04}\n", i)).collect();
+        add_file(&git_dir, &old_path, &content);
+        commit::new_commit(&git_dir, "First", "").unwrap();
+
+        let index_path = format!("{}/index", git_dir);
+        let mut index = Index::load(&index_path, &git_dir, "").unwrap();
+        index.remove_file(&old_path).unwrap();
+        index.write_file().unwrap();
+        fs::remove_file(&old_path).unwrap();
+        add_file(&git_dir, &new_path, &content.replace("0010", "diez"));
+
+        assert_eq!(
+            run_diff(&git_dir, &["--cached", "--name-status"]),
+            format!("A\t{}\nD\t{}\n", new_path, old_path)
+        );
+        assert_eq!(
+            run_diff(&git_dir, &["--cached", "-M", "--name-status"]),
+            format!("R090\t{}\t{}\n", old_path, new_path)
+        );
+        assert_eq!(
+            run_diff(&git_dir, &["--cached", "-M95%", "--name-only"]),
+            format!("{}\n{}\n", new_path, old_path)
+        );
+        let patch = run_diff(&git_dir, &["--cached", "--find-renames=9"]);
+        assert!(patch.starts_with(&format!(
+            "diff --git a/{0} b/{1}\nsimilarity index 90%\nrename from {0}\nrename to {1}\n",
+            old_path, new_path
+        )));
+        assert!(patch.ends_with(&format!(
+            "--- a/{}\n+++ b/{}\n@@ -7,4 +7,4 @@\n line 0007\n line 0008\n line 0009\n-line 0010\n+line diez\n",
+            old_path, new_path
+        )));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
 }
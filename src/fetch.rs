@@ -24,6 +24,24 @@ pub struct FetchEntry {
     remote_repo_url: String,
 }
 
+impl FetchEntry {
+    /// Creates a new FetchEntry for a commit fetched from a branch of a remote repository.
+    ///
+    /// # Arguments
+    ///
+    /// * `commit_hash`: The commit hash of the fetched commit.
+    /// * `branch_name`: The name of the branch from which the commit was fetched.
+    /// * `remote_repo_url`: The URL of the remote repository from which the commit was retrieved.
+    ///
+    pub fn new(commit_hash: &str, branch_name: &str, remote_repo_url: &str) -> FetchEntry {
+        FetchEntry {
+            commit_hash: commit_hash.to_string(),
+            branch_name: branch_name.to_string(),
+            remote_repo_url: remote_repo_url.to_string(),
+        }
+    }
+}
+
 /// Represents the "FETCH_HEAD" file generated during Git fetch operations, containing a list of fetched entries.
 ///
 /// The `FetchHead` struct maintains a list of `FetchEntry` instances, each representing a fetched commit and
@@ -0,0 +1,399 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    configuration::LOGGER_COMMANDS_FILE,
+    diff::{self, DiffOptions},
+    log::{self, Log, LogOptions},
+    logger::Logger,
+    utils,
+};
+
+/// Date of the `From <hash>` line that starts every patch, which is always the same so the
+/// patches can be told apart from real mailboxes.
+const MBOX_FROM_DATE: &str = "Mon Sep 17 00:00:00 2001";
+/// Format of the `Date:` header of the patches (RFC 2822).
+pub const MAIL_DATE_FORMAT: &str = "%a, %-d %b %Y %H:%M:%S %z";
+/// Maximum length of the part of the file names of the patches taken from their subjects.
+const FILE_NAME_SUBJECT_MAX: usize = 52;
+
+/// Logs the 'git format-patch' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_format_patch(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git format-patch': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git format-patch` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FormatPatchOptions {
+    /// The folder where the patches are written (`-o`). It's the working tree if there is none.
+    pub output_directory: Option<String>,
+    /// If true (`--stdout`), the patches are written to the output instead of to files.
+    pub stdout: bool,
+    /// If true (`-n`), the subject says `[PATCH n/m]` even when there is a single patch.
+    pub numbered: bool,
+    /// If given (`-<n>`), the patches are the ones of the last n commits.
+    pub max_count: Option<usize>,
+    /// The revisions that select the commits, as in `git log`.
+    pub revisions: Vec<String>,
+}
+
+impl FormatPatchOptions {
+    /// Parses the arguments of `git format-patch` (the ones after 'format-patch').
+    ///
+    /// A single revision `<since>` selects the commits of HEAD that are not in it, as `<since>..HEAD`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or a value is missing.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<FormatPatchOptions> {
+        let invalid_option = |arg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: invalid option: {}", arg),
+            )
+        };
+        let mut options = FormatPatchOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output-directory" => {
+                    options.output_directory =
+                        Some(args.next().ok_or_else(|| invalid_option(arg))?.clone())
+                }
+                "--stdout" => options.stdout = true,
+                "-n" | "--numbered" => options.numbered = true,
+                _ if arg.starts_with("--output-directory=") => {
+                    options.output_directory =
+                        Some(arg.trim_start_matches("--output-directory=").to_string())
+                }
+                _ if arg.starts_with('-') => {
+                    let count = arg[1..].parse().map_err(|_| invalid_option(arg))?;
+                    options.max_count = Some(count);
+                }
+                _ => options.revisions.push(arg.clone()),
+            }
+        }
+        if let [since] = options.revisions.as_slice() {
+            if !since.contains("..") && !since.starts_with('^') && options.max_count.is_none() {
+                options.revisions = vec![format!("{}..HEAD", since)];
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Returns the name of the file of a patch: its number followed by its subject, where every
+/// sequence of characters that are not letters, digits, dots or underscores is replaced by a dash.
+pub fn patch_file_name(number: usize, subject: &str) -> String {
+    let mut name = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name: String = name
+        .trim_matches(['-', '.'])
+        .chars()
+        .take(FILE_NAME_SUBJECT_MAX)
+        .collect();
+    format!("{:04}-{}.patch", number, name.trim_end_matches(['-', '.']))
+}
+
+/// Writes a commit as an email with its patch, in the mbox format.
+///
+/// The email has the author and date of the commit, its subject with the number of the patch and
+/// its message, followed by the statistics and the unified diff of the changes against its first parent.
+///
+/// # Arguments
+///
+/// * `commit` - The commit.
+/// * `number` - The number of the patch and the total of the series, if the subject shows them.
+/// * `git_dir` - The path to the git directory.
+/// * `output` - Where the email is written.
+///
+pub fn write_patch_email(
+    commit: &Log,
+    number: Option<(usize, usize)>,
+    git_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let author = commit.author();
+    let prefix = match number {
+        Some((number, total)) => format!("[PATCH {}/{}]", number, total),
+        None => "[PATCH]".to_string(),
+    };
+    writeln!(output, "From {} {}", commit.hash(), MBOX_FROM_DATE)?;
+    writeln!(
+        output,
+        "From: {} <{}>",
+        encode_header(&author.name),
+        author.email
+    )?;
+    writeln!(output, "Date: {}", author.format_date(MAIL_DATE_FORMAT))?;
+    writeln!(
+        output,
+        "Subject: {}",
+        encode_header(&format!("{} {}", prefix, commit.subject()))
+    )?;
+    writeln!(output, "MIME-Version: 1.0")?;
+    writeln!(output, "Content-Type: text/plain; charset=UTF-8")?;
+    writeln!(output, "Content-Transfer-Encoding: 8bit")?;
+    writeln!(output)?;
+    let body = commit.body().trim_end();
+    if !body.is_empty() {
+        writeln!(output, "{}", body)?;
+    }
+    writeln!(output, "---")?;
+    let changes = commit.changes()?;
+    let options = DiffOptions::default();
+    diff::write_stat(&changes, git_dir, None, options.algorithm, output)?;
+    writeln!(output)?;
+    diff::write_patch(&changes, git_dir, None, &options, output)?;
+    writeln!(output, "-- ")?;
+    writeln!(output, "{}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(output)
+}
+
+/// Encodes a header value as an RFC 2047 encoded-word (`=?UTF-8?q?...?=`) if it isn't plain ASCII,
+/// since the headers of an email can only have ASCII characters.
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    let mut encoded = String::from("=?UTF-8?q?");
+    for byte in value.bytes() {
+        match byte {
+            b'=' | b'?' | b'_' => encoded += &format!("={:02X}", byte),
+            byte if byte.is_ascii_graphic() => encoded.push(byte as char),
+            byte => encoded += &format!("={:02X}", byte),
+        }
+    }
+    encoded + "?="
+}
+
+/// Writes the commits selected by the revisions as patches to send by email, one mbox file per commit.
+///
+/// The commits are the ones `git log` would show for the revisions, oldest first and without merges.
+/// A single revision `<since>` means the commits of HEAD that are not in it. `-<n>` takes the last n
+/// commits instead. The files are named `0001-<subject>.patch` and their names are written to the
+/// output, unless `--stdout` writes the patches there.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree, where the files are written if there is no `-o`.
+/// * `args` - The arguments of the command, the ones after 'format-patch'.
+/// * `output` - Where the names of the files, or the patches, are written.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, a revision can't be resolved or a file can't be written.
+///
+pub fn git_format_patch(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    log_format_patch(git_dir, args)?;
+    let options = FormatPatchOptions::parse(args)?;
+    let log_options = LogOptions {
+        revisions: options.revisions.clone(),
+        max_count: options.max_count,
+        merges: Some(false),
+        ..Default::default()
+    };
+    let mut commits = log::log_with_options(git_dir, &log_options)?;
+    commits.reverse();
+    let total = commits.len();
+    let directory = match &options.output_directory {
+        Some(directory) => Path::new(root_dir).join(directory),
+        None => Path::new(root_dir).to_path_buf(),
+    };
+    for (i, commit) in commits.iter().enumerate() {
+        let number = (options.numbered || total > 1).then_some((i + 1, total));
+        if options.stdout {
+            write_patch_email(commit, number, git_dir, output)?;
+            continue;
+        }
+        fs::create_dir_all(&directory)?;
+        let path = directory.join(patch_file_name(i + 1, commit.subject()));
+        let mut file = fs::File::create(&path)?;
+        write_patch_email(commit, number, git_dir, &mut file)?;
+        writeln!(output, "{}", path.to_string_lossy())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_file, create_repo};
+
+    const MIME_HEADERS: &str =
+        "MIME-Version: 1.0\nContent-Type: text/plain; charset=UTF-8\nContent-Transfer-Encoding: 8bit\n";
+
+    fn run_format_patch(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_format_patch(git_dir, "", &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// Creates a repository with a base commit and two more, and returns its git directory and
+    /// the three commits.
+    fn create_history(root_dir: &str) -> io::Result<(String, [String; 3])> {
+        let git_dir = create_repo(root_dir)?;
+        let (a, b) = (format!("{}/a.txt", root_dir), format!("{}/b.txt", root_dir));
+        let base = commit_file(&git_dir, &a, "uno\ndos\ntres\n", "Base")?;
+        let second = commit_file(
+            &git_dir,
+            &a,
+            "uno\nDOS\ntres\n",
+            "Cambiar a\n\nEn mayusculas.",
+        )?;
+        let last = commit_file(&git_dir, &b, "nuevo\n", "Agregar b")?;
+        Ok((git_dir, [base, second, last]))
+    }
+
+    #[test]
+    fn test_format_patch_options_parse() -> io::Result<()> {
+        let parse = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            FormatPatchOptions::parse(&args)
+        };
+
+        let since = parse(&["-o", "patches", "main"])?;
+        let last = parse(&["--stdout", "-n", "-2", "main"])?;
+        let range = parse(&["--output-directory=out", "main..topic"])?;
+
+        assert_eq!(since.output_directory.as_deref(), Some("patches"));
+        assert_eq!(since.revisions, vec!["main..HEAD".to_string()]);
+        assert!(last.stdout && last.numbered);
+        assert_eq!(last.max_count, Some(2));
+        assert_eq!(last.revisions, vec!["main".to_string()]);
+        assert_eq!(range.output_directory.as_deref(), Some("out"));
+        assert_eq!(range.revisions, vec!["main..topic".to_string()]);
+        assert!(parse(&["--inline"]).is_err());
+        assert!(parse(&["-o"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_file_names_come_from_the_subjects() {
+        assert_eq!(patch_file_name(1, "Cambiar a"), "0001-Cambiar-a.patch");
+        assert_eq!(
+            patch_file_name(12, "fix: (parser) v1.2 -- ¡ya!"),
+            "0012-fix-parser-v1.2-ya.patch"
+        );
+        assert_eq!(
+            patch_file_name(3, &"a".repeat(80)),
+            format!("0003-{}.patch", "a".repeat(FILE_NAME_SUBJECT_MAX))
+        );
+    }
+
+    #[test]
+    fn test_format_patch_writes_a_numbered_file_per_commit() -> io::Result<()> {
+        let root_dir = "tests/format_patch_files";
+        let (git_dir, [base, _, _]) = create_history(root_dir)?;
+        let patches_dir = format!("{}/patches", root_dir);
+
+        let files = run_format_patch(&git_dir, &["-o", &patches_dir, &base]);
+        let first = fs::read_to_string(format!("{}/0001-Cambiar-a.patch", patches_dir));
+        let second = fs::read_to_string(format!("{}/0002-Agregar-b.patch", patches_dir));
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            files?,
+            format!(
+                "{0}/0001-Cambiar-a.patch\n{0}/0002-Agregar-b.patch\n",
+                patches_dir
+            )
+        );
+        let first = first?;
+        assert!(first.contains(&format!(
+            "Subject: [PATCH 1/2] Cambiar a\n{}\nEn mayusculas.\n---\n",
+            MIME_HEADERS
+        )));
+        assert!(first.contains("\n-dos\n+DOS\n"));
+        assert!(second?.contains("Subject: [PATCH 2/2] Agregar b\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_patch_stdout_writes_the_mbox() -> io::Result<()> {
+        let root_dir = "tests/format_patch_stdout";
+        let (git_dir, [_, _, last]) = create_history(root_dir)?;
+
+        let stdout = run_format_patch(&git_dir, &["--stdout", "-1"])?;
+        let numbered = run_format_patch(&git_dir, &["--stdout", "-n", "-1"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(stdout.starts_with(&format!("From {} {}\n", last, MBOX_FROM_DATE)));
+        assert!(stdout.contains("From: no_user <no_email>\nDate: "));
+        assert!(stdout.contains(&format!(
+            "Subject: [PATCH] Agregar b\n{}\n---\n {}/b.txt | 1 +\n",
+            MIME_HEADERS, root_dir
+        )));
+        assert!(stdout.contains(&format!(
+            "+++ b/{}/b.txt\n@@ -0,0 +1 @@\n+nuevo\n-- \n",
+            root_dir
+        )));
+        assert!(numbered.contains("Subject: [PATCH 1/1] Agregar b\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_patch_encodes_non_ascii_headers() -> io::Result<()> {
+        let root_dir = "tests/format_patch_encoded";
+        let git_dir = create_repo(root_dir)?;
+        commit_file(&git_dir, &format!("{}/a.txt", root_dir), "uno\n", "Base")?;
+        commit_file(
+            &git_dir,
+            &format!("{}/b.txt", root_dir),
+            "ñandú\n",
+            "Agregar ñandú\n\nEl ave más rápida.",
+        )?;
+
+        let stdout = run_format_patch(&git_dir, &["--stdout", "-1"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(encode_header("Agregar b"), "Agregar b");
+        assert_eq!(
+            encode_header("José = ¿Pepe?"),
+            "=?UTF-8?q?Jos=C3=A9=20=3D=20=C2=BFPepe=3F?="
+        );
+        assert!(stdout.contains(&format!(
+            "Subject: =?UTF-8?q?[PATCH]=20Agregar=20=C3=B1and=C3=BA?=\n{}\nEl ave más rápida.\n---\n",
+            MIME_HEADERS
+        )));
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+use std::io::{self, Write};
+
+/// The place of a commit in the graph of the history, and the lanes that go through its row.
+///
+/// The graph is drawn with the newest commits on top. A lane is a vertical line that goes down
+/// from a commit to its parent, so each lane is waiting for the commit it leads to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphRow {
+    pub hash: String,
+    /// The lane the commit is drawn in.
+    pub column: usize,
+    /// The other lanes that were waiting for this commit and end in it, because it is the parent of more than one commit.
+    pub joining_columns: Vec<usize>,
+    /// The lanes that go to the parents of the commit, in the order of the parents.
+    pub parent_columns: Vec<usize>,
+    /// Whether each lane is in use over the commit.
+    pub lanes_before: Vec<bool>,
+    /// Whether each lane is in use under the commit.
+    pub lanes_after: Vec<bool>,
+}
+
+impl GraphRow {
+    /// Returns the number of lanes needed to draw the row.
+    pub fn width(&self) -> usize {
+        self.lanes_before
+            .len()
+            .max(self.lanes_after.len())
+            .max(self.column + 1)
+    }
+
+    /// Returns true if the lane starts under this row, so it was not in use over it.
+    fn starts_here(&self, lane: usize) -> bool {
+        !self.lanes_before.get(lane).copied().unwrap_or(false)
+    }
+}
+
+/// Returns the first lane from `from` on that is not in use, adding a new one if all of them are.
+fn free_lane(lanes: &mut Vec<Option<String>>, from: usize) -> usize {
+    match lanes.iter().skip(from).position(|lane| lane.is_none()) {
+        Some(position) => from + position,
+        None => {
+            lanes.resize(lanes.len().max(from) + 1, None);
+            lanes.len() - 1
+        }
+    }
+}
+
+/// Assigns a lane to every commit of the history.
+///
+/// A commit goes in the lane that was waiting for it (the leftmost one if there are several), or in the
+/// first free lane if it's the tip of a branch. Its first parent continues in the same lane, and every other
+/// parent gets the lane already waiting for it or a new one to the right of the commit.
+/// The lanes are the same for the `log --graph` output and for the graph of the GUI.
+///
+/// # Arguments
+///
+/// * `commits` - The commits as (hash, parents), in an order where no commit comes before its children,
+///   such as the one of `log --topo-order`.
+///
+pub fn assign_lanes(commits: &[(String, Vec<String>)]) -> Vec<GraphRow> {
+    let mut lanes: Vec<Option<String>> = Vec::new();
+    let mut rows = Vec::with_capacity(commits.len());
+    for (hash, parents) in commits {
+        let lanes_before: Vec<bool> = lanes.iter().map(|lane| lane.is_some()).collect();
+        let waiting: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, lane)| lane.as_deref() == Some(hash.as_str()))
+            .map(|(position, _)| position)
+            .collect();
+        let column = match waiting.first() {
+            Some(&column) => column,
+            None => free_lane(&mut lanes, 0),
+        };
+        let joining_columns = waiting.iter().skip(1).copied().collect::<Vec<usize>>();
+        for &joining in &joining_columns {
+            lanes[joining] = None;
+        }
+
+        lanes[column] = parents.first().cloned();
+        let mut parent_columns = Vec::with_capacity(parents.len());
+        if !parents.is_empty() {
+            parent_columns.push(column);
+        }
+        for parent in parents.iter().skip(1) {
+            let lane = match lanes
+                .iter()
+                .position(|lane| lane.as_deref() == Some(parent.as_str()))
+            {
+                Some(lane) => lane,
+                None => {
+                    let lane = free_lane(&mut lanes, column + 1);
+                    lanes[lane] = Some(parent.clone());
+                    lane
+                }
+            };
+            parent_columns.push(lane);
+        }
+        while lanes.last().is_some_and(|lane| lane.is_none()) {
+            lanes.pop();
+        }
+
+        rows.push(GraphRow {
+            hash: hash.clone(),
+            column,
+            joining_columns,
+            parent_columns,
+            lanes_before,
+            lanes_after: lanes.iter().map(|lane| lane.is_some()).collect(),
+        });
+    }
+    rows
+}
+
+/// Returns the prefix of the lines under a commit: a '|' for every lane in use.
+fn continuation_prefix(row: &GraphRow) -> String {
+    let mut chars = vec![' '; 2 * row.width()];
+    for (lane, _) in row
+        .lanes_after
+        .iter()
+        .enumerate()
+        .filter(|(_, used)| **used)
+    {
+        chars[2 * lane] = '|';
+    }
+    chars.into_iter().collect()
+}
+
+/// Returns the line drawn over a commit when other lanes end in it, with a '/' for each of them.
+/// The commit is always in the leftmost lane that was waiting for it, so they all come from its right.
+fn joining_line(row: &GraphRow) -> Option<String> {
+    if row.joining_columns.is_empty() {
+        return None;
+    }
+    let mut chars = vec![' '; 2 * row.width()];
+    for (lane, _) in row
+        .lanes_before
+        .iter()
+        .enumerate()
+        .filter(|(_, used)| **used)
+    {
+        if !row.joining_columns.contains(&lane) {
+            chars[2 * lane] = '|';
+        }
+    }
+    for &lane in &row.joining_columns {
+        chars[2 * lane - 1] = '/';
+    }
+    Some(chars.into_iter().collect())
+}
+
+/// Returns the prefix of the line of a commit: a '*' in its lane and a '|' in the other lanes that go through it.
+/// The lanes that end in the commit are left out, as they were already drawn going into it.
+fn commit_prefix(row: &GraphRow) -> String {
+    let through: Vec<usize> = row
+        .lanes_before
+        .iter()
+        .enumerate()
+        .filter(|(lane, used)| **used && !row.joining_columns.contains(lane))
+        .map(|(lane, _)| lane)
+        .collect();
+    let width = through
+        .iter()
+        .map(|lane| lane + 1)
+        .chain([row.lanes_after.len(), row.column + 1])
+        .max()
+        .unwrap_or_default();
+    let mut chars = vec![' '; 2 * width];
+    for lane in through {
+        chars[2 * lane] = '|';
+    }
+    chars[2 * row.column] = '*';
+    chars.into_iter().collect()
+}
+
+/// Returns the line drawn under a merge, with a '\' or a '/' going to the lane of each parent but the first one.
+fn forking_line(row: &GraphRow) -> Option<String> {
+    let others: Vec<usize> = row
+        .parent_columns
+        .iter()
+        .copied()
+        .filter(|&lane| lane != row.column)
+        .collect();
+    if others.is_empty() {
+        return None;
+    }
+    let mut chars = vec![' '; 2 * row.width()];
+    for (lane, _) in row
+        .lanes_after
+        .iter()
+        .enumerate()
+        .filter(|(_, used)| **used)
+    {
+        if !row.starts_here(lane) || lane == row.column {
+            chars[2 * lane] = '|';
+        }
+    }
+    for lane in others {
+        if lane > row.column {
+            chars[2 * lane - 1] = '\\';
+        } else {
+            chars[2 * lane + 1] = '/';
+        }
+    }
+    Some(chars.into_iter().collect())
+}
+
+/// Writes the graph of the history, with the text of each commit to the right of it.
+///
+/// The commit is drawn as a '*' in its lane on the first line of its text, and the other lanes as '|'.
+/// Lanes that end in a commit are drawn with '/' over it, and lanes that go to the other parents of a merge
+/// with '\' under it.
+///
+/// # Arguments
+///
+/// * `rows` - The rows of the graph, as `assign_lanes` returns them.
+/// * `entries` - The text of each commit, in the same order. It may have more than one line.
+/// * `output` - Where the graph is written.
+///
+pub fn write_graph(
+    rows: &[GraphRow],
+    entries: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    for (row, entry) in rows.iter().zip(entries) {
+        if let Some(line) = joining_line(row) {
+            writeln!(output, "{}", line.trim_end())?;
+        }
+        let mut lines = entry.lines();
+        writeln!(
+            output,
+            "{}{}",
+            commit_prefix(row),
+            lines.next().unwrap_or_default()
+        )?;
+        let mut prefixes = forking_line(row).into_iter();
+        for line in lines {
+            let prefix = prefixes.next().unwrap_or_else(|| continuation_prefix(row));
+            writeln!(output, "{}", format!("{}{}", prefix, line).trim_end())?;
+        }
+        for prefix in prefixes {
+            writeln!(output, "{}", prefix.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, parents: &[&str]) -> (String, Vec<String>) {
+        (
+            hash.to_string(),
+            parents.iter().map(|parent| parent.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_lanes_of_a_merged_branch() {
+        let commits = vec![
+            commit("merge", &["ours", "theirs"]),
+            commit("ours", &["base"]),
+            commit("theirs", &["base"]),
+            commit("base", &[]),
+        ];
+        let rows = assign_lanes(&commits);
+        let columns: Vec<usize> = rows.iter().map(|row| row.column).collect();
+        assert_eq!(columns, vec![0, 0, 1, 0]);
+        assert_eq!(rows[0].parent_columns, vec![0, 1]);
+        assert_eq!(rows[3].joining_columns, vec![1]);
+
+        let entries: Vec<String> = commits.iter().map(|(hash, _)| hash.clone()).collect();
+        let mut output = Vec::new();
+        write_graph(&rows, &entries, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "*   merge\n|\\\n* | ours\n| * theirs\n|/\n* base\n"
+        );
+    }
+
+    #[test]
+    fn test_unrelated_tips_get_their_own_lanes() {
+        let commits = vec![
+            commit("feature", &["main~1"]),
+            commit("main", &["main~1"]),
+            commit("main~1", &[]),
+        ];
+        let rows = assign_lanes(&commits);
+        let entries: Vec<String> = vec!["f\n\nbody".to_string(), "m".to_string(), "r".to_string()];
+        let mut output = Vec::new();
+        write_graph(&rows, &entries, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "* f\n|\n| body\n| * m\n|/\n* r\n"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    attributes::Attributes, cat_file, configuration::LOGGER_COMMANDS_FILE, diff, index::Index,
+    logger::Logger, tree_handler, utils,
+};
+
+/// Logs the 'git grep' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_grep(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git grep': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git grep` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GrepOptions {
+    /// If true (`-n`), the number of each matching line is shown.
+    pub line_number: bool,
+    /// If true (`-i`), the case of the letters is ignored.
+    pub ignore_case: bool,
+    /// If true (`-w`), the pattern only matches whole words.
+    pub word: bool,
+    /// If true (`-l`), only the names of the files with matches are shown.
+    pub files_with_matches: bool,
+    /// If true (`-c`), only the number of matching lines of each file is shown.
+    pub count: bool,
+    /// The regular expression to search for.
+    pub pattern: String,
+    /// The revision whose files are searched. If there is none, the tracked files of the working tree are.
+    pub revision: Option<String>,
+    /// Only the files inside these paths are searched. If it's empty, every file is.
+    pub paths: Vec<String>,
+}
+
+impl GrepOptions {
+    /// Parses the arguments of `git grep` (the ones after 'grep').
+    /// The flags may be combined, as in `-in`. The pattern may also be given with `-e <pattern>`.
+    /// The argument after the pattern is the revision if it resolves to one in `git_dir`, and
+    /// otherwise it's the first path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or there is no pattern.
+    ///
+    pub fn parse(args: &[String], git_dir: &str) -> io::Result<GrepOptions> {
+        let usage = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "usage: git grep [-n] [-i] [-w] [-l] [-c] <pattern> [<rev>] [-- <paths>...]",
+            )
+        };
+        let mut options = GrepOptions::default();
+        let mut pattern = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => options.paths.extend(args.by_ref().cloned()),
+                "-e" => pattern = Some(args.next().ok_or_else(usage)?.clone()),
+                "--line-number" => options.line_number = true,
+                "--ignore-case" => options.ignore_case = true,
+                "--word-regexp" => options.word = true,
+                "--files-with-matches" | "--name-only" => options.files_with_matches = true,
+                "--count" => options.count = true,
+                flags if flags.len() > 1 && flags.starts_with('-') => {
+                    for flag in flags[1..].chars() {
+                        match flag {
+                            'n' => options.line_number = true,
+                            'i' => options.ignore_case = true,
+                            'w' => options.word = true,
+                            'l' => options.files_with_matches = true,
+                            'c' => options.count = true,
+                            _ => {
+                                return Err(io::Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    format!("error: unknown switch `{}'", flag),
+                                ))
+                            }
+                        }
+                    }
+                }
+                _ if pattern.is_none() => pattern = Some(arg.clone()),
+                _ if options.revision.is_none()
+                    && options.paths.is_empty()
+                    && utils::resolve_revision(arg, git_dir).is_ok() =>
+                {
+                    options.revision = Some(arg.clone())
+                }
+                _ => options.paths.push(arg.clone()),
+            }
+        }
+        options.pattern = pattern.ok_or_else(usage)?;
+        Ok(options)
+    }
+
+    /// Builds the regular expression of the pattern, for whole words if `-w` was given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is not a valid regular expression.
+    ///
+    pub fn regex(&self) -> io::Result<Regex> {
+        let pattern = if self.word {
+            format!(r"\b(?:{})\b", self.pattern)
+        } else {
+            self.pattern.clone()
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))
+    }
+}
+
+/// Returns the files to search as (path, content), sorted by path: the ones of the tree of the
+/// revision, or the tracked files of the working tree if there is no revision.
+fn files_to_search(
+    git_dir: &str,
+    root_dir: &str,
+    options: &GrepOptions,
+) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    match &options.revision {
+        Some(revision) => {
+            let commit = utils::resolve_revision(revision, git_dir)?;
+            for (path, hash) in tree_handler::load_tree_from_commit(&commit, git_dir)?.get_files() {
+                if diff::path_is_selected(&path, &options.paths) {
+                    let content = cat_file::cat_file_return_bytes(&hash, git_dir)?;
+                    files.push((path, content));
+                }
+            }
+        }
+        None => {
+            let index = Index::load(&utils::get_index_file_path(git_dir), git_dir, "")?;
+            for (path, _) in index.iter() {
+                let file_path = Path::new(root_dir).join(path);
+                if diff::path_is_selected(path, &options.paths) && file_path.is_file() {
+                    files.push((path.to_string(), fs::read(file_path)?));
+                }
+            }
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(files)
+}
+
+/// Searches the tracked files for the lines that match a regular expression.
+///
+/// Every matching line is written as `[<rev>:]<path>:[<line number>:]<line>`. With `-l` only the
+/// paths of the files with matches are written, and with `-c` the paths followed by the number of
+/// matching lines. Binary files that match are reported without their lines.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `args` - The arguments of the command, the ones after 'grep'.
+/// * `output` - Where the matches are written.
+///
+/// # Returns
+///
+/// Returns true if some line matched.
+///
+/// # Errors
+///
+/// Returns an error if an option or the pattern is invalid, the revision can't be resolved or a file can't be read.
+///
+pub fn git_grep(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    log_grep(git_dir, args)?;
+    let options = GrepOptions::parse(args, git_dir)?;
+    let regex = options.regex()?;
+    let attributes = Attributes::load(git_dir);
+    let prefix = match &options.revision {
+        Some(revision) => format!("{}:", revision),
+        None => String::new(),
+    };
+    let mut found = false;
+    for (path, content) in files_to_search(git_dir, root_dir, &options)? {
+        let text = String::from_utf8_lossy(&content);
+        let matching: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| regex.is_match(line))
+            .collect();
+        if matching.is_empty() {
+            continue;
+        }
+        found = true;
+        if options.files_with_matches {
+            writeln!(output, "{}{}", prefix, path)?;
+        } else if options.count {
+            writeln!(output, "{}{}:{}", prefix, path, matching.len())?;
+        } else if attributes.is_binary_file(&path, &content) {
+            writeln!(output, "Binary file {}{} matches", prefix, path)?;
+        } else {
+            for (number, line) in matching {
+                if options.line_number {
+                    writeln!(output, "{}{}:{}:{}", prefix, path, number + 1, line)?;
+                } else {
+                    writeln!(output, "{}{}:{}", prefix, path, line)?;
+                }
+            }
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commit,
+        test_utils::{add_file, create_repo},
+    };
+
+    fn run_grep(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_grep(git_dir, "", &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// Creates a repository with `main.rs` and `notes.txt` committed, a staged change of `main.rs`
+    /// and an untracked file, and returns its git directory and the paths to both files.
+    fn create_files(root_dir: &str) -> io::Result<(String, String, String)> {
+        let git_dir = create_repo(root_dir)?;
+        let (main, notes) = (
+            format!("{}/main.rs", root_dir),
+            format!("{}/notes.txt", root_dir),
+        );
+        add_file(&git_dir, &main, "fn main() {\n    messi();\n}\n")?;
+        add_file(&git_dir, &notes, "Messi\nmessiah\n")?;
+        commit::new_commit(&git_dir, "Primero", "")?;
+        add_file(&git_dir, &main, "fn main() {\n    dibu();\n}\n")?;
+        fs::write(format!("{}/untracked.txt", root_dir), "messi\n")?;
+        Ok((git_dir, main, notes))
+    }
+
+    #[test]
+    fn test_grep_searches_only_the_tracked_files() -> io::Result<()> {
+        let root_dir = "tests/grep_tracked";
+        let (git_dir, main, notes) = create_files(root_dir)?;
+
+        let working_tree = run_grep(&git_dir, &["-n", "messi"]);
+        let files = run_grep(&git_dir, &["-l", "dibu"]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(working_tree?, format!("{}:2:messiah\n", notes));
+        assert_eq!(files?, format!("{}\n", main));
+        Ok(())
+    }
+
+    #[test]
+    fn test_grep_ignore_case_and_whole_words() -> io::Result<()> {
+        let root_dir = "tests/grep_words";
+        let (git_dir, _, notes) = create_files(root_dir)?;
+
+        let words = run_grep(&git_dir, &["-iw", "messi"]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(words?, format!("{}:Messi\n", notes));
+        Ok(())
+    }
+
+    #[test]
+    fn test_grep_searches_old_revisions() -> io::Result<()> {
+        let root_dir = "tests/grep_revisions";
+        let (git_dir, main, notes) = create_files(root_dir)?;
+
+        let revision = run_grep(&git_dir, &["-n", "m[e]ssi\\(", "HEAD", "--", &main]);
+        let counts = run_grep(&git_dir, &["-c", "-i", "messi", "HEAD"]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(revision?, format!("HEAD:{}:2:    messi();\n", main));
+        assert_eq!(counts?, format!("HEAD:{}:1\nHEAD:{}:2\n", main, notes));
+        Ok(())
+    }
+
+    #[test]
+    fn test_grep_takes_a_path_that_is_not_a_revision() -> io::Result<()> {
+        let root_dir = "tests/grep_paths";
+        let (git_dir, main, notes) = create_files(root_dir)?;
+
+        let path = run_grep(&git_dir, &["-i", "messi", &notes]);
+        let paths = run_grep(&git_dir, &["-l", "i", &main, &notes]);
+        let revision_and_path = run_grep(&git_dir, &["messi", "HEAD", &main]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(path?, format!("{0}:Messi\n{0}:messiah\n", notes));
+        assert_eq!(paths?, format!("{}\n{}\n", main, notes));
+        assert_eq!(revision_and_path?, format!("HEAD:{}:    messi();\n", main));
+        Ok(())
+    }
+}
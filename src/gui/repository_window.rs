@@ -1,5 +1,6 @@
 use crate::add;
 use crate::add::add;
+use crate::blame;
 use crate::branch;
 use crate::branch::git_branch;
 use crate::branch::is_an_existing_branch;
@@ -168,6 +169,9 @@ fn setup_repository_window(builder: &gtk::Builder, new_window: &gtk::Window) ->
     let builder_clone_for_rebase = builder.clone();
     rebase_window(&builder_clone_for_rebase)?;
 
+    let builder_clone_for_blame = builder.clone();
+    blame_window(&builder_clone_for_blame)?;
+
     let builder_clone_for_fetch = builder.clone();
     match apply_style_to_fetch(&builder_clone_for_fetch) {
         Ok(_) => {}
@@ -367,7 +371,7 @@ fn handle_git_push() -> io::Result<()> {
         }
     };
     let branch_name = get_branch_name(&git_dir)?;
-    push::git_push(&branch_name, &git_dir)
+    push::git_push(&branch_name, &git_dir, false)
 }
 
 /// Setup a button with the specified `button_id` using the given GTK builder. This function applies the
@@ -4741,6 +4745,60 @@ fn apply_style_to_button(button: &gtk::Button) {
     }
 }
 
+/// Sets up the blame view.
+///
+/// The user writes the path of a file and, optionally, a range of lines as `start,end`.
+/// Clicking "Blame" shows each line of the file with the commit, author and date that last changed it.
+fn blame_window(builder: &gtk::Builder) -> io::Result<()> {
+    let blame_button = get_button(builder, "blame-button");
+    apply_style_to_button(&blame_button);
+    let (file_entry, range_entry) = match (
+        get_entry(builder, "blame-file-entry"),
+        get_entry(builder, "blame-range-entry"),
+    ) {
+        (Some(file_entry), Some(range_entry)) => (file_entry, range_entry),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Couldn't get the blame entries.\n",
+            ));
+        }
+    };
+    apply_entry_style(&file_entry);
+    apply_entry_style(&range_entry);
+
+    let builder_clone = builder.clone();
+    blame_button.connect_clicked(move |_| {
+        let path = file_entry.get_text().to_string();
+        if path.is_empty() {
+            show_message_dialog("Error", "Specify a file.");
+            return;
+        }
+        let mut args = Vec::new();
+        let range = range_entry.get_text().to_string();
+        if !range.is_empty() {
+            args.push("-L".to_string());
+            args.push(range);
+        }
+        args.push(path);
+
+        let mut output: Vec<u8> = Vec::new();
+        let result = obtain_git_dir()
+            .and_then(|git_dir| blame::git_blame(&git_dir, &args, false, &mut output))
+            .and_then(|_| {
+                update_text_view(
+                    &builder_clone,
+                    "blame-view",
+                    &String::from_utf8_lossy(&output),
+                )
+            });
+        if let Err(e) = result {
+            show_message_dialog("Error", &e.to_string());
+        }
+    });
+    Ok(())
+}
+
 /// Sets up the functionality of merge
 fn rebase_window(builder: &gtk::Builder) -> io::Result<()> {
     let rebase_button = get_button(builder, "make-rebase-button");
@@ -4754,7 +4812,6 @@ fn rebase_window(builder: &gtk::Builder) -> io::Result<()> {
     apply_style_to_button(&abort_button);
 
     let builder_clone = builder.clone();
-    let rebase_button_clone = rebase_button.clone();
 
     let branch_entry = match get_entry(builder, "rebase-branch-entry") {
         Some(branch) => branch,
@@ -4784,31 +4841,132 @@ fn rebase_window(builder: &gtk::Builder) -> io::Result<()> {
                     "Invalid branch",
                     &format!("{:?} not an existing branch", &their_branch),
                 );
-            } else {
-                let current_branch = match get_branch_name(&git_dir) {
-                    Ok(branch) => branch,
-                    Err(error) => {
-                        eprintln!("{:?}", error);
-                        return;
-                    }
-                };
-                let rebase_object =
-                    match rebase::start_rebase_gui(&git_dir, &current_branch, &their_branch) {
-                        Ok(rebase) => rebase,
-                        Err(e) => {
-                            eprintln!("Error starting rebase: {}", e);
-                            return;
-                        }
-                    };
-                rebase_button_clone.set_sensitive(false);
-
-                match rebase::write_rebase_step_into_gui(&builder_clone, rebase_object, &git_dir) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        eprintln!("Error writing rebase step into GUI: {}", e);
-                    }
-                }
+            } else if let Err(e) = rebase::start_rebase_gui(&builder_clone, &git_dir, &their_branch)
+            {
+                eprintln!("Error starting rebase: {}", e);
+            }
+        }
+    });
+
+    let builder_clone = builder.clone();
+    ok_button.connect_clicked(move |_| {
+        if let Err(e) = rebase::continue_rebase_gui(&builder_clone) {
+            eprintln!("Error continuing rebase: {}", e);
+        }
+    });
+
+    let builder_clone = builder.clone();
+    abort_button.connect_clicked(move |_| {
+        if let Err(e) = rebase::abort_rebase_gui(&builder_clone) {
+            show_message_dialog("Error", &e.to_string());
+        }
+    });
+
+    let builder_clone = builder.clone();
+    update_button.connect_clicked(move |_| {
+        if let Err(e) = rebase::resolve_conflicted_file_gui(&builder_clone) {
+            eprintln!("Error updating file: {}", e);
+        }
+    });
+
+    if let Some(combo_box) = builder.get_object::<gtk::ComboBoxText>("rebase-text-list") {
+        let builder_clone = builder.clone();
+        combo_box.connect_changed(move |_| {
+            if let Err(e) = rebase::show_conflicted_file_gui(&builder_clone) {
+                eprintln!("Error writing into TextView: {}", e);
             }
+        });
+    }
+
+    // A rebase may have been left in progress by a previous session or by the command line
+    if let Ok(git_dir) = obtain_git_dir() {
+        rebase::write_rebase_state_into_gui(builder, &git_dir, "")?;
+    }
+    interactive_rebase_window(builder)?;
+    Ok(())
+}
+
+/// Sets up the reorderable todo list used for interactive rebases.
+///
+/// "Load commits" fills the list with the commits of the current branch that are not in the branch
+/// written in the rebase entry. The selected row can be moved up or down and its command changed.
+/// "Interactive rebase" runs the list with the same engine as `git rebase -i`.
+fn interactive_rebase_window(builder: &gtk::Builder) -> io::Result<()> {
+    let load_button = get_button(builder, "rebase-load-todo-button");
+    let start_button = get_button(builder, "interactive-rebase-button");
+    let up_button = get_button(builder, "rebase-move-up-button");
+    let down_button = get_button(builder, "rebase-move-down-button");
+    let set_action_button = get_button(builder, "rebase-set-action-button");
+    for button in [
+        &load_button,
+        &start_button,
+        &up_button,
+        &down_button,
+        &set_action_button,
+    ] {
+        apply_style_to_button(button);
+    }
+
+    let branch_entry = match get_entry(builder, "rebase-branch-entry") {
+        Some(branch) => branch,
+        None => {
+            eprintln!("Couldn't get rebase branch entry,");
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Couldn't get rebase branch entry.\n",
+            ));
+        }
+    };
+
+    let builder_clone = builder.clone();
+    let entry_clone = branch_entry.clone();
+    load_button.connect_clicked(move |_| {
+        let upstream = entry_clone.get_text().to_string();
+        if upstream.is_empty() {
+            show_message_dialog("Error", "Specify branch.");
+            return;
+        }
+        let result = obtain_git_dir().and_then(|git_dir| {
+            rebase::load_todo_list_into_gui(&builder_clone, &git_dir, &upstream)
+        });
+        if let Err(e) = result {
+            show_message_dialog("Error", &e.to_string());
+        }
+    });
+
+    let builder_clone = builder.clone();
+    start_button.connect_clicked(move |_| {
+        let upstream = branch_entry.get_text().to_string();
+        if upstream.is_empty() {
+            show_message_dialog("Error", "Specify branch.");
+            return;
+        }
+        let result = obtain_git_dir().and_then(|git_dir| {
+            rebase::start_interactive_rebase_gui(&builder_clone, &git_dir, &upstream)
+        });
+        if let Err(e) = result {
+            show_message_dialog("Error", &e.to_string());
+        }
+    });
+
+    let builder_clone = builder.clone();
+    up_button.connect_clicked(move |_| {
+        if let Err(e) = rebase::move_selected_todo_row(&builder_clone, -1) {
+            eprintln!("Error moving row: {}", e);
+        }
+    });
+
+    let builder_clone = builder.clone();
+    down_button.connect_clicked(move |_| {
+        if let Err(e) = rebase::move_selected_todo_row(&builder_clone, 1) {
+            eprintln!("Error moving row: {}", e);
+        }
+    });
+
+    let builder_clone = builder.clone();
+    set_action_button.connect_clicked(move |_| {
+        if let Err(e) = rebase::set_action_of_selected_todo_row(&builder_clone) {
+            eprintln!("Error changing the action: {}", e);
         }
     });
     Ok(())
@@ -4951,7 +5109,7 @@ fn get_staged_text() -> io::Result<String> {
     let index_file = format!("{}{}", git_dir, "/index");
     let gitignore_path = format!("{}/{}", working_dir, GIT_IGNORE);
     let index = index::Index::load(&index_file, &git_dir, &gitignore_path)?;
-    let staged_files = status::get_staged_changes(&index, last_commit_tree)
+    let staged_files = status::get_staged_changes(&index, last_commit_tree, &git_dir)
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
     Ok(staged_files)
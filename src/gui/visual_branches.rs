@@ -1,11 +1,9 @@
-use crate::commit::{self, is_merge_commit};
+use crate::graph::{self, GraphRow};
+use crate::log::{self, LogOptions, LogOrder};
 use crate::{branch, utils};
 use gtk::{prelude::BuilderExtManual, WidgetExt};
 use std::f64::consts;
-use std::{
-    collections::{HashMap, HashSet},
-    io,
-};
+use std::{collections::HashMap, io};
 
 const COMMIT_RADIUS: f64 = 15.0;
 const BRANCHES_DISTANCE: i32 = 70;
@@ -14,24 +12,14 @@ const BRANCHES_DISTANCE: i32 = 70;
 struct Commit {
     hash: String,
     message: String,
-    time: String,
-    branch: String,
     parents: Vec<String>,
 }
 
 impl Commit {
-    fn new(
-        hash: String,
-        message: String,
-        time: String,
-        branch: String,
-        parents: Vec<String>,
-    ) -> Commit {
+    fn new(hash: String, message: String, parents: Vec<String>) -> Commit {
         Commit {
             hash,
             message,
-            time,
-            branch,
             parents,
         }
     }
@@ -53,32 +41,31 @@ fn setup_canvas(builder: &gtk::Builder) -> io::Result<gtk::DrawingArea> {
     Ok(drawing_area)
 }
 
-/// Defines the x positions of the branches.
+/// Returns the x position of a lane of the graph.
+fn lane_x(column: usize) -> i32 {
+    20 + BRANCHES_DISTANCE * column as i32
+}
+
+/// Defines the x positions of the branches: the one of the lane of the commit they point to.
 /// Returns a HashMap with the branch name as key and the x position as value.
-fn define_branch_positions(branches: &Vec<String>) -> HashMap<String, i32> {
+fn define_branch_positions(
+    branches: &Vec<String>,
+    rows: &[GraphRow],
+    git_dir: &str,
+) -> io::Result<HashMap<String, i32>> {
     let mut branch_positions: HashMap<String, i32> = HashMap::new();
-    let mut x_pos = 20;
-    if branches.contains(&"master".to_string()) {
-        branch_positions.insert("master".to_string(), x_pos);
-        x_pos += BRANCHES_DISTANCE;
-    }
     for branch in branches {
-        if branch != "master" {
-            branch_positions.insert(branch.clone(), x_pos);
-            x_pos += BRANCHES_DISTANCE;
+        let hash = branch::get_branch_commit_hash(branch, git_dir)?;
+        if let Some(row) = rows.iter().find(|row| row.hash == hash) {
+            branch_positions.insert(branch.clone(), lane_x(row.column));
         }
     }
-    branch_positions
+    Ok(branch_positions)
 }
 
-/// Defines the colors of the branches.
+/// Defines the colors of the lanes of the graph.
 /// Returns a HashMap with the x position as key and the color as value.
-fn define_branch_colors(
-    branches: &Vec<String>,
-    branches_pos: &HashMap<String, i32>,
-) -> HashMap<i32, (f64, f64, f64)> {
-    let mut branch_colors: HashMap<i32, (f64, f64, f64)> = HashMap::new();
-    let mut color_index = 0;
+fn define_lane_colors(lanes: usize) -> HashMap<i32, (f64, f64, f64)> {
     let colors = [
         (0.2, 0.22, 0.95),
         (0.23, 0.65, 0.62),
@@ -86,15 +73,9 @@ fn define_branch_colors(
         (0.9, 0.2, 0.3),
         (0.96, 0.24, 0.82),
     ];
-    for branch in branches {
-        let x_pos = *branches_pos.get(branch).unwrap_or(&20);
-        branch_colors.insert(x_pos, colors[color_index]);
-        color_index += 1;
-        if color_index == colors.len() {
-            color_index = 0;
-        }
-    }
-    branch_colors
+    (0..lanes)
+        .map(|column| (lane_x(column), colors[column % colors.len()]))
+        .collect()
 }
 
 /// Draws the branch names on the canvas.
@@ -160,33 +141,32 @@ fn draw_commit_node_w_message_side(
     });
 }
 
-/// Draws all the commit nodes on the canvas.
+/// Draws all the commit nodes on the canvas, each one in the x of its lane, the oldest ones at the bottom.
 /// Returns a HashMap with the commit hash as key and the node position as value.
 fn draw_nodes(
     drawing_area: &gtk::DrawingArea,
-    commits: &Vec<Commit>,
-    branches: &Vec<String>,
-    branches_pos: &HashMap<String, i32>,
-    branch_colors: &HashMap<i32, (f64, f64, f64)>,
+    commits: &[Commit],
+    rows: &[GraphRow],
+    branches_len: i32,
+    lane_colors: &HashMap<i32, (f64, f64, f64)>,
 ) -> HashMap<String, (i32, i32)> {
     let mut node_positions: HashMap<String, (i32, i32)> = HashMap::new();
     let commits_len = commits.len() as i32;
-    let branches_len = branches.len() as i32;
+    let lanes_len = rows.iter().map(GraphRow::width).max().unwrap_or(1) as i32;
     let mut y = if commits_len * 50 + 20 < 600 {
         600
     } else {
         commits_len * 50 + 20
     };
     y += 20 * branches_len;
-    drawing_area.set_size_request(BRANCHES_DISTANCE * branches_len, y);
+    drawing_area.set_size_request(BRANCHES_DISTANCE * lanes_len, y);
 
-    for commit in commits {
-        let branch = commit.branch.clone();
-        let branch_x = *branches_pos.get(&branch).unwrap_or(&20);
-        let color = *branch_colors.get(&branch_x).unwrap_or(&(0.0, 1.0, 0.22));
+    for (commit, row) in commits.iter().zip(rows).rev() {
+        let branch_x = lane_x(row.column);
+        let color = *lane_colors.get(&branch_x).unwrap_or(&(0.0, 1.0, 0.22));
         let hash = commit.hash.clone();
         let message = commit.message.clone();
-        let message_x = BRANCHES_DISTANCE * branches_len;
+        let message_x = BRANCHES_DISTANCE * lanes_len;
         let message_y = y;
         draw_commit_node_w_message_side(
             drawing_area,
@@ -255,7 +235,7 @@ fn draw_commits_connections(
 
 /// Draws the graph of the git in a visual way.
 /// It uses circles to represent the commits and lines to represent the connections between the commits.
-/// Each branch is represented by a different color.
+/// The commits are placed in the same lanes as in `log --graph`, and each lane has a different color.
 ///
 /// It searches for the git directory in the current directory.
 /// If it finds it, it builds the graph of the git.
@@ -264,136 +244,52 @@ pub fn handle_show_visual_branches_tree(builder: &gtk::Builder) -> io::Result<()
 
     let drawing_area = setup_canvas(builder)?;
 
+    let options = LogOptions {
+        all: true,
+        order: LogOrder::Topo,
+        ..Default::default()
+    };
+    let logs = log::log_with_options(&git_dir, &options)?;
+    let commits: Vec<Commit> = logs
+        .iter()
+        .map(|log| {
+            Commit::new(
+                log.hash().to_string(),
+                log.subject().to_string(),
+                log.parents().to_vec(),
+            )
+        })
+        .collect();
+    let rows = graph::assign_lanes(
+        &commits
+            .iter()
+            .map(|commit| (commit.hash.clone(), commit.parents.clone()))
+            .collect::<Vec<_>>(),
+    );
+
     let branches = branch::get_all_branches(&git_dir)?;
-    let branches_x_positions = define_branch_positions(&branches);
-    let branch_colors = define_branch_colors(&branches, &branches_x_positions);
+    let branches_x_positions = define_branch_positions(&branches, &rows, &git_dir)?;
+    let lanes = rows.iter().map(GraphRow::width).max().unwrap_or(1);
+    let lane_colors = define_lane_colors(lanes);
     draw_branch_names(&drawing_area, &branches, &branches_x_positions);
 
-    let graph = build_git_graph(&git_dir)?;
-    let mut commits: Vec<Commit> = graph.values().cloned().collect();
-    commits.sort_by(|a, b| a.time.cmp(&b.time));
-
     let node_positions = draw_nodes(
         &drawing_area,
         &commits,
-        &branches,
-        &branches_x_positions,
-        &branch_colors,
+        &rows,
+        branches.len() as i32,
+        &lane_colors,
     );
 
     let mut connections: Vec<(String, String)> = Vec::new();
     for node in &commits {
-        let parents = node.parents.clone();
-        let hash = node.hash.clone();
-        if !parents.is_empty() {
-            for parent in parents {
-                let connection = (parent.clone(), hash.clone());
-                connections.push(connection);
-            }
+        for parent in &node.parents {
+            connections.push((parent.clone(), node.hash.clone()));
         }
     }
 
-    draw_commits_connections(&drawing_area, &branch_colors, &node_positions, &connections);
+    draw_commits_connections(&drawing_area, &lane_colors, &node_positions, &connections);
 
     drawing_area.queue_draw();
     Ok(())
 }
-
-/// Inserts a commit in the graph.
-fn process_commit(
-    commit_hash: String,
-    branch_name: String,
-    git_dir: &str,
-    graph: &mut HashMap<String, Commit>,
-    queue: &mut Vec<(String, String)>,
-    visited: &mut HashSet<String>,
-) -> io::Result<()> {
-    if visited.contains(&commit_hash) {
-        return Ok(());
-    }
-
-    match commit::get_commit_message(&commit_hash, git_dir) {
-        Ok(_) => {}
-        Err(_e) => return Ok(()),
-    }
-
-    visited.insert(commit_hash.clone());
-    let commit_msg = commit::get_commit_message(&commit_hash, git_dir)?;
-    let commit_time = commit::get_commit_time(&commit_hash, git_dir)?;
-
-    let parents = if commit::is_merge_commit(&commit_hash, git_dir)? {
-        commit::get_merge_parents(&commit_hash, git_dir)?
-    } else {
-        let parents = vec![commit::get_parent_hash(&commit_hash, git_dir)?];
-        parents
-    };
-
-    let commit = Commit::new(
-        commit_hash.clone(),
-        commit_msg,
-        commit_time,
-        branch_name.clone(),
-        parents,
-    );
-    graph.insert(commit_hash.clone(), commit);
-
-    queue.push((commit_hash, branch_name));
-
-    Ok(())
-}
-
-/// Builds the graph of the git dir.
-/// It returns a HashMap with the commit hash as key and the commit as value.
-/// The commit contains the commit hash, the commit message, the commit time, the branch name and the parent hash.
-/// The graph is built from the heads of the branches.
-fn build_git_graph(git_dir: &str) -> io::Result<HashMap<String, Commit>> {
-    let mut graph: HashMap<String, Commit> = HashMap::new();
-    let mut queue: Vec<(String, String)> = Vec::new();
-    let mut visited: HashSet<String> = HashSet::new();
-
-    for branch_name in branch::get_all_branches(git_dir)? {
-        let head_hash = branch::get_branch_commit_hash(&branch_name, git_dir)?;
-        process_commit(
-            head_hash.clone(),
-            branch_name.clone(),
-            git_dir,
-            &mut graph,
-            &mut queue,
-            &mut visited,
-        )?;
-    }
-
-    // I process master first so it can be clearly seen on the graph
-    let master_hash = branch::get_branch_commit_hash("master", git_dir)?;
-    for commit_hash in utils::get_branch_commit_history(&master_hash, git_dir)? {
-        process_commit(
-            commit_hash.clone(),
-            "master".to_string(),
-            git_dir,
-            &mut graph,
-            &mut queue,
-            &mut visited,
-        )?;
-    }
-
-    while let Some((current_hash, branch_name)) = queue.pop() {
-        let parents = if is_merge_commit(&current_hash, git_dir)? {
-            commit::get_merge_parents(&current_hash, git_dir)?
-        } else {
-            let parents = vec![commit::get_parent_hash(&current_hash, git_dir)?];
-            parents
-        };
-
-        for parent in parents {
-            process_commit(
-                parent.clone(),
-                branch_name.clone(),
-                git_dir,
-                &mut graph,
-                &mut queue,
-                &mut visited,
-            )?;
-        }
-    }
-    Ok(graph)
-}
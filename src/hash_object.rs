@@ -4,6 +4,7 @@ use std::{
     path::Path,
 };
 
+use crate::attributes::Attributes;
 use crate::utils::get_current_time;
 use crate::{configuration::LOGGER_COMMANDS_FILE, logger::Logger};
 use flate2::{write::ZlibEncoder, Compression};
@@ -59,10 +60,20 @@ fn hash_string(content: &str) -> String {
 /// * `file_type` - The type of the file. It is used to create the header.
 ///
 pub fn hash_file_content(path: &str, file_type: &str) -> io::Result<String> {
-    let content = std::fs::read_to_string(path)?;
+    let content = std::fs::read(path)?;
+    Ok(hash_content(&content, file_type))
+}
+
+/// Returns the sha1 hash of the given content adding the type information, the same way as `hash_file_content`.
+/// **It does not store the content**.
+/// ## Parameters
+/// * `content` - The content to hash.
+/// * `file_type` - The type of the content. It is used to create the header.
+///
+pub fn hash_content(content: &[u8], file_type: &str) -> String {
     let header = format!("{file_type} {}\0", content.len());
-    let complete = header + &content;
-    Ok(hash_string(&complete))
+    let complete = [header.as_bytes(), content].concat();
+    hash_byte_array(&complete)
 }
 
 /// Returns the path to the file object in the objects folder.
@@ -107,17 +118,33 @@ fn create_directory(name: &str) -> io::Result<()> {
 /// If the file does not exist, it returns an error.
 /// If the file is already stored, it stores it again.
 ///
+/// The content goes through the clean conversion of the attributes of the repository first,
+/// so text files are stored with LF line endings.
+///
 /// ## Parameters
 /// * `path` - The path to the file.
 /// * `directory` - The path to the git directory.
 ///
 ///
 pub fn store_file(path: &str, git_dir_path: &str) -> io::Result<String> {
-    let content_hash = hash_file_content(path, "blob")?;
-    let output_file_dir: String = git_dir_path.to_string() + "/objects/" + &content_hash[..2] + "/";
-    create_directory(&output_file_dir)?;
-    let output_file_str = output_file_dir + &content_hash[2..];
-    compress_content(path, output_file_str.as_str(), "blob")?;
+    store_file_with_attributes(path, git_dir_path, &Attributes::load(git_dir_path))
+}
+
+/// Same as `store_file`, with the attributes already loaded.
+/// Useful when storing many files, so the attributes files are not read for each one of them.
+///
+/// ## Parameters
+/// * `path` - The path to the file.
+/// * `directory` - The path to the git directory.
+/// * `attributes` - The attributes of the repository.
+///
+pub fn store_file_with_attributes(
+    path: &str,
+    git_dir_path: &str,
+    attributes: &Attributes,
+) -> io::Result<String> {
+    let content = attributes.clean(path, fs::read(path)?);
+    let content_hash = store_bytes_array_to_file(content, git_dir_path, "blob")?;
     log_hash_object(path, git_dir_path)?;
     Ok(content_hash)
 }
@@ -324,11 +351,11 @@ fn compress_content(input_path: &str, output_path: &str, file_type: &str) -> io:
     let output_file = File::create(output_path)?;
     let mut encoder = ZlibEncoder::new(output_file, Compression::default());
 
-    let mut content = std::fs::read_to_string(input_path)?;
+    let content = std::fs::read(input_path)?;
     let header = format!("{file_type} {}\0", content.len());
-    content.insert_str(0, &header);
 
-    encoder.write_all(content.as_bytes())?;
+    encoder.write_all(header.as_bytes())?;
+    encoder.write_all(&content)?;
 
     encoder.finish()?;
     Ok(())
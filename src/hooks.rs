@@ -0,0 +1,267 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::config::Config;
+
+/// The directory of the hooks inside the git directory, unless `core.hooksPath` says otherwise.
+const HOOKS_DIR: &str = "hooks";
+
+/// The hash given to the hooks for a commit or ref that doesn't exist.
+pub const ZERO_HASH: &str = "0000000000000000000000000000000000000000";
+
+/// Returns true if the file can be executed.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Returns true if the file can be executed.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Returns the path of a hook, if it exists and can be executed.
+///
+/// The hooks are looked for in the directory of `core.hooksPath`, relative to the working tree, or
+/// otherwise in the `hooks` directory of the git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `name` - The name of the hook, for example `pre-commit`.
+///
+pub fn find_hook(git_dir: &str, name: &str) -> Option<PathBuf> {
+    let hooks_dir = match Config::load(git_dir)
+        .and_then(|config| config.get_values("core", "hooksPath"))
+        .ok()
+        .and_then(|values| values.last().cloned())
+    {
+        Some(path) if Path::new(&path).is_absolute() => PathBuf::from(path),
+        Some(path) => working_tree(git_dir).join(path),
+        None => Path::new(git_dir).join(HOOKS_DIR),
+    };
+    let hook = hooks_dir.join(name);
+    match is_executable(&hook) {
+        true => Some(hook),
+        false => None,
+    }
+}
+
+/// Returns the working tree of a git directory, the directory that contains it.
+fn working_tree(git_dir: &str) -> PathBuf {
+    match Path::new(git_dir).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Runs a hook, as git does: from the root of the working tree, with `GIT_DIR` set, the given
+/// arguments and, if there is one, the input in its standard input.
+///
+/// Nothing is done if the hook doesn't exist or can't be executed, so the hooks are enabled by
+/// making them executable.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `name` - The name of the hook, for example `pre-commit`.
+/// * `args` - The arguments of the hook.
+/// * `input` - What is written to the standard input of the hook.
+///
+/// # Errors
+///
+/// Returns an error if the hook can't be run or it exits with a non-zero status, so the callers
+/// can abort where git aborts.
+///
+pub fn run_hook(git_dir: &str, name: &str, args: &[&str], input: Option<&str>) -> io::Result<()> {
+    spawn_hook(git_dir, name, args, input, None)
+}
+
+/// Runs a hook like `run_hook`, with `GIT_INDEX_FILE` set to `index_file`, as git does for the
+/// hooks of a commit, so they see the index that is committed even if it isn't written yet.
+pub fn run_hook_with_index(
+    git_dir: &str,
+    name: &str,
+    args: &[&str],
+    index_file: &Path,
+) -> io::Result<()> {
+    spawn_hook(git_dir, name, args, None, Some(index_file))
+}
+
+fn spawn_hook(
+    git_dir: &str,
+    name: &str,
+    args: &[&str],
+    input: Option<&str>,
+    index_file: Option<&Path>,
+) -> io::Result<()> {
+    let Some(hook) = find_hook(git_dir, name) else {
+        return Ok(());
+    };
+    let mut command = Command::new(fs::canonicalize(&hook)?);
+    if let Some(index_file) = index_file {
+        command.env("GIT_INDEX_FILE", index_file);
+    }
+    let mut child = command
+        .args(args)
+        .current_dir(working_tree(git_dir))
+        .env("GIT_DIR", fs::canonicalize(git_dir)?)
+        .stdin(match input {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // The hook may exit without reading its input, so a broken pipe is not an error.
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            match status.code() {
+                Some(code) => format!("error: the '{}' hook exited with status {}", name, code),
+                None => format!("error: the '{}' hook was terminated", name),
+            },
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commit, index::Index, test_utils};
+
+    #[cfg(unix)]
+    fn write_hook(git_dir: &str, name: &str, script: &str) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let path = format!("{}/{}/{}", git_dir, HOOKS_DIR, name);
+        fs::write(&path, format!("#!/bin/sh\n{}", script))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks_run_and_abort_commits() -> io::Result<()> {
+        let root_dir = "tests/hooks_repo";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::create_dir_all(format!("{}/{}", git_dir, HOOKS_DIR))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        fs::write(format!("{}/index", git_dir), "")?;
+        let file = format!("{}/a.txt", root_dir);
+        fs::write(&file, "uno\n")?;
+        let mut index = Index::load(&format!("{}/index", git_dir), &git_dir, "")?;
+        index.add_path(&file)?;
+        index.write_file()?;
+        let commit = |args: &[&str]| -> io::Result<String> {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            commit::git_commit(&git_dir, "", &args, &mut Vec::new())
+        };
+
+        let missing = run_hook(&git_dir, "pre-push", &["origin"], Some("refs\n"));
+        fs::write(format!("{}/{}/pre-push", git_dir, HOOKS_DIR), "exit 1")?;
+        let not_executable = run_hook(&git_dir, "pre-push", &["origin"], None);
+        write_hook(
+            &git_dir,
+            "pre-push",
+            "cat > pushed.txt\necho \"$1 $2\" >> pushed.txt\n",
+        )?;
+        let pushed = run_hook(&git_dir, "pre-push", &["origin", "url"], Some("refs\n"));
+        let pushed_content = fs::read_to_string(format!("{}/pushed.txt", root_dir))?;
+
+        write_hook(&git_dir, "pre-commit", "exit 1\n")?;
+        write_hook(
+            &git_dir,
+            "commit-msg",
+            "grep -q 'MESSI-[0-9]' \"$1\" || exit 1\necho 'Revisado' >> \"$1\"\n",
+        )?;
+        write_hook(&git_dir, "post-commit", "touch committed.txt\n")?;
+        let rejected = commit(&["-m", "MESSI-1 Primero"]);
+        let committed_before = Path::new(&format!("{}/committed.txt", root_dir)).exists();
+        let without_ticket = commit(&["--no-verify", "-m", "Sin ticket"])?;
+        let without_ticket_message = commit::get_commit_full_message(&without_ticket, &git_dir)?;
+        write_hook(&git_dir, "pre-commit", "exit 0\n")?;
+        let no_ticket = commit(&["--allow-empty", "-m", "Otro sin ticket"]);
+        let with_ticket = commit(&["--allow-empty", "-m", "MESSI-2 Segundo"])?;
+        let with_ticket_message = commit::get_commit_full_message(&with_ticket, &git_dir)?;
+        let committed_after = Path::new(&format!("{}/committed.txt", root_dir)).exists();
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(missing.is_ok());
+        assert!(not_executable.is_ok());
+        assert!(pushed.is_ok());
+        assert_eq!(pushed_content, "refs\norigin url\n");
+        assert!(rejected.is_err());
+        assert!(!committed_before);
+        assert_eq!(without_ticket_message.trim_end_matches('\0'), "Sin ticket");
+        assert!(no_ticket.is_err());
+        assert_eq!(
+            with_ticket_message.trim_end_matches('\0'),
+            "MESSI-2 Segundo\nRevisado\n"
+        );
+        assert!(committed_after);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_hooks_see_the_index_of_commit_all() -> io::Result<()> {
+        let root_dir = "tests/hooks_commit_all_repo";
+        let git_dir = test_utils::create_repo(root_dir)?;
+        fs::create_dir_all(format!("{}/{}", git_dir, HOOKS_DIR))?;
+        let file = format!("{}/a.txt", root_dir);
+        test_utils::commit_file(&git_dir, &file, "uno\n", "Primero")?;
+        let index_path = format!("{}/index", git_dir);
+        let commit = |args: &[&str]| -> io::Result<String> {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            commit::git_commit(&git_dir, "", &args, &mut Vec::new())
+        };
+        fs::write(&file, "dos\n")?;
+        let before = fs::read_to_string(&index_path)?;
+
+        write_hook(
+            &git_dir,
+            "pre-commit",
+            "cp \"$GIT_INDEX_FILE\" rejected.txt\nexit 1\n",
+        )?;
+        let rejected = commit(&["-am", "Segundo"]);
+        let rejected_index = fs::read_to_string(format!("{}/rejected.txt", root_dir))?;
+        let after_rejected = fs::read_to_string(&index_path)?;
+        let lock_left = Path::new(&format!("{}/index.lock", git_dir)).exists();
+        write_hook(
+            &git_dir,
+            "pre-commit",
+            "cp \"$GIT_INDEX_FILE\" pre-commit.txt\n",
+        )?;
+        write_hook(
+            &git_dir,
+            "commit-msg",
+            "cp \"$GIT_INDEX_FILE\" commit-msg.txt\n",
+        )?;
+        commit(&["-am", "Segundo"])?;
+        let pre_commit_index = fs::read_to_string(format!("{}/pre-commit.txt", root_dir))?;
+        let commit_msg_index = fs::read_to_string(format!("{}/commit-msg.txt", root_dir))?;
+        let after_commit = fs::read_to_string(&index_path)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(rejected.is_err());
+        assert_eq!(after_rejected, before);
+        assert!(!lock_left);
+        assert_ne!(after_commit, before);
+        assert_eq!(rejected_index, after_commit);
+        assert_eq!(pre_commit_index, after_commit);
+        assert_eq!(commit_msg_index, after_commit);
+        Ok(())
+    }
+}
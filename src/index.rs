@@ -4,6 +4,7 @@ use std::{
     io::{self, Error, Write},
 };
 
+use crate::attributes::Attributes;
 use crate::hash_object;
 use crate::ignorer::Ignorer;
 
@@ -17,6 +18,7 @@ use crate::ignorer::Ignorer;
 pub struct Index {
     map: HashMap<String, String>,
     ignorer: Ignorer,
+    attributes: Attributes,
     path: String,
     git_dir: String,
 }
@@ -26,7 +28,8 @@ impl Index {
     ///
     /// This function initializes a new index and sets its internal fields based
     /// on the provided `index_path` and `git_dir_path`. The `map` field is initialized
-    /// as an empty `HashMap`, the `ignorer` field is loaded from some source, and the
+    /// as an empty `HashMap`, the `ignorer` field is loaded from some source, the `attributes`
+    /// are read from the attributes files of the repository, and the
     /// `path` and `git_dir` fields are set from the input parameters.
     ///
     /// # Arguments
@@ -41,6 +44,7 @@ impl Index {
         Self {
             map: HashMap::new(),
             ignorer: Ignorer::load(gitignore_path),
+            attributes: Attributes::load(git_dir_path),
             path: String::from(index_path),
             git_dir: String::from(git_dir_path),
         }
@@ -101,6 +105,9 @@ impl Index {
     /// all files in every sub-directory is added.
     ///
     /// If the file does not exists, then it will be removed from the index.
+    ///
+    /// Files are stored after the clean conversion given by their attributes, so text files
+    /// committed from Windows are stored with LF line endings.
     pub fn add_path(&mut self, path: &str) -> io::Result<()> {
         if self.ignorer.ignore(path) {
             return Err(Error::new(
@@ -112,7 +119,8 @@ impl Index {
         match fs::metadata(path) {
             Ok(metadata) if metadata.is_dir() => self.add_dir(path),
             Ok(_) => {
-                let new_hash = hash_object::store_file(path, &self.git_dir)?;
+                let new_hash =
+                    hash_object::store_file_with_attributes(path, &self.git_dir, &self.attributes)?;
                 self.add_file(path, &new_hash)
             }
             Err(_) => self.remove_file(path),
@@ -1,10 +1,19 @@
 pub mod add;
+pub mod am;
 pub mod api;
+pub mod apply;
+pub mod archive;
+pub mod attributes;
+pub mod binary;
+pub mod bisect;
+pub mod blame;
 pub mod branch;
 pub mod branch_handler;
+pub mod bundle;
 pub mod cat_file;
 pub mod check_ignore;
 pub mod checkout;
+pub mod clean;
 pub mod client;
 pub mod clone;
 pub mod commit;
@@ -12,9 +21,13 @@ pub mod config;
 pub mod configuration;
 pub mod diff;
 pub mod fetch;
+pub mod format_patch;
 pub mod git_config;
+pub mod graph;
+pub mod grep;
 pub mod gui;
 pub mod hash_object;
+pub mod hooks;
 pub mod ignorer;
 pub mod index;
 pub mod init;
@@ -22,7 +35,9 @@ pub mod log;
 pub mod logger;
 pub mod ls_files;
 pub mod ls_tree;
+pub mod mailmap;
 pub mod merge;
+pub mod mv;
 pub mod packfile;
 pub mod parse_commands;
 pub mod pull;
@@ -31,11 +46,19 @@ pub mod push;
 pub mod rebase;
 pub mod remote;
 pub mod remote_handler;
+pub mod rename;
+pub mod restore;
 pub mod rm;
+pub mod sequencer;
 pub mod server;
 pub mod server_utils;
+pub mod shortlog;
+pub mod show;
 pub mod show_ref;
+pub mod stats;
 pub mod status;
 pub mod tag;
+#[cfg(test)]
+mod test_utils;
 pub mod tree_handler;
 pub mod utils;
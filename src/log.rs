@@ -1,8 +1,16 @@
 use crate::{
-    cat_file, configuration::LOGGER_COMMANDS_FILE, logger::Logger, utils::get_current_time,
+    blame::Signature,
+    cat_file,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff, graph,
+    logger::Logger,
+    rename::{self, RenameOptions},
+    tree_handler,
+    utils::{self, get_current_time},
 };
-use chrono::{TimeZone, Utc};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
 use std::{
+    collections::{HashMap, HashSet},
     fmt::Display,
     fs,
     io::{self, Error, Write},
@@ -10,18 +18,59 @@ use std::{
 };
 
 const DATE_ZERO: &str = "Thu Jan 1 00:00:00 1970 +0000";
+/// Format of the dates of `%ad` and `%cd`, the same one the logs are shown with.
+const DATE_FORMAT: &str = "%a %b %e %T %Y %z";
+/// Format of the dates of `%as` and `%cs`.
+const SHORT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The order in which the commits of the history are shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogOrder {
+    /// Newest commits first, by commit date. A commit is never shown before its children.
+    Date,
+    /// No commit is shown before its children, and the commits of a line of history are kept together.
+    Topo,
+}
+
 /// LogIter is a structure that will help to iterate
 /// through commit logs in the correct way.
 ///
+/// Every parent of a merge is followed (or only the first one if so configured), and the
+/// commits are given newest first by their commit date. A commit reachable through more than
+/// one path is given only once.
+///
 /// Also implements Iterator trait so it has a lot
 /// of flexibility because of that
 pub struct LogIter {
-    log: Option<Log>,
+    /// Commits to be given, with the order they were found in to break ties between equal dates.
+    pending: Vec<(usize, Log)>,
+    found: usize,
+    seen: HashSet<String>,
+    excluded: HashSet<String>,
+    first_parent: bool,
 }
 
 impl LogIter {
-    fn new(log: Log) -> Self {
-        Self { log: Some(log) }
+    fn new(starts: Vec<Log>, excluded: HashSet<String>, first_parent: bool) -> Self {
+        let mut iter = Self {
+            pending: Vec::new(),
+            found: 0,
+            seen: HashSet::new(),
+            excluded,
+            first_parent,
+        };
+        for log in starts {
+            iter.push(log);
+        }
+        iter
+    }
+
+    /// Adds a commit to the pending ones, unless it was already seen or is excluded.
+    fn push(&mut self, log: Log) {
+        if !self.excluded.contains(&log.commit_hash) && self.seen.insert(log.commit_hash.clone()) {
+            self.pending.push((self.found, log));
+            self.found += 1;
+        }
     }
 }
 
@@ -29,11 +78,30 @@ impl Iterator for LogIter {
     type Item = Log;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let actual = self.log.clone();
-        if let Some(log) = &self.log {
-            self.log = log.get_parent_log();
+        let (newest, _) =
+            self.pending
+                .iter()
+                .enumerate()
+                .max_by(|(_, (a_found, a)), (_, (b_found, b))| {
+                    a.commit_time()
+                        .cmp(&b.commit_time())
+                        .then(b_found.cmp(a_found))
+                })?;
+        let (_, log) = self.pending.swap_remove(newest);
+        let count = if self.first_parent {
+            1
+        } else {
+            log.parents.len()
+        };
+        for parent in log.parents.iter().take(count) {
+            if self.seen.contains(parent) || self.excluded.contains(parent) {
+                continue;
+            }
+            if let Ok(parent_log) = Log::load_from_hash(parent, &log.git_dir) {
+                self.push(parent_log.set_oneline(log.oneline));
+            }
         }
-        actual
+        Some(log)
     }
 }
 
@@ -58,12 +126,14 @@ pub struct Log {
     git_dir: String,
     commit_hash: String,
     tree_hash: String,
-    parent_hash: Option<String>,
+    parents: Vec<String>,
     message: String,
     author: String,
     date: String,
     committer: String,
     oneline: bool,
+    /// The references that point to the commit, shown next to its hash.
+    decorations: Vec<String>,
 }
 
 /// Creates a custom `io::Error` with the `InvalidData` kind, representing an error due to
@@ -152,7 +222,13 @@ impl Log {
                 for line in commit_content.lines().take(n) {
                     log.parse_commit_header_line(line)?;
                 }
-                log.message = commit_content.lines().skip(n).collect();
+                log.message = commit_content
+                    .lines()
+                    .skip(n + 1)
+                    .collect::<Vec<&str>>()
+                    .join("\n")
+                    .trim_end()
+                    .to_string();
                 log.git_dir = git_dir.to_string();
                 log.commit_hash = hash.to_string();
                 Ok(log)
@@ -187,7 +263,7 @@ impl Log {
                 self.tree_hash = hash.to_string();
             }
             Some(("parent", hash)) => {
-                self.parent_hash = Some(hash.to_string());
+                self.parents.push(hash.to_string());
             }
             Some(("author", author)) => {
                 let fields: Vec<&str> = author.split(' ').collect();
@@ -225,26 +301,6 @@ impl Log {
         self
     }
 
-    /// Retrieve the parent log of the current commit.
-    ///
-    /// This method attempts to load the log of the commit's parent, if it exists. If successful,
-    /// it returns an `Option<Log>` containing the parent log with an updated oneline mode, otherwise
-    /// it returns `None`.
-    ///
-    /// # Returns
-    ///
-    /// Returns an `Option<Log>` containing the parent log with an updated oneline mode if the parent
-    /// commit exists and can be loaded successfully. Returns `None` otherwise.
-    ///
-    fn get_parent_log(&self) -> Option<Self> {
-        if let Some(parent) = &self.parent_hash {
-            if let Ok(log) = Log::load_from_hash(parent, &self.git_dir) {
-                return Some(log.set_oneline(self.oneline));
-            }
-        }
-        None
-    }
-
     fn get_formatted_date(&self) -> String {
         let (secs, offset) = &self.date.split_once(' ').unwrap_or(("0", "0"));
         let secs = secs.parse::<i64>().unwrap_or(0);
@@ -260,26 +316,185 @@ impl Log {
 
     /// Returns an iterator starting in 'self'
     ///
-    /// When accessing to the next Log, it refers to 'parent log'
+    /// When accessing to the next Log, it refers to the newest of the
+    /// parents not given yet, so every commit reachable from 'self' is given.
     ///
     /// self is consumed
     pub fn iter(self) -> LogIter {
-        LogIter::new(self)
+        LogIter::new(vec![self], HashSet::new(), false)
+    }
+
+    /// Returns the hash of the commit.
+    pub fn hash(&self) -> &str {
+        &self.commit_hash
+    }
+
+    /// Returns the hash of the tree of the commit.
+    pub fn tree_hash(&self) -> &str {
+        &self.tree_hash
+    }
+
+    /// Returns the hashes of the parents of the commit, in order.
+    pub fn parents(&self) -> &[String] {
+        &self.parents
+    }
+
+    /// Returns the references that point to the commit, such as `HEAD -> master` or `tag: v1.0`.
+    pub fn decorations(&self) -> &[String] {
+        &self.decorations
+    }
+
+    /// Returns the references that point to the commit as they are shown next to its hash,
+    /// for example ` (HEAD -> master, tag: v1.0)`, or an empty string if there are none.
+    fn decoration(&self) -> String {
+        if self.decorations.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", self.decorations.join(", "))
+        }
+    }
+
+    /// Returns the complete message of the commit.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the first line of the message.
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+
+    /// Returns the message without its first line and the empty lines after it.
+    pub fn body(&self) -> &str {
+        match self.message.split_once('\n') {
+            Some((_, body)) => body.trim_start_matches('\n'),
+            None => "",
+        }
+    }
+
+    /// Returns the author of the commit and the date it was authored.
+    pub fn author(&self) -> Signature {
+        Signature::parse(&format!("{} {}", self.author, self.date)).unwrap_or_default()
+    }
+
+    /// Returns the committer of the commit and the date it was committed.
+    /// Commits without a committer take the author.
+    pub fn committer(&self) -> Signature {
+        Signature::parse(&self.committer).unwrap_or_else(|| self.author())
+    }
+
+    /// Returns the commit date, in seconds since the epoch, used to sort the history.
+    pub fn commit_time(&self) -> i64 {
+        self.committer().time
+    }
+
+    /// Returns the files the commit changed, compared with its first parent. The first commit added every file it has.
+    pub fn changes(&self) -> io::Result<Vec<diff::FileChange>> {
+        let files = tree_handler::load_tree_from_file(&self.tree_hash, &self.git_dir)?.get_files();
+        let parent_files = match self.parents.first() {
+            Some(parent) => tree_handler::load_tree_from_commit(parent, &self.git_dir)?.get_files(),
+            None => Vec::new(),
+        };
+        Ok(diff::compare_files(&parent_files, &files, &[]))
+    }
+
+    /// Returns true if the commit changed a file inside `paths`, compared with every one of its parents
+    /// (or only the first one if `first_parent` is true). The first commit changed every file it has.
+    fn changes_paths(&self, paths: &[String], first_parent: bool) -> io::Result<bool> {
+        let files = tree_handler::load_tree_from_file(&self.tree_hash, &self.git_dir)?.get_files();
+        if self.parents.is_empty() {
+            return Ok(!diff::compare_files(&[], &files, paths).is_empty());
+        }
+        let count = if first_parent { 1 } else { self.parents.len() };
+        for parent in self.parents.iter().take(count) {
+            let parent_files =
+                tree_handler::load_tree_from_commit(parent, &self.git_dir)?.get_files();
+            if diff::compare_files(&parent_files, &files, paths).is_empty() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Formats the commit with the placeholders of `git log --pretty=format:<format>`.
+    ///
+    /// The placeholders are `%H` and `%h` (commit hash), `%T` and `%t` (tree hash), `%P` and `%p`
+    /// (parent hashes), `%an`, `%ae`, `%ad`, `%as` and `%at` (author name, email, date, short date and
+    /// timestamp), the same ones with `c` for the committer, `%s` (subject), `%b` (body), `%B` (raw message),
+    /// `%d` and `%D` (references, with and without parentheses), `%n` (newline), `%%`, and the colors `%Cred`,
+    /// `%Cgreen`, `%Cblue` and `%Creset`.
+    /// Anything else is copied as it is.
+    pub fn format(&self, format: &str) -> String {
+        let short = |hash: &str| hash.get(..7).unwrap_or(hash).to_string();
+        let mut formatted = String::new();
+        let mut rest = format;
+        while let Some(position) = rest.find('%') {
+            formatted.push_str(&rest[..position]);
+            rest = &rest[position + 1..];
+            let person = match rest.chars().next() {
+                Some('a') => Some(self.author()),
+                Some('c') => Some(self.committer()),
+                _ => None,
+            };
+            let (value, length) = match (person, rest.chars().nth(1)) {
+                (Some(person), Some('n')) => (person.name, 2),
+                (Some(person), Some('e')) => (person.email, 2),
+                (Some(person), Some('d')) => (person.format_date(DATE_FORMAT), 2),
+                (Some(person), Some('s')) => (person.format_date(SHORT_DATE_FORMAT), 2),
+                (Some(person), Some('t')) => (person.time.to_string(), 2),
+                _ => match rest.chars().next() {
+                    Some('H') => (self.commit_hash.clone(), 1),
+                    Some('h') => (short(&self.commit_hash), 1),
+                    Some('T') => (self.tree_hash.clone(), 1),
+                    Some('t') => (short(&self.tree_hash), 1),
+                    Some('P') => (self.parents.join(" "), 1),
+                    Some('p') => (
+                        self.parents
+                            .iter()
+                            .map(|parent| short(parent))
+                            .collect::<Vec<String>>()
+                            .join(" "),
+                        1,
+                    ),
+                    Some('s') => (self.subject().to_string(), 1),
+                    Some('b') => (self.body().to_string(), 1),
+                    Some('B') => (self.message.clone(), 1),
+                    Some('d') => (self.decoration(), 1),
+                    Some('D') => (self.decorations.join(", "), 1),
+                    Some('n') => ("\n".to_string(), 1),
+                    Some('%') => ("%".to_string(), 1),
+                    _ if rest.starts_with("Cred") => ("\x1b[31m".to_string(), 4),
+                    _ if rest.starts_with("Cgreen") => ("\x1b[32m".to_string(), 6),
+                    _ if rest.starts_with("Cblue") => ("\x1b[34m".to_string(), 5),
+                    _ if rest.starts_with("Creset") => ("\x1b[0m".to_string(), 6),
+                    _ => ("%".to_string(), 0),
+                },
+            };
+            formatted.push_str(&value);
+            rest = &rest[length..];
+        }
+        formatted.push_str(rest);
+        formatted
     }
 }
 
 impl Display for Log {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let commit = format!("\x1b[0;33mcommit {}\x1b[0m", &self.commit_hash);
-        let message = self
-            .message
-            .lines()
-            .fold(String::new(), |acc, line| format!("{}\t{}", acc, line));
-
+        let commit = format!(
+            "\x1b[0;33mcommit {}{}\x1b[0m",
+            &self.commit_hash,
+            self.decoration()
+        );
         if self.oneline {
             let commit = commit.replace("commit ", "");
-            return write!(f, "{} {}", commit, message);
+            return write!(f, "{} \t{}", commit, self.subject());
         }
+        let message = self
+            .message
+            .lines()
+            .map(|line| format!("\t{}", line))
+            .collect::<Vec<String>>()
+            .join("\n");
 
         let author = format!("Author: {}", &self.author);
         let date = self.get_formatted_date();
@@ -338,6 +553,535 @@ pub fn log(
     Ok(log.iter().skip(skip).take(amount))
 }
 
+/// The options of the `git log` command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogOptions {
+    /// Maximum number of commits shown (`-n <n>`, `-<n>` or `--max-count=<n>`).
+    pub max_count: Option<usize>,
+    /// Number of commits skipped before starting to show them (`--skip=<n>`).
+    pub skip: usize,
+    pub oneline: bool,
+    /// The format of `--pretty=format:<format>` or `--format=<format>`.
+    pub format: Option<String>,
+    /// Only the commits whose author (`Name <email>`) contains one of these are shown.
+    pub authors: Vec<String>,
+    /// Only the commits whose message contains one of these are shown.
+    pub greps: Vec<String>,
+    /// Only the commits committed at this time or later are shown.
+    pub since: Option<i64>,
+    /// Only the commits committed at this time or earlier are shown.
+    pub until: Option<i64>,
+    /// Only the first parent of the merges is followed.
+    pub first_parent: bool,
+    /// `Some(true)` shows only merges, `Some(false)` leaves them out.
+    pub merges: Option<bool>,
+    pub order: LogOrder,
+    /// The history of a single file is followed across renames.
+    pub follow: bool,
+    /// The history starts in every reference of the repository and in HEAD.
+    pub all: bool,
+    /// The history is drawn as a graph, in topological order.
+    pub graph: bool,
+    /// The references that point to each commit are shown. By default they are shown only with the graph.
+    pub decorate: Option<bool>,
+    /// The revisions the history starts in. `^<rev>` and `<rev1>..<rev2>` leave out the commits reachable from a revision.
+    pub revisions: Vec<String>,
+    /// Only the commits that changed a file inside these paths are shown.
+    pub paths: Vec<String>,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self {
+            max_count: None,
+            skip: 0,
+            oneline: false,
+            format: None,
+            authors: Vec::new(),
+            greps: Vec::new(),
+            since: None,
+            until: None,
+            first_parent: false,
+            merges: None,
+            order: LogOrder::Date,
+            follow: false,
+            all: false,
+            graph: false,
+            decorate: None,
+            revisions: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
+}
+
+/// Parses the dates of `--since` and `--until`: `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]` (in UTC),
+/// `@<timestamp>`, `now`, `yesterday` and relative dates such as `2 weeks ago` or `3.days.ago`.
+///
+/// # Arguments
+///
+/// * `value` - The date to parse.
+/// * `now` - The current time, in seconds since the epoch, that relative dates are counted from.
+///
+/// # Returns
+///
+/// The date in seconds since the epoch, or None if it can't be parsed.
+///
+pub fn parse_date(value: &str, now: i64) -> Option<i64> {
+    if let Some(timestamp) = value.strip_prefix('@') {
+        return timestamp.parse().ok();
+    }
+    match value {
+        "now" => return Some(now),
+        "yesterday" => return Some(now - 24 * 60 * 60),
+        _ => {}
+    }
+    let words: Vec<&str> = value.split(['.', ' ']).filter(|w| !w.is_empty()).collect();
+    if let [amount, unit, "ago"] = words.as_slice() {
+        let amount = amount.parse::<i64>().ok()?;
+        let seconds = match unit.trim_end_matches('s') {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 60 * 60,
+            "day" => 24 * 60 * 60,
+            "week" => 7 * 24 * 60 * 60,
+            "month" => 30 * 24 * 60 * 60,
+            "year" => 365 * 24 * 60 * 60,
+            _ => return None,
+        };
+        return Some(now - amount * seconds);
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&date).timestamp());
+        }
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some(
+        Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .timestamp(),
+    )
+}
+
+impl LogOptions {
+    /// Parses the arguments of `git log` (the ones after 'log').
+    /// Everything after `--` is taken as a path, and any other argument that is not an option as a revision.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments.
+    /// * `now` - The current time, in seconds since the epoch, that relative dates are counted from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or its value is not valid.
+    pub fn parse(args: &[String], now: i64) -> io::Result<LogOptions> {
+        let mut options = LogOptions::default();
+        let invalid_option = |arg: &str| {
+            Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: invalid option: {}", arg),
+            )
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                value
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| invalid_option(arg))
+            };
+            match name {
+                "--" => options.paths.extend(args.by_ref().cloned()),
+                "-n" | "--max-count" => {
+                    options.max_count = Some(value()?.parse().map_err(|_| invalid_option(arg))?)
+                }
+                "--skip" => options.skip = value()?.parse().map_err(|_| invalid_option(arg))?,
+                "--oneline" => options.oneline = true,
+                "--author" => options.authors.push(value()?),
+                "--grep" => options.greps.push(value()?),
+                "--since" | "--after" => {
+                    options.since =
+                        Some(parse_date(&value()?, now).ok_or_else(|| invalid_option(arg))?)
+                }
+                "--until" | "--before" => {
+                    options.until =
+                        Some(parse_date(&value()?, now).ok_or_else(|| invalid_option(arg))?)
+                }
+                "--first-parent" => options.first_parent = true,
+                "--merges" => options.merges = Some(true),
+                "--no-merges" => options.merges = Some(false),
+                "--topo-order" => options.order = LogOrder::Topo,
+                "--date-order" => options.order = LogOrder::Date,
+                "--follow" => options.follow = true,
+                "--all" => options.all = true,
+                "--graph" => {
+                    options.graph = true;
+                    options.order = LogOrder::Topo;
+                }
+                "--decorate" => options.decorate = Some(true),
+                "--no-decorate" => options.decorate = Some(false),
+                "--format" => options.format = Some(value()?),
+                "--pretty" => {
+                    let pretty = value()?;
+                    match pretty.split_once(':') {
+                        Some(("format" | "tformat", format)) => {
+                            options.format = Some(format.to_string())
+                        }
+                        _ if pretty == "oneline" => options.oneline = true,
+                        _ if pretty == "medium" => options.oneline = false,
+                        _ => return Err(invalid_option(arg)),
+                    }
+                }
+                _ if name.len() > 1
+                    && name[1..].chars().all(|c| c.is_ascii_digit())
+                    && name.starts_with('-') =>
+                {
+                    options.max_count = Some(name[1..].parse().map_err(|_| invalid_option(arg))?)
+                }
+                _ if name.starts_with('-') => return Err(invalid_option(arg)),
+                _ => options.revisions.push(arg.to_string()),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Returns true if the commit passes the filters of the options.
+    fn matches(&self, log: &Log) -> io::Result<bool> {
+        let is_merge = log.parents.len() > 1;
+        if self.merges.is_some_and(|merges| merges != is_merge) {
+            return Ok(false);
+        }
+        let time = log.commit_time();
+        if self.since.is_some_and(|since| time < since)
+            || self.until.is_some_and(|until| time > until)
+        {
+            return Ok(false);
+        }
+        let author = log.author();
+        let author = format!("{} <{}>", author.name, author.email);
+        if !self.authors.is_empty()
+            && !self
+                .authors
+                .iter()
+                .any(|pattern| author.contains(pattern.as_str()))
+        {
+            return Ok(false);
+        }
+        if !self.greps.is_empty()
+            && !self
+                .greps
+                .iter()
+                .any(|pattern| log.message.contains(pattern.as_str()))
+        {
+            return Ok(false);
+        }
+        if !self.paths.is_empty() {
+            return log.changes_paths(&self.paths, self.first_parent);
+        }
+        Ok(true)
+    }
+}
+
+/// Sorts the commits so that no commit comes before its children, keeping the commits of a line of history together.
+/// The commits must be given in date order, and the newest tips go first.
+fn topo_sort(logs: Vec<Log>) -> Vec<Log> {
+    let positions: HashMap<String, usize> = logs
+        .iter()
+        .enumerate()
+        .map(|(i, log)| (log.commit_hash.clone(), i))
+        .collect();
+    let mut children = vec![0; logs.len()];
+    for log in &logs {
+        for parent in &log.parents {
+            if let Some(&position) = positions.get(parent) {
+                children[position] += 1;
+            }
+        }
+    }
+    let mut ready: Vec<usize> = (0..logs.len())
+        .rev()
+        .filter(|&i| children[i] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(logs.len());
+    while let Some(i) = ready.pop() {
+        order.push(i);
+        for parent in logs[i].parents.iter().rev() {
+            if let Some(&position) = positions.get(parent) {
+                children[position] -= 1;
+                if children[position] == 0 {
+                    ready.push(position);
+                }
+            }
+        }
+    }
+    let mut logs: Vec<Option<Log>> = logs.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| logs[i].take()).collect()
+}
+
+/// Walks the history with the given options and returns the commits to show, in order.
+///
+/// The walk starts in the revisions of the options (or HEAD) and follows every parent,
+/// unless `first_parent` is set. The commits reachable from an excluded revision are left out.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `options` - The revisions, the filters and the order of the walk.
+///
+/// # Errors
+///
+/// Returns an error if a revision can't be resolved or a commit can't be read.
+///
+pub fn log_with_options(git_dir: &str, options: &LogOptions) -> io::Result<Vec<Log>> {
+    let or_head = |revision: &str| match revision {
+        "" => "HEAD".to_string(),
+        revision => revision.to_string(),
+    };
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+    for revision in &options.revisions {
+        match (revision.split_once(".."), revision.strip_prefix('^')) {
+            (Some((from, to)), _) => {
+                excluded.push(or_head(from));
+                included.push(or_head(to));
+            }
+            (None, Some(revision)) => excluded.push(revision.to_string()),
+            (None, None) => included.push(revision.to_string()),
+        }
+    }
+    if options.all {
+        included.extend(utils::list_refs(git_dir)?.into_iter().map(|(name, _)| name));
+    }
+    if included.is_empty() || options.all {
+        included.push("HEAD".to_string());
+    }
+    let decorations = if options.decorate.unwrap_or(options.graph) {
+        ref_decorations(git_dir)?
+    } else {
+        HashMap::new()
+    };
+    let load = |revisions: &[String]| -> io::Result<Vec<Log>> {
+        revisions
+            .iter()
+            .map(|revision| {
+                let hash = utils::resolve_revision(revision, git_dir)?;
+                Ok(Log::load_from_hash(&hash, git_dir)?.set_oneline(options.oneline))
+            })
+            .collect()
+    };
+    let excluded: HashSet<String> = LogIter::new(load(&excluded)?, HashSet::new(), false)
+        .map(|log| log.commit_hash)
+        .collect();
+    let walk = LogIter::new(load(&included)?, excluded, options.first_parent);
+    let logs: Box<dyn Iterator<Item = Log>> = match options.order {
+        LogOrder::Date => Box::new(walk),
+        LogOrder::Topo => Box::new(topo_sort(walk.collect()).into_iter()),
+    };
+    let logs = logs.map(|mut log| {
+        if let Some(names) = decorations.get(&log.commit_hash) {
+            log.decorations = names.clone();
+        }
+        log
+    });
+    select_logs(logs, options)
+}
+
+/// Returns the logs that pass the filters of the options, leaving out the first `skip` of them
+/// and keeping at most `max_count`.
+fn select_logs(logs: impl Iterator<Item = Log>, options: &LogOptions) -> io::Result<Vec<Log>> {
+    let limit = options
+        .max_count
+        .map(|max_count| max_count + options.skip)
+        .unwrap_or(usize::MAX);
+    let mut selected = Vec::new();
+    for log in logs {
+        if selected.len() >= limit {
+            break;
+        }
+        if options.matches(&log)? {
+            selected.push(log);
+        }
+    }
+    Ok(selected.into_iter().skip(options.skip).collect())
+}
+
+/// Returns the names of the references that point to each commit, as they are shown next to it:
+/// `HEAD -> <branch>` (or `HEAD` if it's detached), the branches, the remote branches and `tag: <tag>`.
+fn ref_decorations(git_dir: &str) -> io::Result<HashMap<String, Vec<String>>> {
+    let mut decorations: HashMap<String, Vec<String>> = HashMap::new();
+    let head = fs::read_to_string(Path::new(git_dir).join("HEAD")).unwrap_or_default();
+    let head_branch = head.trim().strip_prefix("ref: refs/heads/");
+    if head_branch.is_none() {
+        if let Ok(hash) = utils::resolve_revision("HEAD", git_dir) {
+            decorations
+                .entry(hash)
+                .or_default()
+                .push("HEAD".to_string());
+        }
+    }
+    for (name, _) in utils::list_refs(git_dir)? {
+        let hash = match utils::resolve_revision(&name, git_dir) {
+            Ok(hash) => hash,
+            Err(_) => continue,
+        };
+        let shown = if let Some(branch) = name.strip_prefix("refs/heads/") {
+            match head_branch {
+                Some(head_branch) if head_branch == branch => format!("HEAD -> {}", branch),
+                _ => branch.to_string(),
+            }
+        } else if let Some(remote) = name.strip_prefix("refs/remotes/") {
+            remote.to_string()
+        } else if let Some(tag) = name.strip_prefix("refs/tags/") {
+            format!("tag: {}", tag)
+        } else {
+            name.clone()
+        };
+        decorations.entry(hash).or_default().push(shown);
+    }
+    for names in decorations.values_mut() {
+        names.sort_by_key(|name| !name.starts_with("HEAD"));
+    }
+    Ok(decorations)
+}
+
+/// Writes the logs, with the format of the options if there is one, and as a graph if so requested.
+fn write_logs(logs: &[Log], options: &LogOptions, output: &mut impl Write) -> io::Result<()> {
+    let entries: Vec<String> = logs
+        .iter()
+        .map(|log| match &options.format {
+            Some(format) => log.format(format),
+            None => log.to_string(),
+        })
+        .collect();
+    if options.graph {
+        let commits: Vec<(String, Vec<String>)> = logs
+            .iter()
+            .map(|log| (log.commit_hash.clone(), log.parents.clone()))
+            .collect();
+        return graph::write_graph(&graph::assign_lanes(&commits), &entries, output);
+    }
+    for entry in entries {
+        writeln!(output, "{}", entry)?;
+    }
+    Ok(())
+}
+
+/// Shows the history of the repository.
+///
+/// The history starts in the given revisions (or HEAD) and follows every parent of the merges,
+/// newest commits first. It accepts:
+/// * `-n <n>`, `-<n>`, `--max-count=<n>` and `--skip=<n>` to limit the commits shown.
+/// * `--author=<pattern>`, `--grep=<pattern>`, `--since=<date>`, `--until=<date>`, `--merges` and `--no-merges` to filter them.
+/// * `--first-parent` to follow only the first parent of the merges, and `--topo-order` to keep lines of history together.
+/// * `<rev1>..<rev2>` and `^<rev>` to leave out the commits reachable from a revision.
+/// * `-- <paths>` to show only the commits that changed those paths, and `--follow <path>` to follow a file across renames.
+/// * `--oneline`, `--pretty=oneline` and `--pretty=format:<format>` (see `Log::format`) to choose how they are shown.
+/// * `--all` to start in every branch and tag, `--graph` to draw the history as a graph, and `--decorate` to show
+///   the references next to the commits.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments of the command, the ones after 'log'.
+/// * `output` - Where the logs are written.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, a revision can't be resolved or a commit can't be read.
+///
+pub fn git_log(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    let mut options = LogOptions::parse(args, Utc::now().timestamp())?;
+    if options.follow {
+        if options.paths.is_empty() {
+            options.paths.extend(options.revisions.pop());
+        }
+        let path = match options.paths.as_slice() {
+            [path] => path.clone(),
+            _ => {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "fatal: --follow requires exactly one pathspec",
+                ))
+            }
+        };
+        let commit = match options.revisions.first() {
+            Some(revision) => Some(utils::resolve_revision(revision, git_dir)?),
+            None => None,
+        };
+        let logs = log_follow(&path, commit.as_deref(), git_dir, options.oneline)?;
+        // The followed logs already changed the file, under whatever name it had then.
+        options.paths.clear();
+        let logs = select_logs(logs.into_iter(), &options)?;
+        return write_logs(&logs, &options, output);
+    }
+    log_log(
+        Path::new(git_dir),
+        options.revisions.first().map(|revision| revision.as_str()),
+    )?;
+    let logs = log_with_options(git_dir, &options)?;
+    write_logs(&logs, &options, output)
+}
+
+/// Returns the logs of the commits that changed `path`, following the file across renames.
+///
+/// The history is walked from the given commit (or HEAD) through the first parents. When a commit
+/// adds the file as a rename of another one, the walk goes on with the old path. When it adds the
+/// file from scratch, the walk stops there.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file, as it is in the first commit of the walk.
+/// * `commit` - The commit where the walk starts. If it's `None`, HEAD is used.
+/// * `git_dir` - The path to the git directory.
+/// * `oneline` - If true, the logs are shown in one line.
+///
+/// # Errors
+///
+/// Returns an error if a commit or a tree can't be read.
+///
+pub fn log_follow(
+    path: &str,
+    commit: Option<&str>,
+    git_dir: &str,
+    oneline: bool,
+) -> io::Result<Vec<Log>> {
+    log_log(Path::new(git_dir), commit)?;
+    let mut logs = Vec::new();
+    let mut path = path.to_string();
+    let start = Log::load(commit, git_dir)?.set_oneline(oneline);
+    for log in LogIter::new(vec![start], HashSet::new(), true) {
+        let tree = tree_handler::load_tree_from_file(&log.tree_hash, git_dir)?;
+        let parent_tree = match log.parents.first() {
+            Some(parent) => Some(tree_handler::load_tree_from_commit(parent, git_dir)?),
+            None => None,
+        };
+        let hash = tree.get_hash_from_path(&path);
+        let parent_hash = parent_tree
+            .as_ref()
+            .and_then(|parent_tree| parent_tree.get_hash_from_path(&path));
+        if hash != parent_hash {
+            logs.push(log.clone());
+        }
+        if let (Some(_), None, Some(parent_tree)) = (hash, parent_hash, parent_tree) {
+            let renames = rename::find_renamed_paths(
+                &parent_tree.get_files(),
+                &tree.get_files(),
+                git_dir,
+                RenameOptions::default(),
+            );
+            match renames.into_iter().find(|(_, new_path)| new_path == &path) {
+                Some((old_path, _)) => path = old_path,
+                None => break,
+            }
+        }
+    }
+    Ok(logs)
+}
+
 /// Print logs from an iterator.
 ///
 /// This function takes an iterator of logs and prints each log to the console. It is a convenient
@@ -451,4 +1195,210 @@ mod tests {
 
         std::fs::remove_dir_all(git_dir_path)
     }
+
+    #[test]
+    fn test_follow_goes_on_after_a_rename() -> io::Result<()> {
+        let root_dir = "tests/log_follow";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        let index_path = format!("{}/index", git_dir);
+        fs::write(&index_path, "")?;
+        let old_path = format!("{}/old.txt", root_dir);
+        let new_path = format!("{}/new.txt", root_dir);
+        let other_path = format!("{}/other.txt", root_dir);
+        let content: String = (1..=10).map(|i| format!("linea {}\n", i)).collect();
+
+        fs::write(&old_path, &content)?;
+        add::add(&old_path, &index_path, &git_dir, "", None)?;
+        let created = commit::new_commit(&git_dir, "Create", "")?;
+        fs::write(&other_path, "otro\n")?;
+        add::add(&other_path, &index_path, &git_dir, "", None)?;
+        commit::new_commit(&git_dir, "Unrelated", "")?;
+
+        let mut index = crate::index::Index::load(&index_path, &git_dir, "")?;
+        index.remove_file(&old_path)?;
+        index.write_file()?;
+        fs::write(&new_path, content.replace("linea 10", "linea diez"))?;
+        add::add(&new_path, &index_path, &git_dir, "", None)?;
+        let renamed = commit::new_commit(&git_dir, "Rename", "")?;
+
+        let logs = log_follow(&new_path, None, &git_dir, true)?;
+        let hashes: Vec<&str> = logs.iter().map(|log| log.commit_hash.as_str()).collect();
+        fs::remove_dir_all(root_dir)?;
+        assert_eq!(hashes, vec![renamed.as_str(), created.as_str()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_follow_applies_the_filters_and_limits() -> io::Result<()> {
+        let root_dir = "tests/log_follow_filters";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        let index_path = format!("{}/index", git_dir);
+        fs::write(&index_path, "")?;
+        let path = format!("{}/a.txt", root_dir);
+        let mut hashes = Vec::new();
+        for (content, message) in [
+            ("uno\n", "Crear"),
+            ("dos\n", "Cambiar"),
+            ("tres\n", "Cambiar otra vez"),
+        ] {
+            fs::write(&path, content)?;
+            add::add(&path, &index_path, &git_dir, "", None)?;
+            hashes.push(commit::new_commit(&git_dir, message, "")?);
+        }
+        let follow = |args: &[&str]| -> io::Result<String> {
+            let mut args = args.to_vec();
+            args.extend(["--pretty=format:%s", "--follow", "--", path.as_str()]);
+            run_log(&git_dir, &args)
+        };
+
+        let limited = follow(&["-n", "1"])?;
+        let skipped = follow(&["--skip=1"])?;
+        let grepped = follow(&["--grep=Cambiar"])?;
+        let no_author = follow(&["--author=Nadie"])?;
+        let merges = follow(&["--merges"])?;
+        let from_revision = follow(&[hashes[1].as_str(), "-n", "5"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(limited, "Cambiar otra vez\n");
+        assert_eq!(skipped, "Cambiar\nCrear\n");
+        assert_eq!(grepped, "Cambiar otra vez\nCambiar\n");
+        assert_eq!(no_author, "");
+        assert_eq!(merges, "");
+        assert_eq!(from_revision, "Cambiar\nCrear\n");
+        Ok(())
+    }
+
+    fn run_log(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_log(git_dir, &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    #[test]
+    fn test_log_walks_every_parent_and_filters_commits() -> io::Result<()> {
+        let root_dir = "tests/log_walker";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        let index_path = format!("{}/index", git_dir);
+        fs::write(&index_path, "")?;
+        let branch_path = format!("{}/refs/heads/master", git_dir);
+        let file_path = format!("{}/f.txt", root_dir);
+        let other_path = format!("{}/other.txt", root_dir);
+
+        fs::write(&file_path, "a\nb\nc\n")?;
+        add::add(&file_path, &index_path, &git_dir, "", None)?;
+        let base = commit::new_commit(&git_dir, "Base", "")?;
+        fs::write(&file_path, "A\nb\nc\n")?;
+        add::add(&file_path, &index_path, &git_dir, "", None)?;
+        let ours = commit::new_commit(&git_dir, "Ours", "")?;
+        fs::write(&branch_path, &base)?;
+        fs::write(&other_path, "otro\n")?;
+        add::add(&other_path, &index_path, &git_dir, "", None)?;
+        let theirs = commit::new_commit(&git_dir, "Theirs\n\nOn another branch", "")?;
+        fs::write(&branch_path, &ours)?;
+        let merge = commit::new_merge_commit(&git_dir, "Merge", &ours, &theirs, "")?;
+
+        let all = run_log(&git_dir, &["--pretty=format:%h %s"])?;
+        let first_parent = run_log(&git_dir, &["--first-parent", "--format=%H"])?;
+        let no_merges = run_log(&git_dir, &["--no-merges", "-n", "2", "--format=%s"])?;
+        let merges = run_log(&git_dir, &["--merges", "--format=%P"])?;
+        let grep = run_log(&git_dir, &["--grep=another", "--format=%s|%b|%an <%ae>"])?;
+        let range = run_log(&git_dir, &[&format!("{}..HEAD", &ours[..7]), "--format=%s"])?;
+        let paths = run_log(&git_dir, &["--format=%s", "--", &other_path])?;
+        let topo = run_log(&git_dir, &["--topo-order", "--format=%s"])?;
+        let authors = run_log(&git_dir, &["--author=someone else", "--format=%s"])?;
+        let dates = run_log(
+            &git_dir,
+            &["--since=2000-01-01", "--until=now", "-2", "--format=%s"],
+        )?;
+        let oneline = run_log(&git_dir, &["--oneline", "-1"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        let short = |hash: &str| hash[..7].to_string();
+        assert_eq!(
+            all,
+            format!(
+                "{} Merge\n{} Ours\n{} Theirs\n{} Base\n",
+                short(&merge),
+                short(&ours),
+                short(&theirs),
+                short(&base)
+            )
+        );
+        assert_eq!(first_parent, format!("{}\n{}\n{}\n", merge, ours, base));
+        assert_eq!(no_merges, "Ours\nTheirs\n");
+        assert_eq!(merges, format!("{} {}\n", ours, theirs));
+        assert_eq!(grep, "Theirs|On another branch|no_user <no_email>\n");
+        assert_eq!(range, "Merge\nTheirs\n");
+        assert_eq!(paths, "Theirs\n");
+        assert_eq!(topo, "Merge\nOurs\nTheirs\nBase\n");
+        assert_eq!(authors, "");
+        assert_eq!(dates, "Merge\nOurs\n");
+        assert!(oneline.contains(&format!("{}\x1b[0m \tMerge", merge)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_of_every_branch_with_decorations() -> io::Result<()> {
+        let root_dir = "tests/log_graph";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        fs::create_dir_all(format!("{}/refs/tags", git_dir))?;
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        let index_path = format!("{}/index", git_dir);
+        fs::write(&index_path, "")?;
+        let branch_path = format!("{}/refs/heads/master", git_dir);
+        let file_path = format!("{}/f.txt", root_dir);
+
+        fs::write(&file_path, "base\n")?;
+        add::add(&file_path, &index_path, &git_dir, "", None)?;
+        let base = commit::new_commit(&git_dir, "Base", "")?;
+        fs::write(format!("{}/refs/tags/v1", git_dir), &base)?;
+        fs::write(&file_path, "feature\n")?;
+        add::add(&file_path, &index_path, &git_dir, "", None)?;
+        let feature = commit::new_commit(&git_dir, "Feature", "")?;
+        fs::write(format!("{}/refs/heads/feature", git_dir), &feature)?;
+        fs::write(&branch_path, &base)?;
+        fs::write(&file_path, "master\n")?;
+        add::add(&file_path, &index_path, &git_dir, "", None)?;
+        commit::new_commit(&git_dir, "Master", "")?;
+
+        let graph = run_log(&git_dir, &["--graph", "--all", "--format=%s%d"])?;
+        let head_only = run_log(&git_dir, &["--graph", "--format=%s%d"])?;
+        let oneline = run_log(&git_dir, &["--decorate", "--oneline", "-1"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            graph,
+            "* Feature (feature)\n| * Master (HEAD -> master)\n|/\n* Base (tag: v1)\n"
+        );
+        assert_eq!(head_only, "* Master (HEAD -> master)\n* Base (tag: v1)\n");
+        assert!(oneline.contains(" (HEAD -> master)\x1b[0m \tMaster"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_absolute_and_relative_dates() {
+        let now = 1_700_000_000;
+        assert_eq!(parse_date("2023-11-02", now), Some(1_698_883_200));
+        assert_eq!(parse_date("2023-11-02 01:00", now), Some(1_698_886_800));
+        assert_eq!(parse_date("2 days ago", now), Some(now - 2 * 24 * 60 * 60));
+        assert_eq!(parse_date("1.week.ago", now), Some(now - 7 * 24 * 60 * 60));
+        assert_eq!(parse_date("@1234", now), Some(1234));
+        assert_eq!(parse_date("someday", now), None);
+    }
 }
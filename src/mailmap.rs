@@ -0,0 +1,141 @@
+use std::{fs, path::Path};
+
+/// Name of the file, at the root of the working tree, that maps the names and emails of the commits.
+pub const MAILMAP_FILE: &str = ".mailmap";
+
+/// A line of the mailmap: the proper name and email of a person, and the ones found in the commits.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    proper_name: Option<String>,
+    proper_email: Option<String>,
+    commit_name: Option<String>,
+    commit_email: String,
+}
+
+/// Maps the names and emails of the commits to the proper ones, so that the aliases of a person
+/// are counted as the same person. The lines of the file can be:
+///
+/// ```text
+/// Proper Name <commit@email>
+/// <proper@email> <commit@email>
+/// Proper Name <proper@email> <commit@email>
+/// Proper Name <proper@email> Commit Name <commit@email>
+/// ```
+///
+/// Emails are compared without case. Lines starting with '#' are comments.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Mailmap {
+    entries: Vec<Entry>,
+}
+
+/// Splits a line into its (name, email) pairs. The name is None if there is nothing before the '<'.
+fn parse_pairs(line: &str) -> Vec<(Option<String>, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = line;
+    while let (Some(start), Some(end)) = (rest.find('<'), rest.find('>')) {
+        if end < start {
+            break;
+        }
+        let name = rest[..start].trim();
+        let name = (!name.is_empty()).then(|| name.to_string());
+        pairs.push((name, rest[start + 1..end].trim().to_string()));
+        rest = &rest[end + 1..];
+    }
+    pairs
+}
+
+impl Mailmap {
+    /// Loads the `.mailmap` file of the working tree of the repository.
+    /// If there is no such file, nothing is mapped.
+    pub fn load(git_dir: &str) -> Self {
+        let working_dir = Path::new(git_dir).parent().unwrap_or(Path::new(""));
+        let content = fs::read_to_string(working_dir.join(MAILMAP_FILE)).unwrap_or_default();
+        Self::from_content(&content)
+    }
+
+    /// Creates the mailmap from the content of a `.mailmap` file. Lines that can't be parsed are ignored.
+    pub fn from_content(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .filter_map(|line| match parse_pairs(line).as_slice() {
+                [(name, email)] => Some(Entry {
+                    proper_name: name.clone(),
+                    proper_email: None,
+                    commit_name: None,
+                    commit_email: email.clone(),
+                }),
+                [(proper_name, proper_email), (commit_name, commit_email)] => Some(Entry {
+                    proper_name: proper_name.clone(),
+                    proper_email: Some(proper_email.clone()),
+                    commit_name: commit_name.clone(),
+                    commit_email: commit_email.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Returns the proper name and email of the person that signed a commit with `name` and `email`.
+    /// The lines that also give the name of the commit are preferred to the ones that give only its email.
+    /// If no line matches, they are returned as they are.
+    pub fn resolve(&self, name: &str, email: &str) -> (String, String) {
+        let matches_email = |entry: &&Entry| entry.commit_email.eq_ignore_ascii_case(email);
+        let entry = self
+            .entries
+            .iter()
+            .filter(matches_email)
+            .find(|entry| entry.commit_name.as_deref() == Some(name))
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .filter(matches_email)
+                    .find(|entry| entry.commit_name.is_none())
+            });
+        match entry {
+            Some(entry) => (
+                entry.proper_name.as_deref().unwrap_or(name).to_string(),
+                entry.proper_email.as_deref().unwrap_or(email).to_string(),
+            ),
+            None => (name.to_string(), email.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_are_mapped_to_the_proper_person() {
+        let mailmap = Mailmap::from_content(
+            "# Aliases of the team\n\
+             Lionel Messi <lio@afa.com>\n\
+             <lio@afa.com> <leo@barca.com>\n\
+             Lionel Messi <lio@afa.com> Pulga <pulga@mail.com>\n\
+             Angel Di Maria <fideo@afa.com> <FIDEO@mail.com> # old email\n",
+        );
+
+        assert_eq!(
+            mailmap.resolve("leo", "lio@afa.com"),
+            ("Lionel Messi".to_string(), "lio@afa.com".to_string())
+        );
+        assert_eq!(
+            mailmap.resolve("Leo", "leo@barca.com"),
+            ("Leo".to_string(), "lio@afa.com".to_string())
+        );
+        assert_eq!(
+            mailmap.resolve("Pulga", "pulga@mail.com"),
+            ("Lionel Messi".to_string(), "lio@afa.com".to_string())
+        );
+        assert_eq!(
+            mailmap.resolve("Otro", "pulga@mail.com"),
+            ("Otro".to_string(), "pulga@mail.com".to_string())
+        );
+        assert_eq!(
+            mailmap.resolve("fideo", "fideo@mail.com"),
+            ("Angel Di Maria".to_string(), "fideo@afa.com".to_string())
+        );
+    }
+}
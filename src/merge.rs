@@ -6,7 +6,7 @@ use crate::logger::Logger;
 use crate::tree_handler::Tree;
 use crate::utils::get_current_time;
 use crate::{
-    branch, commit, tree_handler,
+    branch, commit, hooks, tree_handler,
     utils::{self, get_git_ignore_path},
 };
 use std::io::Write;
@@ -96,7 +96,16 @@ fn two_way_merge(
     let their_commit = branch::get_branch_commit_hash(their_branch, git_dir)?;
     let our_tree = tree_handler::load_tree_from_commit(&our_commit, git_dir)?;
     let their_tree = tree_handler::load_tree_from_commit(&their_commit, git_dir)?;
-    let (new_tree, conflicting_paths) = tree_handler::merge_trees(&our_tree, &their_tree, git_dir)?;
+    let base_tree = find_common_ancestor(&our_commit, &their_commit, git_dir)
+        .and_then(|ancestor| tree_handler::load_tree_from_commit(&ancestor, git_dir))
+        .ok();
+    let (new_tree, conflicting_paths) = tree_handler::merge_trees(
+        &our_tree,
+        &their_tree,
+        base_tree.as_ref(),
+        their_branch,
+        git_dir,
+    )?;
     our_tree.delete_directories(root_dir)?;
     new_tree.create_directories(root_dir, git_dir)?;
     let index_path = utils::get_index_file_path(git_dir);
@@ -173,12 +182,16 @@ pub fn git_merge(
         fast_forward_merge(our_branch, their_branch, git_dir, root_dir)?;
         let tuple = (our_commit, vec![]);
         log_merge(our_branch, their_branch, git_dir, root_dir)?;
+        run_post_merge_hook(git_dir);
         Ok(tuple)
     } else {
         let conflicting_paths = two_way_merge(our_branch, their_branch, git_dir, root_dir)?;
         let commit_message = format!("Merge branch '{}'", their_branch);
         let hash =
             commit::new_merge_commit(git_dir, &commit_message, &our_commit, &their_commit, "")?;
+        if conflicting_paths.is_empty() {
+            run_post_merge_hook(git_dir);
+        }
         let tuple = (hash, conflicting_paths);
         log_merge(our_branch, their_branch, git_dir, root_dir)?;
 
@@ -186,6 +199,106 @@ pub fn git_merge(
     }
 }
 
+/// Merges several branches into `our_branch` at once, creating a single commit with one parent per branch (an octopus merge).
+/// Branches that are already contained in `our_branch` are skipped. If only one branch is left to merge,
+/// this behaves exactly like `git_merge`.
+/// Each branch is merged into the result of the previous ones using its common ancestor with `our_branch` as base.
+/// If any path conflicts, the merge is stopped before touching the working directory, the index or the branch,
+/// and an error listing the conflicting paths is returned.
+///
+/// # Arguments
+/// * `our_branch` - The name of the branch that will be updated.
+/// * `their_branches` - The names of the branches that will be merged with `our_branch`.
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the root directory.
+///
+/// # Returns
+/// The hash of the commit `our_branch` points to after the merge and the list of conflicting paths.
+///
+/// # Errors
+/// Returns an error if any branch doesn't exist or if the branches can't be merged without conflicts.
+///
+pub fn git_octopus_merge(
+    our_branch: &str,
+    their_branches: &[String],
+    git_dir: &str,
+    root_dir: &str,
+) -> io::Result<(String, Vec<String>)> {
+    let our_commit = branch::get_branch_commit_hash(our_branch, git_dir)?;
+    let our_history = utils::get_branch_commit_history_set(&our_commit, git_dir)?;
+
+    let mut branches_to_merge: Vec<(String, String)> = Vec::new();
+    for their_branch in their_branches {
+        let their_commit = branch::get_branch_commit_hash(their_branch, git_dir)?;
+        let already_merged = our_history.contains(&their_commit)
+            || branches_to_merge
+                .iter()
+                .any(|(_, commit)| commit == &their_commit);
+        if !already_merged {
+            branches_to_merge.push((their_branch.to_string(), their_commit));
+        }
+    }
+
+    match branches_to_merge.len() {
+        0 => return Ok((our_commit, vec![])),
+        1 => return git_merge(our_branch, &branches_to_merge[0].0, git_dir, root_dir),
+        _ => (),
+    }
+
+    let our_tree = tree_handler::load_tree_from_commit(&our_commit, git_dir)?;
+    let mut new_tree = our_tree.clone();
+    for (_, their_commit) in branches_to_merge.iter() {
+        let common_ancestor = find_common_ancestor(&our_commit, their_commit, git_dir)?;
+        let base_tree = tree_handler::load_tree_from_commit(&common_ancestor, git_dir)?;
+        let their_tree = tree_handler::load_tree_from_commit(their_commit, git_dir)?;
+        let (merged_tree, conflicts) =
+            tree_handler::merge_trees_with_base(&base_tree, &new_tree, &their_tree, git_dir);
+        if !conflicts.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!(
+                    "Merge with strategy octopus failed. Conflicts found in:\n{}\n",
+                    conflicts.join("\n")
+                ),
+            ));
+        }
+        new_tree = merged_tree;
+    }
+
+    our_tree.delete_directories(root_dir)?;
+    new_tree.create_directories(root_dir, git_dir)?;
+    let index_path = utils::get_index_file_path(git_dir);
+    let new_index_file_contents =
+        new_tree.build_index_file_from_tree(&index_path, git_dir, &get_git_ignore_path(git_dir))?;
+    new_index_file_contents.write_file()?;
+
+    let names: Vec<String> = branches_to_merge
+        .iter()
+        .map(|(name, _)| format!("'{}'", name))
+        .collect();
+    let commit_message = format!(
+        "Merge branches {} and {}",
+        names[..names.len() - 1].join(", "),
+        names[names.len() - 1]
+    );
+    let mut parents = vec![our_commit];
+    parents.extend(
+        branches_to_merge
+            .iter()
+            .map(|(_, commit)| commit.to_string()),
+    );
+    let hash = commit::new_octopus_merge_commit(git_dir, &commit_message, &parents, "")?;
+    log_merge(our_branch, &their_branches.join(" "), git_dir, root_dir)?;
+    run_post_merge_hook(git_dir);
+    Ok((hash, vec![]))
+}
+
+/// Runs the `post-merge` hook after a merge without conflicts. Its argument says that the merge
+/// wasn't a squash, and as in git, its result doesn't change the outcome of the merge.
+fn run_post_merge_hook(git_dir: &str) {
+    let _ = hooks::run_hook(git_dir, "post-merge", &["0"], None);
+}
+
 /// Given two branches, merges `our_branch` with `their_branch`.
 /// This function is used for the UI, where the user can choose to merge or not.
 /// It will try to do a fast forward merge, if it is not possible, it will do a two way merge.
@@ -234,7 +347,8 @@ pub fn git_merge_for_ui(
 ///
 /// This function performs a merge operation by combining the changes from a remote branch into the
 /// current local branch of a Git repository. The merge process involves updating the index and working
-/// tree to reflect the new merged state.
+/// tree to reflect the new merged state. The `post-merge` hook runs after a fast-forward or a merge
+/// without conflicts.
 ///
 /// # Arguments
 ///
@@ -260,11 +374,19 @@ pub fn merge_remote_branch(branch: &str, remote_hash: &str, git_dir: &str) -> io
         )?;
         new_index_file_contents.write_file()?;
         branch::update_branch_commit_hash(branch, remote_hash, git_dir)?;
+        run_post_merge_hook(git_dir);
         Ok(remote_tree)
     } else {
         let our_tree = tree_handler::load_tree_from_commit(&our_commit, git_dir)?;
         let remote_tree = tree_handler::load_tree_from_commit(remote_hash, git_dir)?;
-        let (new_tree, _conflicts) = tree_handler::merge_trees(&our_tree, &remote_tree, git_dir)?;
+        let base_tree = tree_handler::load_tree_from_commit(&common_ancestor, git_dir)?;
+        let (new_tree, conflicts) = tree_handler::merge_trees(
+            &our_tree,
+            &remote_tree,
+            Some(&base_tree),
+            remote_hash,
+            git_dir,
+        )?;
         let index_path = utils::get_index_file_path(git_dir);
         let new_index_file_contents = new_tree.build_index_file_from_tree(
             &index_path,
@@ -272,6 +394,9 @@ pub fn merge_remote_branch(branch: &str, remote_hash: &str, git_dir: &str) -> io
             &get_git_ignore_path(git_dir),
         )?;
         new_index_file_contents.write_file()?;
+        if conflicts.is_empty() {
+            run_post_merge_hook(git_dir);
+        }
         tree_handler::build_tree_from_index(&index_path, git_dir, &get_git_ignore_path(git_dir))
     }
 }
@@ -301,7 +426,16 @@ fn merge_pull_request(
 ) -> Result<String, io::Error> {
     let base_tree = tree_handler::load_tree_from_commit(base_commit, git_dir)?;
     let pull_request_tree = tree_handler::load_tree_from_commit(pull_request_commit, git_dir)?;
-    let (new_tree, conflicts) = tree_handler::merge_trees(&base_tree, &pull_request_tree, git_dir)?;
+    let ancestor_tree = find_common_ancestor(base_commit, pull_request_commit, git_dir)
+        .and_then(|ancestor| tree_handler::load_tree_from_commit(&ancestor, git_dir))
+        .ok();
+    let (new_tree, conflicts) = tree_handler::merge_trees(
+        &base_tree,
+        &pull_request_tree,
+        ancestor_tree.as_ref(),
+        pull_request_branch,
+        git_dir,
+    )?;
     if conflicts.is_empty() {
         let commit_message = format!("Merge pull request #{}", pull_request_branch);
         let commit_hash = commit::new_pr_merge_commit(
@@ -414,6 +548,8 @@ mod tests {
     const NAME_OF_GIT_DIRECTORY_3: &str = "tests/merge/test_fast_forward_merge/.mgit";
     const NAME_OF_GIT_DIRECTORY_4: &str = "tests/merge/test_true_merge/.mgit";
     const NAME_OF_GIT_DIRECTORY_5: &str = "tests/merge/test_conflict_merge/.mgit";
+    const NAME_OF_GIT_DIRECTORY_6: &str = "tests/merge/test_octopus_merge/.mgit";
+    const NAME_OF_GIT_DIRECTORY_7: &str = "tests/merge/test_octopus_merge_conflict/.mgit";
 
     #[test]
     fn is_fast_forward_returns_true_when_our_branch_commit_is_equal_to_common_commit() {
@@ -1004,10 +1140,153 @@ mod tests {
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
         let expected_contents =
-            "<<<<<<< int bye() { return 0; }\n>>>>>>> int bye() { print('hola'); return -1; }\n";
+"<<<<<<< HEAD\nint bye() { print('hola'); return -1; }\n=======\nint bye() { return 0; }\n>>>>>>> branch\n";
         assert_eq!(contents, expected_contents);
 
         fs::remove_dir_all(NAME_OF_GIT_DIRECTORY_5).unwrap();
         fs::remove_dir_all(root_dir).unwrap();
     }
+
+    fn commit_file_on_branch(git_dir: &str, branch_name: &str, file_path: &str, content: &str) {
+        let head_file_path = format!("{}/HEAD", git_dir);
+        let mut head_file = fs::File::create(&head_file_path).unwrap();
+        head_file
+            .write_all(format!("ref: refs/heads/{}", branch_name).as_bytes())
+            .unwrap();
+        let index_file_path = format!("{}/index", git_dir);
+        let branch_commit = branch::get_branch_commit_hash(branch_name, git_dir).unwrap();
+        let tree = tree_handler::load_tree_from_commit(&branch_commit, git_dir).unwrap();
+        tree.build_index_file_from_tree(&index_file_path, git_dir, "")
+            .unwrap()
+            .write_file()
+            .unwrap();
+
+        let mut file = fs::File::create(file_path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        add::add(file_path, &index_file_path, git_dir, "", None).unwrap();
+        commit::new_commit(git_dir, "Change", "").unwrap();
+    }
+
+    fn create_octopus_repository(git_dir: &str, root_dir: &str) {
+        create_mock_git_dir(git_dir);
+        fs::create_dir_all(format!("{}/src", root_dir)).unwrap();
+        let file_1_path = format!("{}/src/1.c", root_dir);
+        let mut file = fs::File::create(&file_1_path).unwrap();
+        file.write_all(b"int main() { return 0; }").unwrap();
+        let index_file_path = format!("{}/index", git_dir);
+        let _ = fs::File::create(&index_file_path).unwrap();
+        add::add(&file_1_path, &index_file_path, git_dir, "", None).unwrap();
+        commit::new_commit(git_dir, "Initial commit", "").unwrap();
+
+        let _ = branch::create_new_branch(git_dir, "branch_a", None, &mut io::stdout());
+        let _ = branch::create_new_branch(git_dir, "branch_b", None, &mut io::stdout());
+    }
+
+    #[test]
+    fn test_octopus_merge_creates_a_commit_with_all_the_parents() {
+        let git_dir = NAME_OF_GIT_DIRECTORY_6;
+        let root_dir = "tests/merge/test_octopus_merge";
+        create_octopus_repository(git_dir, root_dir);
+
+        let file_2_path = format!("{}/src/2.c", root_dir);
+        commit_file_on_branch(git_dir, "branch_a", &file_2_path, "int a() { return 0; }");
+        let file_3_path = format!("{}/src/3.c", root_dir);
+        commit_file_on_branch(git_dir, "branch_b", &file_3_path, "int b() { return 0; }");
+        let file_4_path = format!("{}/src/4.c", root_dir);
+        commit_file_on_branch(git_dir, "main", &file_4_path, "int c() { return 0; }");
+
+        let main_commit = branch::get_branch_commit_hash("main", git_dir).unwrap();
+        let branch_a_commit = branch::get_branch_commit_hash("branch_a", git_dir).unwrap();
+        let branch_b_commit = branch::get_branch_commit_hash("branch_b", git_dir).unwrap();
+
+        let branches = vec!["branch_a".to_string(), "branch_b".to_string()];
+        let (merge_commit_hash, conflicts) =
+            git_octopus_merge("main", &branches, git_dir, "").unwrap();
+        assert!(conflicts.is_empty());
+
+        let main_branch_hash = branch::get_branch_commit_hash("main", git_dir).unwrap();
+        assert_eq!(main_branch_hash, merge_commit_hash);
+        let parents = commit::get_merge_parents(&merge_commit_hash, git_dir).unwrap();
+        assert_eq!(parents, vec![main_commit, branch_a_commit, branch_b_commit]);
+        let message = commit::get_commit_message(&merge_commit_hash, git_dir).unwrap();
+        assert_eq!(message, "Merge branches 'branch_a' and 'branch_b'");
+
+        for (path, expected) in [
+            (&file_2_path, "int a() { return 0; }"),
+            (&file_3_path, "int b() { return 0; }"),
+            (&file_4_path, "int c() { return 0; }"),
+        ] {
+            let contents = fs::read_to_string(path).unwrap();
+            assert_eq!(contents, expected);
+        }
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_octopus_merge_stops_when_there_are_conflicts() {
+        let git_dir = NAME_OF_GIT_DIRECTORY_7;
+        let root_dir = "tests/merge/test_octopus_merge_conflict";
+        create_octopus_repository(git_dir, root_dir);
+
+        let file_1_path = format!("{}/src/1.c", root_dir);
+        commit_file_on_branch(
+            git_dir,
+            "branch_a",
+            &file_1_path,
+            "int main() { return 1; }",
+        );
+        commit_file_on_branch(
+            git_dir,
+            "branch_b",
+            &file_1_path,
+            "int main() { return 2; }",
+        );
+        let file_2_path = format!("{}/src/2.c", root_dir);
+        commit_file_on_branch(git_dir, "main", &file_2_path, "int a() { return 0; }");
+        let main_commit = branch::get_branch_commit_hash("main", git_dir).unwrap();
+
+        let branches = vec!["branch_a".to_string(), "branch_b".to_string()];
+        let result = git_octopus_merge("main", &branches, git_dir, "");
+        assert!(result.is_err());
+
+        let main_branch_hash = branch::get_branch_commit_hash("main", git_dir).unwrap();
+        assert_eq!(main_branch_hash, main_commit);
+        let contents = fs::read_to_string(&file_1_path).unwrap();
+        assert_eq!(contents, "int main() { return 2; }");
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_merge_remote_branch_runs_the_post_merge_hook() -> io::Result<()> {
+        use crate::test_utils::{commit_file, create_repo};
+        use std::os::unix::fs::PermissionsExt;
+        let root_dir = "tests/merge/test_remote_post_merge";
+        let git_dir = create_repo(root_dir)?;
+        fs::create_dir_all(format!("{}/hooks", git_dir))?;
+        let hook = format!("{}/hooks/post-merge", git_dir);
+        fs::write(&hook, "#!/bin/sh\necho \"$1\" >> merged.txt\n")?;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+        let (a, b) = (format!("{}/a.txt", root_dir), format!("{}/b.txt", root_dir));
+        let base = commit_file(&git_dir, &a, "uno\n", "Base")?;
+        let remote = commit_file(&git_dir, &a, "dos\n", "Remoto")?;
+        branch::update_branch_commit_hash("master", &base, &git_dir)?;
+        let merged = format!("{}/merged.txt", root_dir);
+
+        merge_remote_branch("master", &remote, &git_dir)?;
+        let after_fast_forward = fs::read_to_string(&merged)?;
+        let local = commit_file(&git_dir, &b, "b\n", "Local")?;
+        branch::update_branch_commit_hash("master", &base, &git_dir)?;
+        let other = commit_file(&git_dir, &format!("{}/c.txt", root_dir), "c\n", "Otro")?;
+        branch::update_branch_commit_hash("master", &local, &git_dir)?;
+        merge_remote_branch("master", &other, &git_dir)?;
+        let after_merge = fs::read_to_string(&merged)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(after_fast_forward, "0\n");
+        assert_eq!(after_merge, "0\n0\n");
+        Ok(())
+    }
 }
@@ -0,0 +1,301 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    configuration::LOGGER_COMMANDS_FILE, ignorer::is_subpath, index::Index, logger::Logger, utils,
+};
+
+/// Logs the 'git mv' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_mv(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git mv': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git mv` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MvOptions {
+    /// If true (`-f`), an existing destination file is overwritten.
+    pub force: bool,
+    /// If true (`-k`), the sources that can't be moved are skipped instead of failing.
+    pub skip_errors: bool,
+    /// The paths to move.
+    pub sources: Vec<String>,
+    /// The new path, or the directory the sources are moved into.
+    pub destination: String,
+}
+
+impl MvOptions {
+    /// Parses the arguments of `git mv` (the ones after 'mv').
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown or there isn't a source and a destination.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<MvOptions> {
+        let mut options = MvOptions::default();
+        let mut paths = Vec::new();
+        for arg in args {
+            match arg.as_str() {
+                "-f" | "--force" => options.force = true,
+                "-k" => options.skip_errors = true,
+                _ if arg.starts_with('-') => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("error: unknown option `{}'", arg.trim_start_matches('-')),
+                    ))
+                }
+                _ => paths.push(arg.trim_end_matches('/').to_string()),
+            }
+        }
+        match paths.pop() {
+            Some(destination) if !paths.is_empty() => {
+                options.destination = destination;
+                options.sources = paths;
+                Ok(options)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "usage: git mv [<options>] <source>... <destination>",
+            )),
+        }
+    }
+}
+
+/// Returns the tracked files of a path: the path itself if it's a file, or the files inside it if it's a directory.
+fn tracked_files(index: &Index, path: &str) -> Vec<String> {
+    index
+        .iter()
+        .map(|(tracked, _)| tracked)
+        .filter(|tracked| is_subpath(tracked, path))
+        .cloned()
+        .collect()
+}
+
+/// Checks that a source can be moved to a destination.
+///
+/// # Returns
+///
+/// Returns why it can't be moved, as git says it.
+///
+fn check_move(
+    index: &Index,
+    root_dir: &str,
+    source: &str,
+    destination: &str,
+    force: bool,
+) -> Result<(), &'static str> {
+    let source_path = Path::new(root_dir).join(source);
+    let destination_path = Path::new(root_dir).join(destination);
+    if !source_path.exists() {
+        return Err("bad source");
+    }
+    if tracked_files(index, source).is_empty() {
+        return Err("not under version control");
+    }
+    if source_path.is_dir() && is_subpath(destination, source) {
+        return Err("can not move directory into itself");
+    }
+    if destination_path.is_dir() || (destination_path.exists() && !force) {
+        return Err("destination exists");
+    }
+    if destination_path
+        .parent()
+        .is_some_and(|parent| !parent.as_os_str().is_empty() && !parent.is_dir())
+    {
+        return Err("destination directory does not exist");
+    }
+    Ok(())
+}
+
+/// Returns the moves `git mv` makes, as pairs (source, destination).
+///
+/// With many sources, or if the destination is an existing directory, every source is moved into it
+/// keeping its name. Otherwise the only source is renamed to the destination.
+///
+/// # Arguments
+///
+/// * `index` - The index with the tracked files.
+/// * `root_dir` - The path to the working tree.
+/// * `options` - The sources, the destination and whether to overwrite files or skip errors.
+///
+/// # Errors
+///
+/// Returns an error if a source can't be moved, unless `-k` skips it.
+///
+pub fn plan_moves(
+    index: &Index,
+    root_dir: &str,
+    options: &MvOptions,
+) -> io::Result<Vec<(String, String)>> {
+    let into_directory = Path::new(root_dir).join(&options.destination).is_dir();
+    if options.sources.len() > 1 && !into_directory {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "fatal: destination '{}' is not a directory",
+                options.destination
+            ),
+        ));
+    }
+    let mut moves: Vec<(String, String)> = Vec::new();
+    for source in &options.sources {
+        let destination = match (into_directory, source.rsplit('/').next()) {
+            (true, Some(name)) if options.destination == "." => name.to_string(),
+            (true, Some(name)) => format!("{}/{}", options.destination, name),
+            _ => options.destination.clone(),
+        };
+        let checked = if moves.iter().any(|(_, other)| *other == destination) {
+            Err("multiple sources for the same target")
+        } else {
+            check_move(index, root_dir, source, &destination, options.force)
+        };
+        match checked {
+            Ok(()) => moves.push((source.clone(), destination)),
+            Err(_) if options.skip_errors => {}
+            Err(reason) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "fatal: {}, source={}, destination={}",
+                        reason, source, destination
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(moves)
+}
+
+/// Moves or renames files and directories in the working tree and in the index together.
+///
+/// Every move is checked before anything is changed, and if a file can't be moved on disk, the
+/// ones already moved are moved back, so the working tree and the index always agree.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `args` - The arguments of the command, the ones after 'mv'.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, a source can't be moved or the index can't be written.
+///
+pub fn git_mv(git_dir: &str, root_dir: &str, args: &[String]) -> io::Result<()> {
+    log_mv(git_dir, args)?;
+    let options = MvOptions::parse(args)?;
+    let index_path = utils::get_index_file_path(git_dir);
+    let mut index = Index::load(&index_path, git_dir, "")?;
+    let moves = plan_moves(&index, root_dir, &options)?;
+
+    let root = Path::new(root_dir);
+    for (i, (source, destination)) in moves.iter().enumerate() {
+        if let Err(error) = fs::rename(root.join(source), root.join(destination)) {
+            for (source, destination) in moves[..i].iter().rev() {
+                fs::rename(root.join(destination), root.join(source))?;
+            }
+            return Err(error);
+        }
+    }
+    for (source, destination) in &moves {
+        for file in tracked_files(&index, source) {
+            let Some(hash) = index.get_hash(&file).cloned() else {
+                continue;
+            };
+            index.remove_file(&file)?;
+            index.add_file(&format!("{}{}", destination, &file[source.len()..]), &hash)?;
+        }
+    }
+    index.write_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_object;
+
+    fn run_mv(git_dir: &str, args: &[&str]) -> io::Result<()> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        git_mv(git_dir, "", &args)
+    }
+
+    #[test]
+    fn test_mv_moves_files_and_directories_in_worktree_and_index() -> io::Result<()> {
+        let root_dir = "tests/mv_repo";
+        let git_dir = format!("{}/.mgit", root_dir);
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        fs::create_dir_all(format!("{}/src/util", root_dir))?;
+        fs::create_dir_all(format!("{}/docs", root_dir))?;
+        fs::write(format!("{}/index", git_dir), "")?;
+        let index_path = format!("{}/index", git_dir);
+        let mut index = Index::load(&index_path, &git_dir, "")?;
+        for path in ["a.txt", "b.txt", "src/main.rs", "src/util/mod.rs"] {
+            let path = format!("{}/{}", root_dir, path);
+            fs::write(&path, &path)?;
+            index.add_file(&path, &hash_object::hash_content(path.as_bytes(), "blob"))?;
+        }
+        index.write_file()?;
+        fs::write(format!("{}/nuevo.txt", root_dir), "")?;
+        let path = |path: &str| format!("{}/{}", root_dir, path);
+
+        let untracked = run_mv(&git_dir, &[&path("nuevo.txt"), &path("docs")]);
+        let exists = run_mv(&git_dir, &[&path("a.txt"), &path("b.txt")]);
+        let renamed = run_mv(&git_dir, &[&path("a.txt"), &path("c.txt")]);
+        let skipped = run_mv(
+            &git_dir,
+            &["-k", &path("nuevo.txt"), &path("src"), &path("docs")],
+        );
+        let forced = run_mv(&git_dir, &["-f", &path("c.txt"), &path("b.txt")]);
+        let mut tracked: Vec<String> = Index::load(&index_path, &git_dir, "")?
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+        tracked.sort();
+        let moved_content = fs::read_to_string(path("docs/src/util/mod.rs"));
+        let forced_content = fs::read_to_string(path("b.txt"));
+        let untracked_left = Path::new(&path("nuevo.txt")).is_file();
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(untracked.is_err());
+        assert!(exists.is_err());
+        assert!(renamed.is_ok());
+        assert!(skipped.is_ok());
+        assert!(forced.is_ok());
+        assert_eq!(
+            tracked,
+            vec![
+                path("b.txt"),
+                path("docs/src/main.rs"),
+                path("docs/src/util/mod.rs")
+            ]
+        );
+        assert_eq!(moved_content?, path("src/util/mod.rs"));
+        assert_eq!(forced_content?, path("a.txt"));
+        assert!(untracked_left);
+        Ok(())
+    }
+}
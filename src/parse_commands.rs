@@ -1,22 +1,24 @@
-use crate::branch::{self, get_current_branch_path, git_branch};
+use crate::branch::{get_current_branch_path, git_branch};
 use crate::cat_file::cat_file;
 use crate::check_ignore::git_check_ignore;
 use crate::checkout::checkout_branch;
 use crate::checkout::checkout_commit_detached;
+use crate::checkout::checkout_paths;
 use crate::checkout::create_and_checkout_branch;
 use crate::checkout::create_or_reset_branch;
 use crate::checkout::force_checkout;
+use crate::checkout::run_post_checkout_hook;
 use crate::clone::git_clone;
-use crate::commit::{get_branch_name, new_commit};
+use crate::commit::{get_branch_name, git_commit};
 use crate::config::Config;
 use crate::configuration::{GIT_DIR, GIT_IGNORE, HOST, INDEX};
 use crate::fetch::git_fetch;
 use crate::hash_object::store_file;
 use crate::index::Index;
 use crate::init::git_init;
-use crate::log::print_logs;
 use crate::ls_files::git_ls_files;
 use crate::merge::git_merge;
+use crate::merge::git_octopus_merge;
 use crate::pull::git_pull;
 use crate::remote::git_remote;
 use crate::rm::git_rm;
@@ -24,7 +26,11 @@ use crate::show_ref::git_show_ref;
 use crate::status::{changes_to_be_committed, find_unstaged_changes, find_untracked_files};
 use crate::tree_handler::Tree;
 use crate::utils::{find_git_directory, obtain_git_dir};
-use crate::{add, git_config, log, ls_tree, push, rebase, tag, tree_handler};
+use crate::{
+    add, am, apply, archive, bisect, blame, bundle, clean, diff, format_patch, git_config, grep,
+    hooks, log, ls_tree, mv, push, restore, sequencer, shortlog, show, stats, tag, tree_handler,
+    utils,
+};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
@@ -59,6 +65,22 @@ pub enum GitCommand {
     Rebase,
     Tag,
     Config,
+    Diff,
+    Blame,
+    Annotate,
+    Show,
+    Shortlog,
+    Stats,
+    Bisect,
+    Grep,
+    Archive,
+    Bundle,
+    FormatPatch,
+    Am,
+    Apply,
+    Clean,
+    Mv,
+    Restore,
 }
 
 /// Reads user input from the command line and splits it into a vector of strings.
@@ -140,6 +162,22 @@ pub fn parse_git_command(second_argument: &str) -> Option<GitCommand> {
         "rebase" => Some(GitCommand::Rebase),
         "tag" => Some(GitCommand::Tag),
         "config" => Some(GitCommand::Config),
+        "diff" => Some(GitCommand::Diff),
+        "blame" => Some(GitCommand::Blame),
+        "annotate" => Some(GitCommand::Annotate),
+        "show" => Some(GitCommand::Show),
+        "shortlog" => Some(GitCommand::Shortlog),
+        "stats" => Some(GitCommand::Stats),
+        "bisect" => Some(GitCommand::Bisect),
+        "grep" => Some(GitCommand::Grep),
+        "archive" => Some(GitCommand::Archive),
+        "bundle" => Some(GitCommand::Bundle),
+        "format-patch" => Some(GitCommand::FormatPatch),
+        "am" => Some(GitCommand::Am),
+        "apply" => Some(GitCommand::Apply),
+        "clean" => Some(GitCommand::Clean),
+        "mv" => Some(GitCommand::Mv),
+        "restore" => Some(GitCommand::Restore),
         _ => {
             eprintln!("Not a valid Git option.");
             None
@@ -169,13 +207,13 @@ pub fn handle_git_command(git_command: GitCommand, args: Vec<String>) {
         GitCommand::Rm => handle_rm(args),
         GitCommand::Commit => handle_commit(args),
         GitCommand::Checkout => handle_checkout(args),
-        GitCommand::Log => handle_log(),
+        GitCommand::Log => handle_log(args),
         GitCommand::Clone => handle_clone(args),
         GitCommand::Fetch => handle_fetch(args),
         GitCommand::Merge => handle_merge(args),
         GitCommand::Remote => handle_remote(args),
         GitCommand::Pull => handle_pull(),
-        GitCommand::Push => handle_push(),
+        GitCommand::Push => handle_push(args),
         GitCommand::Branch => handle_branch(args),
         GitCommand::Init => handle_init(args),
         GitCommand::ListFiles => handle_ls_files(args),
@@ -185,6 +223,387 @@ pub fn handle_git_command(git_command: GitCommand, args: Vec<String>) {
         GitCommand::Rebase => handle_rebase(args),
         GitCommand::Tag => handle_tag(args),
         GitCommand::Config => handle_config(args),
+        GitCommand::Diff => handle_diff(args),
+        GitCommand::Blame => handle_blame(args, false),
+        GitCommand::Annotate => handle_blame(args, true),
+        GitCommand::Show => handle_show(args),
+        GitCommand::Shortlog => handle_shortlog(args),
+        GitCommand::Stats => handle_stats(args),
+        GitCommand::Bisect => handle_bisect(args),
+        GitCommand::Grep => handle_grep(args),
+        GitCommand::Archive => handle_archive(args),
+        GitCommand::Bundle => handle_bundle(args),
+        GitCommand::FormatPatch => handle_format_patch(args),
+        GitCommand::Am => handle_am(args),
+        GitCommand::Apply => handle_apply(args),
+        GitCommand::Clean => handle_clean(args),
+        GitCommand::Mv => handle_mv(args),
+        GitCommand::Restore => handle_restore(args),
+    }
+}
+
+/// Handles the 'git show' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_show(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = show::git_show(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git shortlog' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_shortlog(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = shortlog::git_shortlog(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git stats' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_stats(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = stats::git_stats(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git bisect' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_bisect(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = bisect::git_bisect(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git grep' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_grep(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = grep::git_grep(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git archive' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_archive(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = archive::git_archive(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git bundle' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_bundle(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = bundle::git_bundle(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git format-patch' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_format_patch(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) =
+        format_patch::git_format_patch(&git_dir, &working_dir, args, &mut io::stdout())
+    {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git am' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_am(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = am::git_am(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git apply' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_apply(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = apply::git_apply(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git clean' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_clean(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = clean::git_clean(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git mv' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_mv(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = mv::git_mv(&git_dir, &working_dir, args) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git restore' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_restore(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(working_dir) => working_dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = restore::git_restore(&git_dir, &working_dir, args) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git blame' and 'git annotate' commands.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+/// * `annotate` - If true, the output has the format of 'git annotate'.
+///
+fn handle_blame(args: Vec<String>, annotate: bool) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = blame::git_blame(&git_dir, args, annotate, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git diff' command.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments.
+///
+fn handle_diff(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+    if let Err(error) = diff::git_diff(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
     }
 }
 
@@ -389,9 +808,27 @@ fn handle_show_ref(args: Vec<String>) {
     }
 }
 
+/// Handles the 'git rebase' command.
+///
+/// * `git rebase [--onto <newbase>] <upstream>` rebases the current branch onto `<upstream>`,
+///   or onto `<newbase>` taking only the commits after `<upstream>`.
+/// * `git rebase -i [--autosquash] [--todo <file>] <upstream>` starts an interactive rebase. The todo list
+///   is opened in the editor, or read from `<file>` if `--todo` is given.
+/// * `git rebase --continue | --skip | --abort` continues, skips the current commit of, or aborts
+///   a rebase that stopped.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing command-line arguments.
+///
 fn handle_rebase(args: Vec<String>) {
+    let no_verify = args.iter().any(|arg| arg == "--no-verify");
+    let args: Vec<String> = args
+        .into_iter()
+        .filter(|arg| arg != "--no-verify")
+        .collect();
     if args.len() < 3 {
-        eprintln!("Usage: git rebase <branch>");
+        eprintln!("Usage: git rebase [--onto <newbase>] <upstream>");
         return;
     }
 
@@ -402,36 +839,106 @@ fn handle_rebase(args: Vec<String>) {
             return;
         }
     };
-
-    let our_branch = match get_branch_name(&git_dir) {
-        Ok(name) => name,
-        Err(_) => {
-            eprintln!("No se pudo obtener la rama actual");
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(error) => {
+            eprintln!("{}", error);
             return;
         }
     };
 
-    if args.len() == 3 {
-        // Check if the branch given exists
-        let branch_name = &args[2];
-        match branch::get_branch_commit_hash(branch_name, &git_dir) {
-            Ok(_) => {
-                // Do the rebase
-                match rebase::rebase(&our_branch, branch_name, &git_dir) {
-                    Ok(_) => {
-                        println!("Rebase successful");
-                    }
-                    Err(error) => {
-                        eprintln!("{}", error);
-                    }
-                }
+    let result = match args[2].as_str() {
+        "-i" | "--interactive" => {
+            handle_interactive_rebase(&args[2..], &git_dir, &working_dir, no_verify);
+            return;
+        }
+        "--continue" => sequencer::git_rebase_continue(&git_dir, &working_dir, &mut io::stdout()),
+        "--skip" => sequencer::git_rebase_skip(&git_dir, &working_dir, &mut io::stdout()),
+        "--abort" => {
+            if let Err(error) = sequencer::git_rebase_abort(&git_dir, &working_dir) {
+                eprintln!("{}", error);
             }
-            Err(_) => {
-                eprintln!("Branch {} does not exist", branch_name);
+            return;
+        }
+        "--onto" if args.len() == 5 => {
+            run_pre_rebase_hook(&git_dir, &args[4], no_verify).and_then(|_| {
+                sequencer::git_rebase(
+                    &git_dir,
+                    &working_dir,
+                    &args[4],
+                    Some(&args[3]),
+                    &mut io::stdout(),
+                )
+            })
+        }
+        upstream if args.len() == 3 && !upstream.starts_with('-') => {
+            run_pre_rebase_hook(&git_dir, upstream, no_verify).and_then(|_| {
+                sequencer::git_rebase(&git_dir, &working_dir, upstream, None, &mut io::stdout())
+            })
+        }
+        _ => {
+            eprintln!("Usage: git rebase [--onto <newbase>] <upstream>");
+            return;
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+    }
+}
+
+/// Runs the `pre-rebase` hook with the upstream of a rebase, unless `--no-verify` was given.
+/// The rebase doesn't start if the hook fails.
+fn run_pre_rebase_hook(git_dir: &str, upstream: &str, no_verify: bool) -> io::Result<()> {
+    match no_verify {
+        true => Ok(()),
+        false => hooks::run_hook(git_dir, "pre-rebase", &[upstream], None),
+    }
+}
+
+/// Handles 'git rebase -i'.
+///
+/// # Arguments
+///
+/// * `args` - The arguments after 'rebase'.
+/// * `git_dir` - The path to the git directory.
+/// * `working_dir` - The path to the working tree.
+/// * `no_verify` - If true, the `pre-rebase` hook is not run.
+///
+fn handle_interactive_rebase(args: &[String], git_dir: &str, working_dir: &str, no_verify: bool) {
+    let mut autosquash = false;
+    let mut todo_file: Option<&str> = None;
+    let mut upstream: Option<&str> = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--autosquash" => autosquash = true,
+            "--todo" => {
+                i += 1;
+                todo_file = args.get(i).map(|file| file.as_str());
             }
-        };
-    } else {
-        eprintln!("Usage: git rebase <branch>");
+            other => upstream = Some(other),
+        }
+        i += 1;
+    }
+    let upstream = match upstream {
+        Some(upstream) => upstream,
+        None => {
+            eprintln!("Usage: git rebase -i [--autosquash] [--todo <file>] <upstream>");
+            return;
+        }
+    };
+    let result = run_pre_rebase_hook(git_dir, upstream, no_verify).and_then(|_| {
+        sequencer::git_rebase_interactive(
+            git_dir,
+            working_dir,
+            upstream,
+            autosquash,
+            todo_file,
+            &mut io::stdout(),
+        )
+    });
+    if let Err(error) = result {
+        eprintln!("{}", error);
     }
 }
 
@@ -603,6 +1110,7 @@ fn load_index_and_commit_tree(git_dir: &str) -> io::Result<(Index, Tree)> {
 ///
 /// * `index` - A reference to the Git index.
 /// * `commit_tree` - A reference to the commit tree.
+/// * `git_dir` - The path to the git directory.
 ///
 /// # Returns
 ///
@@ -612,9 +1120,18 @@ fn load_index_and_commit_tree(git_dir: &str) -> io::Result<(Index, Tree)> {
 ///
 /// Returns an `io::Error` if there are issues while determining the changes to be committed.
 ///
-fn print_changes_to_be_committed(index: &Index, commit_tree: &Tree) -> io::Result<()> {
+fn print_changes_to_be_committed(
+    index: &Index,
+    commit_tree: &Tree,
+    git_dir: &str,
+) -> io::Result<()> {
     let mut changes_to_be_committed_output: Vec<u8> = vec![];
-    changes_to_be_committed(index, commit_tree, &mut changes_to_be_committed_output)?;
+    changes_to_be_committed(
+        index,
+        commit_tree,
+        git_dir,
+        &mut changes_to_be_committed_output,
+    )?;
 
     if !changes_to_be_committed_output.is_empty() {
         println!();
@@ -760,7 +1277,7 @@ pub fn handle_status() {
         eprintln!("Error al obtener el directorio de trabajo.");
     }
 
-    if let Err(err) = print_changes_to_be_committed(&index, &commit_tree) {
+    if let Err(err) = print_changes_to_be_committed(&index, &commit_tree, &git_dir) {
         eprintln!(
             "Error al imprimir los cambios preparados para commit: {:?}",
             err
@@ -886,10 +1403,10 @@ fn handle_commit(args: Vec<String>) {
                 return;
             }
         };
-    match new_commit(&git_dir, &args[3], &git_ignore_path) {
-        Ok(_) => {}
-        Err(_err) => {}
-    };
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = git_commit(&git_dir, &git_ignore_path, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
 }
 
 /// Get the working directory based on the Git directory.
@@ -945,6 +1462,7 @@ fn handle_checkout_option(
 ///
 /// This function retrieves the Git directory, working directory, and command-line arguments. It then
 /// interprets the provided options and calls corresponding functions to perform the checkout operation.
+/// With `[<rev>] -- <paths>`, only the given paths are checked out, from the revision or from the index.
 ///
 /// # Arguments
 ///
@@ -973,29 +1491,42 @@ pub fn handle_checkout(args: Vec<String>) {
         return;
     }
 
+    let previous_head = utils::resolve_revision("HEAD", &git_dir).ok();
+    if let Some(separator) = args.iter().position(|arg| arg == "--") {
+        let revision = args.get(2..separator).and_then(|revision| revision.first());
+        let paths = &args[separator + 1..];
+        match checkout_paths(&git_dir, &working_dir, revision.map(String::as_str), paths) {
+            Ok(()) => run_post_checkout_hook(&git_dir, previous_head.as_deref(), false),
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    }
+
     let option = &args[2];
     let git_dir1 = Path::new(&git_dir);
     let destination = &args;
 
-    if let Err(err) = handle_checkout_option(git_dir1, &working_dir, option, destination.to_vec()) {
-        match err.kind() {
+    match handle_checkout_option(git_dir1, &working_dir, option, destination.to_vec()) {
+        Ok(()) => run_post_checkout_hook(&git_dir, previous_head.as_deref(), true),
+        Err(err) => match err.kind() {
             std::io::ErrorKind::UnexpectedEof => {
                 eprintln!(" ");
             }
             _ => {
                 eprintln!("Error cambiar de rama : {:?}", err);
             }
-        }
+        },
     }
 }
 
 /// Handles the 'git log' command, displaying commit history for the repository.
 ///
 /// This function retrieves the current directory, finds the Git directory, and calls the 'git log'
-/// function to obtain an iterator over the commit logs. It then prints the logs using the
-/// 'print_logs' function.
+/// function with the arguments after 'log', which walks the history and prints the logs.
 ///
-fn handle_log() {
+/// With `--follow <path>`, only the commits that changed the file are shown, following it across renames.
+///
+fn handle_log(args: Vec<String>) {
     let mut current_dir = match std::env::current_dir() {
         Ok(dir) => dir,
         Err(err) => {
@@ -1010,22 +1541,18 @@ fn handle_log() {
             return;
         }
     };
-    println!("Current dir {}", current_dir.to_string_lossy());
-    println!("Git dir {}", git_dir);
-    let log_iter = match log::log(None, &git_dir, 10, 0, false) {
-        Ok(iter) => iter,
-        Err(_e) => {
-            eprintln!("Error en git log.");
-            return;
-        }
-    };
-    print_logs(log_iter);
+    let args = args.get(2..).unwrap_or_default();
+    if let Err(error) = log::git_log(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
 }
 
 /// Handles the 'clone' command for the custom Git implementation.
 ///
 /// This function takes a vector of command-line arguments (`_args`) and performs
 /// the necessary steps to clone a remote Git repository into the current directory.
+/// If the URL is a bundle file, the bundle is cloned into the directory given at index 3,
+/// or into the current directory if there is none.
 ///
 /// # Arguments
 ///
@@ -1041,6 +1568,16 @@ fn handle_clone(_args: Vec<String>) {
         }
     };
     let url_text = &_args[2];
+    if Path::new(url_text).is_file() {
+        let working_dir = match _args.get(3) {
+            Some(dir) => current_dir.join(dir),
+            None => current_dir,
+        };
+        if let Err(error) = bundle::clone_bundle(url_text, &working_dir.to_string_lossy()) {
+            eprintln!("{}", error);
+        }
+        return;
+    }
     //The remote repo url is the first part of the URL, up until the last '/'.
     let remote_repo_url = match url_text.rsplit_once('/') {
         Some((string, _)) => string,
@@ -1058,6 +1595,7 @@ fn handle_clone(_args: Vec<String>) {
 }
 
 /// Handles the 'fetch' command, which fetches changes from a remote repository.
+/// If the URL is a bundle file, the changes are fetched from the bundle.
 ///
 /// # Arguments
 ///
@@ -1093,6 +1631,14 @@ fn handle_fetch(_args: Vec<String>) {
         }
     };
 
+    if Path::new(url_text).is_file() {
+        match bundle::fetch_bundle(&git_dir, url_text) {
+            Ok(_) => println!("Fetch successful!"),
+            Err(err) => eprintln!("Error during fetch: {}", err),
+        }
+        return;
+    }
+
     let remote_repo_name = url_text.split('/').last().unwrap_or("");
     let result = git_fetch(Some(remote_repo_name), HOST, &working_dir);
 
@@ -1106,11 +1652,12 @@ fn handle_fetch(_args: Vec<String>) {
 ///
 /// This function retrieves the current directory, finds the Git directory, and calls the 'git merge'
 /// function to perform a merge operation. It requires the name of the branch to be merged as an argument.
+/// If more than one branch is given, an octopus merge is performed, creating a single commit with all of them as parents.
 ///
 /// # Arguments
 ///
-/// * `args` - A vector of strings containing command-line arguments, where the second element is
-///            expected to be the name of the branch to be merged.
+/// * `args` - A vector of strings containing command-line arguments, where the elements after the command are
+///            expected to be the names of the branches to be merged.
 ///
 fn handle_merge(args: Vec<String>) {
     let git_dir = match obtain_git_dir() {
@@ -1137,6 +1684,21 @@ fn handle_merge(args: Vec<String>) {
         }
     };
 
+    if args.len() < 3 {
+        eprintln!("Usage: git merge <branch>...");
+        return;
+    }
+
+    if args.len() > 3 {
+        match git_octopus_merge(&branch_name, &args[2..], &git_dir, &working_dir) {
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error en git merge.\n{}", e);
+            }
+        };
+        return;
+    }
+
     match git_merge(&branch_name, &args[2], &git_dir, &working_dir) {
         Ok(_) => {}
         Err(_e) => {
@@ -1227,8 +1789,9 @@ fn handle_pull() {
 ///
 /// This function retrieves the current directory, finds the Git directory, and gets the current branch name.
 /// It then calls the 'git push' function to push changes to the remote repository associated with the current branch.
+/// With `--no-verify`, the `pre-push` hook is not run.
 ///
-fn handle_push() {
+fn handle_push(args: Vec<String>) {
     let git_dir = match obtain_git_dir() {
         Ok(dir) => dir,
         Err(err) => {
@@ -1243,7 +1806,8 @@ fn handle_push() {
             return;
         }
     };
-    match push::git_push(&branch_name, &git_dir) {
+    let no_verify = args.iter().skip(2).any(|arg| arg == "--no-verify");
+    match push::git_push(&branch_name, &git_dir, no_verify) {
         Ok(_) => {
             println!("Push ok")
         }
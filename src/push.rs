@@ -39,16 +39,19 @@ pub fn log_push(branch: &str, git_dir: &str) -> io::Result<()> {
 /// create a Git client. It then calls the `receive_pack` method of the client to push the branch
 /// to the remote repository.
 ///
+/// Unless `no_verify` is true, the `pre-push` hook runs before anything is sent and can abort the push.
+///
 /// # Arguments
 ///
 /// * `branch` - The name of the branch to be pushed.
 /// * `git_dir` - The path to the Git directory.
+/// * `no_verify` - If true (`--no-verify`), the `pre-push` hook is not run.
 ///
 /// # Returns
 ///
 /// A Result indicating success or an io::Error if an issue occurs during the push operation.
 ///
-pub fn git_push(branch: &str, git_dir: &str) -> io::Result<()> {
+pub fn git_push(branch: &str, git_dir: &str, no_verify: bool) -> io::Result<()> {
     let config_file = config::Config::load(git_dir)?;
     let remote_name = "origin";
     let remote_url = config_file.get_url(remote_name, &mut io::stdout())?;
@@ -62,6 +65,9 @@ pub fn git_push(branch: &str, git_dir: &str) -> io::Result<()> {
         }
     };
     let mut client = Client::new(address, repo_name, "localhost");
+    if !no_verify {
+        client.set_pre_push_hook(remote_name, &remote_url);
+    }
     log_push(branch, git_dir)?;
     client.receive_pack(branch, git_dir)
 }
@@ -101,7 +107,7 @@ mod tests {
         //Commit the files
         let commit_message = "Test commit".to_string();
         let result_commit = commit::new_commit(&git_dir_path, &commit_message, "");
-        let result = super::git_push("branch", &git_dir_path);
+        let result = super::git_push("branch", &git_dir_path, false);
         assert!(result_commit.is_ok());
         assert!(result.is_ok());
     }
@@ -1,69 +1,20 @@
 use gtk::{
-    prelude::{BuilderExtManual, ComboBoxExtManual},
-    ButtonExt, ComboBoxExt, ComboBoxTextExt, TextBufferExt, TextViewExt, WidgetExt,
-};
-use std::{
-    cell::RefCell,
-    collections::HashMap,
-    fs::File,
-    io::{self, Write},
-    path::Path,
-    rc::Rc,
+    prelude::{BuilderExtManual, Cast, ComboBoxExtManual},
+    BinExt, ComboBoxTextExt, ContainerExt, LabelExt, ListBoxExt, ListBoxRowExt, TextBufferExt,
+    TextViewExt, WidgetExt,
 };
+use std::{fs, io, path::Path};
 
 use crate::{
-    branch::{self, get_current_branch_path},
-    checkout,
-    commit::{self, get_branch_name},
-    diff,
+    branch,
+    commit::get_branch_name,
     gui::style,
-    hash_object, merge, tree_handler,
+    hash_object,
+    index::Index,
+    sequencer::{self, Sequencer, SequencerStatus},
     utils::{self, obtain_git_dir},
 };
 
-pub enum RebaseState {
-    RebaseStepInProgress,
-    RebaseStepFinished,
-    RebaseFinished,
-}
-
-#[derive(Debug, Clone)]
-pub struct Rebase {
-    our_commits: Vec<String>,
-    active_commit: String,
-    commit_to_rebase: String,
-    original_our_branch_hash: String,
-    rebase_step: RebaseStep,
-}
-
-#[derive(Debug, Clone)]
-struct RebaseStep {
-    diffs: HashMap<String, String>,
-}
-
-// This function will return the intersection between the files that changed between commit1 and commit2 and the files that changed between commit1 and commit3
-fn intersection_files_that_changed_between_commits(
-    commit1: &str,
-    commit2: &str,
-    commit3: &str,
-    git_dir: &str,
-) -> io::Result<Vec<String>> {
-    let commit1_tree = tree_handler::load_tree_from_commit(commit1, git_dir)?;
-    let commit2_tree = tree_handler::load_tree_from_commit(commit2, git_dir)?;
-    let commit3_tree = tree_handler::load_tree_from_commit(commit3, git_dir)?;
-    let changed_files1 = tree_handler::get_files_with_changes(&commit2_tree, &commit1_tree);
-    let changed_files2 = tree_handler::get_files_with_changes(&commit3_tree, &commit1_tree);
-    let mut intersection: Vec<String> = Vec::new();
-    for (file1, _) in &changed_files1 {
-        for (file2, _) in &changed_files2 {
-            if file1 == file2 {
-                intersection.push(file1.clone());
-            }
-        }
-    }
-    Ok(intersection)
-}
-
 /// Obtains a ComboBoxText object from a GTK builder.
 ///
 /// # Arguments
@@ -84,62 +35,6 @@ fn obtain_combo_box_from_builder(builder: &gtk::Builder) -> io::Result<gtk::Comb
     Ok(combo_box)
 }
 
-/// Loads and returns the differences between files in two specified commits.
-///
-/// # Arguments
-///
-/// * `commit_to_rebase` - The commit hash to which the rebase is being performed.
-/// * `active_commit` - The commit hash representing the currently active commit.
-/// * `git_dir` - The path to the Git directory.
-///
-/// # Returns
-///
-/// Returns a Result containing a HashMap of file paths to their respective differences
-/// between the active commit and the commit to rebase. An empty HashMap is returned
-/// if there are no differences or an error occurred.
-///
-/// # Errors
-///
-/// Returns an error if there are issues loading the tree from the specified commits
-/// or if obtaining differences for a file fails.
-///
-fn load_file_diffs(
-    commit_to_rebase: &str,
-    active_commit: &str,
-    git_dir: &str,
-) -> io::Result<HashMap<String, String>> {
-    let tree_to_rebase = tree_handler::load_tree_from_commit(commit_to_rebase, git_dir)?;
-    let tree_active_commit = tree_handler::load_tree_from_commit(active_commit, git_dir)?;
-    let changed_files = tree_handler::get_files_with_changes(&tree_to_rebase, &tree_active_commit);
-    let mut diffs: HashMap<String, String> = HashMap::new();
-    for (file, _) in &changed_files {
-        let hash_active_commit = match tree_active_commit.get_hash_from_path(file) {
-            Some(hash) => hash,
-            None => {
-                eprintln!("Couldn't obtain hash for file {}", file);
-                continue;
-            }
-        };
-        let hash_to_rebase = match tree_to_rebase.get_hash_from_path(file) {
-            Some(hash) => hash,
-            None => {
-                eprintln!("Couldn't obtain hash for file {}", file);
-                continue;
-            }
-        };
-        let diff = diff::return_object_diff_string(&hash_active_commit, &hash_to_rebase, git_dir);
-        match diff {
-            Ok(diff) => {
-                diffs.insert(file.clone(), diff);
-            }
-            Err(_) => {
-                eprintln!("No se pudo obtener el diff para el archivo {}", file);
-            }
-        }
-    }
-    Ok(diffs)
-}
-
 /// Retrieves the root directory of a Git repository based on the specified Git directory.
 ///
 /// # Arguments
@@ -168,29 +63,6 @@ fn get_root_dir(git_dir: &str) -> io::Result<String> {
     Ok(root_dir)
 }
 
-// Write the given hash into the refs/heads/branch_name file pointed by the HEAD file
-fn write_hash_into_branch_file(hash: &str, git_dir: &str) -> io::Result<()> {
-    let branch_path = get_current_branch_path(git_dir)?;
-    let branch_path = format!("{}/{}", git_dir, branch_path);
-    let mut file = match File::create(branch_path) {
-        Ok(file) => file,
-        Err(_error) => {
-            eprintln!("Error creating file");
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo crear el archivo",
-            ));
-        }
-    };
-    match file.write_all(hash.as_bytes()) {
-        Ok(_) => {}
-        Err(_error) => {
-            println!("Error writing to file");
-        }
-    }
-    Ok(())
-}
-
 // Given a message, write it into the text view
 fn write_message_into_text_view(builder: &gtk::Builder, message: &str) -> io::Result<()> {
     let text_view = match style::get_text_view(builder, "rebase-view") {
@@ -267,180 +139,121 @@ fn get_text_view_content(builder: &gtk::Builder) -> io::Result<String> {
     Ok(text)
 }
 
-/// Handles the click event for the "Abort Rebase" button in the GTK application.
-///
-/// This function performs the necessary actions to abort the ongoing rebase process.
+/// Enables the widgets used to resolve a rebase that stopped and disables the button that starts
+/// a new one, or the other way around.
 ///
 /// # Arguments
 ///
 /// * `builder` - A gtk::Builder object containing the graphical interface.
-/// * `original_our_branch_hash` - The original hash of the commit in our branch before rebase.
+/// * `in_progress` - True if there is a rebase in progress.
 ///
-/// # Returns
+fn set_rebase_widgets_sensitive(builder: &gtk::Builder, in_progress: bool) -> io::Result<()> {
+    let combo_box = obtain_combo_box_from_builder(builder)?;
+    combo_box.set_sensitive(in_progress);
+    for button_id in [
+        "rebase-ok-all-button",
+        "abort-rebase-button",
+        "rebase-button",
+    ] {
+        style::get_button(builder, button_id).set_sensitive(in_progress);
+    }
+    style::get_button(builder, "make-rebase-button").set_sensitive(!in_progress);
+    Ok(())
+}
+
+/// Writes the state of the rebase in progress into the GTK graphical interface.
+///
+/// If the rebase stopped because of conflicts, the conflicted files are loaded into the ComboBox so they
+/// can be edited in the TextView. If it stopped for another reason, the message is shown and the user
+/// can continue or abort it. If there is no rebase in progress, the message is shown and a new rebase
+/// can be started.
+///
+/// # Arguments
 ///
-/// Returns a Result with a unit value if the operation is successful.
-/// An error is returned if there are issues writing to files or performing Git operations.
+/// * `builder` - A gtk::Builder object containing the graphical interface.
+/// * `git_dir` - The path to the Git directory.
+/// * `message` - The output of the last rebase operation.
 ///
 /// # Errors
 ///
-/// Returns an error if there are issues writing the original commit hash into the branch file,
-/// checking out to the original branch, or writing a message to the TextView.
+/// Returns an error if the widgets can't be found or the state of the rebase can't be loaded.
 ///
-fn abort_rebase_button_on_click(
+pub fn write_rebase_state_into_gui(
     builder: &gtk::Builder,
-    original_our_branch_hash: String,
+    git_dir: &str,
+    message: &str,
 ) -> io::Result<()> {
-    let git_dir = obtain_git_dir()?;
-    let branch_name = get_branch_name(&git_dir)?;
-    let root_dir = get_root_dir(&git_dir)?;
-    match write_hash_into_branch_file(&original_our_branch_hash, &git_dir) {
-        Ok(_) => {}
-        Err(_e) => {
-            println!("Error writing to branch file");
-        }
-    }
-    let git_dir_path = Path::new(&git_dir);
-    match checkout::checkout_branch(git_dir_path, &root_dir, &branch_name) {
-        Ok(_) => {
-            println!("Checkout to branch {} completed", branch_name);
-        }
-        Err(_e) => {
-            println!("Error checking out to branch {}", branch_name);
-        }
-    }
-
-    write_message_into_text_view(builder, "Rebase abortado")?;
-
-    let rebase_button = style::get_button(builder, "make-rebase-button");
-    rebase_button.set_sensitive(true);
-    let ok_button = style::get_button(builder, "rebase-ok-all-button");
-    ok_button.set_sensitive(false);
-    let abort_button = style::get_button(builder, "abort-rebase-button");
-    abort_button.set_sensitive(false);
     let combo_box = obtain_combo_box_from_builder(builder)?;
-    combo_box.set_sensitive(false);
-    let update_button = style::get_button(builder, "rebase-button");
-    update_button.set_sensitive(false);
-    Ok(())
-}
+    combo_box.remove_all();
+    if !sequencer::is_in_progress(git_dir) {
+        set_rebase_widgets_sensitive(builder, false)?;
+        return write_message_into_text_view(builder, message);
+    }
 
-// Recieves a builder and a diff and writes the combo box and text view with the diff
-fn write_combo_box_and_view(
-    builder: &gtk::Builder,
-    diff: HashMap<String, String>,
-) -> io::Result<()> {
-    let changed_files = diff.keys().cloned().collect::<Vec<String>>();
-    let combo_box = obtain_combo_box_from_builder(builder)?;
-    if changed_files.is_empty() {
-        write_message_into_text_view(
+    set_rebase_widgets_sensitive(builder, true)?;
+    let root_dir = get_root_dir(git_dir)?;
+    let conflicts = Sequencer::load(git_dir, &root_dir)?.conflicted_files();
+    if conflicts.is_empty() {
+        return write_message_into_text_view(
             builder,
-            "No hay problemas con los archivos\n Presione Ok para continuar al siguiente commit",
-        )?;
-        return Ok(());
+            &format!("{}\nPresione Ok para continuar el rebase", message),
+        );
     }
-    for file in changed_files {
+    for file in conflicts {
         combo_box.append_text(&file);
     }
-
     combo_box.set_active(Some(0));
-    let file = match combo_box.get_active_text() {
-        Some(file) => file.to_string(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el archivo seleccionado",
-            ));
-        }
-    };
+    show_conflicted_file_gui(builder)
+}
 
-    let file_text = match diff.get(&file) {
-        Some(diff) => diff.clone(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el diff para el archivo",
-            ));
-        }
-    };
-    write_message_into_text_view(builder, &file_text)?;
-    Ok(())
+/// Writes the output and the result of a rebase operation into the GTK graphical interface.
+fn write_rebase_result_into_gui(
+    builder: &gtk::Builder,
+    git_dir: &str,
+    output: Vec<u8>,
+    result: io::Result<SequencerStatus>,
+) -> io::Result<()> {
+    let mut message = String::from_utf8_lossy(&output).to_string();
+    if let Err(error) = result {
+        message.push_str(&error.to_string());
+    }
+    write_rebase_state_into_gui(builder, git_dir, &message)
 }
 
-/// Handles the change event for the ComboBox in the GTK application.
-///
-/// This function retrieves the selected file from the ComboBox and displays its diff
-/// in the associated TextView within the graphical interface.
+/// Shows, in the TextView, the content of the conflicted file selected in the ComboBox.
 ///
 /// # Arguments
 ///
 /// * `builder` - A gtk::Builder object containing the graphical interface.
-/// * `diff` - A HashMap containing file paths as keys and their corresponding diffs as values.
-///
-/// # Returns
-///
-/// Returns a Result with a unit value if the operation is successful.
-/// An error is returned if the selected file or its corresponding diff cannot be obtained.
-///
-/// # Errors
 ///
-/// Returns an error if the selected file or its corresponding diff cannot be obtained.
-///
-fn combo_box_on_change(builder: &gtk::Builder, diff: HashMap<String, String>) -> io::Result<()> {
+pub fn show_conflicted_file_gui(builder: &gtk::Builder) -> io::Result<()> {
     let combo_box = obtain_combo_box_from_builder(builder)?;
     let file = match combo_box.get_active_text() {
         Some(file) => file.to_string(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el archivo seleccionado",
-            ));
-        }
-    };
-
-    let file_text = match diff.get(&file) {
-        Some(diff) => diff.clone(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el diff para el archivo",
-            ));
-        }
+        None => return Ok(()),
     };
-    write_message_into_text_view(builder, &file_text)?;
-    Ok(())
+    let git_dir = obtain_git_dir()?;
+    let root_dir = get_root_dir(&git_dir)?;
+    let content = fs::read_to_string(Path::new(&root_dir).join(&file)).unwrap_or_default();
+    write_message_into_text_view(builder, &content)
 }
 
 /// Handles the click event for the "Update" button in the GTK application.
 ///
-/// This function retrieves the content of the TextView and the selected file from the ComboBox,
-/// then updates the associated diff in the Rebase structure.
+/// The content of the TextView is written into the conflicted file selected in the ComboBox and
+/// the file is added to the index, marking it as resolved.
 ///
 /// # Arguments
 ///
 /// * `builder` - A gtk::Builder object containing the graphical interface.
-/// * `rebase` - A shared reference to a Rebase structure stored in a RefCell and wrapped in Rc.
-///
-/// # Returns
-///
-/// Returns a Result with a unit value if the operation is successful.
-/// An error is returned if the text content from the TextView or the selected file cannot be obtained.
 ///
 /// # Errors
 ///
-/// Returns an error if the text content from the TextView or the selected file cannot be obtained.
+/// Returns an error if the selected file can't be obtained or it can't be written or added to the index.
 ///
-fn update_button_on_click(builder: &gtk::Builder, rebase: Rc<RefCell<Rebase>>) -> io::Result<()> {
-    let text = match get_text_view_content(builder) {
-        Ok(text) => text,
-        Err(e) => {
-            eprintln!("Error getting text from TextView: {}", e);
-            return Err(e);
-        }
-    };
-    let file = match obtain_combo_box_from_builder(builder)
-        .unwrap()
-        .get_active_text()
-    {
+pub fn resolve_conflicted_file_gui(builder: &gtk::Builder) -> io::Result<()> {
+    let combo_box = obtain_combo_box_from_builder(builder)?;
+    let file = match combo_box.get_active_text() {
         Some(file) => file.to_string(),
         None => {
             return Err(io::Error::new(
@@ -449,456 +262,222 @@ fn update_button_on_click(builder: &gtk::Builder, rebase: Rc<RefCell<Rebase>>) -
             ));
         }
     };
+    let text = get_text_view_content(builder)?;
+    let git_dir = obtain_git_dir()?;
+    let root_dir = get_root_dir(&git_dir)?;
+    fs::write(Path::new(&root_dir).join(&file), &text)?;
 
-    {
-        let mut rebase_step = rebase.borrow_mut();
-        rebase_step.rebase_step.diffs.insert(file, text);
-    }
-    Ok(())
+    let hash = hash_object::store_string_to_file(&text, &git_dir, "blob")?;
+    let index_path = utils::get_index_file_path(&git_dir);
+    let mut index = Index::load(&index_path, &git_dir, "")?;
+    index.add_file(&file, &hash)?;
+    index.write_file()
 }
 
-/// Loads and writes the file differences (diffs) into the GTK application's ComboBox and TextView.
-///
-/// This function retrieves the file differences (diffs) between the commit to rebase and the active commit
-/// from the specified Git directory. It then updates the ComboBox with the file paths and displays
-/// the content of the selected file in the TextView within the graphical interface.
+/// Starts a rebase of the current branch onto `upstream` and shows its result in the GTK application.
 ///
 /// # Arguments
 ///
 /// * `builder` - A gtk::Builder object containing the graphical interface.
-/// * `rebase` - A shared reference to a Rebase structure stored in a RefCell and wrapped in Rc.
-/// * `git_dir` - A string representing the path to the Git directory.
-///
-/// # Returns
-///
-/// Returns a Result with a unit value if the operation is successful.
-/// An error is returned if the file differences cannot be loaded or if updating the ComboBox and TextView fails.
-///
-/// # Errors
-///
-/// Returns an error if the file differences cannot be loaded or if updating the ComboBox and TextView fails.
+/// * `git_dir` - The path to the Git directory.
+/// * `upstream` - The branch the current branch is rebased onto.
 ///
-fn load_and_write_diffs(
-    builder: &gtk::Builder,
-    rebase: Rc<RefCell<Rebase>>,
-    git_dir: &str,
-) -> io::Result<()> {
-    let commit_to_rebase = &rebase.borrow().commit_to_rebase.clone();
-    let active_commit = &rebase.borrow().active_commit.clone();
-    let diffs = load_file_diffs(commit_to_rebase, active_commit, git_dir)?;
-    write_combo_box_and_view(builder, diffs.clone())?;
-    let mut rebase_step = rebase.borrow_mut();
-    rebase_step.rebase_step.diffs = diffs;
-    Ok(())
+pub fn start_rebase_gui(builder: &gtk::Builder, git_dir: &str, upstream: &str) -> io::Result<()> {
+    let root_dir = get_root_dir(git_dir)?;
+    let mut output: Vec<u8> = Vec::new();
+    let result = sequencer::git_rebase(git_dir, &root_dir, upstream, None, &mut output);
+    write_rebase_result_into_gui(builder, git_dir, output, result)
 }
 
-/// Sets up the event handlers for the buttons in the GTK application.
-///
-/// This function initializes and connects event handlers for the "Update," "Abort Rebase," and "OK All" buttons
-/// in the GTK interface. It enables these buttons and associates their respective actions with the corresponding functions.
+/// Handles the click event for the "Ok" button in the GTK application: the rebase in progress
+/// is continued, committing the resolved files.
 ///
 /// # Arguments
 ///
 /// * `builder` - A gtk::Builder object containing the graphical interface.
-/// * `rebase` - A shared reference to a Rebase structure stored in a RefCell and wrapped in Rc.
-/// * `git_dir` - A string representing the path to the Git directory.
-///
-fn setup_buttons(builder: &gtk::Builder, rebase: Rc<RefCell<Rebase>>, git_dir: &str) {
-    let update_button = style::get_button(builder, "rebase-button");
-    update_button.set_sensitive(true);
-    let rebase_step_clone = Rc::clone(&rebase);
-    let builder_clone = builder.clone();
-    update_button.connect_clicked(move |_| {
-        let result = update_button_on_click(&builder_clone, Rc::clone(&rebase_step_clone));
-        eprintln!("{:#?}", result);
-    });
-
-    let abort_button = style::get_button(builder, "abort-rebase-button");
-    abort_button.set_sensitive(true);
-    let rebase_step_clone = Rc::clone(&rebase);
-    let builder_clone = builder.clone();
-    abort_button.connect_clicked(move |_| {
-        let rebase_step = rebase_step_clone.borrow();
-        let original_our_branch_hash = rebase_step.original_our_branch_hash.clone();
-        abort_rebase_button_on_click(&builder_clone, original_our_branch_hash).unwrap();
-    });
-
-    let ok_button = style::get_button(builder, "rebase-ok-all-button");
-    ok_button.set_sensitive(true);
-    let builder_clone = builder.clone();
-    let git_dir_clone = git_dir.to_string().clone();
-    let rebase_rf = Rc::clone(&rebase);
-    ok_button.connect_clicked(move |_| {
-        let rebase_clone = rebase_rf.borrow().clone();
-        let _ = next_rebase_iteration(&builder_clone, rebase_clone, &git_dir_clone);
-    });
+///
+pub fn continue_rebase_gui(builder: &gtk::Builder) -> io::Result<()> {
+    let git_dir = obtain_git_dir()?;
+    let root_dir = get_root_dir(&git_dir)?;
+    let mut output: Vec<u8> = Vec::new();
+    let result = sequencer::git_rebase_continue(&git_dir, &root_dir, &mut output);
+    write_rebase_result_into_gui(builder, &git_dir, output, result)
 }
 
-/// Writes the current state of the rebase step into the GTK graphical interface.
-///
-/// This function updates the GTK interface to reflect the current state of the rebase step.
-/// It disables the "Make Rebase" button, sets the combo box to be sensitive, removes all items from the combo box,
-/// clears the text view, loads and writes the file differences, and sets up the event handlers for buttons.
+/// Handles the click event for the "Abort Rebase" button in the GTK application: the branch,
+/// index and working tree go back to where they were before the rebase.
 ///
 /// # Arguments
 ///
 /// * `builder` - A gtk::Builder object containing the graphical interface.
-/// * `rebase` - A shared reference to a Rebase structure stored in a RefCell and wrapped in Rc.
-/// * `git_dir` - A string representing the path to the Git directory.
 ///
-pub fn write_rebase_step_into_gui(
-    builder: &gtk::Builder,
-    rebase: Rc<RefCell<Rebase>>,
-    git_dir: &str,
-) -> io::Result<()> {
-    let rebase_button = style::get_button(builder, "make-rebase-button");
-    rebase_button.set_sensitive(false);
-
-    let combo_box = obtain_combo_box_from_builder(builder)?;
-    combo_box.set_sensitive(true);
-    combo_box.remove_all();
-    write_message_into_text_view(builder, "")?;
-    let rebase_clone = Rc::clone(&rebase);
-    load_and_write_diffs(builder, rebase_clone, git_dir)?;
-    let rebase_step_clone = Rc::clone(&rebase);
-    let builder_clone = builder.clone();
-    combo_box.connect_changed(move |_| {
-        let diff = rebase_step_clone.borrow().rebase_step.diffs.clone();
-        let result = combo_box_on_change(&builder_clone, diff);
-        match result {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Error writing into TextView: {}", e);
-            }
-        }
-    });
-
-    setup_buttons(builder, Rc::clone(&rebase), git_dir);
-    Ok(())
+pub fn abort_rebase_gui(builder: &gtk::Builder) -> io::Result<()> {
+    let git_dir = obtain_git_dir()?;
+    let root_dir = get_root_dir(&git_dir)?;
+    sequencer::git_rebase_abort(&git_dir, &root_dir)?;
+    write_rebase_state_into_gui(builder, &git_dir, "Rebase abortado")
 }
 
-/// Creates a new commit during the rebase process and returns its hash.
-///
-/// This function takes a Rebase structure and the path to the Git directory as input.
-/// It loads the tree of the commit to be rebased, applies the changes specified in the rebase step,
-/// creates a new commit with the updated tree, and returns the hash of the new commit.
+/// Obtains the ListBox that holds the todo list of the interactive rebase from a GTK builder.
 ///
 /// # Arguments
 ///
-/// * `rebase` - A reference to the Rebase structure representing the current state of the rebase.
-/// * `git_dir` - A string representing the path to the Git directory.
-///
-/// # Returns
-///
-/// A Result containing the hash of the newly created commit or an IO error.
+/// * `builder` - A gtk::Builder object containing the graphical interface.
 ///
-fn create_rebase_commit(rebase: &Rebase, git_dir: &str) -> io::Result<String> {
-    let commit_to_rebase = &rebase.commit_to_rebase.clone();
-    let tree_to_rebase = tree_handler::load_tree_from_commit(commit_to_rebase, git_dir)?;
-    let mut tree_with_changes = tree_to_rebase.clone();
-    let rebase_step = &rebase.rebase_step;
-    for (file, diff) in &rebase_step.diffs {
-        let hash = hash_object::store_string_to_file(diff, git_dir, "blob")?;
-        tree_with_changes.update_tree(file, &hash)
+fn obtain_todo_list_from_builder(builder: &gtk::Builder) -> io::Result<gtk::ListBox> {
+    match builder.get_object::<gtk::ListBox>("rebase-todo-list") {
+        Some(list) => Ok(list),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No se pudo encontrar el ListBox con ID rebase-todo-list",
+        )),
     }
-
-    let active_commit = &rebase.active_commit.clone();
-    let commit_message = format!("Rebasing with commit {}", &active_commit[0..7]);
-    let new_commit_hash: String = commit::new_rebase_commit(
-        git_dir,
-        &commit_message,
-        &rebase.commit_to_rebase,
-        &tree_with_changes,
-    )?;
-
-    Ok(new_commit_hash)
 }
 
-/// Finalizes the rebase process, updating the branch and displaying a completion message.
-///
-/// This function is responsible for completing the rebase process. It checks out the branch
-/// that was rebased, sets the completion message in the TextView, and adjusts the sensitivity
-/// of relevant UI elements in the GTK builder. Finally, it returns an error indicating that
-/// there are no more commits for rebase, as this function is typically called after the last rebase step.
-///
-/// # Arguments
-///
-/// * `builder` - A reference to the GTK builder containing the UI elements.
-/// * `git_dir` - A string representing the path to the Git directory.
-///
-/// # Returns
-///
-/// An IO Result indicating the completion of the rebase with an error indicating
-/// that there are no more commits for rebase.
-///
-fn finalize_rebase(builder: &gtk::Builder, git_dir: &str) -> io::Result<()> {
-    let text_view = match style::get_text_view(builder, "rebase-view") {
-        Some(text_view) => text_view,
-        None => {
-            println!("No se pudo obtener el TextView");
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo obtener el TextView",
-            ));
-        }
-    };
-    match text_view.get_buffer() {
-        Some(buffer) => {
-            let branch_name = get_branch_name(git_dir)?;
-            let root_dir = match Path::new(&git_dir).parent() {
-                Some(dir) => dir.to_string_lossy().to_string(),
-                None => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::NotFound,
-                        "No se pudo encontrar el working dir",
-                    ));
-                }
-            };
-            let git_dir_path = Path::new(&git_dir);
-            checkout::checkout_branch(git_dir_path, &root_dir, &branch_name)?;
-            buffer.set_text("Rebase finalizado");
-        }
-        None => {
-            println!("No se pudo obtener el buffer del TextView");
-        }
-    };
-    let combo_box = obtain_combo_box_from_builder(builder)?;
-    let update_button = style::get_button(builder, "rebase-button");
-    let ok_button = style::get_button(builder, "rebase-ok-all-button");
-    let rebase_button = style::get_button(builder, "make-rebase-button");
-    combo_box.set_sensitive(false);
-    update_button.set_sensitive(false);
-    ok_button.set_sensitive(false);
-    rebase_button.set_sensitive(true);
-
-    Err(io::Error::new(
-        io::ErrorKind::Other,
-        "No hay más commits para rebase",
-    ))
+/// Returns the text of a row of the todo list.
+fn get_todo_row_text(row: &gtk::Widget) -> Option<String> {
+    let row = row.clone().downcast::<gtk::ListBoxRow>().ok()?;
+    let label = row.get_child()?.downcast::<gtk::Label>().ok()?;
+    Some(label.get_text().to_string())
 }
 
-/// Initiates the next iteration of the rebase process.
-///
-/// This function performs the next iteration of the rebase process, creating a new commit
-/// with the changes from the current rebase step. If there are more commits to rebase,
-/// it updates the rebase information and writes the new rebase step into the GUI. Otherwise,
-/// it finalizes the rebase by checking out the rebased branch and displaying a completion message.
+/// Fills the todo list of the GUI with a `pick` for every commit of the current branch that is not in `upstream`.
+/// The rows can then be reordered and their commands changed before starting the interactive rebase.
 ///
 /// # Arguments
 ///
-/// * `builder` - A reference to the GTK builder containing the UI elements.
-/// * `rebase` - The current state of the rebase process.
-/// * `git_dir` - A string representing the path to the Git directory.
+/// * `builder` - A gtk::Builder object containing the graphical interface.
+/// * `git_dir` - The path to the Git directory.
+/// * `upstream` - The branch (or revision) the current branch will be rebased onto.
 ///
-/// # Returns
+/// # Errors
 ///
-/// An IO Result indicating the success of the next rebase iteration.
+/// Returns an error if the upstream can't be resolved or the list can't be found.
 ///
-pub fn next_rebase_iteration(
+pub fn load_todo_list_into_gui(
     builder: &gtk::Builder,
-    rebase: Rebase,
     git_dir: &str,
+    upstream: &str,
 ) -> io::Result<()> {
-    let new_commit_hash = create_rebase_commit(&rebase, git_dir)?;
-    let mut new_rebase = rebase;
-
-    match new_rebase.our_commits.pop() {
-        Some(commit) => {
-            new_rebase.active_commit = commit;
-            new_rebase.commit_to_rebase = new_commit_hash;
-            new_rebase.rebase_step.diffs = HashMap::new();
-            write_rebase_step_into_gui(builder, Rc::new(RefCell::new(new_rebase)), git_dir)?;
-        }
-        None => {
-            finalize_rebase(builder, git_dir)?;
-        }
-    };
+    let list = obtain_todo_list_from_builder(builder)?;
+    for child in list.get_children() {
+        list.remove(&child);
+    }
+    let branch_name = get_branch_name(git_dir)?;
+    let head = branch::get_branch_commit_hash(&branch_name, git_dir)?;
+    let onto = utils::resolve_revision(upstream, git_dir)?;
+    let todo = sequencer::build_todo_list(&head, &onto, git_dir)?;
+    if todo.is_empty() {
+        write_message_into_text_view(builder, "No hay commits con los que hacer rebase")?;
+    }
+    for item in todo {
+        let label = gtk::Label::new(Some(&item.to_string()));
+        label.set_halign(gtk::Align::Start);
+        list.insert(&label, -1);
+    }
+    list.show_all();
     Ok(())
 }
 
-/// Performs a fast-forward rebase by updating the rebase commit with changes from the new commit.
-///
-/// This function updates the tree of the rebase commit with changes from the new commit.
-/// It calculates the differences between the new commit and the common ancestor commit.
-/// The resulting tree is used to create a new rebase commit, effectively fast-forwarding
-/// the rebase process.
+/// Moves the selected row of the todo list `offset` positions (negative values move it up).
 ///
 /// # Arguments
 ///
-/// * `our_commit` - The hash of the commit in the current branch before rebase.
-/// * `rebased_commit` - The hash of the commit in the rebased branch.
-/// * `common_ancestor` - The hash of the common ancestor commit of `our_commit` and `rebased_commit`.
-/// * `git_dir` - A string representing the path to the Git directory.
-///
-/// # Returns
-///
-/// A string representing the hash of the new rebase commit created through the fast-forward process.
+/// * `builder` - A gtk::Builder object containing the graphical interface.
+/// * `offset` - How many positions the row is moved.
 ///
-fn fast_forward_rebase_commit(
-    our_commit: &str,
-    rebased_commit: &str,
-    common_ancestor: &str,
-    git_dir: &str,
-) -> io::Result<String> {
-    let rebased_tree = tree_handler::load_tree_from_commit(rebased_commit, git_dir)?;
-    let our_tree = tree_handler::load_tree_from_commit(our_commit, git_dir)?;
-    let common_ancestor_tree = tree_handler::load_tree_from_commit(common_ancestor, git_dir)?;
-
-    let mut new_tree = rebased_tree;
-
-    let files_changed_this_commit =
-        tree_handler::get_files_with_changes(&common_ancestor_tree, &our_tree);
-
-    for (path, hash) in files_changed_this_commit {
-        new_tree.update_tree(&path, &hash);
+pub fn move_selected_todo_row(builder: &gtk::Builder, offset: i32) -> io::Result<()> {
+    let list = obtain_todo_list_from_builder(builder)?;
+    let row = match list.get_selected_row() {
+        Some(row) => row,
+        None => return Ok(()),
+    };
+    let new_index = row.get_index() + offset;
+    if new_index < 0 || new_index >= list.get_children().len() as i32 {
+        return Ok(());
     }
-
-    let commit_message = format!("Rebasing with commit {}", &our_commit[0..7]);
-    let new_commit_hash: String =
-        commit::new_rebase_commit(git_dir, &commit_message, rebased_commit, &new_tree)?;
-
-    Ok(new_commit_hash)
+    list.remove(&row);
+    list.insert(&row, new_index);
+    list.select_row(Some(&row));
+    Ok(())
 }
 
-// Do a fast forward merge, this means that we simply put our branch commits on top of theirs.
-// For every commit in our branch since the common ancestor, we create a new commit with the updates
-// and point our branch to the last commit
-fn fast_forward_rebase(
-    our_branch_hash: &str,
-    their_branch_hash: &str,
-    git_dir: &str,
-) -> io::Result<()> {
-    let common_ancestor = merge::find_common_ancestor(our_branch_hash, their_branch_hash, git_dir)?;
-    let mut our_branch_commits =
-        utils::get_branch_commit_history_until(our_branch_hash, git_dir, &common_ancestor)?;
-
-    let mut our_new_branch_hash: String = their_branch_hash.to_string();
-
-    while let Some(commit) = our_branch_commits.pop() {
-        our_new_branch_hash =
-            fast_forward_rebase_commit(&commit, &our_new_branch_hash, &common_ancestor, git_dir)?;
-    }
-
-    let branch_name = get_branch_name(git_dir)?;
-    let root_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el working dir",
-            ));
-        }
+/// Changes the command of the selected row of the todo list to the one chosen in the actions combo box.
+///
+/// # Arguments
+///
+/// * `builder` - A gtk::Builder object containing the graphical interface.
+///
+pub fn set_action_of_selected_todo_row(builder: &gtk::Builder) -> io::Result<()> {
+    let list = obtain_todo_list_from_builder(builder)?;
+    let combo_box = style::get_combo_box(builder, "rebase-action-combo")?;
+    let action = match combo_box.get_active_text() {
+        Some(action) => action.to_string(),
+        None => return Ok(()),
     };
-    write_hash_into_branch_file(&our_new_branch_hash, git_dir)?;
-    let git_dir_path = Path::new(&git_dir);
-    checkout::checkout_branch(git_dir_path, &root_dir, &branch_name)?;
-
+    let row = match list.get_selected_row() {
+        Some(row) => row,
+        None => return Ok(()),
+    };
+    if let Some(child) = row.get_child() {
+        if let Ok(label) = child.downcast::<gtk::Label>() {
+            let text = label.get_text().to_string();
+            let (_, rest) = text.split_once(' ').unwrap_or(("", &text));
+            label.set_text(&format!("{} {}", action, rest));
+        }
+    }
     Ok(())
 }
 
-/// Initializes and starts the interactive rebase process in a graphical user interface (GUI).
-///
-/// This function prepares the necessary data for rebase, including identifying the common ancestor
-/// of the source and target branches, determining the commits to rebase, and checking for conflicts.
-/// It then creates and returns a `Rebase` struct wrapped in a `RefCell` and `Rc` for GUI interaction.
+/// Runs the interactive rebase described by the todo list of the GUI.
+/// It uses the same engine as `git rebase -i`, so a rebase that stops can be continued from the command line.
+/// The output of the rebase is shown in the rebase TextView.
 ///
 /// # Arguments
 ///
-/// * `git_dir` - A string representing the path to the Git directory.
-/// * `our_branch` - The name of the branch from which the rebase originates.
-/// * `branch_to_rebase` - The name of the branch to rebase onto the `our_branch`.
-///
-/// # Returns
-///
-/// An `io::Result` containing a reference-counted, mutable `Rebase` wrapped in a `RefCell`.
+/// * `builder` - A gtk::Builder object containing the graphical interface.
+/// * `git_dir` - The path to the Git directory.
+/// * `upstream` - The branch (or revision) the current branch is rebased onto.
 ///
 /// # Errors
 ///
-/// Returns an error if there are no commits available for rebase or if a fast-forward rebase is performed.
+/// Returns an error if the todo list is invalid or the rebase can't be started.
 ///
-pub fn start_rebase_gui(
+pub fn start_interactive_rebase_gui(
+    builder: &gtk::Builder,
     git_dir: &str,
-    our_branch: &str,
-    branch_to_rebase: &str,
-) -> io::Result<Rc<RefCell<Rebase>>> {
-    let our_branch_hash = branch::get_branch_commit_hash(our_branch, git_dir)?;
-    let their_branch_hash = branch::get_branch_commit_hash(branch_to_rebase, git_dir)?;
-    let common_ancestor =
-        merge::find_common_ancestor(&our_branch_hash, &their_branch_hash, git_dir)?;
-
-    let mut our_commits =
-        utils::get_branch_commit_history_until(&our_branch_hash, git_dir, &common_ancestor)?;
-    let active_commit = match our_commits.pop() {
-        Some(commit) => commit,
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "No hay commits con los que hacer rebase",
-            ));
-        }
-    };
-
-    // If there are no conflicts, we will do a fast forward rebase
-    let conflicting_files = intersection_files_that_changed_between_commits(
-        &common_ancestor,
-        &our_branch_hash,
-        &their_branch_hash,
-        git_dir,
-    )?;
-
-    if conflicting_files.is_empty() {
-        fast_forward_rebase(&our_branch_hash, &their_branch_hash, git_dir)?;
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "No hay conflictos, se hizo un fast forward rebase",
-        ));
+    upstream: &str,
+) -> io::Result<()> {
+    let list = obtain_todo_list_from_builder(builder)?;
+    let rows: Vec<String> = list
+        .get_children()
+        .iter()
+        .filter_map(get_todo_row_text)
+        .collect();
+    let todo = sequencer::parse_todo(&rows.join("\n"), git_dir)?;
+    if todo.is_empty() {
+        write_message_into_text_view(builder, "Nothing to do")?;
+        return Ok(());
     }
 
-    let rebase = Rebase {
-        our_commits,
-        active_commit,
-        commit_to_rebase: their_branch_hash.clone(),
-        original_our_branch_hash: our_branch_hash,
-        rebase_step: RebaseStep {
-            diffs: HashMap::new(),
-        },
-    };
-    Ok(Rc::new(RefCell::new(rebase)))
-}
-
-// A function to do a rebase without gui
-pub fn rebase(our_branch: &str, their_branch: &str, git_dir: &str) -> io::Result<()> {
-    // We will will only do a fast forward rebase. If the rebase is not fast forward, we will tell the user to go to the gui
-    let our_branch_hash = branch::get_branch_commit_hash(our_branch, git_dir)?;
-    let their_branch_hash = branch::get_branch_commit_hash(their_branch, git_dir)?;
-    let common_ancestor =
-        merge::find_common_ancestor(&our_branch_hash, &their_branch_hash, git_dir)?;
-
-    let conflicting_files = intersection_files_that_changed_between_commits(
-        &common_ancestor,
-        &our_branch_hash,
-        &their_branch_hash,
-        git_dir,
-    )?;
-
-    if conflicting_files.is_empty() {
-        println!("El rebase es fast forward");
-        fast_forward_rebase(&our_branch_hash, &their_branch_hash, git_dir)?;
-        Ok(())
-    } else {
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "El rebase no es fast forward, por favor use la interfaz gráfica",
-        ))
+    let root_dir = get_root_dir(git_dir)?;
+    let branch_name = get_branch_name(git_dir)?;
+    let onto = utils::resolve_revision(upstream, git_dir)?;
+    let mut sequencer = Sequencer::start(git_dir, &root_dir, &branch_name, &onto, todo)?;
+    let mut output: Vec<u8> = Vec::new();
+    let result = sequencer.run(&mut output);
+    for child in list.get_children() {
+        list.remove(&child);
     }
+    write_rebase_result_into_gui(builder, git_dir, output, result)
 }
 
 // Tests
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::add;
-    use std::fs;
+    use crate::{add, commit, tree_handler};
+    use std::io::Write;
 
     const NAME_OF_GIT_DIRECTORY_1: &str = "tests/rebase_tests/test1/.mgit";
 
@@ -969,6 +548,12 @@ mod tests {
         let head_file_path = format!("{}/HEAD", git_dir);
         let mut head_file = fs::File::create(&head_file_path).unwrap();
         head_file.write_all(b"ref: refs/heads/master").unwrap();
+        let master_tree = tree_handler::load_tree_from_commit(&commit_1_hash, git_dir).unwrap();
+        master_tree
+            .build_index_file_from_tree(&index_file_path, git_dir, "")
+            .unwrap()
+            .write_file()
+            .unwrap();
 
         let file_path = "src/pizza.c";
         let add_path = format!("{}/{}", test_dir, file_path);
@@ -985,9 +570,8 @@ mod tests {
             .write_all(format!("ref: refs/heads/test").as_bytes())
             .unwrap();
 
-        let our_branch = "test";
         let their_branch = "master";
-        let result = rebase(our_branch, their_branch, git_dir);
+        let result = sequencer::git_rebase(git_dir, "", their_branch, None, &mut io::sink());
         assert!(result.is_ok());
 
         // Check if the amount of commits is correct
@@ -0,0 +1,430 @@
+use crate::{
+    attributes::Attributes,
+    diff::{self, FileChange},
+};
+use std::{collections::HashMap, io};
+
+/// Minimum similarity, as a percentage, for two files to be considered a rename when it's not specified.
+pub const DEFAULT_SIMILARITY: usize = 50;
+/// Beyond this many deleted or added files only exact renames are detected,
+/// because comparing the content of every pair would take too long.
+const RENAME_LIMIT: usize = 1000;
+const EMPTY_BLOB_HASH: &str = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+
+/// The options of the rename and copy detection (`-M<n>%` and `-C<n>%`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenameOptions {
+    /// Minimum similarity, as a percentage, between the old and the new file.
+    pub threshold: usize,
+    /// If true, added files can also be detected as copies of modified or deleted files.
+    pub copies: bool,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_SIMILARITY,
+            copies: false,
+        }
+    }
+}
+
+impl RenameOptions {
+    /// Builds the options from the value of `-M`/`-C`, for example `90%`, `9` (same as 90%) or an empty string.
+    /// Returns `None` if the value is not a valid score.
+    pub fn with_score(score: &str, copies: bool) -> Option<RenameOptions> {
+        let threshold = if score.is_empty() {
+            DEFAULT_SIMILARITY
+        } else {
+            parse_score(score)?
+        };
+        Some(RenameOptions { threshold, copies })
+    }
+}
+
+/// Parses a similarity score the way git does.
+/// A number followed by '%' is a percentage. Otherwise the digits are the decimals of a fraction,
+/// so `5` is 50% and `75` is 75%.
+pub fn parse_score(score: &str) -> Option<usize> {
+    let (digits, percentage) = match score.strip_suffix('%') {
+        Some(digits) => (digits, true),
+        None => (score.trim_start_matches("0."), false),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if percentage {
+        return digits.parse::<usize>().ok().map(|value| value.min(100));
+    }
+    let padded = format!("{:0<2}", digits);
+    padded[..2].parse().ok()
+}
+
+/// Returns how similar two contents are, as a percentage.
+/// It's the amount of bytes of the lines both contents have in common, over the size of the biggest one.
+pub fn similarity_index(old: &str, new: &str) -> usize {
+    let biggest = old.len().max(new.len());
+    if biggest == 0 {
+        return 0;
+    }
+    let mut old_lines: HashMap<&str, usize> = HashMap::new();
+    for line in old.split_inclusive('\n') {
+        *old_lines.entry(line).or_default() += 1;
+    }
+    let mut common = 0;
+    for line in new.split_inclusive('\n') {
+        if let Some(count) = old_lines.get_mut(line) {
+            if *count > 0 {
+                *count -= 1;
+                common += line.len();
+            }
+        }
+    }
+    common * 100 / biggest
+}
+
+/// Pairs every destination with the first source that has the same hash.
+/// Returns tuples (source, destination) with indexes into the given lists.
+/// A source is used at most once unless `reuse_sources` is true.
+fn exact_pairs(
+    sources: &[Option<&String>],
+    destinations: &[Option<&String>],
+    reuse_sources: bool,
+) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut used = vec![false; sources.len()];
+    for (destination, destination_hash) in destinations.iter().enumerate() {
+        if destination_hash.is_none()
+            || destination_hash.map(|h| h.as_str()) == Some(EMPTY_BLOB_HASH)
+        {
+            continue;
+        }
+        let found = (0..sources.len()).find(|source| {
+            (reuse_sources || !used[*source]) && sources[*source] == *destination_hash
+        });
+        if let Some(source) = found {
+            used[source] = true;
+            pairs.push((source, destination));
+        }
+    }
+    pairs
+}
+
+/// Pairs the sources and destinations whose contents are at least `threshold` percent similar,
+/// starting with the most similar pairs.
+/// Returns tuples (source, destination, similarity) with indexes into the given lists.
+/// A source is used at most once unless `reuse_sources` is true.
+fn similar_pairs(
+    sources: &[Option<String>],
+    destinations: &[Option<String>],
+    threshold: usize,
+    reuse_sources: bool,
+) -> Vec<(usize, usize, usize)> {
+    if sources.len() > RENAME_LIMIT || destinations.len() > RENAME_LIMIT {
+        return Vec::new();
+    }
+    let mut candidates = Vec::new();
+    for (destination, destination_content) in destinations.iter().enumerate() {
+        for (source, source_content) in sources.iter().enumerate() {
+            if let (Some(old), Some(new)) = (source_content, destination_content) {
+                let score = similarity_index(old, new);
+                if score >= threshold && score > 0 {
+                    candidates.push((source, destination, score));
+                }
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.2.cmp(&a.2).then(a.1.cmp(&b.1)).then(a.0.cmp(&b.0)));
+
+    let mut pairs = Vec::new();
+    let mut used_sources = vec![false; sources.len()];
+    let mut used_destinations = vec![false; destinations.len()];
+    for (source, destination, score) in candidates {
+        if used_destinations[destination] || (used_sources[source] && !reuse_sources) {
+            continue;
+        }
+        used_sources[source] = true;
+        used_destinations[destination] = true;
+        pairs.push((source, destination, score));
+    }
+    pairs
+}
+
+/// Replaces the pairs of deleted and added files that are similar enough with a single rename ('R').
+/// If `options.copies` is set, the remaining added files that are similar enough to a modified or
+/// deleted file are marked as copies ('C').
+///
+/// Files are paired by hash first, and the rest by content. Binary files and files whose content
+/// can't be read are only paired by hash.
+///
+/// # Arguments
+///
+/// * `changes` - The changed files, as returned by `diff::compare_files`.
+/// * `git_dir` - The path to the git directory.
+/// * `working_dir` - If given, the new side of the files is read from this working tree.
+/// * `options` - The threshold and whether copies are detected.
+///
+/// # Returns
+///
+/// The changes with the renames and copies, sorted by path.
+pub fn detect_renames(
+    changes: Vec<FileChange>,
+    git_dir: &str,
+    working_dir: Option<&str>,
+    options: RenameOptions,
+) -> Vec<FileChange> {
+    let (deleted, others): (Vec<FileChange>, Vec<FileChange>) =
+        changes.into_iter().partition(|change| change.status == 'D');
+    let (mut added, mut result): (Vec<FileChange>, Vec<FileChange>) =
+        others.into_iter().partition(|change| change.status == 'A');
+    let attributes = Attributes::load(git_dir);
+    let text = |path: &str, content: io::Result<Vec<u8>>| match content {
+        Ok(content) if !attributes.is_binary_file(path, &content) => {
+            Some(String::from_utf8_lossy(&content).to_string())
+        }
+        _ => None,
+    };
+    let old_content = |change: &FileChange| {
+        let content = diff::read_side(&change.old_hash, &change.path, git_dir, None, &attributes);
+        text(&change.path, content)
+    };
+    let new_content = |change: &FileChange| {
+        let content = diff::read_side(
+            &change.new_hash,
+            &change.path,
+            git_dir,
+            working_dir,
+            &attributes,
+        );
+        text(&change.path, content)
+    };
+
+    let mut renamed = vec![false; deleted.len()];
+    let old_hashes: Vec<Option<&String>> = deleted.iter().map(|c| c.old_hash.as_ref()).collect();
+    let new_hashes: Vec<Option<&String>> = added.iter().map(|c| c.new_hash.as_ref()).collect();
+    let mut pairs: Vec<(usize, usize, usize)> = exact_pairs(&old_hashes, &new_hashes, false)
+        .into_iter()
+        .map(|(source, destination)| (source, destination, 100))
+        .collect();
+    let sources: Vec<usize> = (0..deleted.len())
+        .filter(|i| !pairs.iter().any(|pair| pair.0 == *i))
+        .collect();
+    let destinations: Vec<usize> = (0..added.len())
+        .filter(|i| !pairs.iter().any(|pair| pair.1 == *i))
+        .collect();
+    if !sources.is_empty() && !destinations.is_empty() {
+        let source_contents: Vec<Option<String>> =
+            sources.iter().map(|i| old_content(&deleted[*i])).collect();
+        let destination_contents: Vec<Option<String>> = destinations
+            .iter()
+            .map(|i| new_content(&added[*i]))
+            .collect();
+        for (source, destination, score) in similar_pairs(
+            &source_contents,
+            &destination_contents,
+            options.threshold,
+            false,
+        ) {
+            pairs.push((sources[source], destinations[destination], score));
+        }
+    }
+    for (source, destination, score) in pairs.iter() {
+        renamed[*source] = true;
+        added[*destination].set_source(&deleted[*source], 'R', *score);
+    }
+
+    if options.copies {
+        let sources: Vec<&FileChange> = deleted
+            .iter()
+            .chain(result.iter().filter(|change| change.status == 'M'))
+            .collect();
+        let destinations: Vec<usize> = (0..added.len())
+            .filter(|i| added[*i].status == 'A')
+            .collect();
+        let old_hashes: Vec<Option<&String>> =
+            sources.iter().map(|c| c.old_hash.as_ref()).collect();
+        let new_hashes: Vec<Option<&String>> = destinations
+            .iter()
+            .map(|i| added[*i].new_hash.as_ref())
+            .collect();
+        let mut copies: Vec<(usize, usize, usize)> = exact_pairs(&old_hashes, &new_hashes, true)
+            .into_iter()
+            .map(|(source, destination)| (source, destinations[destination], 100))
+            .collect();
+        let uncopied: Vec<usize> = destinations
+            .into_iter()
+            .filter(|i| !copies.iter().any(|copy| copy.1 == *i))
+            .collect();
+        if !uncopied.is_empty() {
+            let source_contents: Vec<Option<String>> =
+                sources.iter().map(|change| old_content(change)).collect();
+            let destination_contents: Vec<Option<String>> =
+                uncopied.iter().map(|i| new_content(&added[*i])).collect();
+            for (source, destination, score) in similar_pairs(
+                &source_contents,
+                &destination_contents,
+                options.threshold,
+                true,
+            ) {
+                copies.push((source, uncopied[destination], score));
+            }
+        }
+        let copies: Vec<(FileChange, usize, usize)> = copies
+            .into_iter()
+            .map(|(source, destination, score)| (sources[source].clone(), destination, score))
+            .collect();
+        for (source, destination, score) in copies {
+            added[destination].set_source(&source, 'C', score);
+        }
+    }
+
+    result.extend(
+        deleted
+            .into_iter()
+            .zip(renamed)
+            .filter(|(_, renamed)| !renamed)
+            .map(|(change, _)| change),
+    );
+    result.extend(added);
+    result.sort_by(|a, b| a.path.cmp(&b.path));
+    result
+}
+
+/// Finds the files of `old_files` that were renamed in `new_files`.
+/// Only the paths that are missing in the other side are considered.
+///
+/// # Returns
+///
+/// A map from the old path to the new one.
+pub fn find_renamed_paths(
+    old_files: &[(String, String)],
+    new_files: &[(String, String)],
+    git_dir: &str,
+    options: RenameOptions,
+) -> HashMap<String, String> {
+    let changes = diff::compare_files(old_files, new_files, &[]);
+    detect_renames(changes, git_dir, None, options)
+        .into_iter()
+        .filter(|change| change.status == 'R')
+        .filter_map(|change| change.old_path.map(|old_path| (old_path, change.path)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_object;
+    use std::{fs, io};
+
+    fn change(path: &str, status: char, old: Option<&str>, new: Option<&str>) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            status,
+            old_hash: old.map(|hash| hash.to_string()),
+            new_hash: new.map(|hash| hash.to_string()),
+            old_path: None,
+            similarity: None,
+        }
+    }
+
+    #[test]
+    fn test_scores_are_parsed_like_git() {
+        assert_eq!(parse_score("90%"), Some(90));
+        assert_eq!(parse_score("9"), Some(90));
+        assert_eq!(parse_score("75"), Some(75));
+        assert_eq!(parse_score("05"), Some(5));
+        assert_eq!(parse_score("150%"), Some(100));
+        assert_eq!(parse_score("abc"), None);
+        assert_eq!(
+            RenameOptions::with_score("", false).map(|o| o.threshold),
+            Some(50)
+        );
+    }
+
+    #[test]
+    fn test_similarity_counts_the_common_lines() {
+        assert_eq!(similarity_index("a\nb\nc\nd\n", "a\nb\nc\nd\n"), 100);
+        assert_eq!(similarity_index("a\nb\nc\nd\n", "a\nb\nx\ny\n"), 50);
+        assert_eq!(similarity_index("a\n", ""), 0);
+    }
+
+    #[test]
+    fn test_renames_are_detected_by_hash_and_by_content() -> io::Result<()> {
+        let git_dir = "tests/rename/repo";
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        let content = "uno\ndos\ntres\ncuatro\ncinco\nseis\nsiete\nocho\nnueve\ndiez\n";
+        let original = hash_object::store_string_to_file(content, git_dir, "blob")?;
+        let edited =
+            hash_object::store_string_to_file(&content.replace("cinco", "5"), git_dir, "blob")?;
+        let other = hash_object::store_string_to_file("nada que ver\n", git_dir, "blob")?;
+
+        let changes = vec![
+            change("a.txt", 'D', Some(&original), None),
+            change("b.txt", 'A', None, Some(&original)),
+            change("c.txt", 'D', Some(&original), None),
+            change("d.txt", 'A', None, Some(&edited)),
+            change("e.txt", 'A', None, Some(&other)),
+        ];
+        let result = detect_renames(changes, git_dir, None, RenameOptions::default());
+        fs::remove_dir_all("tests/rename")?;
+
+        let summary: Vec<(String, char, Option<String>, Option<usize>)> = result
+            .into_iter()
+            .map(|c| (c.path, c.status, c.old_path, c.similarity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "b.txt".to_string(),
+                    'R',
+                    Some("a.txt".to_string()),
+                    Some(100)
+                ),
+                (
+                    "d.txt".to_string(),
+                    'R',
+                    Some("c.txt".to_string()),
+                    Some(88)
+                ),
+                ("e.txt".to_string(), 'A', None, None),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_threshold_and_copies() -> io::Result<()> {
+        let git_dir = "tests/rename_copies/repo";
+        fs::create_dir_all(format!("{}/objects", git_dir))?;
+        let content = "uno\ndos\ntres\ncuatro\n";
+        let original = hash_object::store_string_to_file(content, git_dir, "blob")?;
+        let modified =
+            hash_object::store_string_to_file("uno\ndos\ntres\ncuatro\ncinco\n", git_dir, "blob")?;
+        let half = hash_object::store_string_to_file("uno\ndos\n3\n4\n", git_dir, "blob")?;
+
+        let changes = vec![
+            change("a.txt", 'M', Some(&original), Some(&modified)),
+            change("b.txt", 'A', None, Some(&original)),
+            change("c.txt", 'D', Some(&original), None),
+            change("d.txt", 'A', None, Some(&half)),
+            change("e.txt", 'A', None, Some(&original)),
+        ];
+        let strict = RenameOptions::with_score("90%", true).unwrap_or_default();
+        let result = detect_renames(changes, git_dir, None, strict);
+        fs::remove_dir_all("tests/rename_copies")?;
+
+        let summary: Vec<(&str, char)> =
+            result.iter().map(|c| (c.path.as_str(), c.status)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a.txt", 'M'),
+                ("b.txt", 'R'),
+                ("d.txt", 'A'),
+                ("e.txt", 'C')
+            ]
+        );
+        Ok(())
+    }
+}
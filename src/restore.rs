@@ -0,0 +1,463 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    apply, attributes::Attributes, cat_file, configuration::LOGGER_COMMANDS_FILE,
+    ignorer::is_subpath, index::Index, log::Log, logger::Logger, utils,
+};
+
+/// Mode of the tree entries that are trees, as `cat_tree` returns it.
+const TREE_MODE: &str = "40000";
+
+/// Logs the 'git restore' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_restore(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git restore': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git restore` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RestoreOptions {
+    /// If true (`--staged`), the index entries of the paths are restored.
+    pub staged: bool,
+    /// If true (`--worktree`), the files of the working tree are restored. It's the default without `--staged`.
+    pub worktree: bool,
+    /// The revision the paths are restored from (`--source`). Without it, the working tree is restored
+    /// from the index and the index from HEAD.
+    pub source: Option<String>,
+    /// If true (`--overlay`), the files the source doesn't have are kept instead of removed, as
+    /// `git checkout <revision> -- <paths>` does.
+    pub overlay: bool,
+    /// The paths to restore.
+    pub paths: Vec<String>,
+}
+
+impl RestoreOptions {
+    /// Parses the arguments of `git restore` (the ones after 'restore').
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown, a value is missing or there are no paths.
+    ///
+    pub fn parse(args: &[String]) -> io::Result<RestoreOptions> {
+        let invalid_option = |arg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: invalid option: {}", arg),
+            )
+        };
+        let mut options = RestoreOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => options.paths.extend(args.by_ref().cloned()),
+                "-S" | "--staged" => options.staged = true,
+                "-W" | "--worktree" => options.worktree = true,
+                "--overlay" => options.overlay = true,
+                "--no-overlay" => options.overlay = false,
+                "-s" | "--source" => {
+                    options.source = Some(args.next().ok_or_else(|| invalid_option(arg))?.clone())
+                }
+                _ if arg.starts_with("--source=") => {
+                    options.source = Some(arg.trim_start_matches("--source=").to_string())
+                }
+                _ if arg.starts_with('-') => return Err(invalid_option(arg)),
+                _ => options.paths.push(arg.clone()),
+            }
+        }
+        if options.paths.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fatal: you must specify path(s) to restore",
+            ));
+        }
+        if !options.staged {
+            options.worktree = true;
+        }
+        Ok(options)
+    }
+}
+
+/// Adds the files of a tree, walking its subtrees, to the hashes and the modes of the files.
+fn collect_files(
+    tree_hash: &str,
+    path: &str,
+    git_dir: &str,
+    files: &mut HashMap<String, String>,
+    modes: &mut HashMap<String, String>,
+) -> io::Result<()> {
+    for (mode, name, hash) in cat_file::cat_tree(tree_hash, git_dir)? {
+        let file = match path.is_empty() {
+            true => name,
+            false => format!("{}/{}", path, name),
+        };
+        if mode.trim() == TREE_MODE {
+            collect_files(&hash, &file, git_dir, files, modes)?;
+        } else {
+            modes.insert(file.clone(), mode.trim().to_string());
+            files.insert(file, hash);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the files (path, hash) of the tree of a revision, and the mode of each of them.
+fn revision_files(
+    revision: &str,
+    git_dir: &str,
+) -> io::Result<(HashMap<String, String>, HashMap<String, String>)> {
+    let commit = utils::resolve_revision(revision, git_dir)?;
+    let log = Log::load(Some(&commit), git_dir)?;
+    let mut files = HashMap::new();
+    let mut modes = HashMap::new();
+    collect_files(log.tree_hash(), "", git_dir, &mut files, &mut modes)?;
+    Ok((files, modes))
+}
+
+/// Returns true if a file is inside one of the paths.
+fn path_is_selected(file: &str, paths: &[String]) -> bool {
+    paths.iter().any(|path| {
+        let path = path.trim_end_matches('/');
+        path == "." || path.is_empty() || is_subpath(file, path)
+    })
+}
+
+/// Restores some paths of the index and the working tree, leaving every other path as it is.
+///
+/// The index entries are set to the ones of the source, and the files of the working tree are
+/// written with their content in the source, converted by the smudge of their attributes and with
+/// the executable mode of their entry in a source revision. The files of the paths the source doesn't have are
+/// removed, unless `overlay` is set. By default the source is HEAD for the index and the index for
+/// the working tree.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `options` - What is restored, from where, and which paths.
+///
+/// # Errors
+///
+/// Returns an error if the source can't be resolved, a path doesn't match any known file, or a file
+/// can't be written.
+///
+pub fn restore(git_dir: &str, root_dir: &str, options: &RestoreOptions) -> io::Result<()> {
+    let index_path = utils::get_index_file_path(git_dir);
+    let mut index = Index::load(&index_path, git_dir, "")?;
+    let index_files: HashMap<String, String> = index
+        .iter()
+        .map(|(path, hash)| (path.clone(), hash.clone()))
+        .collect();
+    let (source, modes) = match (&options.source, options.staged) {
+        (Some(revision), _) => revision_files(revision, git_dir)?,
+        (None, true) => revision_files("HEAD", git_dir).unwrap_or_default(),
+        (None, false) => (index_files.clone(), HashMap::new()),
+    };
+    let attributes = Attributes::load(git_dir);
+
+    for path in &options.paths {
+        let selected = [path.clone()];
+        if !source
+            .keys()
+            .chain(index_files.keys())
+            .any(|file| path_is_selected(file, &selected))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "error: pathspec '{}' did not match any file(s) known to git",
+                    path
+                ),
+            ));
+        }
+    }
+    let files: BTreeSet<&String> = source
+        .keys()
+        .chain(index_files.keys())
+        .filter(|file| path_is_selected(file, &options.paths))
+        .collect();
+
+    for file in files {
+        let hash = source.get(file);
+        if hash.is_none() && options.overlay {
+            continue;
+        }
+        if options.staged {
+            match hash {
+                Some(hash) => index.add_file(file, hash)?,
+                None => index.remove_file(file)?,
+            }
+        }
+        if options.worktree {
+            let file_path = Path::new(root_dir).join(file);
+            match hash {
+                Some(hash) => {
+                    if let Some(parent) = file_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    let content = cat_file::cat_file_return_bytes(hash, git_dir)?;
+                    fs::write(&file_path, attributes.smudge(file, content))?;
+                    if let Some(mode) = modes.get(file) {
+                        apply::set_file_mode(&file_path, mode)?;
+                    }
+                }
+                None if file_path.is_file() => fs::remove_file(file_path)?,
+                None => {}
+            }
+        }
+    }
+    if options.staged {
+        index.write_file()?;
+    }
+    Ok(())
+}
+
+/// Restores files of the working tree or the index, discarding their changes or unstaging them.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `args` - The arguments of the command, the ones after 'restore'.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or the paths can't be restored.
+///
+pub fn git_restore(git_dir: &str, root_dir: &str, args: &[String]) -> io::Result<()> {
+    log_restore(git_dir, args)?;
+    let options = RestoreOptions::parse(args)?;
+    restore(git_dir, root_dir, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        add, checkout, hash_object,
+        test_utils::{commit_file, create_repo},
+    };
+
+    fn run_restore(git_dir: &str, args: &[&str]) -> io::Result<()> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        git_restore(git_dir, "", &args)
+    }
+
+    /// Creates a repository where `a.txt` is committed, then `b.txt`, then a new version of
+    /// `a.txt`, and returns its git directory and the paths to both files.
+    fn create_history(root_dir: &str) -> io::Result<(String, String, String)> {
+        let git_dir = create_repo(root_dir)?;
+        let a = format!("{}/a.txt", root_dir);
+        let b = format!("{}/b.txt", root_dir);
+        commit_file(&git_dir, &a, "uno\n", "Primero")?;
+        commit_file(&git_dir, &b, "b\n", "Segundo")?;
+        commit_file(&git_dir, &a, "dos\n", "Tercero")?;
+        Ok((git_dir, a, b))
+    }
+
+    fn staged_hash(git_dir: &str, path: &str) -> io::Result<Option<String>> {
+        let index = Index::load(&format!("{}/index", git_dir), git_dir, "")?;
+        Ok(index.get_hash(path).cloned())
+    }
+
+    #[test]
+    fn test_restore_unknown_paths_is_an_error() -> io::Result<()> {
+        let root_dir = "tests/restore_unknown";
+        let (git_dir, _, _) = create_history(root_dir)?;
+
+        let missing = run_restore(&git_dir, &["nada.txt"]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_discards_the_changes_of_the_working_tree() -> io::Result<()> {
+        let root_dir = "tests/restore_worktree";
+        let (git_dir, a, b) = create_history(root_dir)?;
+        fs::write(&a, "cambio\n")?;
+        fs::write(&b, "cambio\n")?;
+
+        run_restore(&git_dir, &["--", &a])?;
+        let a_after_discard = fs::read_to_string(&a)?;
+        let b_after_discard = fs::read_to_string(&b)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(a_after_discard, "dos\n");
+        assert_eq!(b_after_discard, "cambio\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_staged_unstages_and_keeps_the_working_tree() -> io::Result<()> {
+        let root_dir = "tests/restore_staged";
+        let (git_dir, _, b) = create_history(root_dir)?;
+        fs::write(&b, "cambio\n")?;
+        add::add(&b, &format!("{}/index", git_dir), &git_dir, "", None)?;
+
+        run_restore(&git_dir, &["--staged", &b])?;
+        let b_after_unstage = fs::read_to_string(&b)?;
+        let b_staged = staged_hash(&git_dir, &b)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(b_after_unstage, "cambio\n");
+        assert_eq!(b_staged, Some(hash_object::hash_content(b"b\n", "blob")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_paths_takes_the_files_of_a_revision() -> io::Result<()> {
+        let root_dir = "tests/restore_checkout";
+        let (git_dir, a, b) = create_history(root_dir)?;
+
+        checkout::checkout_paths(&git_dir, "", Some("HEAD~2"), std::slice::from_ref(&a))?;
+        let a_after_checkout = fs::read_to_string(&a)?;
+        let a_staged = staged_hash(&git_dir, &a)?;
+        let b_still_there = Path::new(&b).is_file();
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(a_after_checkout, "uno\n");
+        assert_eq!(a_staged, Some(hash_object::hash_content(b"uno\n", "blob")));
+        assert!(b_still_there);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_source_removes_the_files_it_does_not_have() -> io::Result<()> {
+        let root_dir = "tests/restore_source";
+        let (git_dir, a, b) = create_history(root_dir)?;
+
+        run_restore(
+            &git_dir,
+            &["--source=HEAD~2", "--staged", "--worktree", "."],
+        )?;
+        let a_after_source = fs::read_to_string(&a)?;
+        let b_after_source = Path::new(&b).exists();
+        let b_staged = staged_hash(&git_dir, &b)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(a_after_source, "uno\n");
+        assert!(!b_after_source);
+        assert_eq!(b_staged, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_directory_keeps_the_files_the_revision_does_not_have() -> io::Result<()> {
+        let root_dir = "tests/restore_overlay_repo";
+        let git_dir = create_repo(root_dir)?;
+        fs::create_dir_all(format!("{}/src", root_dir))?;
+        let a = format!("{}/src/a.txt", root_dir);
+        let b = format!("{}/src/b.txt", root_dir);
+        commit_file(&git_dir, &a, "uno\n", "Primero")?;
+        commit_file(&git_dir, &b, "b\n", "Segundo")?;
+        commit_file(&git_dir, &a, "dos\n", "Tercero")?;
+
+        let src = format!("{}/src", root_dir);
+        checkout::checkout_paths(&git_dir, "", Some("HEAD~2"), std::slice::from_ref(&src))?;
+        let a_after_checkout = fs::read_to_string(&a)?;
+        let b_after_checkout = fs::read_to_string(&b)?;
+        let b_staged = staged_hash(&git_dir, &b)?;
+        run_restore(&git_dir, &["--source=HEAD~2", "--overlay", &src])?;
+        let b_after_overlay = Path::new(&b).exists();
+        run_restore(&git_dir, &["--source=HEAD~2", &src])?;
+        let b_after_restore = Path::new(&b).exists();
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(a_after_checkout, "uno\n");
+        assert_eq!(b_after_checkout, "b\n");
+        assert!(b_staged.is_some());
+        assert!(b_after_overlay);
+        assert!(!b_after_restore);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_converts_the_line_endings_of_the_attributes() -> io::Result<()> {
+        let root_dir = "tests/restore_smudge_repo";
+        let git_dir = create_repo(root_dir)?;
+        fs::write(format!("{}/.gitattributes", root_dir), "*.bat eol=crlf\n")?;
+        let script = format!("{}/run.bat", root_dir);
+        commit_file(&git_dir, &script, "echo uno\necho dos\n", "Primero")?;
+
+        fs::write(&script, "cambio\n")?;
+        run_restore(&git_dir, &[&script])?;
+        let from_index = fs::read_to_string(&script)?;
+        fs::write(&script, "cambio\n")?;
+        run_restore(&git_dir, &["--source=HEAD", &script])?;
+        let from_head = fs::read_to_string(&script)?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(from_index, "echo uno\r\necho dos\r\n");
+        assert_eq!(from_head, "echo uno\r\necho dos\r\n");
+        Ok(())
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_keeps_the_executable_mode_of_the_source() -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let root_dir = "tests/restore_mode_repo";
+        let git_dir = create_repo(root_dir)?;
+        // The trees of this repository are always written with regular files, so the tree of the
+        // commit is written by hand, with the script under `tests/restore_mode_repo`.
+        let tree = |mode: &str, name: &str, hash: &str| -> io::Result<String> {
+            let mut entry = format!("{} {}\0", mode, name).into_bytes();
+            entry.extend(
+                (0..hash.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hash[i..i + 2], 16).unwrap_or_default()),
+            );
+            hash_object::store_bytes_array_to_file(entry, &git_dir, "tree")
+        };
+        let blob = hash_object::store_string_to_file("echo hola\n", &git_dir, "blob")?;
+        let repo_tree = tree("100755", "run.sh", &blob)?;
+        let tests_tree = tree("40000", "restore_mode_repo", &repo_tree)?;
+        let root_tree = tree("40000", "tests", &tests_tree)?;
+        let commit = hash_object::store_string_to_file(
+            &format!(
+                "tree {}\nauthor Ada <ada@mail.com> 1700000000 +0000\ncommitter Ada <ada@mail.com> 1700000000 +0000\n\nEjecutable\0",
+                root_tree
+            ),
+            &git_dir,
+            "commit",
+        )?;
+        let script = format!("{}/run.sh", root_dir);
+
+        run_restore(
+            &git_dir,
+            &["--source", &commit, "--staged", "--worktree", &script],
+        )?;
+        let content = fs::read_to_string(&script)?;
+        let mode = fs::metadata(&script)?.permissions().mode();
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(content, "echo hola\n");
+        assert_eq!(mode & 0o111, 0o111);
+        Ok(())
+    }
+}
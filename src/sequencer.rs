@@ -0,0 +1,1142 @@
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    attributes::Attributes,
+    branch, commit,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff, hash_object,
+    logger::Logger,
+    merge,
+    tree_handler::{self, Tree},
+    utils::{self, get_current_time},
+};
+
+/// Name of the folder, inside the git directory, where the state of a rebase in progress is kept.
+pub const REBASE_MERGE_DIR: &str = "rebase-merge";
+const TODO_FILE: &str = "git-rebase-todo";
+const DONE_FILE: &str = "done";
+const HEAD_NAME_FILE: &str = "head-name";
+const ORIG_HEAD_FILE: &str = "orig-head";
+const ONTO_FILE: &str = "onto";
+const MESSAGE_FILE: &str = "message";
+const AMEND_FILE: &str = "amend";
+const STOPPED_SHA_FILE: &str = "stopped-sha";
+const CONFLICTS_FILE: &str = "conflicts";
+const NO_PARENT: &str = "0000000000000000000000000000000000000000";
+const TODO_HELP: &str = "
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# e, edit <commit> = use commit, but stop for amending
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like \"squash\", but discard this commit's log message
+# x, exec <command> = run command (the rest of the line) using shell
+# d, drop <commit> = remove commit
+#
+# These lines can be re-ordered; they are executed from top to bottom.
+#
+# If you remove a line here THAT COMMIT WILL BE LOST.
+#
+# However, if you remove everything, the rebase will be aborted.
+";
+
+/// Logs the 'git rebase' command with the specified Git directory, branch and new base.
+///
+/// This function logs the 'git rebase' command with the provided Git directory, branch and new base
+/// to a file named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `branch_name` - The name of the branch being rebased.
+/// * `onto` - The hash of the commit the branch is rebased onto.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_rebase(git_dir: &str, branch_name: &str, onto: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git rebase': Git Directory '{}', Branch '{}', Onto '{}', {}",
+        git_dir,
+        branch_name,
+        onto,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The commands that can be used in the todo list of an interactive rebase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TodoAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+    Exec,
+}
+
+impl TodoAction {
+    /// Parses a command of the todo list. Both the long and the one letter forms are accepted.
+    pub fn parse(word: &str) -> Option<TodoAction> {
+        match word {
+            "p" | "pick" => Some(TodoAction::Pick),
+            "r" | "reword" => Some(TodoAction::Reword),
+            "e" | "edit" => Some(TodoAction::Edit),
+            "s" | "squash" => Some(TodoAction::Squash),
+            "f" | "fixup" => Some(TodoAction::Fixup),
+            "d" | "drop" => Some(TodoAction::Drop),
+            "x" | "exec" => Some(TodoAction::Exec),
+            _ => None,
+        }
+    }
+
+    /// Returns the long name of the command, as it is written in the todo list.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoAction::Pick => "pick",
+            TodoAction::Reword => "reword",
+            TodoAction::Edit => "edit",
+            TodoAction::Squash => "squash",
+            TodoAction::Fixup => "fixup",
+            TodoAction::Drop => "drop",
+            TodoAction::Exec => "exec",
+        }
+    }
+}
+
+/// A line of the todo list of a rebase.
+/// For `exec` lines `commit` is empty and `message` holds the command to run,
+/// for the rest `message` is the subject of the commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoItem {
+    pub action: TodoAction,
+    pub commit: String,
+    pub message: String,
+}
+
+impl TodoItem {
+    pub fn new(action: TodoAction, commit: &str, message: &str) -> Self {
+        Self {
+            action,
+            commit: commit.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Parses a line of the todo list. Empty lines and comments (lines starting with '#') return `None`.
+    /// Abbreviated commit hashes are expanded to the complete hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is unknown or the commit can't be found.
+    pub fn parse_line(line: &str, git_dir: &str) -> io::Result<Option<TodoItem>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let action = match TodoAction::parse(word) {
+            Some(action) => action,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("error: invalid line in the todo list: {}", line),
+                ))
+            }
+        };
+        if action == TodoAction::Exec {
+            return Ok(Some(TodoItem::new(action, "", rest.trim())));
+        }
+        let (commit, message) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+        match utils::expand_object_hash(commit, git_dir) {
+            Some(commit) => Ok(Some(TodoItem::new(action, &commit, message))),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("error: could not parse '{}' in the todo list", commit),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TodoItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.action == TodoAction::Exec {
+            write!(f, "exec {}", self.message)
+        } else {
+            let short_hash = self.commit.get(..7).unwrap_or(&self.commit);
+            write!(
+                f,
+                "{} {} {}",
+                self.action.as_str(),
+                short_hash,
+                self.message
+            )
+        }
+    }
+}
+
+/// Parses a whole todo list, ignoring comments and empty lines.
+pub fn parse_todo(content: &str, git_dir: &str) -> io::Result<Vec<TodoItem>> {
+    let mut todo = Vec::new();
+    for line in content.lines() {
+        if let Some(item) = TodoItem::parse_line(line, git_dir)? {
+            todo.push(item);
+        }
+    }
+    Ok(todo)
+}
+
+/// Formats a todo list, one command per line.
+pub fn format_todo(todo: &[TodoItem]) -> String {
+    todo.iter().map(|item| format!("{}\n", item)).collect()
+}
+
+/// Builds the default todo list for rebasing `head` onto `upstream`: a `pick` for every commit
+/// reachable from `head` (following first parents) that is not reachable from `upstream`, oldest first.
+///
+/// # Arguments
+///
+/// * `head` - The hash of the last commit of the branch being rebased.
+/// * `upstream` - The hash of the commit that limits the commits to rebase.
+/// * `git_dir` - The path to the git directory.
+///
+pub fn build_todo_list(head: &str, upstream: &str, git_dir: &str) -> io::Result<Vec<TodoItem>> {
+    let common_ancestor = merge::find_common_ancestor(head, upstream, git_dir)?;
+    if common_ancestor == head {
+        return Ok(Vec::new());
+    }
+    let commits = utils::get_branch_commit_history_until(head, git_dir, &common_ancestor)?;
+    let mut todo = Vec::new();
+    for commit_hash in commits.iter().rev() {
+        let subject = commit::get_commit_message(commit_hash, git_dir)?;
+        todo.push(TodoItem::new(TodoAction::Pick, commit_hash, &subject));
+    }
+    Ok(todo)
+}
+
+/// Moves every commit whose subject starts with "fixup! " or "squash! " right after the commit
+/// it refers to, changing its command to `fixup` or `squash`.
+/// The commit is referred to by the beginning of its subject or by its (abbreviated) hash.
+/// Commits whose target can't be found are left where they are.
+pub fn autosquash(todo: Vec<TodoItem>) -> Vec<TodoItem> {
+    let mut result: Vec<TodoItem> = Vec::new();
+    let mut pending: Vec<TodoItem> = Vec::new();
+    for item in todo {
+        let (action, target) = match (
+            item.message.strip_prefix("fixup! "),
+            item.message.strip_prefix("squash! "),
+        ) {
+            (Some(target), _) => (TodoAction::Fixup, target),
+            (_, Some(target)) => (TodoAction::Squash, target),
+            _ => {
+                result.push(item);
+                continue;
+            }
+        };
+        let target = target
+            .trim_start_matches("fixup! ")
+            .trim_start_matches("squash! ");
+        let position = result.iter().position(|candidate| {
+            candidate.action != TodoAction::Exec
+                && (candidate.message.starts_with(target)
+                    || (target.len() >= 4 && candidate.commit.starts_with(target)))
+        });
+        match position {
+            Some(mut position) => {
+                while position + 1 < result.len()
+                    && matches!(
+                        result[position + 1].action,
+                        TodoAction::Fixup | TodoAction::Squash
+                    )
+                {
+                    position += 1;
+                }
+                let mut item = item.clone();
+                item.action = action;
+                result.insert(position + 1, item);
+            }
+            None => pending.push(item),
+        }
+    }
+    result.append(&mut pending);
+    result
+}
+
+/// Returns true if there is a rebase in progress in the given git directory.
+pub fn is_in_progress(git_dir: &str) -> bool {
+    Path::new(git_dir).join(REBASE_MERGE_DIR).exists()
+}
+
+/// Writes the todo list in `.git/rebase-merge/git-rebase-todo`, opens it in the editor and returns the edited list.
+/// The editor is taken from `GIT_SEQUENCE_EDITOR`, or the usual commit message editor if it's not set.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `todo` - The todo list proposed to the user.
+/// * `onto` - The hash of the commit the branch will be rebased onto.
+///
+/// # Errors
+///
+/// Returns an error if the editor fails or the edited list can't be parsed.
+pub fn edit_todo_list(git_dir: &str, todo: &[TodoItem], onto: &str) -> io::Result<Vec<TodoItem>> {
+    let state_dir = Path::new(git_dir).join(REBASE_MERGE_DIR);
+    let existed = state_dir.exists();
+    fs::create_dir_all(&state_dir)?;
+    let todo_path = state_dir.join(TODO_FILE);
+    let header = format!(
+        "\n# Rebase onto {} ({} commands)\n",
+        onto.get(..7).unwrap_or(onto),
+        todo.len()
+    );
+    fs::write(&todo_path, format_todo(todo) + &header + TODO_HELP)?;
+
+    let editor = match std::env::var("GIT_SEQUENCE_EDITOR") {
+        Ok(editor) if !editor.trim().is_empty() => editor,
+        _ => utils::get_editor(),
+    };
+    let result = utils::launch_editor(&editor, &todo_path.to_string_lossy())
+        .and_then(|_| fs::read_to_string(&todo_path))
+        .and_then(|content| parse_todo(&content, git_dir));
+    if !existed {
+        fs::remove_dir_all(&state_dir)?;
+    }
+    result
+}
+
+/// Result of running the steps of a rebase.
+#[derive(Debug, PartialEq)]
+pub enum SequencerStatus {
+    /// Every step was run and the rebase is over.
+    Finished,
+    /// The rebase stopped waiting for the user (an `edit`, a conflict or a failed `exec`).
+    Stopped,
+}
+
+/// The state of a rebase in progress. It is persisted in `.git/rebase-merge/`
+/// so the rebase can be continued by a later command.
+#[derive(Debug, Clone)]
+pub struct Sequencer {
+    git_dir: String,
+    root_dir: String,
+    pub head_name: String,
+    pub orig_head: String,
+    pub onto: String,
+    pub todo: Vec<TodoItem>,
+    pub done: Vec<TodoItem>,
+}
+
+impl Sequencer {
+    /// Starts a new rebase of `branch_name` onto `onto` that will run the given todo list.
+    /// The state is saved and the branch, index and working tree are moved to `onto`.
+    /// The steps are not run until `run` is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `git_dir` - The path to the git directory.
+    /// * `root_dir` - The path to the working tree.
+    /// * `branch_name` - The name of the branch being rebased. It must be the current branch.
+    /// * `onto` - The hash of the commit the branch will be rebased onto.
+    /// * `todo` - The steps to run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is another rebase in progress or the state can't be written.
+    pub fn start(
+        git_dir: &str,
+        root_dir: &str,
+        branch_name: &str,
+        onto: &str,
+        todo: Vec<TodoItem>,
+    ) -> io::Result<Sequencer> {
+        if is_in_progress(git_dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "There is already a rebase in progress. Use --continue or --abort.",
+            ));
+        }
+        let orig_head = branch::get_branch_commit_hash(branch_name, git_dir)?;
+        let sequencer = Sequencer {
+            git_dir: git_dir.to_string(),
+            root_dir: root_dir.to_string(),
+            head_name: branch_name.to_string(),
+            orig_head: orig_head.clone(),
+            onto: onto.to_string(),
+            todo,
+            done: Vec::new(),
+        };
+        fs::create_dir_all(sequencer.state_path(""))?;
+        sequencer.save()?;
+
+        let orig_tree = tree_handler::load_tree_from_commit(&orig_head, git_dir)?;
+        let onto_tree = tree_handler::load_tree_from_commit(onto, git_dir)?;
+        sequencer.update_working_tree(&orig_tree, &onto_tree)?;
+        branch::update_branch_commit_hash(branch_name, onto, git_dir)?;
+        log_rebase(git_dir, branch_name, onto)?;
+        Ok(sequencer)
+    }
+
+    /// Loads the state of the rebase in progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no rebase in progress or its state is corrupted.
+    pub fn load(git_dir: &str, root_dir: &str) -> io::Result<Sequencer> {
+        if !is_in_progress(git_dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No rebase in progress?",
+            ));
+        }
+        let mut sequencer = Sequencer {
+            git_dir: git_dir.to_string(),
+            root_dir: root_dir.to_string(),
+            head_name: String::new(),
+            orig_head: String::new(),
+            onto: String::new(),
+            todo: Vec::new(),
+            done: Vec::new(),
+        };
+        let head_name = fs::read_to_string(sequencer.state_path(HEAD_NAME_FILE))?;
+        sequencer.head_name = head_name
+            .trim()
+            .trim_start_matches("refs/heads/")
+            .to_string();
+        sequencer.orig_head = fs::read_to_string(sequencer.state_path(ORIG_HEAD_FILE))?
+            .trim()
+            .to_string();
+        sequencer.onto = fs::read_to_string(sequencer.state_path(ONTO_FILE))?
+            .trim()
+            .to_string();
+        let todo = fs::read_to_string(sequencer.state_path(TODO_FILE))?;
+        sequencer.todo = parse_todo(&todo, git_dir)?;
+        let done = fs::read_to_string(sequencer.state_path(DONE_FILE)).unwrap_or_default();
+        sequencer.done = parse_todo(&done, git_dir)?;
+        Ok(sequencer)
+    }
+
+    /// Returns the path to a file of the state of the rebase.
+    fn state_path(&self, file_name: &str) -> PathBuf {
+        Path::new(&self.git_dir)
+            .join(REBASE_MERGE_DIR)
+            .join(file_name)
+    }
+
+    /// Writes the state of the rebase into `.git/rebase-merge/`.
+    fn save(&self) -> io::Result<()> {
+        fs::write(
+            self.state_path(HEAD_NAME_FILE),
+            format!("refs/heads/{}\n", self.head_name),
+        )?;
+        fs::write(self.state_path(ORIG_HEAD_FILE), &self.orig_head)?;
+        fs::write(self.state_path(ONTO_FILE), &self.onto)?;
+        fs::write(self.state_path(TODO_FILE), format_todo(&self.todo))?;
+        fs::write(self.state_path(DONE_FILE), format_todo(&self.done))?;
+        Ok(())
+    }
+
+    /// Returns the hash of the commit the branch being rebased points to.
+    fn head(&self) -> io::Result<String> {
+        branch::get_branch_commit_hash(&self.head_name, &self.git_dir)
+    }
+
+    /// Replaces the files of `old_tree` in the working tree with the ones of `new_tree` and rebuilds the index.
+    fn update_working_tree(&self, old_tree: &Tree, new_tree: &Tree) -> io::Result<()> {
+        old_tree.delete_directories(&self.root_dir)?;
+        new_tree.create_directories(&self.root_dir, &self.git_dir)?;
+        let index_path = utils::get_index_file_path(&self.git_dir);
+        let index = new_tree.build_index_file_from_tree(&index_path, &self.git_dir, "")?;
+        index.write_file()
+    }
+
+    /// Applies the changes introduced by `commit_hash` on top of `head_tree`.
+    /// Files changed by both sides are merged from their version in the parent of the commit, and only
+    /// the ones where the changes overlap are left with conflict markers and reported as conflicts.
+    /// Binary files keep the version of `head_tree`.
+    fn cherry_pick_tree(
+        &self,
+        commit_hash: &str,
+        head_tree: &Tree,
+    ) -> io::Result<(Tree, Vec<String>)> {
+        let parent = commit::get_parent_hash(commit_hash, &self.git_dir)?;
+        if parent == NO_PARENT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "error: cannot rebase a root commit",
+            ));
+        }
+        let base_tree = tree_handler::load_tree_from_commit(&parent, &self.git_dir)?;
+        let commit_tree = tree_handler::load_tree_from_commit(commit_hash, &self.git_dir)?;
+        let (mut new_tree, mut conflicts) =
+            tree_handler::merge_trees_with_base(&base_tree, head_tree, &commit_tree, &self.git_dir);
+        let attributes = Attributes::load(&self.git_dir);
+        let subject = commit::get_commit_message(commit_hash, &self.git_dir)?;
+        let label = format!("{} ({})", &commit_hash[..7], subject.trim_end_matches('\0'));
+        let mut merged = Vec::new();
+        for path in conflicts.iter() {
+            if let (Some(ours), Some(theirs)) = (
+                head_tree.get_hash_from_path(path),
+                commit_tree.get_hash_from_path(path),
+            ) {
+                if attributes.is_binary_object(path, &ours, &self.git_dir)
+                    || attributes.is_binary_object(path, &theirs, &self.git_dir)
+                {
+                    continue;
+                }
+                let base = base_tree.get_hash_from_path(path);
+                let (content, conflicting) = diff::merge_objects(
+                    base.as_deref(),
+                    &ours,
+                    &theirs,
+                    &self.git_dir,
+                    ("HEAD", &label),
+                )?;
+                let hash = hash_object::store_string_to_file(&content, &self.git_dir, "blob")?;
+                new_tree.update_tree(path, &hash);
+                if !conflicting {
+                    merged.push(path.clone());
+                }
+            }
+        }
+        conflicts.retain(|path| !merged.contains(path));
+        Ok((new_tree, conflicts))
+    }
+
+    /// Opens the message in the editor and returns it without comments.
+    fn edit_message(&self, message: &str) -> io::Result<String> {
+        let path = Path::new(&self.git_dir).join(commit::COMMIT_EDITMSG);
+        fs::write(
+            &path,
+            format!(
+                "{}\n\n# Please enter the commit message for your changes. Lines starting\n# with '#' will be ignored, and an empty message aborts the commit.\n",
+                message.trim_end()
+            ),
+        )?;
+        utils::launch_editor(&utils::get_editor(), &path.to_string_lossy())?;
+        let edited = fs::read_to_string(&path)?;
+        let message = edited
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        if message.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Aborting commit due to empty commit message.",
+            ));
+        }
+        Ok(message + "\n")
+    }
+
+    /// Applies the commit of the todo item on top of the branch.
+    /// If `squash` is true, the result replaces the last commit of the branch instead of going on top of it.
+    /// Returns true if the rebase has to stop because of conflicts.
+    fn apply_commit(
+        &self,
+        item: &TodoItem,
+        squash: bool,
+        message: &str,
+        output: &mut impl Write,
+    ) -> io::Result<bool> {
+        let head = self.head()?;
+        let head_tree = tree_handler::load_tree_from_commit(&head, &self.git_dir)?;
+        let (new_tree, conflicts) = self.cherry_pick_tree(&item.commit, &head_tree)?;
+        let parent = if squash {
+            commit::get_parent_hash(&head, &self.git_dir)?
+        } else {
+            head.clone()
+        };
+
+        if !conflicts.is_empty() {
+            self.update_working_tree(&head_tree, &new_tree)?;
+            fs::write(self.state_path(MESSAGE_FILE), message)?;
+            fs::write(self.state_path(STOPPED_SHA_FILE), &item.commit)?;
+            fs::write(self.state_path(CONFLICTS_FILE), conflicts.join("\n"))?;
+            if squash {
+                fs::write(self.state_path(AMEND_FILE), &head)?;
+            }
+            writeln!(output, "error: could not apply {}", item)?;
+            for path in conflicts {
+                writeln!(output, "CONFLICT (content): Merge conflict in {}", path)?;
+            }
+            writeln!(output, "Resolve all conflicts manually, mark them as resolved with \"git add <paths>\", then run \"git rebase --continue\".")?;
+            return Ok(true);
+        }
+
+        let (tree_hash, _) = tree_handler::write_tree(&new_tree, &self.git_dir)?;
+        if !squash
+            && !tree_handler::has_tree_changed_since_last_commit(&tree_hash, &head, &self.git_dir)
+        {
+            writeln!(
+                output,
+                "dropping {} -- patch contents already upstream",
+                item
+            )?;
+            return Ok(false);
+        }
+        commit::new_rebase_commit(&self.git_dir, message, &parent, &new_tree)?;
+        self.update_working_tree(&head_tree, &new_tree)?;
+        Ok(false)
+    }
+
+    /// Runs a single step of the todo list. Returns true if the rebase has to stop.
+    fn run_step(&self, item: &TodoItem, output: &mut impl Write) -> io::Result<bool> {
+        match item.action {
+            TodoAction::Pick => {
+                let message = commit::get_commit_full_message(&item.commit, &self.git_dir)?;
+                self.apply_commit(item, false, &message, output)
+            }
+            TodoAction::Reword => {
+                let message = commit::get_commit_full_message(&item.commit, &self.git_dir)?;
+                let message = self.edit_message(&message)?;
+                self.apply_commit(item, false, &message, output)
+            }
+            TodoAction::Edit => {
+                let message = commit::get_commit_full_message(&item.commit, &self.git_dir)?;
+                if self.apply_commit(item, false, &message, output)? {
+                    return Ok(true);
+                }
+                fs::write(self.state_path(AMEND_FILE), self.head()?)?;
+                fs::write(self.state_path(STOPPED_SHA_FILE), &item.commit)?;
+                writeln!(output, "Stopped at {}", item)?;
+                writeln!(output, "You can amend the commit now: stage your changes with \"git add <paths>\" and they will be added to it when you run \"git rebase --continue\".")?;
+                Ok(true)
+            }
+            TodoAction::Squash | TodoAction::Fixup => {
+                let has_previous_commit = self.done[..self.done.len() - 1]
+                    .iter()
+                    .any(|done| !matches!(done.action, TodoAction::Drop | TodoAction::Exec));
+                if !has_previous_commit {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "error: cannot '{}' without a previous commit",
+                            item.action.as_str()
+                        ),
+                    ));
+                }
+                let head = self.head()?;
+                let head_message = commit::get_commit_full_message(&head, &self.git_dir)?;
+                let message = if item.action == TodoAction::Squash {
+                    let message = commit::get_commit_full_message(&item.commit, &self.git_dir)?;
+                    let combined = format!("{}\n\n{}", head_message.trim_end(), message);
+                    let squash_continues = self.todo.first().is_some_and(|next| {
+                        matches!(next.action, TodoAction::Squash | TodoAction::Fixup)
+                    });
+                    if squash_continues {
+                        combined
+                    } else {
+                        self.edit_message(&combined)?
+                    }
+                } else {
+                    head_message
+                };
+                self.apply_commit(item, true, &message, output)
+            }
+            TodoAction::Drop => Ok(false),
+            TodoAction::Exec => {
+                writeln!(output, "Executing: {}", item.message)?;
+                let working_dir = if self.root_dir.is_empty() {
+                    "."
+                } else {
+                    &self.root_dir
+                };
+                let status = Command::new("sh")
+                    .arg("-c")
+                    .arg(&item.message)
+                    .current_dir(working_dir)
+                    .status()?;
+                if status.success() {
+                    return Ok(false);
+                }
+                writeln!(output, "warning: execution failed: {}", item.message)?;
+                writeln!(
+                    output,
+                    "You can fix the problem, and then run \"git rebase --continue\"."
+                )?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Runs the remaining steps of the todo list, saving the state after each one.
+    /// When every step is done the state is removed and the rebase is over.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a step fails. The step is put back in the todo list, so it can be retried.
+    pub fn run(&mut self, output: &mut impl Write) -> io::Result<SequencerStatus> {
+        while !self.todo.is_empty() {
+            let item = self.todo.remove(0);
+            self.done.push(item.clone());
+            self.save()?;
+            match self.run_step(&item, output) {
+                Ok(true) => return Ok(SequencerStatus::Stopped),
+                Ok(false) => {}
+                Err(error) => {
+                    self.done.pop();
+                    self.todo.insert(0, item);
+                    self.save()?;
+                    return Err(error);
+                }
+            }
+        }
+        fs::remove_dir_all(self.state_path(""))?;
+        writeln!(
+            output,
+            "Successfully rebased and updated refs/heads/{}.",
+            self.head_name
+        )?;
+        Ok(SequencerStatus::Finished)
+    }
+
+    /// Continues a stopped rebase.
+    /// If the rebase stopped because of conflicts, the index (with the conflicts resolved) is committed.
+    /// If it stopped because of an `edit`, the changes in the index are added to the last commit.
+    /// Then the remaining steps are run.
+    pub fn continue_rebase(&mut self, output: &mut impl Write) -> io::Result<SequencerStatus> {
+        let head = self.head()?;
+        let index_path = utils::get_index_file_path(&self.git_dir);
+        let amend = self.state_path(AMEND_FILE).exists();
+        if let Ok(message) = fs::read_to_string(self.state_path(MESSAGE_FILE)) {
+            let tree = tree_handler::build_tree_from_index(&index_path, &self.git_dir, "")?;
+            let parent = if amend {
+                commit::get_parent_hash(&head, &self.git_dir)?
+            } else {
+                head
+            };
+            commit::new_rebase_commit(&self.git_dir, &message, &parent, &tree)?;
+        } else if amend {
+            let tree = tree_handler::build_tree_from_index(&index_path, &self.git_dir, "")?;
+            let (tree_hash, _) = tree_handler::write_tree(&tree, &self.git_dir)?;
+            if tree_handler::has_tree_changed_since_last_commit(&tree_hash, &head, &self.git_dir) {
+                let message = commit::get_commit_full_message(&head, &self.git_dir)?;
+                let parent = commit::get_parent_hash(&head, &self.git_dir)?;
+                commit::new_rebase_commit(&self.git_dir, &message, &parent, &tree)?;
+            }
+        }
+        self.clear_stopped_state()?;
+        self.run(output)
+    }
+
+    /// Skips the commit the rebase stopped at.
+    /// The index and the working tree are reset to the last commit of the branch and the remaining steps are run.
+    pub fn skip(&mut self, output: &mut impl Write) -> io::Result<SequencerStatus> {
+        let head = self.head()?;
+        let index_path = utils::get_index_file_path(&self.git_dir);
+        let index_tree = tree_handler::build_tree_from_index(&index_path, &self.git_dir, "")?;
+        let head_tree = tree_handler::load_tree_from_commit(&head, &self.git_dir)?;
+        self.update_working_tree(&index_tree, &head_tree)?;
+        self.clear_stopped_state()?;
+        self.run(output)
+    }
+
+    /// Aborts the rebase: the branch, index and working tree go back to where they were
+    /// before the rebase started and the state is removed.
+    pub fn abort(&self) -> io::Result<()> {
+        let index_path = utils::get_index_file_path(&self.git_dir);
+        let index_tree = tree_handler::build_tree_from_index(&index_path, &self.git_dir, "")?;
+        let orig_tree = tree_handler::load_tree_from_commit(&self.orig_head, &self.git_dir)?;
+        self.update_working_tree(&index_tree, &orig_tree)?;
+        branch::update_branch_commit_hash(&self.head_name, &self.orig_head, &self.git_dir)?;
+        fs::remove_dir_all(self.state_path(""))
+    }
+
+    /// Returns the paths with conflicts of the step the rebase stopped at.
+    /// The list is empty if the rebase didn't stop because of conflicts.
+    pub fn conflicted_files(&self) -> Vec<String> {
+        fs::read_to_string(self.state_path(CONFLICTS_FILE))
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    /// Removes the files that describe why the rebase stopped.
+    fn clear_stopped_state(&self) -> io::Result<()> {
+        for file_name in [MESSAGE_FILE, AMEND_FILE, STOPPED_SHA_FILE, CONFLICTS_FILE] {
+            let path = self.state_path(file_name);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rebases the current branch: the commits that are in the branch but not in `upstream` are
+/// applied, oldest first, on top of `onto` (or `upstream` if `onto` is not given).
+/// If a commit can't be applied the rebase stops, and it can be resumed with `--continue`,
+/// `--skip` or `--abort`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `upstream` - The revision that limits the commits to rebase.
+/// * `onto` - The revision the commits are applied on. Defaults to `upstream`.
+/// * `output` - Where the progress of the rebase is written.
+///
+/// # Errors
+///
+/// Returns an error if there is a rebase in progress, a revision can't be resolved or a step fails.
+pub fn git_rebase(
+    git_dir: &str,
+    root_dir: &str,
+    upstream: &str,
+    onto: Option<&str>,
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    if is_in_progress(git_dir) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "There is already a rebase in progress. Use --continue or --abort.",
+        ));
+    }
+    let branch_name = commit::get_branch_name(git_dir)?;
+    let head = branch::get_branch_commit_hash(&branch_name, git_dir)?;
+    let upstream_hash = utils::resolve_revision(upstream, git_dir)?;
+    let onto = match onto {
+        Some(onto) => utils::resolve_revision(onto, git_dir)?,
+        None => upstream_hash.clone(),
+    };
+    let todo = build_todo_list(&head, &upstream_hash, git_dir)?;
+    if todo.is_empty()
+        && onto == upstream_hash
+        && merge::find_common_ancestor(&head, &onto, git_dir)? == onto
+    {
+        writeln!(output, "Current branch {} is up to date.", branch_name)?;
+        return Ok(SequencerStatus::Finished);
+    }
+    let mut sequencer = Sequencer::start(git_dir, root_dir, &branch_name, &onto, todo)?;
+    sequencer.run(output)
+}
+
+/// Starts an interactive rebase of the current branch onto `upstream`.
+/// The todo list is opened in the editor (or read from `todo_file` if given) before running it.
+/// If the resulting list is empty, nothing is done.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `root_dir` - The path to the working tree.
+/// * `upstream` - The revision the branch is rebased onto.
+/// * `with_autosquash` - If true, `fixup!` and `squash!` commits are moved next to the commit they refer to.
+/// * `todo_file` - A file with the todo list to use instead of opening the editor.
+/// * `output` - Where the progress of the rebase is written.
+///
+/// # Errors
+///
+/// Returns an error if there is a rebase in progress, the upstream can't be resolved or a step fails.
+pub fn git_rebase_interactive(
+    git_dir: &str,
+    root_dir: &str,
+    upstream: &str,
+    with_autosquash: bool,
+    todo_file: Option<&str>,
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    if is_in_progress(git_dir) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "There is already a rebase in progress. Use --continue or --abort.",
+        ));
+    }
+    let branch_name = commit::get_branch_name(git_dir)?;
+    let head = branch::get_branch_commit_hash(&branch_name, git_dir)?;
+    let onto = utils::resolve_revision(upstream, git_dir)?;
+    let mut todo = build_todo_list(&head, &onto, git_dir)?;
+    if with_autosquash {
+        todo = autosquash(todo);
+    }
+    let todo = match todo_file {
+        Some(path) => parse_todo(&fs::read_to_string(path)?, git_dir)?,
+        None => edit_todo_list(git_dir, &todo, &onto)?,
+    };
+    if todo.is_empty() {
+        writeln!(output, "Nothing to do")?;
+        return Ok(SequencerStatus::Finished);
+    }
+    let mut sequencer = Sequencer::start(git_dir, root_dir, &branch_name, &onto, todo)?;
+    sequencer.run(output)
+}
+
+/// Continues the rebase in progress. See `Sequencer::continue_rebase`.
+pub fn git_rebase_continue(
+    git_dir: &str,
+    root_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    let mut sequencer = Sequencer::load(git_dir, root_dir)?;
+    sequencer.continue_rebase(output)
+}
+
+/// Skips the current step of the rebase in progress. See `Sequencer::skip`.
+pub fn git_rebase_skip(
+    git_dir: &str,
+    root_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<SequencerStatus> {
+    let mut sequencer = Sequencer::load(git_dir, root_dir)?;
+    sequencer.skip(output)
+}
+
+/// Aborts the rebase in progress. See `Sequencer::abort`.
+pub fn git_rebase_abort(git_dir: &str, root_dir: &str) -> io::Result<()> {
+    let sequencer = Sequencer::load(git_dir, root_dir)?;
+    sequencer.abort()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add;
+
+    fn create_mock_git_dir(git_dir: &str) {
+        let _ = fs::remove_dir_all(git_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir)).unwrap();
+        fs::create_dir_all(format!("{}/refs/heads", git_dir)).unwrap();
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master").unwrap();
+        fs::write(format!("{}/index", git_dir), "").unwrap();
+    }
+
+    fn commit_file(git_dir: &str, path: &str, content: &str, message: &str) -> String {
+        fs::write(path, content).unwrap();
+        let index_path = format!("{}/index", git_dir);
+        add::add(path, &index_path, git_dir, "", None).unwrap();
+        commit::new_commit(git_dir, message, "").unwrap()
+    }
+
+    /// Creates a repository with a commit in master and three commits on top of it in the branch 'topic'.
+    /// HEAD is left in 'topic'.
+    fn create_repository(root_dir: &str) -> (String, Vec<String>) {
+        let git_dir = format!("{}/.mgit", root_dir);
+        create_mock_git_dir(&git_dir);
+        let src_dir = format!("{}/src", root_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        let base = commit_file(&git_dir, &format!("{}/main.c", src_dir), "main\n", "Base");
+        branch::create_new_branch(&git_dir, "topic", None, &mut io::sink()).unwrap();
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/topic").unwrap();
+        let first = commit_file(&git_dir, &format!("{}/a.c", src_dir), "a\n", "Add a");
+        let second = commit_file(&git_dir, &format!("{}/b.c", src_dir), "b\n", "Add b");
+        let third = commit_file(
+            &git_dir,
+            &format!("{}/a.c", src_dir),
+            "a2\n",
+            "fixup! Add a",
+        );
+        (base, vec![first, second, third])
+    }
+
+    fn subjects(git_dir: &str) -> Vec<String> {
+        let head = branch::get_branch_commit_hash("topic", git_dir).unwrap();
+        utils::get_branch_commit_history_with_messages(&head, git_dir)
+            .unwrap()
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn test_todo_item_is_parsed_and_formatted() {
+        let root_dir = "tests/sequencer/test_parse";
+        let (_, commits) = create_repository(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+
+        let line = format!("s {} Add b", &commits[1][..7]);
+        let item = TodoItem::parse_line(&line, &git_dir).unwrap().unwrap();
+        assert_eq!(
+            item,
+            TodoItem::new(TodoAction::Squash, &commits[1], "Add b")
+        );
+        assert_eq!(item.to_string(), format!("squash {}", &line[2..]));
+
+        let item = TodoItem::parse_line("x make test", &git_dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(item, TodoItem::new(TodoAction::Exec, "", "make test"));
+        assert!(TodoItem::parse_line("# comment", &git_dir)
+            .unwrap()
+            .is_none());
+        assert!(TodoItem::parse_line("merge abc", &git_dir).is_err());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_autosquash_moves_fixup_commits() {
+        let todo = vec![
+            TodoItem::new(TodoAction::Pick, "1111111111", "Add a"),
+            TodoItem::new(TodoAction::Pick, "2222222222", "Add b"),
+            TodoItem::new(TodoAction::Pick, "3333333333", "fixup! Add a"),
+            TodoItem::new(TodoAction::Pick, "4444444444", "squash! 2222"),
+        ];
+        let todo = autosquash(todo);
+        let actions: Vec<(TodoAction, &str)> = todo
+            .iter()
+            .map(|item| (item.action, &item.commit[..1]))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (TodoAction::Pick, "1"),
+                (TodoAction::Fixup, "3"),
+                (TodoAction::Pick, "2"),
+                (TodoAction::Squash, "4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interactive_rebase_reorders_fixes_and_drops_commits() {
+        let root_dir = "tests/sequencer/test_reorder";
+        let (base, commits) = create_repository(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+        let todo_path = format!("{}/todo", root_dir);
+        let todo = format!(
+            "pick {} Add b\npick {} Add a\nfixup {} fixup! Add a\n",
+            commits[1], commits[0], commits[2]
+        );
+        fs::write(&todo_path, todo).unwrap();
+
+        let status = git_rebase_interactive(
+            &git_dir,
+            "",
+            "master",
+            false,
+            Some(&todo_path),
+            &mut io::sink(),
+        )
+        .unwrap();
+        assert_eq!(status, SequencerStatus::Finished);
+        assert!(!is_in_progress(&git_dir));
+        assert_eq!(subjects(&git_dir), vec!["Add a", "Add b", "Base"]);
+        let head = branch::get_branch_commit_hash("topic", &git_dir).unwrap();
+        let history = utils::get_branch_commit_history(&head, &git_dir).unwrap();
+        assert_eq!(history[2], base);
+        let content = fs::read_to_string(format!("{}/src/a.c", root_dir)).unwrap();
+        assert_eq!(content, "a2\n");
+
+        let todo = format!("drop {}\npick {}\n", history[1], history[0]);
+        fs::write(&todo_path, todo).unwrap();
+        git_rebase_interactive(
+            &git_dir,
+            "",
+            "master",
+            false,
+            Some(&todo_path),
+            &mut io::sink(),
+        )
+        .unwrap();
+        assert_eq!(subjects(&git_dir), vec!["Add a", "Base"]);
+        assert!(!Path::new(&format!("{}/src/b.c", root_dir)).exists());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_interactive_rebase_stops_on_edit_and_continues() {
+        let root_dir = "tests/sequencer/test_edit";
+        let (_, commits) = create_repository(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+        let todo_path = format!("{}/todo", root_dir);
+        let todo = format!(
+            "edit {} Add a\nexec true\npick {} Add b\n",
+            commits[0], commits[1]
+        );
+        fs::write(&todo_path, todo).unwrap();
+
+        let status = git_rebase_interactive(
+            &git_dir,
+            "",
+            "master",
+            false,
+            Some(&todo_path),
+            &mut io::sink(),
+        )
+        .unwrap();
+        assert_eq!(status, SequencerStatus::Stopped);
+        assert!(is_in_progress(&git_dir));
+        let sequencer = Sequencer::load(&git_dir, "").unwrap();
+        assert_eq!(sequencer.todo.len(), 2);
+        assert_eq!(sequencer.done.len(), 1);
+
+        let file_path = format!("{}/src/a.c", root_dir);
+        fs::write(&file_path, "amended\n").unwrap();
+        let index_path = format!("{}/index", git_dir);
+        add::add(&file_path, &index_path, &git_dir, "", None).unwrap();
+
+        let status = git_rebase_continue(&git_dir, "", &mut io::sink()).unwrap();
+        assert_eq!(status, SequencerStatus::Finished);
+        assert_eq!(subjects(&git_dir), vec!["Add b", "Add a", "Base"]);
+        let head = branch::get_branch_commit_hash("topic", &git_dir).unwrap();
+        let parent = commit::get_parent_hash(&head, &git_dir).unwrap();
+        let tree = tree_handler::load_tree_from_commit(&parent, &git_dir).unwrap();
+        let hash = tree.get_hash_from_path(&file_path).unwrap();
+        let content = crate::cat_file::cat_file_return_content(&hash, &git_dir).unwrap();
+        assert_eq!(content, "amended\n");
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rebase_onto_stops_on_conflicts_and_skips_the_commit() {
+        let root_dir = "tests/sequencer/test_skip";
+        let (_, commits) = create_repository(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+        let a_path = format!("{}/src/a.c", root_dir);
+
+        let mut output: Vec<u8> = Vec::new();
+        let status = git_rebase(&git_dir, "", &commits[0], Some("master"), &mut output).unwrap();
+        assert_eq!(status, SequencerStatus::Stopped);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("Merge conflict in {}", a_path)));
+        let sequencer = Sequencer::load(&git_dir, "").unwrap();
+        assert_eq!(sequencer.conflicted_files(), vec![a_path.clone()]);
+        assert_eq!(sequencer.orig_head, commits[2]);
+
+        let status = git_rebase_skip(&git_dir, "", &mut io::sink()).unwrap();
+        assert_eq!(status, SequencerStatus::Finished);
+        assert!(!is_in_progress(&git_dir));
+        assert_eq!(subjects(&git_dir), vec!["Add b", "Base"]);
+        assert!(!Path::new(&a_path).exists());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rebase_abort_restores_the_branch() {
+        let root_dir = "tests/sequencer/test_abort";
+        let (_, commits) = create_repository(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+
+        let status =
+            git_rebase(&git_dir, "", &commits[0], Some("master"), &mut io::sink()).unwrap();
+        assert_eq!(status, SequencerStatus::Stopped);
+        assert!(git_rebase(&git_dir, "", "master", None, &mut io::sink()).is_err());
+
+        git_rebase_abort(&git_dir, "").unwrap();
+        assert!(!is_in_progress(&git_dir));
+        let head = branch::get_branch_commit_hash("topic", &git_dir).unwrap();
+        assert_eq!(head, commits[2]);
+        let content = fs::read_to_string(format!("{}/src/a.c", root_dir)).unwrap();
+        assert_eq!(content, "a2\n");
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
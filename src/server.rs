@@ -1,3 +1,4 @@
+use crate::archive::{self, ArchiveOptions};
 use crate::packfile::handler::{create_packfile, unpack_packfile};
 use crate::server_utils::*;
 
@@ -11,10 +12,13 @@ use std::{fs, thread};
 
 const CAPABILITIES: &str = "multi_ack side-band-64k ofs-delta";
 const ZERO_HASH: &str = "0000000000000000000000000000000000000000";
+// Max size of the data sent in each pkt-line of the side-band, leaving room for the length and the band
+const SIDE_BAND_DATA_SIZE: usize = 65515;
 
 enum Command {
     UploadPack,
     ReceivePack,
+    UploadArchive,
 }
 
 impl TryFrom<&str> for Command {
@@ -24,6 +28,7 @@ impl TryFrom<&str> for Command {
         match value {
             "git-upload-pack" => Ok(Command::UploadPack),
             "git-receive-pack" => Ok(Command::ReceivePack),
+            "git-upload-archive" => Ok(Command::UploadArchive),
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Invalid command: {}", value),
@@ -64,6 +69,7 @@ impl ServerInstace {
         let result = match command {
             Command::UploadPack => self.upload_pack(),
             Command::ReceivePack => self.receive_pack(),
+            Command::UploadArchive => self.upload_archive(),
         };
         match result {
             Ok(_) => Ok(()),
@@ -135,6 +141,38 @@ impl ServerInstace {
         self.make_refs_changes(new_refs)
     }
 
+    // Receives the arguments of 'git archive' from the client, one per line, until a flush
+    // Then, the archive is created and sent to the client through the side-band
+    // Writing the archive to a file of the server is not allowed
+    fn upload_archive(&mut self) -> io::Result<()> {
+        let mut args = Vec::new();
+        loop {
+            let (size, line) = read_pkt_line(&mut self.socket)?;
+            if size == 0 {
+                break;
+            }
+            if let Some(arg) = line.trim_end_matches('\n').strip_prefix("argument ") {
+                args.push(arg.to_string());
+            }
+        }
+        let options = ArchiveOptions::parse(&args)?;
+        if options.output.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "--output is not allowed in a remote archive",
+            ));
+        }
+        let mut content = Vec::new();
+        archive::write_archive(&self.git_dir_path, &options, &mut content)?;
+        self.send(&pkt_line("ACK\n"))?;
+        self.flush()?;
+        for chunk in content.chunks(SIDE_BAND_DATA_SIZE) {
+            let data: Vec<u8> = [vec![1], chunk.to_vec()].concat();
+            self.send_bytes(&pkt_line_bytes(&data))?;
+        }
+        self.flush()
+    }
+
     // Sends the server refs to the client
     fn send_refs(&mut self) -> io::Result<()> {
         log("Sending refs...")?;
@@ -294,7 +294,7 @@ impl CommitHashes {
 ///
 /// An `io::Result` containing a `HashSet` of tuples representing object types and their hashes.
 ///
-fn get_objects_tree_objects(hash: &str, git_dir: &str) -> io::Result<HashSet<String>> {
+pub fn get_objects_tree_objects(hash: &str, git_dir: &str) -> io::Result<HashSet<String>> {
     let mut objects: HashSet<String> = HashSet::new();
     objects.insert(hash.to_string());
     let content = cat_file::cat_tree(hash, git_dir)?;
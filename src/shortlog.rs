@@ -0,0 +1,222 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use chrono::Utc;
+
+use crate::{
+    configuration::LOGGER_COMMANDS_FILE,
+    log::{self, LogOptions},
+    logger::Logger,
+    mailmap::Mailmap,
+    utils,
+};
+
+/// Logs the 'git shortlog' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_shortlog(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git shortlog': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git shortlog` command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShortlogOptions {
+    /// If true (`-s` or `--summary`), only the number of commits of each author is shown.
+    pub summary: bool,
+    /// If true (`-n` or `--numbered`), the authors with more commits come first instead of sorting them by name.
+    pub numbered: bool,
+    /// If true (`-e` or `--email`), the email is shown next to the name of each author.
+    pub email: bool,
+    /// The options of the walk of the history, such as the range of commits.
+    pub log: LogOptions,
+}
+
+impl ShortlogOptions {
+    /// Parses the arguments of `git shortlog` (the ones after 'shortlog').
+    /// `-s`, `-n` and `-e` may be combined, as in `-sn`. Any other argument is an option of `git log`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option of `git log` is invalid.
+    ///
+    pub fn parse(args: &[String], now: i64) -> io::Result<ShortlogOptions> {
+        let mut options = ShortlogOptions::default();
+        let mut log_args = Vec::new();
+        for arg in args {
+            match arg.as_str() {
+                "--summary" => options.summary = true,
+                "--numbered" => options.numbered = true,
+                "--email" => options.email = true,
+                flags
+                    if flags.len() > 1
+                        && flags.starts_with('-')
+                        && flags[1..].chars().all(|flag| "sne".contains(flag)) =>
+                {
+                    options.summary |= flags.contains('s');
+                    options.numbered |= flags.contains('n');
+                    options.email |= flags.contains('e');
+                }
+                _ => log_args.push(arg.clone()),
+            }
+        }
+        options.log = LogOptions::parse(&log_args, now)?;
+        Ok(options)
+    }
+}
+
+/// Returns the subjects of the commits of each author, oldest commits first.
+/// The authors are named as the mailmap says, with their email if `email` is true.
+///
+/// # Errors
+///
+/// Returns an error if a revision can't be resolved or a commit can't be read.
+///
+pub fn group_by_author(
+    git_dir: &str,
+    options: &ShortlogOptions,
+) -> io::Result<BTreeMap<String, Vec<String>>> {
+    let mailmap = Mailmap::load(git_dir);
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for log in log::log_with_options(git_dir, &options.log)?.iter().rev() {
+        let author = log.author();
+        let (name, email) = mailmap.resolve(&author.name, &author.email);
+        let key = if options.email {
+            format!("{} <{}>", name, email)
+        } else {
+            name
+        };
+        groups
+            .entry(key)
+            .or_default()
+            .push(log.subject().to_string());
+    }
+    Ok(groups)
+}
+
+/// Summarizes the history grouping the subjects of the commits by author.
+///
+/// For every author it writes its name and number of commits, followed by the subjects of its commits.
+/// With `-s` only the number of commits is written, with `-n` the authors are sorted by number of commits
+/// and with `-e` their emails are shown. The aliases of an author are merged as the `.mailmap` file says.
+/// The commits are the ones `git log` would show with the rest of the arguments, such as `<rev1>..<rev2>`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments of the command, the ones after 'shortlog'.
+/// * `output` - Where the summary is written.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, a revision can't be resolved or a commit can't be read.
+///
+pub fn git_shortlog(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    log_shortlog(git_dir, args)?;
+    let options = ShortlogOptions::parse(args, Utc::now().timestamp())?;
+    let mut groups: Vec<(String, Vec<String>)> =
+        group_by_author(git_dir, &options)?.into_iter().collect();
+    if options.numbered {
+        groups.sort_by_key(|(_, subjects)| std::cmp::Reverse(subjects.len()));
+    }
+    for (author, subjects) in groups {
+        if options.summary {
+            writeln!(output, "{:6}\t{}", subjects.len(), author)?;
+            continue;
+        }
+        writeln!(output, "{} ({}):", author, subjects.len())?;
+        for subject in subjects {
+            writeln!(output, "      {}", subject)?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_file, create_repo};
+    use std::fs;
+
+    fn run_shortlog(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_shortlog(git_dir, &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// Creates a repository with three commits of the same author, and returns its git directory
+    /// and the first commit.
+    fn create_history(root_dir: &str) -> io::Result<(String, String)> {
+        let git_dir = create_repo(root_dir)?;
+        let file = format!("{}/file.txt", root_dir);
+        let first = commit_file(&git_dir, &file, "uno\n", "Primero")?;
+        commit_file(&git_dir, &file, "dos\n", "Segundo")?;
+        commit_file(&git_dir, &file, "tres\n", "Tercero")?;
+        Ok((git_dir, first))
+    }
+
+    #[test]
+    fn test_shortlog_groups_subjects_by_author() -> io::Result<()> {
+        let root_dir = "tests/shortlog_authors";
+        let (git_dir, _) = create_history(root_dir)?;
+
+        let plain = run_shortlog(&git_dir, &[])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            plain,
+            "no_user (3):\n      Primero\n      Segundo\n      Tercero\n\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortlog_summary_of_a_range() -> io::Result<()> {
+        let root_dir = "tests/shortlog_summary";
+        let (git_dir, first) = create_history(root_dir)?;
+
+        let summary = run_shortlog(&git_dir, &["-sn", &format!("{}..", first)])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(summary, "     2\tno_user\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortlog_maps_the_authors_with_the_mailmap() -> io::Result<()> {
+        let root_dir = "tests/shortlog_mailmap";
+        let (git_dir, _) = create_history(root_dir)?;
+        fs::write(
+            format!("{}/.mailmap", root_dir),
+            "Lionel Messi <lio@afa.com> <no_email>\n",
+        )?;
+
+        let mapped = run_shortlog(&git_dir, &["-e"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(mapped.starts_with("Lionel Messi <lio@afa.com> (3):\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,458 @@
+use std::io::{self, Write};
+
+use crate::{
+    blame::Signature,
+    cat_file,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff::{self, DiffFormat, DiffOptions},
+    logger::Logger,
+    rename, tree_handler, utils,
+};
+
+/// Format of the dates shown by `git show`, for example `Thu Nov 2 18:30:00 2023 -0300`.
+const DATE_FORMAT: &str = "%a %b %-d %H:%M:%S %Y %z";
+/// Mode of the tree entries that are trees, as `cat_tree` returns it.
+const TREE_MODE: &str = "40000";
+
+/// Logs the 'git show' command with the specified revisions and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `revisions` - The revisions to show.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_show(git_dir: &str, revisions: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git show': Revisions '{}', Git Directory '{}', {}",
+        revisions.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of the `git show` command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShowOptions {
+    /// How the changes of the commits are shown, and which paths.
+    pub diff: DiffOptions,
+    /// If false (`-s` or `--no-patch`), the changes of the commits are not shown.
+    pub patch: bool,
+    /// The objects to show. If there are none, HEAD is shown.
+    pub revisions: Vec<String>,
+}
+
+impl ShowOptions {
+    /// Parses the arguments of `git show` (the ones after 'show').
+    /// Besides `-s` and `--no-patch`, it accepts the options of `git diff` that choose how the changes are shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is unknown.
+    pub fn parse(args: &[String]) -> io::Result<ShowOptions> {
+        let patch = !args.iter().any(|arg| arg == "-s" || arg == "--no-patch");
+        let diff_args: Vec<String> = args
+            .iter()
+            .filter(|arg| *arg != "-s" && *arg != "--no-patch")
+            .cloned()
+            .collect();
+        let mut diff = DiffOptions::parse(&diff_args)?;
+        let mut revisions: Vec<String> = diff.revisions.drain(..).collect();
+        if revisions.is_empty() {
+            revisions.push("HEAD".to_string());
+        }
+        Ok(ShowOptions {
+            diff,
+            patch,
+            revisions,
+        })
+    }
+}
+
+/// The header and the message of a commit or a tag object.
+#[derive(Debug, Default)]
+struct ShowObject {
+    headers: Vec<(String, String)>,
+    message: String,
+}
+
+impl ShowObject {
+    /// Splits the content of a commit or a tag into its header lines and its message.
+    fn parse(content: &str) -> Self {
+        let (header, message) = content.split_once("\n\n").unwrap_or((content, ""));
+        Self {
+            headers: header
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            message: message.trim_end_matches(['\0', '\n']).to_string(),
+        }
+    }
+
+    /// Returns the values of the header lines with the given key, in order.
+    fn values(&self, key: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(header, _)| header == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Returns the value of the first header line with the given key.
+    fn value(&self, key: &str) -> Option<&str> {
+        self.values(key).first().copied()
+    }
+}
+
+/// Writes a signature line such as `Author: <name> <<email>>` followed by its date.
+fn write_signature(title: &str, value: &str, output: &mut impl Write) -> io::Result<()> {
+    match Signature::parse(value) {
+        Some(signature) => {
+            writeln!(
+                output,
+                "{}: {} <{}>",
+                title, signature.name, signature.email
+            )?;
+            writeln!(output, "Date:   {}", signature.format_date(DATE_FORMAT))
+        }
+        None => writeln!(output, "{}: {}", title, value),
+    }
+}
+
+/// Writes the message of a commit, indented with four spaces as `git log` does.
+fn write_indented_message(message: &str, output: &mut impl Write) -> io::Result<()> {
+    for line in message.lines() {
+        if line.is_empty() {
+            writeln!(output)?;
+        } else {
+            writeln!(output, "    {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the files (path, hash) of the tree of a commit.
+fn commit_files(commit_hash: &str, git_dir: &str) -> io::Result<Vec<(String, String)>> {
+    Ok(tree_handler::load_tree_from_commit(commit_hash, git_dir)?.get_files())
+}
+
+/// Writes a commit: its hash, its parents if it's a merge, its author, its message and its changes.
+///
+/// The changes are the ones against the first parent, or against nothing for the first commit.
+/// For merges, the patch is a combined diff against every parent.
+fn write_commit(
+    hash: &str,
+    git_dir: &str,
+    options: &ShowOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let commit = ShowObject::parse(&cat_file::cat_file_return_content(hash, git_dir)?);
+    let parents = commit.values("parent");
+    writeln!(output, "commit {}", hash)?;
+    if parents.len() > 1 {
+        let parents: Vec<&str> = parents
+            .iter()
+            .map(|parent| parent.get(..7).unwrap_or(parent))
+            .collect();
+        writeln!(output, "Merge: {}", parents.join(" "))?;
+    }
+    if let Some(author) = commit.value("author") {
+        write_signature("Author", author, output)?;
+    }
+    writeln!(output)?;
+    write_indented_message(&commit.message, output)?;
+    if !options.patch {
+        return Ok(());
+    }
+
+    let files = commit_files(hash, git_dir)?;
+    if parents.len() > 1 && options.diff.format == DiffFormat::Patch {
+        let parent_files = parents
+            .iter()
+            .map(|parent| commit_files(parent, git_dir))
+            .collect::<io::Result<Vec<_>>>()?;
+        let changes = diff::compare_merge_files(&parent_files, &files, &options.diff.paths);
+        if !changes.is_empty() {
+            writeln!(output)?;
+        }
+        return diff::write_combined_patch(&changes, git_dir, &options.diff, output);
+    }
+    let parent_files = match parents.first() {
+        Some(parent) => commit_files(parent, git_dir)?,
+        None => Vec::new(),
+    };
+    let mut changes = diff::compare_files(&parent_files, &files, &options.diff.paths);
+    if let Some(renames) = options.diff.renames {
+        changes = rename::detect_renames(changes, git_dir, None, renames);
+    }
+    if !changes.is_empty() {
+        writeln!(output)?;
+    }
+    diff::write_changes(&changes, git_dir, None, &options.diff, output)
+}
+
+/// Writes an annotated tag: its name, its tagger and its message, followed by the object it tags.
+fn write_tag(
+    hash: &str,
+    git_dir: &str,
+    options: &ShowOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let tag = ShowObject::parse(&cat_file::cat_file_return_content(hash, git_dir)?);
+    writeln!(output, "tag {}", tag.value("tag").unwrap_or(hash))?;
+    if let Some(tagger) = tag.value("tagger") {
+        write_signature("Tagger", tagger, output)?;
+    }
+    writeln!(output)?;
+    writeln!(output, "{}", tag.message)?;
+    writeln!(output)?;
+    match tag.value("object") {
+        Some(object) => show_object(object, object, git_dir, options, output),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("fatal: bad tag {}", hash),
+        )),
+    }
+}
+
+/// Writes the names of the entries of a tree, with a '/' after the ones that are trees.
+fn write_tree(hash: &str, name: &str, git_dir: &str, output: &mut impl Write) -> io::Result<()> {
+    writeln!(output, "tree {}", name)?;
+    writeln!(output)?;
+    for (mode, entry, _) in cat_file::cat_tree(hash, git_dir)? {
+        if mode.trim() == TREE_MODE {
+            writeln!(output, "{}/", entry)?;
+        } else {
+            writeln!(output, "{}", entry)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes an object in the way that suits its type.
+///
+/// # Arguments
+///
+/// * `hash` - The hash of the object.
+/// * `name` - The name the object was given with, shown for trees.
+/// * `git_dir` - The path to the git directory.
+/// * `options` - How the changes of the commits are shown.
+/// * `output` - Where the object is written.
+///
+fn show_object(
+    hash: &str,
+    name: &str,
+    git_dir: &str,
+    options: &ShowOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    match cat_file::cat_file_return_type(hash, git_dir)?.as_str() {
+        "commit" => write_commit(hash, git_dir, options, output),
+        "tag" => write_tag(hash, git_dir, options, output),
+        "tree" => write_tree(hash, name, git_dir, output),
+        _ => output.write_all(&cat_file::cat_file_return_bytes(hash, git_dir)?),
+    }
+}
+
+/// Shows one or more objects.
+///
+/// * Commits are shown with their author, their message and the diff against their first parent.
+///   Merges are shown with a combined diff against every parent.
+/// * Annotated tags are shown with their tagger and their message, followed by the tagged object.
+/// * Trees are shown as the list of their entries.
+/// * Blobs are shown as their raw content.
+///
+/// The objects are given as revisions (`HEAD`, `main~2`, `v1.0`, a hash) or as `<rev>:<path>` to name
+/// a file or a directory at a revision. If none is given, HEAD is shown.
+/// `-s` hides the diff of the commits, and the options of `git diff` such as `--stat` or `-U<n>` change it.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments of the command, the ones after 'show'.
+/// * `output` - Where the objects are written.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, a revision can't be resolved or an object can't be read.
+///
+pub fn git_show(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    let options = ShowOptions::parse(args)?;
+    log_show(git_dir, &options.revisions)?;
+    for (i, revision) in options.revisions.iter().enumerate() {
+        if i > 0 {
+            writeln!(output)?;
+        }
+        let hash = utils::resolve_object(revision, git_dir)?;
+        show_object(&hash, revision, git_dir, &options, output)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commit, hash_object,
+        test_utils::{add_file, commit_file, create_repo},
+    };
+    use std::fs;
+
+    fn run_show(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut output: Vec<u8> = Vec::new();
+        git_show(git_dir, &args, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// Creates a repository with `dir/b.txt` and two versions of `a.txt`, and returns its git
+    /// directory, the path to `a.txt` and the last commit.
+    fn create_history(root_dir: &str) -> io::Result<(String, String, String)> {
+        let git_dir = create_repo(root_dir)?;
+        let file_path = format!("{}/a.txt", root_dir);
+        fs::create_dir_all(format!("{}/dir", root_dir))?;
+        commit_file(&git_dir, &format!("{}/dir/b.txt", root_dir), "b\n", "First")?;
+        commit_file(&git_dir, &file_path, "uno\n", "Second")?;
+        let third = commit_file(&git_dir, &file_path, "uno\ndos\n", "Third\n\nWith a body")?;
+        Ok((git_dir, file_path, third))
+    }
+
+    /// Creates a repository where `Merge` joins two commits that changed `f.txt`, and returns its
+    /// git directory, the path to `f.txt` and the merge and its parents.
+    fn create_merge(root_dir: &str) -> io::Result<(String, String, [String; 3])> {
+        let git_dir = create_repo(root_dir)?;
+        let file_path = format!("{}/f.txt", root_dir);
+        let branch_path = format!("{}/refs/heads/master", git_dir);
+        let base = commit_file(&git_dir, &file_path, "a\nb\nc\n", "Base")?;
+        let ours = commit_file(&git_dir, &file_path, "A\nb\nc\n", "Ours")?;
+        fs::write(&branch_path, &base)?;
+        let theirs = commit_file(&git_dir, &file_path, "a\nb\nC\n", "Theirs")?;
+        fs::write(&branch_path, &ours)?;
+        add_file(&git_dir, &file_path, "A\nb\nC\nmerged\n")?;
+        let merge = commit::new_merge_commit(&git_dir, "Merge", &ours, &theirs, "")?;
+        Ok((git_dir, file_path, [merge, ours, theirs]))
+    }
+
+    #[test]
+    fn test_show_head_writes_the_commit_and_its_diff() -> io::Result<()> {
+        let root_dir = "tests/show_head";
+        let (git_dir, file_path, third) = create_history(root_dir)?;
+
+        let show_head = run_show(&git_dir, &[])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(show_head.starts_with(&format!(
+            "commit {}\nAuthor: no_user <no_email>\nDate:   ",
+            third
+        )));
+        assert!(show_head.ends_with(&format!(
+            "\n\n    Third\n\n    With a body\n\ndiff --git a/{0} b/{0}\nindex {1}..{2} 100644\n--- a/{0}\n+++ b/{0}\n@@ -1 +1,2 @@\n uno\n+dos\n",
+            file_path,
+            &hash_object::hash_content(b"uno\n", "blob")[..7],
+            &hash_object::hash_content(b"uno\ndos\n", "blob")[..7]
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_files_at_a_revision() -> io::Result<()> {
+        let root_dir = "tests/show_files";
+        let (git_dir, file_path, _) = create_history(root_dir)?;
+
+        let show_file = run_show(&git_dir, &[&format!("HEAD~1:{}", file_path)])?;
+        let missing = run_show(&git_dir, &["HEAD:nothing.txt"]);
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(show_file, "uno\n");
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_trees_at_a_revision() -> io::Result<()> {
+        let root_dir = "tests/show_trees";
+        let (git_dir, _, _) = create_history(root_dir)?;
+
+        let show_tree = run_show(&git_dir, &[&format!("HEAD:{}", root_dir)])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert_eq!(
+            show_tree,
+            format!("tree HEAD:{}\n\na.txt\ndir/\n", root_dir)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_no_patch_leaves_out_the_diff() -> io::Result<()> {
+        let root_dir = "tests/show_no_patch";
+        let (git_dir, _, third) = create_history(root_dir)?;
+
+        let show_stat = run_show(&git_dir, &["--stat", "-s", &third[..7]])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(show_stat.ends_with("    With a body\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_merges_with_a_combined_diff() -> io::Result<()> {
+        let root_dir = "tests/show_merge";
+        let (git_dir, file_path, [merge, ours, theirs]) = create_merge(root_dir)?;
+
+        let show_merge = run_show(&git_dir, &[&merge])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(show_merge.starts_with(&format!(
+            "commit {}\nMerge: {} {}\n",
+            merge,
+            &ours[..7],
+            &theirs[..7]
+        )));
+        assert!(show_merge.ends_with(&format!(
+            "\n    Merge\n\ndiff --cc {0}\nindex {1},{2}..{3}\n--- a/{0}\n+++ b/{0}\n@@@ -1,3 -1,3 +1,4 @@@\n -a\n +A\n  b\n- c\n+ C\n++merged\n",
+            file_path,
+            &hash_object::hash_content(b"A\nb\nc\n", "blob")[..7],
+            &hash_object::hash_content(b"a\nb\nC\n", "blob")[..7],
+            &hash_object::hash_content(b"A\nb\nC\nmerged\n", "blob")[..7]
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_tags_with_their_commit() -> io::Result<()> {
+        let root_dir = "tests/show_tag";
+        let (git_dir, _, [merge, ours, theirs]) = create_merge(root_dir)?;
+        let tag = hash_object::store_string_to_file(
+            &format!(
+                "object {}\ntype commit\ntag v1.0\ntagger no_user no_email 1671321600 -0300\n\nFirst release\n",
+                merge
+            ),
+            &git_dir,
+            "tag",
+        )?;
+        fs::write(format!("{}/refs/tags/v1.0", git_dir), &tag)?;
+
+        let show_tag = run_show(&git_dir, &["v1.0"])?;
+        fs::remove_dir_all(root_dir)?;
+
+        assert!(show_tag.starts_with(&format!(
+            "tag v1.0\nTagger: no_user <no_email>\nDate:   Sat Dec 17 21:00:00 2022 -0300\n\nFirst release\n\ncommit {}\nMerge: {} {}\n",
+            merge,
+            &ours[..7],
+            &theirs[..7]
+        )));
+        assert!(show_tag.contains("\n    Merge\n\ndiff --cc "));
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use chrono::Utc;
+
+use crate::{
+    attributes::Attributes,
+    cat_file,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff::{self, Edit, FileChange},
+    log::{self, LogOptions},
+    logger::Logger,
+    mailmap::Mailmap,
+    utils,
+};
+
+/// Number of files shown in the list of the most frequently changed files.
+const TOP_FILES: usize = 10;
+/// Format of the weeks the commits are grouped by, for example `2023-W44`.
+const WEEK_FORMAT: &str = "%G-W%V";
+
+/// Logs the 'git stats' command with the specified arguments and Git directory.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `args` - The arguments of the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+pub fn log_stats(git_dir: &str, args: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git stats': Args '{}', Git Directory '{}', {}",
+        args.join(" "),
+        git_dir,
+        utils::get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The contributions to a repository, counted over a range of its history.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RepositoryStats {
+    /// The number of commits of each author in each week, by (week, author).
+    pub commits_per_week: BTreeMap<(String, String), usize>,
+    /// The number of commits that changed each file.
+    pub changes_per_file: HashMap<String, usize>,
+    /// The lines added and removed by each author.
+    pub lines_per_author: BTreeMap<String, (usize, usize)>,
+}
+
+impl RepositoryStats {
+    /// Returns the files changed by more commits, with the number of commits, sorted by it and then by path.
+    pub fn most_changed_files(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut files: Vec<(&str, usize)> = self
+            .changes_per_file
+            .iter()
+            .map(|(path, changes)| (path.as_str(), *changes))
+            .collect();
+        files.sort_by(|(path_a, a), (path_b, b)| b.cmp(a).then(path_a.cmp(path_b)));
+        files.truncate(count);
+        files
+    }
+}
+
+/// Returns the number of lines added and removed by a change. Binary files have no lines.
+fn count_lines(
+    change: &FileChange,
+    git_dir: &str,
+    attributes: &Attributes,
+) -> io::Result<(usize, usize)> {
+    let read = |hash: &Option<String>| match hash {
+        Some(hash) => cat_file::cat_file_return_bytes(hash, git_dir),
+        None => Ok(Vec::new()),
+    };
+    let (old_content, new_content) = (read(&change.old_hash)?, read(&change.new_hash)?);
+    if attributes.is_binary_file(&change.path, &old_content)
+        || attributes.is_binary_file(&change.path, &new_content)
+    {
+        return Ok((0, 0));
+    }
+    let old_lines = diff::split_lines(&String::from_utf8_lossy(&old_content));
+    let new_lines = diff::split_lines(&String::from_utf8_lossy(&new_content));
+    let (mut added, mut removed) = (0, 0);
+    for edit in diff::compute_edits(&old_lines, &new_lines) {
+        match edit {
+            Edit::Insert(_) => added += 1,
+            Edit::Delete(_) => removed += 1,
+            Edit::Equal(_, _) => {}
+        }
+    }
+    Ok((added, removed))
+}
+
+/// Counts the contributions of the commits `git log` would show with the given options.
+///
+/// Every commit is compared with its first parent. Merges are only counted as commits of their author,
+/// since their changes were already counted in the commits they merge. The aliases of an author are
+/// merged as the `.mailmap` file says.
+///
+/// # Errors
+///
+/// Returns an error if a revision can't be resolved or a commit, a tree or a blob can't be read.
+///
+pub fn collect_stats(git_dir: &str, options: &LogOptions) -> io::Result<RepositoryStats> {
+    let mailmap = Mailmap::load(git_dir);
+    let attributes = Attributes::load(git_dir);
+    let mut stats = RepositoryStats::default();
+    for log in log::log_with_options(git_dir, options)? {
+        let author = log.author();
+        let (name, _) = mailmap.resolve(&author.name, &author.email);
+        *stats
+            .commits_per_week
+            .entry((author.format_date(WEEK_FORMAT), name.clone()))
+            .or_default() += 1;
+        if log.parents().len() > 1 {
+            continue;
+        }
+        let lines = stats.lines_per_author.entry(name).or_default();
+        for change in log.changes()? {
+            let (added, removed) = count_lines(&change, git_dir, &attributes)?;
+            lines.0 += added;
+            lines.1 += removed;
+            *stats.changes_per_file.entry(change.path).or_default() += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// Shows statistics of the contributions to the repository: the commits of each author per week,
+/// the files changed by more commits and the lines added and removed by each author.
+/// The commits counted are the ones `git log` would show with the same arguments, such as `<rev1>..<rev2>`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `args` - The arguments of the command, the ones after 'stats'.
+/// * `output` - Where the statistics are written.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, a revision can't be resolved or an object can't be read.
+///
+pub fn git_stats(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    log_stats(git_dir, args)?;
+    let options = LogOptions::parse(args, Utc::now().timestamp())?;
+    let stats = collect_stats(git_dir, &options)?;
+
+    writeln!(output, "Commits per author per week:")?;
+    for ((week, author), commits) in &stats.commits_per_week {
+        writeln!(output, "  {}  {:6}  {}", week, commits, author)?;
+    }
+    writeln!(output)?;
+    writeln!(output, "Most changed files:")?;
+    for (path, changes) in stats.most_changed_files(TOP_FILES) {
+        writeln!(output, "  {:6}  {}", changes, path)?;
+    }
+    writeln!(output)?;
+    writeln!(output, "Lines per author:")?;
+    for (author, (added, removed)) in &stats.lines_per_author {
+        writeln!(output, "  +{:<6} -{:<6} {}", added, removed, author)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_file, create_repo};
+    use std::fs;
+
+    /// Creates a repository with three commits of `a.txt` and `b.txt` by an author the mailmap
+    /// names `Lionel Messi`, and returns its git directory and the paths to both files.
+    fn create_history(root_dir: &str) -> io::Result<(String, String, String)> {
+        let git_dir = create_repo(root_dir)?;
+        fs::write(
+            format!("{}/.mailmap", root_dir),
+            "Lionel Messi <no_email>\n",
+        )?;
+        let (a, b) = (format!("{}/a.txt", root_dir), format!("{}/b.txt", root_dir));
+        commit_file(&git_dir, &a, "uno\ndos\n", "Primero")?;
+        commit_file(&git_dir, &b, "tres\n", "Segundo")?;
+        commit_file(&git_dir, &a, "uno\n2\n", "Tercero")?;
+        Ok((git_dir, a, b))
+    }
+
+    #[test]
+    fn test_stats_count_commits_files_and_lines_per_author() -> io::Result<()> {
+        let root_dir = "tests/stats_authors";
+        let (git_dir, a, b) = create_history(root_dir)?;
+
+        let stats = collect_stats(&git_dir, &LogOptions::default());
+        fs::remove_dir_all(root_dir)?;
+
+        let stats = stats?;
+        assert_eq!(stats.commits_per_week.values().sum::<usize>(), 3);
+        assert!(stats
+            .commits_per_week
+            .keys()
+            .all(|(_, author)| author == "Lionel Messi"));
+        assert_eq!(
+            stats.most_changed_files(10),
+            vec![(a.as_str(), 2), (b.as_str(), 1)]
+        );
+        assert_eq!(stats.lines_per_author["Lionel Messi"], (4, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_are_written_as_tables() -> io::Result<()> {
+        let root_dir = "tests/stats_output";
+        let (git_dir, a, b) = create_history(root_dir)?;
+
+        let mut output = Vec::new();
+        let written = git_stats(&git_dir, &[], &mut output);
+        fs::remove_dir_all(root_dir)?;
+
+        written?;
+        let output = String::from_utf8_lossy(&output).to_string();
+        assert!(output.contains(&format!(
+            "Most changed files:\n       2  {}\n       1  {}\n",
+            a, b
+        )));
+        assert!(output.contains("  +4      -1      Lionel Messi\n"));
+        Ok(())
+    }
+}
@@ -1,13 +1,15 @@
 const BLOB: &str = "blob";
 use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::diff::{self, FileChange};
 use crate::hash_object;
 use crate::index::Index;
 use crate::logger::Logger;
+use crate::rename::{self, RenameOptions};
 use crate::tree_handler::Tree;
 use crate::utils::get_current_time;
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Logs the 'git status' command with optional counts for untracked, unstaged, and staged files.
 ///
@@ -53,6 +55,52 @@ pub fn log_status(
     Ok(())
 }
 
+/// Recursively finds the files and directories of a Git repository that are not tracked.
+///
+/// This function traverses the directory structure starting from the `current_directory` and compares
+/// it with the files tracked in the Git `index`. Directories are listed before their content, and the
+/// paths starting with '.' are skipped. Ignored paths are not filtered out.
+///
+/// # Arguments
+///
+/// * `current_directory` - A reference to the current directory to start searching for untracked files.
+/// * `base_directory` - A reference to the base directory of the Git repository.
+/// * `index` - A reference to the Git index containing tracked file information.
+///
+/// # Returns
+///
+/// Returns the untracked paths, relative to `base_directory`.
+///
+/// # Errors
+///
+/// Returns an error if a directory can't be read.
+///
+pub fn find_untracked_paths(
+    current_directory: &Path,
+    base_directory: &Path,
+    index: &Index,
+) -> io::Result<Vec<PathBuf>> {
+    let mut untracked_paths = Vec::new();
+    for entry in fs::read_dir(current_directory)? {
+        let entry_path = entry?.path();
+        let Ok(relative_entry_path) = entry_path.strip_prefix(base_directory) else {
+            eprintln!("We've found some kind of mistake in git status");
+            continue;
+        };
+        let relative_entry_path_str = relative_entry_path.to_string_lossy().to_string();
+        if relative_entry_path_str.starts_with('.') || index.contains(&relative_entry_path_str) {
+            continue;
+        }
+        if entry_path.is_dir() {
+            untracked_paths.push(relative_entry_path.to_path_buf());
+            untracked_paths.extend(find_untracked_paths(&entry_path, base_directory, index)?);
+        } else if entry_path.is_file() {
+            untracked_paths.push(relative_entry_path.to_path_buf());
+        }
+    }
+    Ok(untracked_paths)
+}
+
 /// Recursively find and write information about untracked files in a Git repository.
 ///
 /// This function traverses the directory structure starting from the `current_directory`, compares
@@ -77,111 +125,114 @@ pub fn find_untracked_files(
     index: &Index,
     output: &mut impl Write,
 ) -> Result<(), io::Error> {
-    for entry in fs::read_dir(current_directory)? {
-        let entry = entry?;
-        let entry_path = entry.path();
-        let mut count = 0;
-        if let Ok(relative_entry_path) = entry_path.strip_prefix(base_directory) {
-            let relative_entry_path_str = relative_entry_path.to_string_lossy().to_string();
-            if !relative_entry_path_str.starts_with('.')
-                && !index.path_should_be_ignored(&relative_entry_path_str)
-                && !index.contains(&relative_entry_path_str)
-            {
-                if entry_path.is_dir() {
-                    let buffer = format!("\x1b[31m\t\t{}x1b[0m\n", relative_entry_path_str);
-                    output.write_all(buffer.as_bytes())?;
-                    count += 1;
-                    find_untracked_files(&entry_path, base_directory, index, output)?
-                }
-                if entry_path.is_file() {
-                    let buffer = format!("\t\t{}\n", relative_entry_path_str);
-                    count += 1;
-                    output.write_all(buffer.as_bytes())?;
-                }
-            }
-        } else {
-            eprintln!("We've found some kind of mistake in git status");
+    let mut count = 0;
+    for path in find_untracked_paths(current_directory, base_directory, index)? {
+        let relative_entry_path_str = path.to_string_lossy().to_string();
+        if index.path_should_be_ignored(&relative_entry_path_str) {
+            continue;
         }
-        log_status(Some(count), None, None)?;
+        let buffer = if base_directory.join(&path).is_dir() {
+            format!("\x1b[31m\t\t{}x1b[0m\n", relative_entry_path_str)
+        } else {
+            format!("\t\t{}\n", relative_entry_path_str)
+        };
+        output.write_all(buffer.as_bytes())?;
+        count += 1;
     }
-    Ok(())
+    log_status(Some(count), None, None)
 }
 
-/// Find and write information about unstaged changes in a Git repository's index.
+/// Returns the changes staged in the index compared with the tree of the last commit.
 ///
-/// This function compares the hash of files in the provided `Index` with their current content
-/// to identify modified files that haven't been staged for commit. It writes the information
-/// about these changes to the provided `output`.
+/// Files that were moved are reported as renames ('R') instead of a deletion and an addition,
+/// pairing them first by hash and then by content similarity.
 ///
 /// # Arguments
 ///
 /// * `index` - A reference to the Git index containing file information.
-/// * `git_dir` - A string representing the path to the Git repository directory.
-/// * `output` - A mutable reference to a writer where the information about unstaged changes will be written.
+/// * `commit_tree` - The tree of the last commit, or `None` if there are no commits yet.
+/// * `git_dir` - The path to the git directory, used to read the content of the files.
+///
+pub fn staged_file_changes(
+    index: &Index,
+    commit_tree: Option<&Tree>,
+    git_dir: &str,
+) -> Vec<FileChange> {
+    let commit_files = match commit_tree {
+        Some(tree) => tree.get_files(),
+        None => Vec::new(),
+    };
+    let changes = diff::compare_files(&commit_files, &diff::index_files(index), &[]);
+    rename::detect_renames(changes, git_dir, None, RenameOptions::default())
+}
+
+/// Returns the label `git status` uses for a change, for example 'modified' or 'renamed'.
+pub fn status_label(change: &FileChange) -> &str {
+    match change.status {
+        'A' => "new file",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        _ => "modified",
+    }
+}
+
+/// Returns the name `git status` shows for a change: 'old -> new' for renames and copies, otherwise the path.
+pub fn status_name(change: &FileChange) -> String {
+    match &change.old_path {
+        Some(old_path) => format!("{} -> {}", old_path, change.path),
+        None => change.path.clone(),
+    }
+}
+
+/// Find and write information about the changes staged for commit.
+///
+/// This function compares the files in the provided `Index` with the ones in the tree of the last
+/// commit and writes every new, modified, deleted or renamed file to the provided `output`.
+///
+/// # Arguments
+///
+/// * `index` - A reference to the Git index containing file information.
+/// * `commit_tree` - A reference to the tree of the last commit.
+/// * `git_dir` - The path to the git directory, used to detect renamed files.
+/// * `output` - A mutable reference to a writer where the information about staged changes will be written.
 ///
 /// # Errors
 ///
 /// This function can return an `io::Result` which contains an `io::Error` if there is an issue
-/// writing to the `output` or if there are issues with file operations, such as hashing the file content.
+/// writing to the `output`.
 ///
 pub fn changes_to_be_committed(
     index: &Index,
     commit_tree: &Tree,
+    git_dir: &str,
     output: &mut impl Write,
 ) -> io::Result<()> {
-    let mut count = 0;
-    for (path, hash) in index.iter() {
-        match commit_tree.get_hash_from_path(path) {
-            Some(new_hash) => {
-                if hash.ne(&new_hash) {
-                    count += 1;
-                    let buffer = format!("\x1b[31m\t\tmodified:\t {}\x1b[0m\n", path);
-                    output.write_all(buffer.as_bytes())?;
-                }
-            }
-            None => {
-                count += 1;
-                let buffer = format!("\x1b[31m\t\tmodified:\t {}\x1b[0m\n", path);
-                output.write_all(buffer.as_bytes())?;
-            }
-        }
+    let changes = staged_file_changes(index, Some(commit_tree), git_dir);
+    for change in changes.iter() {
+        let buffer = format!(
+            "\x1b[31m\t\t{}:\t {}\x1b[0m\n",
+            status_label(change),
+            status_name(change)
+        );
+        output.write_all(buffer.as_bytes())?;
     }
-    log_status(None, Some(count), None)?;
+    log_status(None, Some(changes.len() as i32), None)?;
     Ok(())
 }
 
-/// Return a string containing all staged changes in a Git repository's index.
-pub fn get_staged_changes(index: &Index, commit_tree: Option<Tree>) -> Result<String, io::Error> {
-    let output = match commit_tree {
-        Some(tree) => {
-            let mut local_output = vec![];
-            let result = changes_to_be_committed(index, &tree, &mut local_output);
-            if result.is_ok() {
-                local_output
-            } else {
-                vec![]
-            }
-        }
-        None => {
-            let mut output: Vec<u8> = vec![];
-            for (path, _) in index.iter() {
-                let buffer = format!("\x1b[31m\t\tmodified:\t {}\x1b[0m\n", path);
-                output.write_all(buffer.as_bytes())?;
-            }
-            output
-        }
-    };
-    if let Ok(result) = String::from_utf8(output) {
-        let mut resultado = result;
-        resultado = resultado.replace("\x1b[31m\t\tmodified:\t ", "");
-        resultado = resultado.replace("\x1b[0m\n", "\n");
-        Ok(resultado)
-    } else {
-        Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Parent hash not found",
-        ))
-    }
+/// Return a string containing all staged changes in a Git repository's index, one per line.
+/// Renamed files are shown as 'old -> new'.
+pub fn get_staged_changes(
+    index: &Index,
+    commit_tree: Option<Tree>,
+    git_dir: &str,
+) -> Result<String, io::Error> {
+    let changes = staged_file_changes(index, commit_tree.as_ref(), git_dir);
+    Ok(changes
+        .iter()
+        .map(|change| format!("{}\n", status_name(change)))
+        .collect())
 }
 
 /// Find and write information about unstaged changes in a Git repository's index.
@@ -355,7 +406,7 @@ mod tests {
         let mut commit_hash = String::new();
         current_commit_file.read_to_string(&mut commit_hash)?;
         let tree = tree_handler::load_tree_from_commit(&commit_hash, &mgit_path)?;
-        changes_to_be_committed(&index, &tree, &mut output)?;
+        changes_to_be_committed(&index, &tree, &mgit_path, &mut output)?;
         Ok(())
     }
 
@@ -466,4 +517,45 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_moved_files_are_shown_as_renamed() -> Result<(), io::Error> {
+        let root_dir = "tests/status_renames";
+        let git_dir = "tests/status_renames/.mgit";
+        let _ = std::fs::remove_dir_all(root_dir);
+        std::fs::create_dir_all(format!("{}/objects", git_dir))?;
+        std::fs::create_dir_all(format!("{}/refs/heads", git_dir))?;
+        std::fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/master")?;
+        let index_path = format!("{}/index", git_dir);
+        std::fs::write(&index_path, "")?;
+        let old_path = format!("{}/old.txt", root_dir);
+        let new_path = format!("{}/new.txt", root_dir);
+        std::fs::write(&old_path, "uno\ndos\ntres\ncuatro\n")?;
+        crate::add::add(&old_path, &index_path, git_dir, "", None)?;
+        let commit_hash = commit::new_commit(git_dir, "First", "")?;
+
+        let mut index = Index::load(&index_path, git_dir, "")?;
+        index.remove_file(&old_path)?;
+        index.write_file()?;
+        std::fs::rename(&old_path, &new_path)?;
+        crate::add::add(&new_path, &index_path, git_dir, "", None)?;
+
+        let index = Index::load(&index_path, git_dir, "")?;
+        let tree = tree_handler::load_tree_from_commit(&commit_hash, git_dir)?;
+        let mut output: Vec<u8> = vec![];
+        changes_to_be_committed(&index, &tree, git_dir, &mut output)?;
+        let staged = get_staged_changes(&index, Some(tree), git_dir)?;
+        std::fs::remove_dir_all(root_dir)?;
+
+        let output = String::from_utf8_lossy(&output).to_string();
+        assert_eq!(
+            output,
+            format!(
+                "\x1b[31m\t\trenamed:\t {} -> {}\x1b[0m\n",
+                old_path, new_path
+            )
+        );
+        assert_eq!(staged, format!("{} -> {}\n", old_path, new_path));
+        Ok(())
+    }
 }
@@ -0,0 +1,23 @@
+use std::{fs, io};
+
+use crate::{add, commit, configuration::GIT_DIR_FOR_TEST, init};
+
+/// Creates an empty repository in `root_dir` with `git init`, removing whatever was there before,
+/// and returns the path to its git directory.
+pub fn create_repo(root_dir: &str) -> io::Result<String> {
+    let _ = fs::remove_dir_all(root_dir);
+    init::git_init(root_dir, GIT_DIR_FOR_TEST, "master", None)?;
+    Ok(format!("{}/{}", root_dir, GIT_DIR_FOR_TEST))
+}
+
+/// Writes a file of the working tree and adds it to the index.
+pub fn add_file(git_dir: &str, path: &str, content: &str) -> io::Result<()> {
+    fs::write(path, content)?;
+    add::add(path, &format!("{}/index", git_dir), git_dir, "", None)
+}
+
+/// Writes a file of the working tree, adds it to the index and commits it. Returns the hash of the commit.
+pub fn commit_file(git_dir: &str, path: &str, content: &str, message: &str) -> io::Result<String> {
+    add_file(git_dir, path, content)?;
+    commit::new_commit(git_dir, message, "")
+}
@@ -1,13 +1,16 @@
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     io::{self, Write},
     path::{Path, PathBuf},
 };
 
 use crate::{
+    attributes::{Attributes, MergeDriver},
     cat_file::{self, cat_tree},
     diff, hash_object,
     index::{self},
+    rename::{self, RenameOptions},
 };
 
 const BLOB_NORMAL_MODE: &str = "100644";
@@ -177,6 +180,8 @@ impl Tree {
     }
 
     /// Given a tree, recreates the directories and files stored in the tree in the working tree.
+    /// Files go through the smudge conversion of their attributes, so the ones with `eol=crlf`
+    /// are written with CRLF line endings.
     pub fn create_directories(&self, parent_dir: &str, git_dir_path: &str) -> io::Result<()> {
         if parent_dir.is_empty() && self.name.is_empty() {
             let dir_path = self.name.to_string();
@@ -197,10 +202,11 @@ impl Tree {
             fs::create_dir_all(&dir_path)?;
         }
 
+        let attributes = Attributes::load(git_dir_path);
         for file in &self.files {
             let path = dir_path.to_string() + "/" + &file.0;
-            let mut new_file = fs::File::create(path)?;
-            cat_file::cat_file(&file.1, git_dir_path, &mut new_file)?;
+            let content = cat_file::cat_file_return_bytes(&file.1, git_dir_path)?;
+            fs::write(&path, attributes.smudge(&path, content))?;
         }
 
         for subdirs in &self.directories {
@@ -243,6 +249,11 @@ impl Tree {
         Ok(())
     }
 
+    /// Returns every file of the tree as a tuple (path, hash), with the complete path from the root tree.
+    pub fn get_files(&self) -> Vec<(String, String)> {
+        self.squash_tree_into_vec("")
+    }
+
     /// Squash the tree into a vector of tuples (file_name, hash). So a file that is in a subtree will have its complete path from the root tree.
     fn squash_tree_into_vec(&self, parent_dir: &str) -> Vec<(String, String)> {
         let mut result = Vec::new();
@@ -434,6 +445,11 @@ pub fn build_tree_from_index(
     git_ignore_path: &str,
 ) -> io::Result<Tree> {
     let index = index::Index::load(index_path, git_dir_path, git_ignore_path)?;
+    build_tree_from_entries(&index)
+}
+
+/// Builds a tree from the entries of an index, which may not have been written to its file yet.
+pub fn build_tree_from_entries(index: &index::Index) -> io::Result<Tree> {
     let mut tree = Tree::new("");
 
     //Iterates over the index struct, adding each file to the tree.
@@ -650,15 +666,24 @@ pub fn print_tree_console(tree: &Tree, depth: usize) {
 /// * `our_tree` - The tree representing our current state.
 /// * `their_tree` - The tree representing the state to be merged.
 /// * `new_tree` - The new tree being constructed with merged contents.
+/// * `merged_paths` - Paths of their tree that were already merged with a renamed file of ours.
 ///
 /// # Returns
 ///
 /// Returns the merged tree containing both our and their changes.
 ///
-fn merge_their_tree_into_ours(our_tree: &Tree, their_tree: &Tree, mut new_tree: Tree) -> Tree {
+fn merge_their_tree_into_ours(
+    our_tree: &Tree,
+    their_tree: &Tree,
+    mut new_tree: Tree,
+    merged_paths: &HashSet<String>,
+) -> Tree {
     let their_tree_vec = their_tree.squash_tree_into_vec("");
 
     for (path, hash) in their_tree_vec {
+        if merged_paths.contains(&path) {
+            continue;
+        }
         let mut path_vec = path.split('/').collect::<Vec<&str>>();
         let filename = match path_vec.pop() {
             Some(filename) => filename,
@@ -679,37 +704,79 @@ fn merge_their_tree_into_ours(our_tree: &Tree, their_tree: &Tree, mut new_tree:
     new_tree
 }
 
+/// What every file of a merge is merged with: their tree, the name of their side and the attributes.
+struct MergeContext<'a> {
+    their_tree: &'a Tree,
+    their_label: &'a str,
+    attributes: &'a Attributes,
+}
+
 /// Merges a file from the current branch with the same file on the other branch if it exists.
+/// `their_path` is the path of the file on the other branch, which is different from `path` if it was renamed.
+/// If both versions differ, the `merge` attribute of the file decides how they are combined (see `merge_driver`).
+/// Text files are merged with `diff::merge_three_way` from `base_hash`, the version of the common
+/// ancestor, and only the parts both sides changed are left between conflict markers.
 fn merge_file(
     path: &str,
     hash: &str,
-    their_tree: &Tree,
+    their_path: &str,
+    base_hash: Option<&str>,
     current_tree: &mut Tree,
-    filename: &str,
+    context: &MergeContext,
     git_dir: &str,
 ) -> io::Result<String> {
-    let their_hash = their_tree.get_hash_from_path(path);
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    let their_hash = context.their_tree.get_hash_from_path(their_path);
     match their_hash {
         Some(their_hash) => {
             if their_hash == hash {
                 current_tree.add_file(filename, hash);
-                Ok("".to_string())
-            } else {
-                let mut new_file = fs::File::create(path)?;
-                let diff = diff::return_object_diff_string(&their_hash, hash, git_dir);
-
-                match diff {
-                    Ok(diff) => {
-                        new_file.write_all(diff.as_bytes())?;
-                        let new_hash = hash_object::store_string_to_file(&diff, git_dir, "blob")?;
-                        current_tree.add_file(filename, &new_hash);
-                        Ok(path.to_string())
-                    }
-                    Err(_) => {
-                        current_tree.add_file(filename, hash);
-                        Ok("".to_string())
+                return Ok("".to_string());
+            }
+            let driver = merge_driver(
+                path,
+                hash,
+                their_path,
+                &their_hash,
+                git_dir,
+                context.attributes,
+            );
+            let merged = match driver {
+                MergeDriver::Ours => {
+                    current_tree.add_file(filename, hash);
+                    return Ok("".to_string());
+                }
+                MergeDriver::Binary => {
+                    current_tree.add_file(filename, hash);
+                    return Ok(path.to_string());
+                }
+                MergeDriver::Union => diff::return_object_union_string(hash, &their_hash, git_dir)
+                    .map(|union| (union, false))
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+                MergeDriver::Text => diff::merge_objects(
+                    base_hash,
+                    hash,
+                    &their_hash,
+                    git_dir,
+                    ("HEAD", context.their_label),
+                ),
+            };
+
+            match merged {
+                Ok((merged, conflicting)) => {
+                    let mut new_file = fs::File::create(path)?;
+                    new_file.write_all(merged.as_bytes())?;
+                    let new_hash = hash_object::store_string_to_file(&merged, git_dir, "blob")?;
+                    current_tree.add_file(filename, &new_hash);
+                    match conflicting {
+                        true => Ok(path.to_string()),
+                        false => Ok("".to_string()),
                     }
                 }
+                Err(_) => {
+                    current_tree.add_file(filename, hash);
+                    Ok("".to_string())
+                }
             }
         }
         None => {
@@ -719,6 +786,76 @@ fn merge_file(
     }
 }
 
+/// Returns how a file that differs in both sides must be merged.
+/// The `merge` attribute of the file decides first. Without it, binary files can't be merged line by line,
+/// so ours is kept and the path is reported as conflicting, and the rest are merged as text.
+fn merge_driver(
+    path: &str,
+    hash: &str,
+    their_path: &str,
+    their_hash: &str,
+    git_dir: &str,
+    attributes: &Attributes,
+) -> MergeDriver {
+    match attributes.get(path).merge_driver() {
+        Some(driver) => driver,
+        None if attributes.is_binary_object(path, hash, git_dir)
+            || attributes.is_binary_object(their_path, their_hash, git_dir) =>
+        {
+            MergeDriver::Binary
+        }
+        None => MergeDriver::Text,
+    }
+}
+
+/// Finds the files that were renamed in one of the trees and pairs their paths.
+/// Returns a map from the path in our tree to the path in their tree and the path the merged file will have.
+///
+/// With a common ancestor, the renames of each side are found comparing it with the ancestor, and the
+/// merged file keeps the new name. Without one, the files only in our tree are compared with the ones
+/// only in their tree, and the merged file keeps the name it has in their tree.
+fn find_renamed_files(
+    our_tree: &Tree,
+    their_tree: &Tree,
+    base_tree: Option<&Tree>,
+    git_dir: &str,
+) -> HashMap<String, (String, String)> {
+    let our_files = our_tree.get_files();
+    let their_files = their_tree.get_files();
+    let mut renamed = HashMap::new();
+    match base_tree {
+        Some(base_tree) => {
+            let base_files = base_tree.get_files();
+            let options = RenameOptions::default();
+            let their_renames =
+                rename::find_renamed_paths(&base_files, &their_files, git_dir, options);
+            for (old_path, their_path) in their_renames {
+                if our_tree.get_hash_from_path(&old_path).is_some() {
+                    renamed.insert(old_path, (their_path.clone(), their_path));
+                }
+            }
+            let our_renames = rename::find_renamed_paths(&base_files, &our_files, git_dir, options);
+            for (old_path, our_path) in our_renames {
+                if their_tree.get_hash_from_path(&old_path).is_some() {
+                    renamed.insert(our_path.clone(), (old_path, our_path));
+                }
+            }
+        }
+        None => {
+            let renames = rename::find_renamed_paths(
+                &our_files,
+                &their_files,
+                git_dir,
+                RenameOptions::default(),
+            );
+            for (our_path, their_path) in renames {
+                renamed.insert(our_path, (their_path.clone(), their_path));
+            }
+        }
+    }
+    renamed
+}
+
 /// Given two trees, it merges them into a new tree.
 /// The new tree will have the files of both trees.
 /// There are three cases:
@@ -726,9 +863,17 @@ fn merge_file(
 /// * If a file is in both trees and has different hashes, the diff between the two files will be calculated and added to the new tree.
 /// * If a file is in one tree but not in the other, it will be added to the new tree.
 ///
+/// Binary files that differ keep our version and are reported as conflicting.
+///
+/// Files renamed in one of the trees are merged with the same file in the other one, so the changes
+/// of both sides are kept under the new name.
+///
 /// ## Arguments
 /// * `our_tree`: The tree of the current branch.
 /// * `their_tree`: The tree of the branch we want to merge.
+/// * `base_tree`: The tree of the common ancestor, if it's known. It's used to tell which side renamed a file,
+///   and as the base of the three-way merge of the files both sides changed.
+/// * `their_label`: The name of their side, written after the `>>>>>>>` conflict markers.
 /// * `git_dir`: The path to the git folder.
 ///
 /// ## Errors
@@ -737,38 +882,178 @@ fn merge_file(
 pub fn merge_trees(
     our_tree: &Tree,
     their_tree: &Tree,
+    base_tree: Option<&Tree>,
+    their_label: &str,
     git_dir: &str,
 ) -> io::Result<(Tree, Vec<String>)> {
     let our_tree_vec = our_tree.squash_tree_into_vec("");
     let mut new_tree = Tree::new("");
     let mut conflicting_paths: Vec<String> = Vec::new();
-
-    for (path, hash) in our_tree_vec {
-        let mut path_vec = path.split('/').collect::<Vec<&str>>();
-        let filename = match path_vec.pop() {
-            Some(filename) => filename,
-            None => {
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "Invalid path in index file.\n",
-                ))
+    let renamed = find_renamed_files(our_tree, their_tree, base_tree, git_dir);
+    let attributes = Attributes::load(git_dir);
+    let context = MergeContext {
+        their_tree,
+        their_label,
+        attributes: &attributes,
+    };
+    let mut merged_paths: HashSet<String> = HashSet::new();
+
+    for (our_path, hash) in our_tree_vec {
+        let base_hash = base_tree.and_then(|base_tree| base_tree.get_hash_from_path(&our_path));
+        let (their_path, path) = match renamed.get(&our_path) {
+            Some((their_path, path)) => {
+                merged_paths.insert(their_path.clone());
+                (their_path.clone(), path.clone())
             }
+            None => (our_path.clone(), our_path),
         };
+        let mut path_vec = path.split('/').collect::<Vec<&str>>();
+        if path_vec.pop().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Invalid path in index file.\n",
+            ));
+        }
         let mut current_tree = &mut new_tree;
         for dir in path_vec {
             current_tree = current_tree.get_or_create_dir(dir);
         }
-        let result = merge_file(&path, &hash, their_tree, current_tree, filename, git_dir)?;
+        let base_hash = base_hash
+            .or_else(|| base_tree.and_then(|base_tree| base_tree.get_hash_from_path(&their_path)));
+        let result = merge_file(
+            &path,
+            &hash,
+            &their_path,
+            base_hash.as_deref(),
+            current_tree,
+            &context,
+            git_dir,
+        )?;
         if !result.is_empty() {
             conflicting_paths.push(result);
         }
     }
 
-    let new_tree = merge_their_tree_into_ours(our_tree, their_tree, new_tree);
+    let new_tree = merge_their_tree_into_ours(our_tree, their_tree, new_tree, &merged_paths);
     let tuple = (new_tree, conflicting_paths);
     Ok(tuple)
 }
 
+/// Moves the file at `from` to `to` in a list of files, unless `to` is already taken.
+fn move_file(files: &mut BTreeMap<String, String>, from: &str, to: &str) {
+    if from != to && !files.contains_key(to) {
+        if let Some(hash) = files.remove(from) {
+            files.insert(to.to_string(), hash);
+        }
+    }
+}
+
+/// Given three trees, it merges `their_tree` into `our_tree` using `base_tree` as the common ancestor.
+/// Unlike `merge_trees`, nothing is written to the working directory, and only the files merged with the
+/// `union` driver are written to the objects folder.
+/// For every path present in any of the trees:
+/// * If ours and theirs have the same hash, that version is kept.
+/// * If only one side changed the path since the base, that side's version is kept (a deletion counts as a change).
+/// * If both sides changed the path in different ways, the path is reported as conflicting and our version is kept.
+///   Files with `merge=ours` keep our version without conflict, and files with `merge=union` keep the lines of both.
+///
+/// Renames are detected comparing each side with the base. A file renamed by one side is merged
+/// with the same file of the other side under its new name. If both sides renamed it to different
+/// names, both names are reported as conflicting.
+///
+/// ## Arguments
+/// * `base_tree`: The tree of the common ancestor.
+/// * `our_tree`: The tree of the current branch.
+/// * `their_tree`: The tree of the branch we want to merge.
+/// * `git_dir`: The path to the git folder, used to compare the content of the files.
+///
+/// ## Returns
+/// A tuple with the merged tree and the list of conflicting paths.
+pub fn merge_trees_with_base(
+    base_tree: &Tree,
+    our_tree: &Tree,
+    their_tree: &Tree,
+    git_dir: &str,
+) -> (Tree, Vec<String>) {
+    let base_list = base_tree.get_files();
+    let mut base_files: BTreeMap<String, String> = base_list.iter().cloned().collect();
+    let mut our_files: BTreeMap<String, String> = our_tree.get_files().into_iter().collect();
+    let mut their_files: BTreeMap<String, String> = their_tree.get_files().into_iter().collect();
+    let options = RenameOptions::default();
+    let our_renames =
+        rename::find_renamed_paths(&base_list, &our_tree.get_files(), git_dir, options);
+    let their_renames =
+        rename::find_renamed_paths(&base_list, &their_tree.get_files(), git_dir, options);
+
+    let mut conflicting_paths: Vec<String> = Vec::new();
+    for (old_path, our_path) in our_renames.iter() {
+        match their_renames.get(old_path) {
+            Some(their_path) if their_path != our_path => {
+                conflicting_paths.push(our_path.clone());
+                conflicting_paths.push(their_path.clone());
+            }
+            Some(_) => move_file(&mut base_files, old_path, our_path),
+            None => {
+                move_file(&mut base_files, old_path, our_path);
+                move_file(&mut their_files, old_path, our_path);
+            }
+        }
+    }
+    for (old_path, their_path) in their_renames.iter() {
+        if !our_renames.contains_key(old_path) {
+            move_file(&mut base_files, old_path, their_path);
+            move_file(&mut our_files, old_path, their_path);
+        }
+    }
+
+    let mut paths: Vec<&String> = base_files
+        .keys()
+        .chain(our_files.keys())
+        .chain(their_files.keys())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let attributes = Attributes::load(git_dir);
+    let mut new_tree = Tree::new("");
+    for path in paths {
+        let base_hash = base_files.get(path);
+        let our_hash = our_files.get(path);
+        let their_hash = their_files.get(path);
+
+        let merged_hash = if our_hash == their_hash || base_hash == their_hash {
+            our_hash.cloned()
+        } else if base_hash == our_hash {
+            their_hash.cloned()
+        } else {
+            let driver = attributes.get(path).merge_driver();
+            let union = match (driver, our_hash, their_hash) {
+                (Some(MergeDriver::Union), Some(ours), Some(theirs)) => {
+                    diff::return_object_union_string(ours, theirs, git_dir)
+                        .ok()
+                        .and_then(|union| {
+                            hash_object::store_string_to_file(&union, git_dir, "blob").ok()
+                        })
+                }
+                _ => None,
+            };
+            if union.is_none()
+                && driver != Some(MergeDriver::Ours)
+                && !conflicting_paths.contains(path)
+            {
+                conflicting_paths.push(path.clone());
+            }
+            union.or_else(|| our_hash.cloned())
+        };
+
+        if let Some(hash) = merged_hash {
+            new_tree.update_tree(path, &hash);
+        }
+    }
+    conflicting_paths.sort();
+    (new_tree, conflicting_paths)
+}
+
 pub fn get_files_with_changes(our_tree: &Tree, their_tree: &Tree) -> Vec<(String, String)> {
     let our_tree_entries = our_tree.squash_tree_into_vec("");
     let result = our_tree_entries
@@ -1088,4 +1373,109 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(git_dir_path);
     }
+
+    #[test]
+    fn test_binary_files_that_differ_keep_ours_and_conflict() -> io::Result<()> {
+        let git_dir = "tests/tree_handler_binary/.mgit";
+        std::fs::create_dir_all(format!("{}/objects", git_dir))?;
+        let ours = hash_object::store_bytes_array_to_file(b"\0\x01ours".to_vec(), git_dir, "blob")?;
+        let theirs =
+            hash_object::store_bytes_array_to_file(b"\0\x02theirs".to_vec(), git_dir, "blob")?;
+        let our_tree = tree_with(&[("image.png", &ours)]);
+        let their_tree = tree_with(&[("image.png", &theirs)]);
+
+        let (merged, conflicts) = merge_trees(&our_tree, &their_tree, None, "theirs", git_dir)?;
+        std::fs::remove_dir_all("tests/tree_handler_binary")?;
+
+        assert_eq!(conflicts, vec!["image.png".to_string()]);
+        assert_eq!(merged.get_hash_from_path("image.png"), Some(ours));
+        assert!(!Path::new("image.png").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_attributes_convert_line_endings_and_choose_the_merge_driver() -> io::Result<()> {
+        let root_dir = "tests/tree_handler_attributes";
+        let git_dir = format!("{}/.mgit", root_dir);
+        std::fs::create_dir_all(format!("{}/objects", git_dir))?;
+        std::fs::write(
+            format!("{}/.gitattributes", root_dir),
+            "*.bat eol=crlf\nCHANGELOG merge=union\nversion merge=ours\n",
+        )?;
+        let script_path = format!("{}/run.bat", root_dir);
+        std::fs::write(&script_path, "echo 1\r\necho 2\r\n")?;
+        let script = hash_object::store_file(&script_path, &git_dir)?;
+        let store = |content: &str| hash_object::store_string_to_file(content, &git_dir, "blob");
+        assert_eq!(script, store("echo 1\necho 2\n")?);
+
+        std::fs::remove_file(&script_path)?;
+        let mut tree = tree_with(&[("run.bat", &script)]);
+        tree.name = root_dir.to_string();
+        tree.create_directories("", &git_dir)?;
+        assert_eq!(
+            std::fs::read_to_string(&script_path)?,
+            "echo 1\r\necho 2\r\n"
+        );
+
+        let base_tree = tree_with(&[("CHANGELOG", &store("v1\n")?), ("version", &store("1\n")?)]);
+        let our_tree = tree_with(&[
+            ("CHANGELOG", &store("v1\nours\n")?),
+            ("version", &store("2\n")?),
+        ]);
+        let their_tree = tree_with(&[
+            ("CHANGELOG", &store("v1\ntheirs\n")?),
+            ("version", &store("3\n")?),
+        ]);
+        let (merged, conflicts) =
+            merge_trees_with_base(&base_tree, &our_tree, &their_tree, &git_dir);
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            merged.get_hash_from_path("CHANGELOG"),
+            Some(store("v1\nours\ntheirs\n")?)
+        );
+        assert_eq!(merged.get_hash_from_path("version"), Some(store("2\n")?));
+
+        std::fs::remove_dir_all(root_dir)
+    }
+
+    fn tree_with(files: &[(&str, &str)]) -> Tree {
+        let mut tree = Tree::new("");
+        for (path, hash) in files {
+            tree.update_tree(path, hash);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_merge_with_base_follows_renames() -> io::Result<()> {
+        let git_dir = "tests/tree_handler_renames/.mgit";
+        std::fs::create_dir_all(format!("{}/objects", git_dir))?;
+        let content: String = (1..=10).map(|i| format!("linea {}\n", i)).collect();
+        let base = hash_object::store_string_to_file(&content, git_dir, "blob")?;
+        let edited = content.replace("linea 1\n", "linea uno\n");
+        let ours = hash_object::store_string_to_file(&edited, git_dir, "blob")?;
+        let renamed = content.replace("linea 10\n", "linea diez\n");
+        let theirs = hash_object::store_string_to_file(&renamed, git_dir, "blob")?;
+
+        let base_tree = tree_with(&[("a.txt", &base), ("other.txt", "1234")]);
+        let our_tree = tree_with(&[("a.txt", &ours), ("other.txt", "1234")]);
+        let exact_rename = tree_with(&[("b.txt", &base), ("other.txt", "1234")]);
+        let (merged, conflicts) =
+            merge_trees_with_base(&base_tree, &our_tree, &exact_rename, git_dir);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.get_hash_from_path("b.txt"), Some(ours.clone()));
+        assert_eq!(merged.get_hash_from_path("a.txt"), None);
+
+        let their_tree = tree_with(&[("dir/b.txt", &theirs), ("other.txt", "1234")]);
+        let (merged, conflicts) =
+            merge_trees_with_base(&base_tree, &base_tree, &their_tree, git_dir);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.get_hash_from_path("dir/b.txt"), Some(theirs.clone()));
+        assert_eq!(merged.get_hash_from_path("a.txt"), None);
+
+        let (_, conflicts) = merge_trees_with_base(&base_tree, &our_tree, &their_tree, git_dir);
+        assert_eq!(conflicts, vec!["dir/b.txt".to_string()]);
+
+        std::fs::remove_dir_all("tests/tree_handler_renames")
+    }
 }
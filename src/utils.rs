@@ -1,8 +1,13 @@
-use std::{collections::HashSet, io, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use chrono::{DateTime, FixedOffset, Offset, Utc};
 
-use crate::{commit, configuration::GIT_DIR};
+use crate::{cat_file, commit, configuration::GIT_DIR};
 
 /// Obtains the path to the Git directory of the current project.
 ///
@@ -286,6 +291,327 @@ pub fn get_timestamp() -> io::Result<(i64, String)> {
     Ok((timestamp, offset_formatted_for_timestamp))
 }
 
+/// Returns the references of the repository that point to an object, as (name, hash) sorted by name.
+///
+/// Every file under the `refs` folder of the git directory is read, for example `refs/heads/master`
+/// or `refs/tags/v1.0`. Symbolic references, such as `refs/remotes/origin/HEAD`, are left out.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+///
+/// # Errors
+///
+/// Returns an error if a folder or a reference can't be read.
+///
+pub fn list_refs(git_dir: &str) -> io::Result<Vec<(String, String)>> {
+    let mut refs = Vec::new();
+    let mut pending = vec![PathBuf::from("refs")];
+    while let Some(relative) = pending.pop() {
+        let path = Path::new(git_dir).join(&relative);
+        if path.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                pending.push(relative.join(entry?.file_name()));
+            }
+        } else if path.is_file() {
+            let hash = fs::read_to_string(&path)?.trim().to_string();
+            if hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                refs.push((relative.to_string_lossy().replace('\\', "/"), hash));
+            }
+        }
+    }
+    refs.sort();
+    Ok(refs)
+}
+
+/// Finds the complete hash of an object given the beginning of it.
+///
+/// The objects folder of the git directory is searched for an object whose hash starts with `prefix`.
+/// The prefix must have at least 4 characters and must match exactly one object.
+///
+/// # Arguments
+///
+/// * `prefix`: The beginning of the hash. It can also be the complete hash.
+/// * `git_dir`: A string representing the path to the Git repository directory.
+///
+/// # Returns
+///
+/// Returns the complete hash of the object, or `None` if no object (or more than one) matches.
+///
+pub fn expand_object_hash(prefix: &str, git_dir: &str) -> Option<String> {
+    if prefix.len() < 4 || prefix.len() > 40 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let prefix = prefix.to_lowercase();
+    let objects_dir = Path::new(git_dir).join("objects").join(&prefix[..2]);
+    let entries = fs::read_dir(objects_dir).ok()?;
+    let mut found: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(&prefix[2..]))
+        .map(|name| format!("{}{}", &prefix[..2], name))
+        .collect();
+    if found.len() == 1 {
+        found.pop()
+    } else {
+        None
+    }
+}
+
+/// Resolves a revision to the hash of the commit it refers to.
+///
+/// The following forms are accepted:
+/// * `HEAD`, a branch name, a tag name or a (possibly abbreviated) commit hash.
+/// * Any of the above followed by `~<n>` or `^`, which walk `n` (or one) first parents back.
+///
+/// # Arguments
+///
+/// * `revision`: The revision to resolve.
+/// * `git_dir`: A string representing the path to the Git repository directory.
+///
+/// # Returns
+///
+/// Returns the hash of the commit the revision refers to.
+///
+/// # Errors
+///
+/// Returns an error if the revision can't be resolved or walks past the first commit.
+///
+pub fn resolve_revision(revision: &str, git_dir: &str) -> io::Result<String> {
+    let unknown_revision = || {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: bad revision '{}'", revision),
+        )
+    };
+    let (name, generations) = match revision.find(['~', '^']) {
+        Some(position) => {
+            let (name, suffix) = revision.split_at(position);
+            let mut generations = 0;
+            let mut chars = suffix.chars().peekable();
+            while let Some(c) = chars.next() {
+                let mut digits = String::new();
+                while let Some(digit) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(*digit);
+                    chars.next();
+                }
+                generations += match (c, digits.parse::<usize>()) {
+                    ('~', Ok(n)) => n,
+                    ('~', Err(_)) | ('^', Err(_)) => 1,
+                    ('^', Ok(1)) => 1,
+                    _ => return Err(unknown_revision()),
+                };
+            }
+            (name, generations)
+        }
+        None => (revision, 0),
+    };
+
+    let mut hash = resolve_name(name, git_dir).ok_or_else(unknown_revision)?;
+    for _ in 0..generations {
+        hash = commit::get_parent_hash(&hash, git_dir)?;
+        if hash.chars().all(|c| c == '0') {
+            return Err(unknown_revision());
+        }
+    }
+    Ok(hash)
+}
+
+/// Resolves a revision to the hash of the object it names, which may be a commit, a tag, a tree or a blob.
+///
+/// Unlike `resolve_revision`, annotated tags are not peeled, so the name of one of them resolves to the tag object.
+/// Besides the forms accepted by `resolve_revision`, `<rev>:<path>` names the blob or the tree found at `path`
+/// in the tree of `<rev>`. If `<rev>` is empty, HEAD is used.
+///
+/// # Arguments
+///
+/// * `revision`: The revision to resolve.
+/// * `git_dir`: A string representing the path to the Git repository directory.
+///
+/// # Errors
+///
+/// Returns an error if the revision can't be resolved or the path is not in its tree.
+///
+pub fn resolve_object(revision: &str, git_dir: &str) -> io::Result<String> {
+    if let Some((revision, path)) = revision.split_once(':') {
+        let revision = if revision.is_empty() {
+            "HEAD"
+        } else {
+            revision
+        };
+        let commit_hash = resolve_revision(revision, git_dir)?;
+        return find_in_commit_tree(&commit_hash, path, git_dir)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: path '{}' does not exist in '{}'", path, revision),
+            )
+        });
+    }
+    if !revision.contains(['~', '^']) {
+        if let Some(hash) = resolve_reference(revision, git_dir) {
+            return Ok(hash);
+        }
+    }
+    resolve_revision(revision, git_dir)
+}
+
+/// Returns the hash of the blob or tree found at `path` in the tree of a commit, or None if there is nothing there.
+/// An empty path names the tree of the commit itself.
+fn find_in_commit_tree(commit_hash: &str, path: &str, git_dir: &str) -> io::Result<Option<String>> {
+    let content = cat_file::cat_file_return_content(commit_hash, git_dir)?;
+    let mut hash = match content.lines().next().and_then(|l| l.strip_prefix("tree ")) {
+        Some(tree) => tree.to_string(),
+        None => return Ok(None),
+    };
+    let mut is_tree = true;
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        if !is_tree {
+            return Ok(None);
+        }
+        let entry = cat_file::cat_tree(&hash, git_dir)?
+            .into_iter()
+            .find(|(_, entry_name, _)| entry_name == name);
+        match entry {
+            Some((mode, _, entry_hash)) => {
+                is_tree = mode.trim() == "40000";
+                hash = entry_hash;
+            }
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(hash))
+}
+
+/// Resolves `HEAD`, a branch, a tag or an abbreviated hash (without suffixes) to a commit hash.
+fn resolve_name(name: &str, git_dir: &str) -> Option<String> {
+    resolve_reference(name, git_dir).map(|hash| peel_tag(&hash, git_dir))
+}
+
+/// Resolves `HEAD`, a branch, a tag or an abbreviated hash (without suffixes) to the hash it names,
+/// without peeling annotated tags.
+fn resolve_reference(name: &str, git_dir: &str) -> Option<String> {
+    if name == "HEAD" || name.is_empty() {
+        let head = fs::read_to_string(Path::new(git_dir).join("HEAD")).ok()?;
+        return match head.trim().strip_prefix("ref: ") {
+            Some(reference) => fs::read_to_string(Path::new(git_dir).join(reference))
+                .ok()
+                .map(|hash| hash.trim().to_string()),
+            None => head.split_whitespace().next().map(|hash| hash.to_string()),
+        };
+    }
+
+    for reference in [
+        name.to_string(),
+        format!("refs/heads/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/remotes/{}", name),
+    ] {
+        let path = Path::new(git_dir).join(&reference);
+        if reference.starts_with("refs/") && path.is_file() {
+            return Some(fs::read_to_string(path).ok()?.trim().to_string());
+        }
+    }
+
+    expand_object_hash(name, git_dir)
+}
+
+/// If the hash belongs to an annotated tag, returns the hash of the object it points to.
+fn peel_tag(hash: &str, git_dir: &str) -> String {
+    match cat_file::cat_file_return_content(hash, git_dir) {
+        Ok(content) => match content
+            .lines()
+            .next()
+            .and_then(|l| l.strip_prefix("object "))
+        {
+            Some(object) => object.to_string(),
+            None => hash.to_string(),
+        },
+        Err(_) => hash.to_string(),
+    }
+}
+
+/// Returns the command used to edit files such as commit messages.
+///
+/// It is taken from the `GIT_EDITOR` environment variable, then from `EDITOR`,
+/// and defaults to `vi` if none of them is set.
+pub fn get_editor() -> String {
+    for variable in ["GIT_EDITOR", "EDITOR"] {
+        if let Ok(editor) = std::env::var(variable) {
+            if !editor.trim().is_empty() {
+                return editor;
+            }
+        }
+    }
+    "vi".to_string()
+}
+
+/// Opens the file in the given editor and waits until the editor is closed.
+///
+/// The editor command is run through the shell, so it may contain arguments (for example `code --wait`).
+///
+/// # Arguments
+///
+/// * `editor`: The command that launches the editor.
+/// * `path`: The path to the file to edit.
+///
+/// # Errors
+///
+/// Returns an error if the editor can't be launched or if it exits with a non-zero status.
+///
+pub fn launch_editor(editor: &str, path: &str) -> io::Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            format!("There was a problem with the editor '{}'", editor),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns true if `path` matches the glob `pattern`.
+///
+/// `*` matches any run of characters except '/', `?` matches a single character except '/'
+/// and `**` matches anything, including '/'. As in .gitignore, a pattern without '/' is
+/// matched against the file name only, and a leading '/' anchors it to the root.
+///
+/// # Arguments
+///
+/// * `pattern`: The glob, for example `*.png` or `assets/**/*.bin`.
+/// * `path`: The path of the file, relative to the root of the repository.
+///
+pub fn matches_pattern(pattern: &str, path: &str) -> bool {
+    if pattern.contains('/') {
+        glob_match(pattern.trim_start_matches('/').as_bytes(), path.as_bytes())
+    } else {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        glob_match(pattern.as_bytes(), name.as_bytes())
+    }
+}
+
+/// Matches `text` against the glob `pattern`, backtracking on every `*`.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=text.len()).any(|i| {
+                (i == 0 || text[i - 1] == b'/' || rest.is_empty()) && glob_match(rest, &text[i..])
+            })
+        }
+        Some(b'*') => (0..=text.len())
+            .take_while(|i| *i == 0 || text[*i - 1] != b'/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && glob_match(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, io::Write};
@@ -414,6 +740,22 @@ mod tests {
         let _ = fs::remove_dir_all("tests/utils/parents3");
     }
 
+    #[test]
+    fn test_glob_patterns() {
+        assert!(matches_pattern("*.png", "img/logo.png"));
+        assert!(matches_pattern("logo.???", "logo.png"));
+        assert!(!matches_pattern("*.png", "img/logo.png.txt"));
+        assert!(matches_pattern("img/*.png", "img/logo.png"));
+        assert!(!matches_pattern("img/*.png", "img/icons/logo.png"));
+        assert!(matches_pattern("img/**/*.png", "img/icons/logo.png"));
+        assert!(matches_pattern("img/**/*.png", "img/logo.png"));
+        assert!(matches_pattern("**/data", "a/b/data"));
+        assert!(matches_pattern("/build/*", "build/out.o"));
+        assert!(!matches_pattern("/build/*", "src/build/out.o"));
+        assert!(matches_pattern("/Makefile", "Makefile"));
+        assert!(!matches_pattern("/Makefile", "src/Makefile"));
+    }
+
     #[test]
     fn test_get_timestamp() -> io::Result<()> {
         let result = get_timestamp()?;